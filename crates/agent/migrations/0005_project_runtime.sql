@@ -0,0 +1,8 @@
+ALTER TABLE projects
+ADD COLUMN runtime TEXT NOT NULL DEFAULT 'auto';
+
+ALTER TABLE projects
+ADD COLUMN memory_limit_mb INTEGER;
+
+ALTER TABLE projects
+ADD COLUMN cpu_quota_percent INTEGER;
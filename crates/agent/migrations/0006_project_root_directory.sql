@@ -0,0 +1,5 @@
+ALTER TABLE projects
+ADD COLUMN root_directory TEXT NOT NULL DEFAULT '';
+
+ALTER TABLE projects
+ADD COLUMN watch_paths TEXT NOT NULL DEFAULT '[]';
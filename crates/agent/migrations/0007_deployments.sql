@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS deployments (
+    id TEXT PRIMARY KEY,
+    project_id TEXT NOT NULL,
+    git_ref TEXT NOT NULL,
+    commit_sha TEXT,
+    status TEXT NOT NULL,
+    log TEXT NOT NULL DEFAULT '[]',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_deployments_project_id
+ON deployments(project_id, created_at);
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS project_domains (
+    id TEXT PRIMARY KEY,
+    project_id TEXT NOT NULL,
+    domain TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    verification_error TEXT,
+    verified_at DATETIME,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_project_domains_domain
+ON project_domains(domain);
@@ -0,0 +1,3 @@
+ALTER TABLE project_domains ADD COLUMN redirect_status INTEGER;
+ALTER TABLE project_domains ADD COLUMN redirect_preserve_path BOOLEAN NOT NULL DEFAULT 1;
+ALTER TABLE project_domains ADD COLUMN is_primary BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+ALTER TABLE projects
+ADD COLUMN routing TEXT NOT NULL DEFAULT '{}';
@@ -0,0 +1,11 @@
+ALTER TABLE projects
+ADD COLUMN access_rules TEXT NOT NULL DEFAULT '{}';
+
+CREATE TABLE IF NOT EXISTS project_access_users (
+    project_id TEXT NOT NULL,
+    username TEXT NOT NULL,
+    password_hash TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (project_id, username),
+    FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+ALTER TABLE projects
+ADD COLUMN maintenance TEXT NOT NULL DEFAULT '{}';
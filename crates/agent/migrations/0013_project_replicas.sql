@@ -0,0 +1,12 @@
+ALTER TABLE projects
+ADD COLUMN replicas INTEGER NOT NULL DEFAULT 1;
+
+-- Servers running a replica alongside the project's ingress server (projects.server_id).
+CREATE TABLE IF NOT EXISTS project_replicas (
+    project_id TEXT NOT NULL,
+    server_id TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY(project_id, server_id),
+    FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE CASCADE,
+    FOREIGN KEY(server_id) REFERENCES servers(id)
+);
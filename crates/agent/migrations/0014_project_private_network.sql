@@ -0,0 +1,14 @@
+ALTER TABLE projects
+ADD COLUMN internal_name TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_projects_internal_name ON projects(internal_name);
+
+-- Projects allowed to call another project's service over the private network.
+CREATE TABLE IF NOT EXISTS project_service_access (
+    target_project_id TEXT NOT NULL,
+    caller_project_id TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY(target_project_id, caller_project_id),
+    FOREIGN KEY(target_project_id) REFERENCES projects(id) ON DELETE CASCADE,
+    FOREIGN KEY(caller_project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+ALTER TABLE projects
+ADD COLUMN stream_ports TEXT NOT NULL DEFAULT '[]';
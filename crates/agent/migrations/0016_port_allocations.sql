@@ -0,0 +1,39 @@
+-- Port the app listens on behind its socket proxy. NULL for projects whose old derived
+-- backend port (port + 10000) did not fit; one is allocated on their next deployment.
+ALTER TABLE projects
+ADD COLUMN backend_port INTEGER;
+
+UPDATE projects SET backend_port = port + 10000 WHERE port + 10000 <= 65535;
+
+-- Ports held on each server. A port number is held by one project at a time, whatever the
+-- protocol. Rows are reserved before the project row exists, so they are removed together with
+-- the project by the application rather than through a foreign key.
+CREATE TABLE IF NOT EXISTS port_allocations (
+    server_id TEXT NOT NULL,
+    port INTEGER NOT NULL,
+    project_id TEXT NOT NULL,
+    -- 'front', 'backend' or 'stream'.
+    kind TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY(server_id, port),
+    FOREIGN KEY(server_id) REFERENCES servers(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_port_allocations_project_id ON port_allocations(project_id);
+
+-- Existing projects keep their ports on every server running them. When two of them already
+-- collide, the first one keeps the reservation.
+INSERT OR IGNORE INTO port_allocations (server_id, port, project_id, kind)
+SELECT server_id, port, id, 'front' FROM projects
+UNION ALL
+SELECT r.server_id, p.port, p.id, 'front' FROM project_replicas r JOIN projects p ON p.id = r.project_id;
+
+INSERT OR IGNORE INTO port_allocations (server_id, port, project_id, kind)
+SELECT server_id, backend_port, id, 'backend' FROM projects WHERE backend_port IS NOT NULL
+UNION ALL
+SELECT r.server_id, p.backend_port, p.id, 'backend' FROM project_replicas r JOIN projects p ON p.id = r.project_id WHERE p.backend_port IS NOT NULL;
+
+INSERT OR IGNORE INTO port_allocations (server_id, port, project_id, kind)
+SELECT p.server_id, json_extract(s.value, '$.port'), p.id, 'stream' FROM projects p, json_each(p.stream_ports) s
+UNION
+SELECT p.server_id, json_extract(s.value, '$.target_port'), p.id, 'stream' FROM projects p, json_each(p.stream_ports) s;
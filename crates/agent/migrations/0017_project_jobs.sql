@@ -0,0 +1,3 @@
+-- JSON array of scheduled jobs set in the dashboard; the repository manifest can add more.
+ALTER TABLE projects
+ADD COLUMN jobs TEXT NOT NULL DEFAULT '[]';
@@ -0,0 +1,3 @@
+-- JSON array of process types run next to the web process; the repository Procfile can add more.
+ALTER TABLE projects
+ADD COLUMN processes TEXT NOT NULL DEFAULT '[]';
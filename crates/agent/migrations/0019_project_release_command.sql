@@ -0,0 +1,3 @@
+-- Command run once per deployment after the build, before the new release goes live.
+ALTER TABLE projects
+ADD COLUMN release_command TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,3 @@
+-- JSON array of persistent directories mounted into the app, kept across deploys.
+ALTER TABLE projects
+ADD COLUMN volumes TEXT NOT NULL DEFAULT '[]';
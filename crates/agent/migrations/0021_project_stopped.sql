@@ -0,0 +1,3 @@
+-- Set while the project is deliberately stopped, so nothing wakes it until it is started.
+ALTER TABLE projects
+ADD COLUMN stopped INTEGER NOT NULL DEFAULT 0;
@@ -1,9 +1,12 @@
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use serde::Deserialize;
 
+use crate::deployment::acme::{AcmeSettings, LETS_ENCRYPT_DIRECTORY_URL};
+use crate::deployment::dns_provider::DnsProviderConfig;
+
 const DEFAULT_CONFIG_PATH: &str = "/opt/nanoscale/config.json";
 
 const DEFAULT_DB_PATH: &str = "/opt/nanoscale/data/nanoscale.db";
@@ -17,11 +20,21 @@ const DEFAULT_WORKER_IP: &str = "127.0.0.1";
 const DEFAULT_WORKER_NAME: &str = "worker-node";
 const DEFAULT_WORKER_BIND: &str = "0.0.0.0:4000";
 
+const DEFAULT_TLS_RENEWAL_WINDOW_DAYS: u64 = 30;
+
 #[derive(Clone, Debug, Default, Deserialize)]
 #[serde(default)]
 pub struct NanoScaleConfig {
     pub database_path: Option<String>,
     pub tls_email: Option<String>,
+    /// Certificates expiring within this many days are renewed.
+    pub tls_renewal_window_days: Option<u64>,
+    /// ACME directory certificates are ordered from; Let's Encrypt production by default.
+    pub acme_directory_url: Option<String>,
+    /// Extra PEM root trusted when talking to the ACME server, e.g. a Pebble test CA.
+    pub acme_ca_bundle: Option<String>,
+    /// DNS API used for DNS-01 challenges, which the base domain's wildcard certificate needs.
+    pub dns_provider: Option<DnsProviderConfig>,
     pub orchestrator: OrchestratorConfig,
     pub worker: WorkerConfig,
     pub github: GitHubConfig,
@@ -35,6 +48,9 @@ pub struct OrchestratorConfig {
     pub server_name: Option<String>,
     pub worker_ip: Option<String>,
     pub base_domain: Option<String>,
+    /// Server that terminates TLS for every project and proxies to the app servers over the
+    /// private network; each project's own server does when unset.
+    pub ingress_server_id: Option<String>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -144,6 +160,15 @@ impl NanoScaleConfig {
             .map(ToString::to_string)
     }
 
+    pub fn orchestrator_ingress_server_id(&self) -> Option<String> {
+        self.orchestrator
+            .ingress_server_id
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(ToString::to_string)
+    }
+
     pub fn tls_email(&self) -> Option<String> {
         self.tls_email
             .as_deref()
@@ -158,6 +183,63 @@ impl NanoScaleConfig {
             })
     }
 
+    #[must_use]
+    pub fn tls_renewal_window_days(&self) -> u64 {
+        self.tls_renewal_window_days
+            .filter(|days| *days > 0)
+            .unwrap_or(DEFAULT_TLS_RENEWAL_WINDOW_DAYS)
+    }
+
+    #[must_use]
+    pub fn acme_directory_url(&self) -> String {
+        self.acme_directory_url
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(ToString::to_string)
+            .or_else(|| {
+                std::env::var("NANOSCALE_ACME_DIRECTORY_URL")
+                    .ok()
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+            })
+            .unwrap_or_else(|| LETS_ENCRYPT_DIRECTORY_URL.to_string())
+    }
+
+    pub fn acme_ca_bundle(&self) -> Option<String> {
+        self.acme_ca_bundle
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(ToString::to_string)
+            .or_else(|| {
+                std::env::var("NANOSCALE_ACME_CA_BUNDLE")
+                    .ok()
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+            })
+    }
+
+    /// ACME settings for this host, including the DNS provider when one is configured.
+    ///
+    /// # Errors
+    /// Returns an error if the `dns_provider` section is invalid.
+    pub fn acme_settings(&self) -> Result<AcmeSettings> {
+        let dns_provider = self
+            .dns_provider
+            .as_ref()
+            .map(DnsProviderConfig::build)
+            .transpose()
+            .context("invalid dns_provider config")?;
+
+        Ok(AcmeSettings {
+            directory_url: self.acme_directory_url(),
+            ca_bundle: self.acme_ca_bundle().map(PathBuf::from),
+            dns_provider,
+            ..AcmeSettings::default()
+        })
+    }
+
     #[must_use]
     pub fn worker_orchestrator_url(&self) -> String {
         self.worker
@@ -317,6 +399,10 @@ mod tests {
             config.worker_orchestrator_url(),
             DEFAULT_WORKER_ORCHESTRATOR_URL
         );
+        assert_eq!(
+            config.tls_renewal_window_days(),
+            DEFAULT_TLS_RENEWAL_WINDOW_DAYS
+        );
 
         std::env::remove_var("NANOSCALE_CONFIG_PATH");
     }
@@ -332,9 +418,17 @@ mod tests {
             r#"{
   "database_path": "  /tmp/test.db  ",
   "tls_email": "  admin@example.com  ",
+  "tls_renewal_window_days": 21,
+  "dns_provider": {
+    "type": "rfc2136",
+    "server": "127.0.0.1",
+    "zone": "example.com",
+    "key_file": "/opt/nanoscale/config/tsig.key"
+  },
   "orchestrator": {
     "bind_address": "  127.0.0.1:9999  ",
-    "base_domain": "  Example.COM.  "
+    "base_domain": "  Example.COM.  ",
+    "ingress_server_id": "  orchestrator-local  "
   },
   "worker": {
     "orchestrator_url": "  http://localhost:1234  ",
@@ -355,11 +449,18 @@ mod tests {
         let config = NanoScaleConfig::load().expect("load should succeed");
         assert_eq!(config.database_path(), "/tmp/test.db");
         assert_eq!(config.tls_email().as_deref(), Some("admin@example.com"));
+        assert_eq!(config.tls_renewal_window_days(), 21);
+        let acme_settings = config.acme_settings().expect("acme settings");
+        assert!(acme_settings.dns_provider.is_some());
         assert_eq!(config.orchestrator_bind_address(), "127.0.0.1:9999");
         assert_eq!(
             config.orchestrator_base_domain().as_deref(),
             Some("Example.COM.")
         );
+        assert_eq!(
+            config.orchestrator_ingress_server_id().as_deref(),
+            Some("orchestrator-local")
+        );
         assert_eq!(config.worker_orchestrator_url(), "http://localhost:1234");
         assert_eq!(config.worker_ip(), "10.0.0.5");
         assert_eq!(config.worker_name(), "worker-a");
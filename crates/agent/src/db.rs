@@ -4,8 +4,14 @@ use anyhow::{bail, Result};
 use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
 use sqlx::{Pool, Sqlite};
 
+mod access;
+mod deployments;
+mod domains;
 mod github;
+mod network;
+mod ports;
 mod projects;
+mod replicas;
 mod servers;
 mod types;
 mod users;
@@ -14,10 +20,12 @@ mod users;
 mod tests;
 
 pub use types::{
-    GitHubInstallationRecord, GitHubRepositoryRecord, GitHubUserLinkRecord, NewGitHubInstallation,
-    NewGitHubRepository, NewGitHubUserLink, NewGitHubWebhookDelivery, NewProject,
-    NewProjectGitHubLink, NewServer, NewUser, ProjectDetailsRecord, ProjectGitHubLinkRecord,
-    ProjectListRecord, ServerConnectionInfo, ServerRecord, UserRecord,
+    DeploymentRecord, GitHubInstallationRecord, GitHubRepositoryRecord, GitHubUserLinkRecord,
+    NewDeployment, NewGitHubInstallation, NewGitHubRepository, NewGitHubUserLink,
+    NewGitHubWebhookDelivery, NewProject, NewProjectDomain, NewProjectGitHubLink, NewServer,
+    NewUser, PortAllocationRecord, PortKind, PrivateServiceRecord, ProjectAccessUserRecord,
+    ProjectDetailsRecord, ProjectDomainRecord, ProjectGitHubLinkRecord, ProjectListRecord,
+    ServerConnectionInfo, ServerLoadRecord, ServerRecord, UserRecord,
 };
 
 const BASE_PROJECT_PORT: i64 = 3100;
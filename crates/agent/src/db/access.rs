@@ -0,0 +1,78 @@
+use anyhow::Result;
+
+use super::{DbClient, ProjectAccessUserRecord};
+
+impl DbClient {
+    /// Replaces a project's IP allow/deny rules (stored as JSON).
+    ///
+    /// # Errors
+    /// Returns an error if the update fails.
+    pub async fn set_project_access_rules(&self, project_id: &str, rules: &str) -> Result<()> {
+        sqlx::query("UPDATE projects SET access_rules = ?2 WHERE id = ?1")
+            .bind(project_id)
+            .bind(rules)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Adds a basic auth user to a project, or replaces the password of an existing one.
+    ///
+    /// # Errors
+    /// Returns an error if the insert fails.
+    pub async fn upsert_project_access_user(
+        &self,
+        project_id: &str,
+        username: &str,
+        password_hash: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO project_access_users (project_id, username, password_hash) VALUES (?1, ?2, ?3) ON CONFLICT(project_id, username) DO UPDATE SET password_hash = excluded.password_hash",
+        )
+        .bind(project_id)
+        .bind(username)
+        .bind(password_hash)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Lists a project's basic auth users by username.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn list_project_access_users(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<ProjectAccessUserRecord>> {
+        let rows = sqlx::query_as::<_, ProjectAccessUserRecord>(
+            "SELECT username, password_hash, created_at FROM project_access_users WHERE project_id = ?1 ORDER BY username ASC",
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Removes a basic auth user from a project. Returns whether a row was removed.
+    ///
+    /// # Errors
+    /// Returns an error if the delete fails.
+    pub async fn delete_project_access_user(
+        &self,
+        project_id: &str,
+        username: &str,
+    ) -> Result<bool> {
+        let result =
+            sqlx::query("DELETE FROM project_access_users WHERE project_id = ?1 AND username = ?2")
+                .bind(project_id)
+                .bind(username)
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
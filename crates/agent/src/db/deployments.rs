@@ -0,0 +1,43 @@
+use anyhow::Result;
+
+use super::{DbClient, DeploymentRecord, NewDeployment};
+
+impl DbClient {
+    /// Records the outcome of one deployment attempt.
+    ///
+    /// # Errors
+    /// Returns an error if the insert fails.
+    pub async fn insert_deployment(&self, deployment: &NewDeployment) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO deployments (id, project_id, git_ref, commit_sha, status, log) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )
+        .bind(&deployment.id)
+        .bind(&deployment.project_id)
+        .bind(&deployment.git_ref)
+        .bind(deployment.commit_sha.as_deref())
+        .bind(&deployment.status)
+        .bind(&deployment.log)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Lists a project's deployments, newest first.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn list_deployments_for_project(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<DeploymentRecord>> {
+        let rows = sqlx::query_as::<_, DeploymentRecord>(
+            "SELECT id, project_id, git_ref, commit_sha, status, log, created_at FROM deployments WHERE project_id = ?1 ORDER BY created_at DESC, rowid DESC",
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+}
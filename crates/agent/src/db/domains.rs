@@ -0,0 +1,147 @@
+use anyhow::Result;
+
+use super::{DbClient, NewProjectDomain, ProjectDomainRecord};
+
+impl DbClient {
+    /// Attaches a custom domain to a project in the `pending` state.
+    ///
+    /// # Errors
+    /// Returns an error if the insert fails (including when the domain is already attached).
+    pub async fn insert_project_domain(&self, domain: &NewProjectDomain) -> Result<()> {
+        sqlx::query("INSERT INTO project_domains (id, project_id, domain) VALUES (?1, ?2, ?3)")
+            .bind(&domain.id)
+            .bind(&domain.project_id)
+            .bind(&domain.domain)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Lists a project's custom domains in the order they were added.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn list_project_domains(&self, project_id: &str) -> Result<Vec<ProjectDomainRecord>> {
+        let rows = sqlx::query_as::<_, ProjectDomainRecord>(
+            "SELECT id, project_id, domain, status, verification_error, verified_at, redirect_status, redirect_preserve_path, is_primary, created_at FROM project_domains WHERE project_id = ?1 ORDER BY created_at ASC, rowid ASC",
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Lists the verified custom domains of a project; only these are routed and certified.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn list_verified_project_domains(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<ProjectDomainRecord>> {
+        let rows = sqlx::query_as::<_, ProjectDomainRecord>(
+            "SELECT id, project_id, domain, status, verification_error, verified_at, redirect_status, redirect_preserve_path, is_primary, created_at FROM project_domains WHERE project_id = ?1 AND status = 'verified' ORDER BY created_at ASC, rowid ASC",
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Fetches one custom domain of a project.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn get_project_domain(
+        &self,
+        project_id: &str,
+        domain_id: &str,
+    ) -> Result<Option<ProjectDomainRecord>> {
+        let row = sqlx::query_as::<_, ProjectDomainRecord>(
+            "SELECT id, project_id, domain, status, verification_error, verified_at, redirect_status, redirect_preserve_path, is_primary, created_at FROM project_domains WHERE project_id = ?1 AND id = ?2",
+        )
+        .bind(project_id)
+        .bind(domain_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Stores the result of a DNS verification attempt.
+    ///
+    /// # Errors
+    /// Returns an error if the update fails.
+    pub async fn set_project_domain_verification(
+        &self,
+        domain_id: &str,
+        verification_error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE project_domains SET status = CASE WHEN ?2 IS NULL THEN 'verified' ELSE 'failed' END, verification_error = ?2, verified_at = CASE WHEN ?2 IS NULL THEN CURRENT_TIMESTAMP ELSE NULL END WHERE id = ?1",
+        )
+        .bind(domain_id)
+        .bind(verification_error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Stores how a domain is routed. Marking a domain primary clears the flag on the project's
+    /// other domains.
+    ///
+    /// # Errors
+    /// Returns an error if either update fails.
+    pub async fn set_project_domain_routing(
+        &self,
+        project_id: &str,
+        domain_id: &str,
+        redirect_status: Option<i64>,
+        redirect_preserve_path: bool,
+        is_primary: bool,
+    ) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+
+        if is_primary {
+            sqlx::query(
+                "UPDATE project_domains SET is_primary = 0 WHERE project_id = ?1 AND id <> ?2",
+            )
+            .bind(project_id)
+            .bind(domain_id)
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        sqlx::query(
+            "UPDATE project_domains SET redirect_status = ?3, redirect_preserve_path = ?4, is_primary = ?5 WHERE project_id = ?1 AND id = ?2",
+        )
+        .bind(project_id)
+        .bind(domain_id)
+        .bind(redirect_status)
+        .bind(redirect_preserve_path)
+        .bind(is_primary)
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Detaches a custom domain from a project. Returns whether a row was removed.
+    ///
+    /// # Errors
+    /// Returns an error if the delete fails.
+    pub async fn delete_project_domain(&self, project_id: &str, domain_id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM project_domains WHERE project_id = ?1 AND id = ?2")
+            .bind(project_id)
+            .bind(domain_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
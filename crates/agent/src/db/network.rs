@@ -0,0 +1,126 @@
+use anyhow::Result;
+
+use super::{DbClient, PrivateServiceRecord};
+
+impl DbClient {
+    /// Sets or clears the name a project's service is reachable by on the private network.
+    ///
+    /// # Errors
+    /// Returns an error if the update fails, including when another project uses the name.
+    pub async fn set_project_internal_name(
+        &self,
+        project_id: &str,
+        internal_name: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query("UPDATE projects SET internal_name = ?2 WHERE id = ?1")
+            .bind(project_id)
+            .bind(internal_name)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns whether a project other than `project_id` already uses `internal_name`.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn is_internal_name_in_use(
+        &self,
+        internal_name: &str,
+        project_id: &str,
+    ) -> Result<bool> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM projects WHERE internal_name = ?1 AND id != ?2",
+        )
+        .bind(internal_name)
+        .bind(project_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count > 0)
+    }
+
+    /// Replaces the projects allowed to call `target_project_id`.
+    ///
+    /// # Errors
+    /// Returns an error if the transaction fails.
+    pub async fn set_project_service_callers(
+        &self,
+        target_project_id: &str,
+        caller_project_ids: &[String],
+    ) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query("DELETE FROM project_service_access WHERE target_project_id = ?1")
+            .bind(target_project_id)
+            .execute(&mut *transaction)
+            .await?;
+        for caller_project_id in caller_project_ids {
+            sqlx::query(
+                "INSERT INTO project_service_access (target_project_id, caller_project_id) VALUES (?1, ?2)",
+            )
+            .bind(target_project_id)
+            .bind(caller_project_id)
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Lists the projects allowed to call `target_project_id`.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn list_project_service_callers(
+        &self,
+        target_project_id: &str,
+    ) -> Result<Vec<String>> {
+        let rows = sqlx::query_scalar::<_, String>(
+            "SELECT caller_project_id FROM project_service_access WHERE target_project_id = ?1 ORDER BY caller_project_id ASC",
+        )
+        .bind(target_project_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Lists the named services `caller_project_id` is allowed to call.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn list_project_service_targets(
+        &self,
+        caller_project_id: &str,
+    ) -> Result<Vec<PrivateServiceRecord>> {
+        let rows = sqlx::query_as::<_, PrivateServiceRecord>(
+            "SELECT p.internal_name, p.port, s.ip_address FROM project_service_access a JOIN projects p ON p.id = a.target_project_id JOIN servers s ON s.id = p.server_id WHERE a.caller_project_id = ?1 AND p.internal_name IS NOT NULL ORDER BY p.internal_name ASC",
+        )
+        .bind(caller_project_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Lists the addresses of every server running a project allowed to call
+    /// `target_project_id`, replicas included.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn list_project_caller_server_ips(
+        &self,
+        target_project_id: &str,
+    ) -> Result<Vec<String>> {
+        let rows = sqlx::query_scalar::<_, String>(
+            "SELECT DISTINCT s.ip_address FROM project_service_access a JOIN projects p ON p.id = a.caller_project_id JOIN servers s ON s.id = p.server_id OR s.id IN (SELECT r.server_id FROM project_replicas r WHERE r.project_id = p.id) WHERE a.target_project_id = ?1 ORDER BY s.ip_address ASC",
+        )
+        .bind(target_project_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+}
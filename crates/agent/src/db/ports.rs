@@ -0,0 +1,220 @@
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+use anyhow::{bail, Result};
+use sqlx::{Sqlite, Transaction};
+
+use super::{DbClient, PortAllocationRecord, PortKind, BASE_PROJECT_PORT};
+
+/// Ports handed out for projects' sockets.
+const FRONT_PORTS: RangeInclusive<i64> = BASE_PROJECT_PORT..=9_999;
+/// Ports handed out for apps' own listeners, clear of the front pool and of the kernel's
+/// ephemeral range.
+const BACKEND_PORTS: RangeInclusive<i64> = 20_000..=29_999;
+/// Reservations whose project was never stored are left by creations that failed half way;
+/// they are reclaimed once this old.
+const ABANDONED_AFTER: &str = "-1 hour";
+
+impl DbClient {
+    /// Reserves, for `project_id`, the lowest port of `kind`'s pool that is free on every server
+    /// in `server_ids`. The port is chosen and reserved in one write transaction, so concurrent
+    /// callers never get the same one. Returns `None` when the pool is exhausted.
+    ///
+    /// # Errors
+    /// Returns an error if the transaction fails or `kind` has no pool.
+    pub async fn reserve_pooled_port(
+        &self,
+        project_id: &str,
+        server_ids: &[String],
+        kind: PortKind,
+    ) -> Result<Option<i64>> {
+        let pool = match kind {
+            PortKind::Front => FRONT_PORTS,
+            PortKind::Backend => BACKEND_PORTS,
+            PortKind::Stream => bail!("stream ports are not allocated from a pool"),
+        };
+
+        let mut transaction = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        reclaim_abandoned(&mut transaction).await?;
+        let mut taken = HashSet::new();
+        for server_id in server_ids {
+            let ports = sqlx::query_scalar::<_, i64>(
+                "SELECT port FROM port_allocations WHERE server_id = ?1 AND port BETWEEN ?2 AND ?3",
+            )
+            .bind(server_id)
+            .bind(pool.start())
+            .bind(pool.end())
+            .fetch_all(&mut *transaction)
+            .await?;
+            taken.extend(ports);
+        }
+        let Some(port) = pool.clone().find(|port| !taken.contains(port)) else {
+            return Ok(None);
+        };
+        insert_reservations(&mut transaction, project_id, server_ids, kind, port).await?;
+        transaction.commit().await?;
+
+        Ok(Some(port))
+    }
+
+    /// Reserves `port` for `project_id` on every server in `server_ids`. Reserves nothing and
+    /// returns `false` when any of them already has the port held.
+    ///
+    /// # Errors
+    /// Returns an error if the transaction fails.
+    pub async fn reserve_port(
+        &self,
+        project_id: &str,
+        server_ids: &[String],
+        kind: PortKind,
+        port: i64,
+    ) -> Result<bool> {
+        let mut transaction = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        reclaim_abandoned(&mut transaction).await?;
+        for server_id in server_ids {
+            let held = sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM port_allocations WHERE server_id = ?1 AND port = ?2",
+            )
+            .bind(server_id)
+            .bind(port)
+            .fetch_one(&mut *transaction)
+            .await?;
+            if held > 0 {
+                return Ok(false);
+            }
+        }
+        insert_reservations(&mut transaction, project_id, server_ids, kind, port).await?;
+        transaction.commit().await?;
+
+        Ok(true)
+    }
+
+    /// Frees `port` on every server where `project_id` holds it.
+    ///
+    /// # Errors
+    /// Returns an error if the delete fails.
+    pub async fn release_port(&self, project_id: &str, port: i64) -> Result<()> {
+        sqlx::query("DELETE FROM port_allocations WHERE project_id = ?1 AND port = ?2")
+            .bind(project_id)
+            .bind(port)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Frees every port held by `project_id`, for a project whose creation failed before it
+    /// was stored.
+    ///
+    /// # Errors
+    /// Returns an error if the delete fails.
+    pub async fn release_project_ports(&self, project_id: &str) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        release_ports(&mut transaction, project_id, None).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Returns who holds `port` on `server_id`, if anyone.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn find_port_allocation(
+        &self,
+        server_id: &str,
+        port: i64,
+    ) -> Result<Option<PortAllocationRecord>> {
+        let row = sqlx::query_as::<_, PortAllocationRecord>(
+            "SELECT server_id, port, project_id, kind FROM port_allocations WHERE server_id = ?1 AND port = ?2",
+        )
+        .bind(server_id)
+        .bind(port)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Lists the ports held by `project_id`, by server and port.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn list_project_port_allocations(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<PortAllocationRecord>> {
+        let rows = sqlx::query_as::<_, PortAllocationRecord>(
+            "SELECT server_id, port, project_id, kind FROM port_allocations WHERE project_id = ?1 ORDER BY server_id ASC, port ASC",
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+}
+
+/// Reserves the public and target ports in the stored `stream_ports` of `project_id` on its
+/// server. A number used over both protocols is held once.
+pub(super) async fn reserve_stream_ports(
+    transaction: &mut Transaction<'_, Sqlite>,
+    project_id: &str,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO port_allocations (server_id, port, project_id, kind) SELECT p.server_id, json_extract(s.value, '$.port'), p.id, 'stream' FROM projects p, json_each(p.stream_ports) s WHERE p.id = ?1 UNION SELECT p.server_id, json_extract(s.value, '$.target_port'), p.id, 'stream' FROM projects p, json_each(p.stream_ports) s WHERE p.id = ?1",
+    )
+    .bind(project_id)
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+}
+
+/// Frees the ports of `kind`, or all of them, held by `project_id`.
+pub(super) async fn release_ports(
+    transaction: &mut Transaction<'_, Sqlite>,
+    project_id: &str,
+    kind: Option<PortKind>,
+) -> Result<()> {
+    sqlx::query("DELETE FROM port_allocations WHERE project_id = ?1 AND (?2 IS NULL OR kind = ?2)")
+        .bind(project_id)
+        .bind(kind.map(PortKind::as_str))
+        .execute(&mut **transaction)
+        .await?;
+
+    Ok(())
+}
+
+async fn insert_reservations(
+    transaction: &mut Transaction<'_, Sqlite>,
+    project_id: &str,
+    server_ids: &[String],
+    kind: PortKind,
+    port: i64,
+) -> Result<()> {
+    for server_id in server_ids {
+        sqlx::query(
+            "INSERT INTO port_allocations (server_id, port, project_id, kind) VALUES (?1, ?2, ?3, ?4)",
+        )
+        .bind(server_id)
+        .bind(port)
+        .bind(project_id)
+        .bind(kind.as_str())
+        .execute(&mut **transaction)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn reclaim_abandoned(transaction: &mut Transaction<'_, Sqlite>) -> Result<()> {
+    sqlx::query(
+        "DELETE FROM port_allocations WHERE project_id NOT IN (SELECT id FROM projects) AND created_at < datetime('now', ?1)",
+    )
+    .bind(ABANDONED_AFTER)
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+}
@@ -1,15 +1,19 @@
 use anyhow::Result;
 
-use super::{DbClient, NewProject, ProjectDetailsRecord, ProjectListRecord, BASE_PROJECT_PORT};
+use super::ports::{release_ports, reserve_stream_ports};
+use super::{DbClient, NewProject, PortKind, ProjectDetailsRecord, ProjectListRecord};
 
 impl DbClient {
-    /// Inserts a new project record.
+    /// Inserts a new project record and reserves its stream ports on its server. Its front
+    /// and backend ports must already be reserved.
     ///
     /// # Errors
-    /// Returns an error if the insert fails.
+    /// Returns an error if the insert fails, including when a stream port is held by another
+    /// project.
     pub async fn insert_project(&self, project: &NewProject) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
         sqlx::query(
-            "INSERT INTO projects (id, server_id, name, repo_url, branch, install_command, build_command, start_command, output_directory, env_vars, port, domain, source_provider, source_repo_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            "INSERT INTO projects (id, server_id, name, repo_url, branch, install_command, build_command, start_command, output_directory, env_vars, port, domain, source_provider, source_repo_id, runtime, memory_limit_mb, cpu_quota_percent, root_directory, watch_paths, routing, access_rules, maintenance, replicas, stream_ports, backend_port, jobs, processes, release_command, volumes) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29)",
         )
         .bind(&project.id)
         .bind(&project.server_id)
@@ -25,19 +29,132 @@ impl DbClient {
         .bind(project.domain.as_deref())
         .bind(&project.source_provider)
         .bind(project.source_repo_id)
-        .execute(&self.pool)
+        .bind(&project.runtime)
+        .bind(project.memory_limit_mb)
+        .bind(project.cpu_quota_percent)
+        .bind(&project.root_directory)
+        .bind(&project.watch_paths)
+        .bind(&project.routing)
+        .bind(&project.access_rules)
+        .bind(&project.maintenance)
+        .bind(project.replicas)
+        .bind(&project.stream_ports)
+        .bind(project.backend_port)
+        .bind(&project.jobs)
+        .bind(&project.processes)
+        .bind(&project.release_command)
+        .bind(&project.volumes)
+        .execute(&mut *transaction)
         .await?;
+        reserve_stream_ports(&mut transaction, &project.id).await?;
+        transaction.commit().await?;
 
         Ok(())
     }
 
-    /// Deletes a project by id.
+    /// Deletes a project by id and frees every port it holds.
     ///
     /// # Errors
     /// Returns an error if the delete fails.
     pub async fn delete_project_by_id(&self, project_id: &str) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
         sqlx::query("DELETE FROM projects WHERE id = ?1")
             .bind(project_id)
+            .execute(&mut *transaction)
+            .await?;
+        release_ports(&mut transaction, project_id, None).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Replaces a project's maintenance mode (stored as JSON).
+    ///
+    /// # Errors
+    /// Returns an error if the update fails.
+    pub async fn set_project_maintenance(&self, project_id: &str, maintenance: &str) -> Result<()> {
+        sqlx::query("UPDATE projects SET maintenance = ?2 WHERE id = ?1")
+            .bind(project_id)
+            .bind(maintenance)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Replaces a project's public TCP/UDP ports (stored as JSON) and the ports they hold on its
+    /// server.
+    ///
+    /// # Errors
+    /// Returns an error if the update fails, including when a port is held by another project.
+    pub async fn set_project_stream_ports(
+        &self,
+        project_id: &str,
+        stream_ports: &str,
+    ) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query("UPDATE projects SET stream_ports = ?2 WHERE id = ?1")
+            .bind(project_id)
+            .bind(stream_ports)
+            .execute(&mut *transaction)
+            .await?;
+        release_ports(&mut transaction, project_id, Some(PortKind::Stream)).await?;
+        reserve_stream_ports(&mut transaction, project_id).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Replaces a project's scheduled jobs (stored as JSON).
+    ///
+    /// # Errors
+    /// Returns an error if the update fails.
+    pub async fn set_project_jobs(&self, project_id: &str, jobs: &str) -> Result<()> {
+        sqlx::query("UPDATE projects SET jobs = ?2 WHERE id = ?1")
+            .bind(project_id)
+            .bind(jobs)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Replaces a project's process types (stored as JSON).
+    ///
+    /// # Errors
+    /// Returns an error if the update fails.
+    pub async fn set_project_processes(&self, project_id: &str, processes: &str) -> Result<()> {
+        sqlx::query("UPDATE projects SET processes = ?2 WHERE id = ?1")
+            .bind(project_id)
+            .bind(processes)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Replaces a project's volumes (stored as JSON).
+    ///
+    /// # Errors
+    /// Returns an error if the update fails.
+    pub async fn set_project_volumes(&self, project_id: &str, volumes: &str) -> Result<()> {
+        sqlx::query("UPDATE projects SET volumes = ?2 WHERE id = ?1")
+            .bind(project_id)
+            .bind(volumes)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Records whether the project is deliberately stopped.
+    ///
+    /// # Errors
+    /// Returns an error if the update fails.
+    pub async fn set_project_stopped(&self, project_id: &str, stopped: bool) -> Result<()> {
+        sqlx::query("UPDATE projects SET stopped = ?2 WHERE id = ?1")
+            .bind(project_id)
+            .bind(stopped)
             .execute(&self.pool)
             .await?;
 
@@ -49,8 +166,8 @@ impl DbClient {
     /// # Errors
     /// Returns an error if the query fails.
     pub async fn list_projects(&self) -> Result<Vec<ProjectListRecord>> {
-        let rows = sqlx::query_as::<_, (String, String, String, String, String, i64, Option<String>, String, Option<i64>, String)>(
-            "SELECT id, name, repo_url, branch, start_command, port, domain, source_provider, source_repo_id, created_at FROM projects ORDER BY created_at DESC",
+        let rows = sqlx::query_as::<_, (String, String, String, String, String, i64, Option<String>, String, Option<i64>, bool, String)>(
+            "SELECT id, name, repo_url, branch, start_command, port, domain, source_provider, source_repo_id, stopped, created_at FROM projects ORDER BY created_at DESC",
         )
         .fetch_all(&self.pool)
         .await?;
@@ -68,6 +185,7 @@ impl DbClient {
                     domain,
                     source_provider,
                     source_repo_id,
+                    stopped,
                     created_at,
                 )| {
                     ProjectListRecord {
@@ -80,6 +198,7 @@ impl DbClient {
                         domain,
                         source_provider,
                         source_repo_id,
+                        stopped,
                         created_at,
                     }
                 },
@@ -113,100 +232,33 @@ impl DbClient {
         &self,
         project_id: &str,
     ) -> Result<Option<ProjectDetailsRecord>> {
-        let row = sqlx::query_as::<
-            _,
-            (
-                String,
-                String,
-                String,
-                String,
-                String,
-                String,
-                String,
-                String,
-                String,
-                String,
-                i64,
-                Option<String>,
-                String,
-                Option<i64>,
-                String,
-                Option<String>,
-            ),
-        >(
-            "SELECT p.id, p.server_id, p.name, p.repo_url, p.branch, p.install_command, p.build_command, p.start_command, p.output_directory, p.env_vars, p.port, p.domain, p.source_provider, p.source_repo_id, p.created_at, s.name FROM projects p LEFT JOIN servers s ON s.id = p.server_id WHERE p.id = ?1",
+        let row = sqlx::query_as::<_, ProjectDetailsRecord>(
+            "SELECT p.id, p.server_id, p.name, p.repo_url, p.branch, p.install_command, p.build_command, p.start_command, p.output_directory, p.env_vars, p.port, p.backend_port, p.domain, p.source_provider, p.source_repo_id, p.runtime, p.memory_limit_mb, p.cpu_quota_percent, p.root_directory, p.watch_paths, p.routing, p.access_rules, p.maintenance, p.replicas, p.internal_name, p.stream_ports, p.jobs, p.processes, p.volumes, p.release_command, p.stopped, p.created_at, s.name AS server_name FROM projects p LEFT JOIN servers s ON s.id = p.server_id WHERE p.id = ?1",
         )
         .bind(project_id)
         .fetch_optional(&self.pool)
         .await?;
 
-        Ok(row.map(
-            |(
-                id,
-                server_id,
-                name,
-                repo_url,
-                branch,
-                install_command,
-                build_command,
-                start_command,
-                output_directory,
-                env_vars,
-                port,
-                domain,
-                source_provider,
-                source_repo_id,
-                created_at,
-                server_name,
-            )| ProjectDetailsRecord {
-                id,
-                server_id,
-                name,
-                repo_url,
-                branch,
-                install_command,
-                build_command,
-                start_command,
-                output_directory,
-                env_vars,
-                port,
-                domain,
-                source_provider,
-                source_repo_id,
-                created_at,
-                server_name,
-            },
-        ))
-    }
-
-    /// Returns the next available project port.
-    ///
-    /// # Errors
-    /// Returns an error if the query fails.
-    pub async fn next_available_project_port(&self) -> Result<i64> {
-        let max_port = sqlx::query_scalar::<_, Option<i64>>("SELECT MAX(port) FROM projects")
-            .fetch_one(&self.pool)
-            .await?
-            .unwrap_or(BASE_PROJECT_PORT - 1);
-
-        Ok(if max_port < BASE_PROJECT_PORT {
-            BASE_PROJECT_PORT
-        } else {
-            max_port + 1
-        })
+        Ok(row)
     }
 
-    /// Checks whether a port is already assigned to a project.
+    /// Records the backend port of a project migrated without one. The port must already be
+    /// reserved.
     ///
     /// # Errors
-    /// Returns an error if the query fails.
-    pub async fn is_project_port_in_use(&self, port: i64) -> Result<bool> {
-        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM projects WHERE port = ?1")
-            .bind(port)
-            .fetch_one(&self.pool)
+    /// Returns an error if the update fails.
+    pub async fn set_project_backend_port(
+        &self,
+        project_id: &str,
+        backend_port: i64,
+    ) -> Result<()> {
+        sqlx::query("UPDATE projects SET backend_port = ?2 WHERE id = ?1")
+            .bind(project_id)
+            .bind(backend_port)
+            .execute(&self.pool)
             .await?;
 
-        Ok(count > 0)
+        Ok(())
     }
 
     /// Checks whether a domain is already assigned to a project.
@@ -215,7 +267,7 @@ impl DbClient {
     /// Returns an error if the query fails.
     pub async fn is_project_domain_in_use(&self, domain: &str) -> Result<bool> {
         let count = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM projects WHERE domain IS NOT NULL AND domain = ?1",
+            "SELECT (SELECT COUNT(*) FROM projects WHERE domain IS NOT NULL AND domain = ?1) + (SELECT COUNT(*) FROM project_domains WHERE domain = ?1)",
         )
         .bind(domain)
         .fetch_one(&self.pool)
@@ -0,0 +1,80 @@
+use anyhow::Result;
+
+use super::{DbClient, ServerConnectionInfo, ServerLoadRecord};
+
+impl DbClient {
+    /// Replaces the servers running a project's replicas next to its ingress server.
+    ///
+    /// # Errors
+    /// Returns an error if the transaction fails.
+    pub async fn set_project_replicas(
+        &self,
+        project_id: &str,
+        server_ids: &[String],
+    ) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query("DELETE FROM project_replicas WHERE project_id = ?1")
+            .bind(project_id)
+            .execute(&mut *transaction)
+            .await?;
+        for server_id in server_ids {
+            sqlx::query("INSERT INTO project_replicas (project_id, server_id) VALUES (?1, ?2)")
+                .bind(project_id)
+                .bind(server_id)
+                .execute(&mut *transaction)
+                .await?;
+        }
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Lists connection info for the servers running a project's replicas, excluding the
+    /// ingress server, in the order they were placed.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn list_project_replica_servers(
+        &self,
+        project_id: &str,
+    ) -> Result<Vec<ServerConnectionInfo>> {
+        let rows = sqlx::query_as::<_, (String, String, String)>(
+            "SELECT s.id, s.ip_address, s.secret_key FROM project_replicas r JOIN servers s ON s.id = r.server_id WHERE r.project_id = ?1 ORDER BY r.created_at ASC, s.id ASC",
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, ip_address, secret_key)| ServerConnectionInfo {
+                id,
+                ip_address,
+                secret_key,
+            })
+            .collect())
+    }
+
+    /// Lists servers with the number of projects and replicas each one runs, least loaded
+    /// first.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub async fn list_server_loads(&self) -> Result<Vec<ServerLoadRecord>> {
+        let rows = sqlx::query_as::<_, (String, String, String, i64)>(
+            "SELECT s.id, s.name, s.status, (SELECT COUNT(*) FROM projects p WHERE p.server_id = s.id) + (SELECT COUNT(*) FROM project_replicas r WHERE r.server_id = s.id) AS workloads FROM servers s ORDER BY workloads ASC, s.name ASC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, name, status, workloads)| ServerLoadRecord {
+                id,
+                name,
+                status,
+                workloads,
+            })
+            .collect())
+    }
+}
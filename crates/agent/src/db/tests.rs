@@ -23,9 +23,24 @@ fn new_project(id: &str, server_id: &str, port: i64, domain: Option<&str>) -> Ne
         output_directory: ".next/standalone".to_string(),
         env_vars: "[]".to_string(),
         port,
+        backend_port: port + 10_000,
         domain: domain.map(ToString::to_string),
         source_provider: "manual".to_string(),
         source_repo_id: None,
+        runtime: "auto".to_string(),
+        memory_limit_mb: None,
+        cpu_quota_percent: None,
+        root_directory: String::new(),
+        watch_paths: "[]".to_string(),
+        routing: "{}".to_string(),
+        access_rules: "{}".to_string(),
+        maintenance: "{}".to_string(),
+        replicas: 1,
+        stream_ports: "[]".to_string(),
+        jobs: "[]".to_string(),
+        processes: "[]".to_string(),
+        volumes: "[]".to_string(),
+        release_command: String::new(),
     }
 }
 
@@ -120,13 +135,24 @@ async fn projects_crud_and_port_domain_checks() {
         .await
         .expect("insert server");
 
-    let next = db.next_available_project_port().await.expect("next port");
+    let servers = ["srv-1".to_string()];
+    let next = db
+        .reserve_pooled_port("p1", &servers, PortKind::Front)
+        .await
+        .expect("reserve")
+        .expect("free port");
     assert_eq!(next, DbClient::min_project_port());
 
-    let project = new_project("p1", "srv-1", next, Some("app.example.com"));
+    let project = NewProject {
+        release_command: "bunx prisma migrate deploy".to_string(),
+        ..new_project("p1", "srv-1", next, Some("app.example.com"))
+    };
     db.insert_project(&project).await.expect("insert project");
 
-    assert!(db.is_project_port_in_use(next).await.expect("port in use"));
+    assert!(!db
+        .reserve_port("p2", &servers, PortKind::Front, next)
+        .await
+        .expect("reserve"));
     assert!(db
         .is_project_domain_in_use("app.example.com")
         .await
@@ -148,8 +174,29 @@ async fn projects_crud_and_port_domain_checks() {
     assert_eq!(details.id, project.id);
     assert_eq!(details.server_id, "srv-1");
     assert_eq!(details.domain.as_deref(), Some("app.example.com"));
+    assert_eq!(details.server_name.as_deref(), Some("server-srv-1"));
+    assert_eq!(details.runtime, "auto");
+    assert_eq!(details.memory_limit_mb, None);
+    assert_eq!(details.release_command, "bunx prisma migrate deploy");
+    assert!(!details.stopped);
+
+    db.set_project_stopped(&project.id, true)
+        .await
+        .expect("stop");
+    assert!(db.list_projects().await.expect("list")[0].stopped);
+    assert!(
+        db.get_project_by_id(&project.id)
+            .await
+            .expect("get")
+            .expect("exists")
+            .stopped
+    );
 
-    let next2 = db.next_available_project_port().await.expect("next port");
+    let next2 = db
+        .reserve_pooled_port("p2", &servers, PortKind::Front)
+        .await
+        .expect("reserve")
+        .expect("free port");
     assert_eq!(next2, next + 1);
 
     db.delete_project_by_id(&project.id).await.expect("delete");
@@ -159,3 +206,457 @@ async fn projects_crud_and_port_domain_checks() {
         .expect("get")
         .is_none());
 }
+
+#[tokio::test]
+async fn deployments_are_listed_newest_first_and_removed_with_project() {
+    let db = temp_db().await;
+    db.insert_server(&new_server("srv-1", "secret"))
+        .await
+        .expect("insert server");
+    db.insert_project(&new_project("p1", "srv-1", 3100, None))
+        .await
+        .expect("insert project");
+
+    for (id, commit_sha, status) in [
+        (
+            "d1",
+            Some("0123456789abcdef0123456789abcdef01234567"),
+            "succeeded",
+        ),
+        ("d2", None, "failed"),
+    ] {
+        db.insert_deployment(&NewDeployment {
+            id: id.to_string(),
+            project_id: "p1".to_string(),
+            git_ref: "release/1.2".to_string(),
+            commit_sha: commit_sha.map(ToString::to_string),
+            status: status.to_string(),
+            log: "[]".to_string(),
+        })
+        .await
+        .expect("insert deployment");
+    }
+
+    let deployments = db
+        .list_deployments_for_project("p1")
+        .await
+        .expect("list deployments");
+    assert_eq!(deployments.len(), 2);
+    assert_eq!(deployments[0].id, "d2");
+    assert_eq!(
+        deployments[1].commit_sha.as_deref(),
+        Some("0123456789abcdef0123456789abcdef01234567")
+    );
+
+    db.delete_project_by_id("p1").await.expect("delete");
+    assert!(db
+        .list_deployments_for_project("p1")
+        .await
+        .expect("list deployments")
+        .is_empty());
+}
+
+#[tokio::test]
+async fn project_domains_track_verification_and_uniqueness() {
+    let db = temp_db().await;
+    db.insert_server(&new_server("srv-1", "secret"))
+        .await
+        .expect("insert server");
+    db.insert_project(&new_project("p1", "srv-1", 3100, Some("app.example.com")))
+        .await
+        .expect("insert project");
+
+    let new_domain = |id: &str, domain: &str| NewProjectDomain {
+        id: id.to_string(),
+        project_id: "p1".to_string(),
+        domain: domain.to_string(),
+    };
+    db.insert_project_domain(&new_domain("d1", "www.customer.com"))
+        .await
+        .expect("insert domain");
+    db.insert_project_domain(&new_domain("d2", "shop.customer.com"))
+        .await
+        .expect("insert domain");
+    assert!(db
+        .insert_project_domain(&new_domain("d3", "www.customer.com"))
+        .await
+        .is_err());
+    assert!(db
+        .is_project_domain_in_use("shop.customer.com")
+        .await
+        .expect("domain in use"));
+
+    db.set_project_domain_verification("d1", None)
+        .await
+        .expect("verify");
+    db.set_project_domain_verification("d2", Some("no matching record"))
+        .await
+        .expect("fail");
+
+    let domains = db.list_project_domains("p1").await.expect("list domains");
+    assert_eq!(domains.len(), 2);
+    assert_eq!(domains[0].status, "verified");
+    assert!(domains[0].verified_at.is_some());
+    assert_eq!(domains[1].status, "failed");
+    assert_eq!(
+        domains[1].verification_error.as_deref(),
+        Some("no matching record")
+    );
+    let verified = db
+        .list_verified_project_domains("p1")
+        .await
+        .expect("verified domains");
+    assert_eq!(verified.len(), 1);
+    assert_eq!(verified[0].domain, "www.customer.com");
+
+    assert!(db.delete_project_domain("p1", "d2").await.expect("delete"));
+    assert!(!db.delete_project_domain("p1", "d2").await.expect("delete"));
+    assert!(db
+        .get_project_domain("p1", "d2")
+        .await
+        .expect("get")
+        .is_none());
+}
+
+#[tokio::test]
+async fn project_domain_routing_keeps_a_single_primary() {
+    let db = temp_db().await;
+    db.insert_server(&new_server("srv-1", "secret"))
+        .await
+        .expect("insert server");
+    db.insert_project(&new_project("p1", "srv-1", 3100, None))
+        .await
+        .expect("insert project");
+    for (id, domain) in [("d1", "customer.com"), ("d2", "www.customer.com")] {
+        db.insert_project_domain(&NewProjectDomain {
+            id: id.to_string(),
+            project_id: "p1".to_string(),
+            domain: domain.to_string(),
+        })
+        .await
+        .expect("insert domain");
+    }
+
+    db.set_project_domain_routing("p1", "d2", None, true, true)
+        .await
+        .expect("primary d2");
+    db.set_project_domain_routing("p1", "d1", None, true, true)
+        .await
+        .expect("primary d1");
+    db.set_project_domain_routing("p1", "d2", Some(308), false, false)
+        .await
+        .expect("redirect d2");
+
+    let domains = db.list_project_domains("p1").await.expect("list domains");
+    assert!(domains[0].is_primary);
+    assert_eq!(domains[0].redirect_status, None);
+    assert!(!domains[1].is_primary);
+    assert_eq!(domains[1].redirect_status, Some(308));
+    assert!(!domains[1].redirect_preserve_path);
+}
+
+#[tokio::test]
+async fn project_access_users_upsert_and_cascade_with_project() {
+    let db = temp_db().await;
+    db.insert_server(&new_server("srv-1", "secret"))
+        .await
+        .expect("insert server");
+    db.insert_project(&new_project("p1", "srv-1", 3100, None))
+        .await
+        .expect("insert project");
+
+    db.set_project_access_rules("p1", r#"{"allow":["10.0.0.0/8"]}"#)
+        .await
+        .expect("set rules");
+    db.set_project_maintenance("p1", r#"{"enabled":true}"#)
+        .await
+        .expect("set maintenance");
+    db.set_project_stream_ports(
+        "p1",
+        r#"[{"port":1883,"protocol":"tcp","target_port":11883}]"#,
+    )
+    .await
+    .expect("set stream ports");
+    db.set_project_jobs(
+        "p1",
+        r#"[{"name":"cleanup","schedule":"0 3 * * *","command":"node scripts/cleanup.js","timeout_seconds":600}]"#,
+    )
+    .await
+    .expect("set jobs");
+    db.set_project_processes(
+        "p1",
+        r#"[{"name":"worker","command":"node worker.js","replicas":2}]"#,
+    )
+    .await
+    .expect("set processes");
+    db.set_project_volumes("p1", r#"[{"name":"db","mount_path":"./data"}]"#)
+        .await
+        .expect("set volumes");
+    db.upsert_project_access_user("p1", "preview", "$6$a$old")
+        .await
+        .expect("insert user");
+    db.upsert_project_access_user("p1", "preview", "$6$a$new")
+        .await
+        .expect("update user");
+    db.upsert_project_access_user("p1", "ci", "$6$b$hash")
+        .await
+        .expect("insert second user");
+
+    let project = db
+        .get_project_by_id("p1")
+        .await
+        .expect("get")
+        .expect("project");
+    assert_eq!(project.access_rules, r#"{"allow":["10.0.0.0/8"]}"#);
+    assert_eq!(project.maintenance, r#"{"enabled":true}"#);
+    assert_eq!(
+        project.stream_ports,
+        r#"[{"port":1883,"protocol":"tcp","target_port":11883}]"#
+    );
+    assert!(project.jobs.contains(r#""name":"cleanup""#));
+    assert!(project.processes.contains(r#""replicas":2"#));
+    assert_eq!(project.volumes, r#"[{"name":"db","mount_path":"./data"}]"#);
+    let users = db.list_project_access_users("p1").await.expect("list");
+    assert_eq!(
+        users
+            .iter()
+            .map(|user| (user.username.as_str(), user.password_hash.as_str()))
+            .collect::<Vec<_>>(),
+        vec![("ci", "$6$b$hash"), ("preview", "$6$a$new")]
+    );
+
+    assert!(db
+        .delete_project_access_user("p1", "ci")
+        .await
+        .expect("delete"));
+    assert!(!db
+        .delete_project_access_user("p1", "ci")
+        .await
+        .expect("delete again"));
+
+    db.delete_project_by_id("p1").await.expect("delete project");
+    assert!(db
+        .list_project_access_users("p1")
+        .await
+        .expect("list")
+        .is_empty());
+}
+
+#[tokio::test]
+async fn project_replicas_count_towards_server_load_and_cascade_with_project() {
+    let db = temp_db().await;
+    for id in ["srv-1", "srv-2", "srv-3"] {
+        db.insert_server(&new_server(id, "secret"))
+            .await
+            .expect("insert server");
+    }
+    let mut project = new_project("p1", "srv-1", 3100, None);
+    project.replicas = 2;
+    db.insert_project(&project).await.expect("insert project");
+    db.set_project_replicas("p1", &["srv-2".to_string()])
+        .await
+        .expect("set replicas");
+
+    let stored = db
+        .get_project_by_id("p1")
+        .await
+        .expect("get")
+        .expect("project");
+    assert_eq!(stored.replicas, 2);
+    let members = db.list_project_replica_servers("p1").await.expect("list");
+    assert_eq!(
+        members
+            .iter()
+            .map(|server| server.id.as_str())
+            .collect::<Vec<_>>(),
+        vec!["srv-2"]
+    );
+    let loads = db.list_server_loads().await.expect("loads");
+    assert_eq!(
+        loads
+            .iter()
+            .map(|server| (server.id.as_str(), server.workloads))
+            .collect::<Vec<_>>(),
+        vec![("srv-3", 0), ("srv-1", 1), ("srv-2", 1)]
+    );
+
+    db.delete_project_by_id("p1").await.expect("delete project");
+    assert!(db
+        .list_project_replica_servers("p1")
+        .await
+        .expect("list")
+        .is_empty());
+}
+
+#[tokio::test]
+async fn private_service_access_resolves_targets_and_caller_servers() {
+    let db = temp_db().await;
+    for (id, ip_address) in [
+        ("srv-1", "10.0.0.1"),
+        ("srv-2", "10.0.0.2"),
+        ("srv-3", "10.0.0.3"),
+    ] {
+        let mut server = new_server(id, "secret");
+        server.ip_address = ip_address.to_string();
+        db.insert_server(&server).await.expect("insert server");
+    }
+    db.insert_project(&new_project("api", "srv-1", 3100, None))
+        .await
+        .expect("insert api");
+    db.insert_project(&new_project("web", "srv-2", 3101, None))
+        .await
+        .expect("insert web");
+    db.set_project_replicas("web", &["srv-3".to_string()])
+        .await
+        .expect("set replicas");
+
+    db.set_project_internal_name("api", Some("billing"))
+        .await
+        .expect("set name");
+    assert!(db
+        .is_internal_name_in_use("billing", "web")
+        .await
+        .expect("in use"));
+    assert!(!db
+        .is_internal_name_in_use("billing", "api")
+        .await
+        .expect("own name"));
+    assert!(db
+        .set_project_internal_name("web", Some("billing"))
+        .await
+        .is_err());
+
+    db.set_project_service_callers("api", &["web".to_string()])
+        .await
+        .expect("set callers");
+    assert_eq!(
+        db.list_project_service_callers("api")
+            .await
+            .expect("callers"),
+        vec!["web".to_string()]
+    );
+    let targets = db
+        .list_project_service_targets("web")
+        .await
+        .expect("targets");
+    assert_eq!(
+        targets
+            .iter()
+            .map(|target| (
+                target.internal_name.as_str(),
+                target.port,
+                target.ip_address.as_str()
+            ))
+            .collect::<Vec<_>>(),
+        vec![("billing", 3100, "10.0.0.1")]
+    );
+    assert_eq!(
+        db.list_project_caller_server_ips("api")
+            .await
+            .expect("sources"),
+        vec!["10.0.0.2".to_string(), "10.0.0.3".to_string()]
+    );
+
+    db.delete_project_by_id("web").await.expect("delete caller");
+    assert!(db
+        .list_project_service_callers("api")
+        .await
+        .expect("callers")
+        .is_empty());
+}
+
+#[tokio::test]
+async fn port_allocations_are_per_server_and_reclaimed_on_delete() {
+    let db = temp_db().await;
+    for id in ["srv-1", "srv-2"] {
+        db.insert_server(&new_server(id, "secret"))
+            .await
+            .expect("insert server");
+    }
+    let reserve = |project_id: &'static str, server_ids: &'static [&'static str], kind| {
+        let db = db.clone();
+        async move {
+            let server_ids = server_ids
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            db.reserve_pooled_port(project_id, &server_ids, kind)
+                .await
+                .expect("reserve")
+                .expect("free port")
+        }
+    };
+
+    assert_eq!(reserve("p1", &["srv-1"], PortKind::Front).await, 3100);
+    assert_eq!(reserve("p1", &["srv-1"], PortKind::Backend).await, 20_000);
+    assert_eq!(reserve("p2", &["srv-2"], PortKind::Front).await, 3100);
+    assert_eq!(
+        reserve("p3", &["srv-1", "srv-2"], PortKind::Front).await,
+        3101
+    );
+    assert!(!db
+        .reserve_port("p4", &["srv-2".to_string()], PortKind::Front, 3101)
+        .await
+        .expect("reserve"));
+    assert!(db
+        .reserve_port("p4", &["srv-2".to_string()], PortKind::Front, 20_000)
+        .await
+        .expect("reserve"));
+
+    let mut project = new_project("p1", "srv-1", 3100, None);
+    project.backend_port = 20_000;
+    project.stream_ports = r#"[{"port":5000,"protocol":"tcp","target_port":15000},{"port":5000,"protocol":"udp","target_port":15000}]"#.to_string();
+    db.insert_project(&project).await.expect("insert project");
+    let held = |allocations: Vec<PortAllocationRecord>| {
+        allocations
+            .into_iter()
+            .map(|allocation| (allocation.server_id, allocation.port, allocation.kind))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        held(db.list_project_port_allocations("p1").await.expect("list")),
+        vec![
+            ("srv-1".to_string(), 3100, "front".to_string()),
+            ("srv-1".to_string(), 5000, "stream".to_string()),
+            ("srv-1".to_string(), 15_000, "stream".to_string()),
+            ("srv-1".to_string(), 20_000, "backend".to_string()),
+        ]
+    );
+    db.insert_project(&NewProject {
+        stream_ports: r#"[{"port":6000,"protocol":"tcp","target_port":5000}]"#.to_string(),
+        ..new_project("p5", "srv-1", 3102, None)
+    })
+    .await
+    .expect_err("stream port held by p1");
+
+    db.set_project_stream_ports("p1", "[]")
+        .await
+        .expect("clear streams");
+    assert_eq!(
+        db.find_port_allocation("srv-1", 20_000)
+            .await
+            .expect("find")
+            .map(|allocation| allocation.project_id),
+        Some("p1".to_string())
+    );
+    assert!(db
+        .find_port_allocation("srv-1", 5000)
+        .await
+        .expect("find")
+        .is_none());
+
+    db.delete_project_by_id("p1").await.expect("delete");
+    assert!(db
+        .list_project_port_allocations("p1")
+        .await
+        .expect("list")
+        .is_empty());
+    assert_eq!(reserve("p6", &["srv-1"], PortKind::Front).await, 3100);
+
+    db.release_project_ports("p4").await.expect("release");
+    assert!(db
+        .find_port_allocation("srv-2", 20_000)
+        .await
+        .expect("find")
+        .is_none());
+}
@@ -20,9 +20,31 @@ pub struct NewProject {
     pub output_directory: String,
     pub env_vars: String,
     pub port: i64,
+    /// Port the app listens on behind its socket proxy.
+    pub backend_port: i64,
     pub domain: Option<String>,
     pub source_provider: String,
     pub source_repo_id: Option<i64>,
+    pub runtime: String,
+    pub memory_limit_mb: Option<i64>,
+    pub cpu_quota_percent: Option<i64>,
+    pub root_directory: String,
+    pub watch_paths: String,
+    pub routing: String,
+    pub access_rules: String,
+    pub maintenance: String,
+    /// Number of servers running the project, the ingress included.
+    pub replicas: i64,
+    /// JSON array of public TCP/UDP ports forwarded to the app.
+    pub stream_ports: String,
+    /// JSON array of scheduled jobs.
+    pub jobs: String,
+    /// JSON array of process types run next to the web process.
+    pub processes: String,
+    /// JSON array of persistent volumes mounted into the app.
+    pub volumes: String,
+    /// Command run before each release goes live; empty for none.
+    pub release_command: String,
 }
 
 #[derive(Debug, Clone)]
@@ -33,6 +55,56 @@ pub struct ServerRecord {
     pub status: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct ServerLoadRecord {
+    pub id: String,
+    pub name: String,
+    pub status: String,
+    /// Projects and replicas the server already runs.
+    pub workloads: i64,
+}
+
+/// A project another project may call over the private network.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PrivateServiceRecord {
+    pub internal_name: String,
+    pub port: i64,
+    /// Address of the server running the service.
+    pub ip_address: String,
+}
+
+/// What a project holds a port on a server for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortKind {
+    /// The socket systemd starts the app from.
+    Front,
+    /// The app's own listener behind the socket proxy.
+    Backend,
+    /// A public TCP/UDP port or its loopback target.
+    Stream,
+}
+
+impl PortKind {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Front => "front",
+            Self::Backend => "backend",
+            Self::Stream => "stream",
+        }
+    }
+}
+
+/// A port held on a server.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PortAllocationRecord {
+    pub server_id: String,
+    pub port: i64,
+    pub project_id: String,
+    /// [`PortKind::as_str`] of what the port is used for.
+    pub kind: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct ServerConnectionInfo {
     pub id: String,
@@ -64,10 +136,11 @@ pub struct ProjectListRecord {
     pub domain: Option<String>,
     pub source_provider: String,
     pub source_repo_id: Option<i64>,
+    pub stopped: bool,
     pub created_at: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, sqlx::FromRow)]
 pub struct ProjectDetailsRecord {
     pub id: String,
     pub server_id: String,
@@ -80,13 +153,84 @@ pub struct ProjectDetailsRecord {
     pub output_directory: String,
     pub env_vars: String,
     pub port: i64,
+    /// `None` for projects migrated with a backend port that did not fit.
+    pub backend_port: Option<i64>,
     pub domain: Option<String>,
     pub source_provider: String,
     pub source_repo_id: Option<i64>,
+    pub runtime: String,
+    pub memory_limit_mb: Option<i64>,
+    pub cpu_quota_percent: Option<i64>,
+    pub root_directory: String,
+    pub watch_paths: String,
+    pub routing: String,
+    pub access_rules: String,
+    pub maintenance: String,
+    pub replicas: i64,
+    /// Name other projects reach the service by, as `<name>.internal`.
+    pub internal_name: Option<String>,
+    pub stream_ports: String,
+    pub jobs: String,
+    pub processes: String,
+    pub volumes: String,
+    pub release_command: String,
+    /// Deliberately stopped; redeploys keep it down until it is started.
+    pub stopped: bool,
     pub created_at: String,
     pub server_name: Option<String>,
 }
 
+#[derive(Debug, Clone)]
+pub struct NewDeployment {
+    pub id: String,
+    pub project_id: String,
+    pub git_ref: String,
+    pub commit_sha: Option<String>,
+    pub status: String,
+    pub log: String,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DeploymentRecord {
+    pub id: String,
+    pub project_id: String,
+    pub git_ref: String,
+    pub commit_sha: Option<String>,
+    pub status: String,
+    pub log: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewProjectDomain {
+    pub id: String,
+    pub project_id: String,
+    pub domain: String,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ProjectAccessUserRecord {
+    pub username: String,
+    /// SHA-512 crypt hash written to the project's htpasswd file.
+    pub password_hash: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ProjectDomainRecord {
+    pub id: String,
+    pub project_id: String,
+    pub domain: String,
+    pub status: String,
+    pub verification_error: Option<String>,
+    pub verified_at: Option<String>,
+    /// 301 or 308 when requests are redirected to the primary domain; `None` serves the project.
+    pub redirect_status: Option<i64>,
+    pub redirect_preserve_path: bool,
+    pub is_primary: bool,
+    pub created_at: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct NewGitHubUserLink {
     pub id: String,
@@ -0,0 +1,309 @@
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::net::IpAddr;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Directory holding one htpasswd file per protected project. It belongs to the agent and is
+/// group-readable by nginx's workers, which read the file on every request.
+pub const HTPASSWD_PATH: &str = "/opt/nanoscale/htpasswd";
+
+const MAX_ACCESS_RULES: usize = 64;
+const MAX_USERNAME_LENGTH: usize = 64;
+const MIN_PASSWORD_LENGTH: usize = 8;
+const MAX_PASSWORD_LENGTH: usize = 256;
+
+/// IP allow/deny lists of a project, set through the dashboard.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AccessRules {
+    /// Addresses or CIDR ranges allowed in; when non-empty everyone else is denied.
+    pub allow: Vec<String>,
+    /// Addresses or CIDR ranges refused, checked before `allow`.
+    pub deny: Vec<String>,
+    /// Leaves `/.well-known/acme-challenge/` open so certificates can still be issued.
+    pub exempt_acme_challenge: bool,
+}
+
+impl Default for AccessRules {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            deny: Vec::new(),
+            exempt_acme_challenge: true,
+        }
+    }
+}
+
+/// A basic auth credential; only the crypt(3) hash ever leaves the orchestrator.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BasicAuthUser {
+    pub username: String,
+    pub password_hash: String,
+}
+
+/// Everything nginx needs to restrict who reaches a project.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AccessControl {
+    #[serde(flatten)]
+    pub rules: AccessRules,
+    /// Basic auth users; the site asks for a password when this is non-empty.
+    pub users: Vec<BasicAuthUser>,
+}
+
+impl AccessRules {
+    /// Checks that every entry is an IP address or CIDR range nginx accepts.
+    ///
+    /// # Errors
+    /// Returns an error naming the first invalid entry, or when a list is too long.
+    pub fn validate(&self) -> Result<()> {
+        for (label, entries) in [("allow", &self.allow), ("deny", &self.deny)] {
+            if entries.len() > MAX_ACCESS_RULES {
+                bail!("access {label} list allows at most {MAX_ACCESS_RULES} entries");
+            }
+            for entry in entries {
+                validate_cidr(entry)
+                    .with_context(|| format!("invalid access {label} entry {entry:?}"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl AccessControl {
+    /// Validates the IP rules and the stored credentials before they are written out.
+    ///
+    /// # Errors
+    /// Returns an error for an invalid rule, username, or password hash.
+    pub fn validate(&self) -> Result<()> {
+        self.rules.validate()?;
+        for user in &self.users {
+            validate_username(&user.username)?;
+            if !user.password_hash.starts_with("$6$")
+                || user
+                    .password_hash
+                    .chars()
+                    .any(|character| character == ':' || character.is_whitespace())
+            {
+                bail!(
+                    "basic auth user {} has an unsupported password hash",
+                    user.username
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// nginx directives enforcing these rules inside a location, each indented for a location
+    /// block. Deny entries come first because nginx stops at the first matching rule.
+    #[must_use]
+    pub fn location_directives(&self, htpasswd_file: &Path) -> String {
+        let mut directives = self
+            .rules
+            .deny
+            .iter()
+            .map(|entry| format!("deny {entry};"))
+            .chain(
+                self.rules
+                    .allow
+                    .iter()
+                    .map(|entry| format!("allow {entry};")),
+            )
+            .collect::<Vec<_>>();
+        if !self.rules.allow.is_empty() {
+            directives.push("deny all;".to_string());
+        }
+        if !self.users.is_empty() {
+            directives.push("auth_basic \"Restricted\";".to_string());
+            directives.push(format!("auth_basic_user_file {};", htpasswd_file.display()));
+        }
+
+        directives
+            .iter()
+            .map(|directive| ["        ", directive, "\n"].concat())
+            .collect()
+    }
+}
+
+/// Checks a basic auth username: 1-64 characters of `[A-Za-z0-9._-]`.
+///
+/// # Errors
+/// Returns an error describing the allowed characters.
+pub fn validate_username(username: &str) -> Result<()> {
+    let valid = (1..=MAX_USERNAME_LENGTH).contains(&username.len())
+        && username
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "._-".contains(character));
+    if !valid {
+        bail!("username must be 1-{MAX_USERNAME_LENGTH} characters of [A-Za-z0-9._-]");
+    }
+
+    Ok(())
+}
+
+/// Hashes a basic auth password with SHA-512 crypt, which nginx verifies through crypt(3).
+///
+/// # Errors
+/// Returns an error if the password is too short or too long, or hashing fails.
+pub fn hash_password(password: &str) -> Result<String> {
+    if !(MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&password.chars().count()) {
+        bail!("password must be {MIN_PASSWORD_LENGTH}-{MAX_PASSWORD_LENGTH} characters");
+    }
+
+    pwhash::sha512_crypt::hash(password)
+        .map_err(|error| anyhow!("failed to hash password: {error}"))
+}
+
+/// Path of the htpasswd file for `project_id` under `dir`.
+#[must_use]
+pub fn htpasswd_file(dir: &Path, project_id: &str) -> PathBuf {
+    dir.join(project_id)
+}
+
+/// Writes the project's htpasswd file, or removes it when the project has no users.
+///
+/// The file is replaced atomically and is readable by the directory's group (nginx) only.
+///
+/// # Errors
+/// Returns an error if the file cannot be written or removed.
+pub fn install_htpasswd(dir: &Path, project_id: &str, users: &[BasicAuthUser]) -> Result<()> {
+    let path = htpasswd_file(dir, project_id);
+    if users.is_empty() {
+        return remove_htpasswd(dir, project_id);
+    }
+
+    let contents = users
+        .iter()
+        .map(|user| [user.username.as_str(), ":", &user.password_hash, "\n"].concat())
+        .collect::<String>();
+    let tmp_path = path.with_extension("tmp");
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o640)
+        .open(&tmp_path)
+        .with_context(|| format!("failed to open {}", tmp_path.display()))?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, &path).with_context(|| format!("failed to install {}", path.display()))
+}
+
+/// Removes the project's htpasswd file if there is one.
+///
+/// # Errors
+/// Returns an error if the file exists but cannot be removed.
+pub fn remove_htpasswd(dir: &Path, project_id: &str) -> Result<()> {
+    let path = htpasswd_file(dir, project_id);
+    match fs::remove_file(&path) {
+        Err(error) if error.kind() != ErrorKind::NotFound => {
+            Err(error).with_context(|| format!("failed to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_cidr(entry: &str) -> Result<()> {
+    let (address, prefix) = entry
+        .split_once('/')
+        .map_or((entry, None), |(address, prefix)| (address, Some(prefix)));
+    let address = address
+        .parse::<IpAddr>()
+        .map_err(|_| anyhow!("expected an IP address or CIDR range"))?;
+
+    if let Some(prefix) = prefix {
+        let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+        prefix
+            .parse::<u8>()
+            .ok()
+            .filter(|prefix| *prefix <= max_prefix)
+            .ok_or_else(|| anyhow!("prefix length must be between 0 and {max_prefix}"))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    #[test]
+    fn rules_accept_addresses_and_cidrs_only() {
+        let rules = |allow: &[&str]| AccessRules {
+            allow: allow.iter().map(ToString::to_string).collect(),
+            ..AccessRules::default()
+        };
+        rules(&["203.0.113.7", "10.0.0.0/8", "2001:db8::/32"])
+            .validate()
+            .expect("valid rules");
+        assert!(rules(&["all"]).validate().is_err());
+        assert!(rules(&["10.0.0.0/33"]).validate().is_err());
+        assert!(rules(&["10.0.0.1; return 200"]).validate().is_err());
+    }
+
+    #[test]
+    fn location_directives_deny_first_and_close_allow_lists() {
+        let access = AccessControl {
+            rules: AccessRules {
+                allow: vec!["10.0.0.0/8".to_string()],
+                deny: vec!["10.0.0.5".to_string()],
+                exempt_acme_challenge: true,
+            },
+            users: vec![BasicAuthUser {
+                username: "preview".to_string(),
+                password_hash: "$6$salt$hash".to_string(),
+            }],
+        };
+        access.validate().expect("valid access");
+
+        assert_eq!(
+            access.location_directives(Path::new("/opt/nanoscale/htpasswd/p1")),
+            "        deny 10.0.0.5;\n        allow 10.0.0.0/8;\n        deny all;\n        auth_basic \"Restricted\";\n        auth_basic_user_file /opt/nanoscale/htpasswd/p1;\n"
+        );
+        assert_eq!(
+            AccessControl::default().location_directives(Path::new("/unused")),
+            ""
+        );
+    }
+
+    #[test]
+    fn hashed_password_verifies_and_is_rejected_when_short() {
+        let hash = hash_password("correct horse").expect("hash");
+        assert!(hash.starts_with("$6$"));
+        assert!(pwhash::sha512_crypt::verify("correct horse", &hash));
+        assert!(hash_password("short").is_err());
+        assert!(validate_username("ci:bot").is_err());
+    }
+
+    #[test]
+    fn install_htpasswd_writes_group_readable_file_and_removes_it() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let users = [BasicAuthUser {
+            username: "preview".to_string(),
+            password_hash: "$6$salt$hash".to_string(),
+        }];
+
+        install_htpasswd(dir.path(), "p1", &users).expect("install");
+        let path = htpasswd_file(dir.path(), "p1");
+        assert_eq!(
+            fs::read_to_string(&path).expect("read"),
+            "preview:$6$salt$hash\n"
+        );
+        assert_eq!(
+            fs::metadata(&path).expect("metadata").permissions().mode() & 0o777,
+            0o640
+        );
+
+        install_htpasswd(dir.path(), "p1", &[]).expect("remove");
+        assert!(!path.exists());
+        remove_htpasswd(dir.path(), "p1").expect("already removed");
+    }
+}
@@ -0,0 +1,123 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::deployment::dns_provider::DnsProvider;
+use crate::deployment::tls::ACME_WEBROOT_PATH;
+
+mod account;
+mod challenges;
+mod client;
+
+use account::AccountKey;
+use client::AcmeClient;
+
+pub const LETS_ENCRYPT_DIRECTORY_URL: &str = "https://acme-v02.api.letsencrypt.org/directory";
+const ACCOUNT_KEY_PATH: &str = "/opt/nanoscale/data/acme/account.pem";
+const CHALLENGE_DIRECTORY: &str = ".well-known/acme-challenge";
+const POLL_ATTEMPTS: usize = 60;
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Where certificates come from and where this host keeps its ACME state.
+#[derive(Clone, Debug)]
+pub struct AcmeSettings {
+    pub directory_url: String,
+    /// Extra PEM root trusted for the ACME server itself, e.g. Pebble's test CA.
+    pub ca_bundle: Option<PathBuf>,
+    /// PKCS#8 PEM account key, created on first use and reused afterwards.
+    pub account_key_path: PathBuf,
+    /// Directory nginx serves under `/.well-known/acme-challenge/`.
+    pub webroot: PathBuf,
+    /// Publishes DNS-01 records; wildcard certificates cannot be issued without one.
+    pub dns_provider: Option<Arc<dyn DnsProvider>>,
+}
+
+impl Default for AcmeSettings {
+    fn default() -> Self {
+        Self {
+            directory_url: LETS_ENCRYPT_DIRECTORY_URL.to_string(),
+            ca_bundle: None,
+            account_key_path: PathBuf::from(ACCOUNT_KEY_PATH),
+            webroot: PathBuf::from(ACME_WEBROOT_PATH),
+            dns_provider: None,
+        }
+    }
+}
+
+/// A freshly issued certificate chain and its private key, both PEM.
+#[derive(Debug)]
+pub struct IssuedCertificate {
+    pub chain_pem: String,
+    pub key_pem: String,
+}
+
+/// Runs an ACME v2 (RFC 8555) order for `domains`.
+///
+/// Orders containing a wildcard (`*.example.com`) are validated with DNS-01 through the
+/// configured [`DnsProvider`]; every other order uses HTTP-01 via the webroot. The account is registered on first use (or looked up when the key is already known to the
+/// CA), so renewals can pass `email: None`.
+///
+/// # Errors
+/// Returns an error if the account key cannot be loaded or created, the CA rejects a request,
+/// a wildcard is requested without a DNS provider, a challenge fails validation, or polling
+/// times out.
+pub async fn issue_certificate(
+    settings: &AcmeSettings,
+    domains: &[String],
+    email: Option<&str>,
+) -> Result<IssuedCertificate> {
+    if domains.is_empty() {
+        bail!("an ACME order needs at least one domain");
+    }
+
+    let key = AccountKey::load_or_create(&settings.account_key_path)?;
+    let mut client = AcmeClient::connect(settings, key).await?;
+    client.register(email).await?;
+    client.order(settings, domains).await
+}
+
+fn pem_encode(label: &str, der: &[u8]) -> String {
+    let encoded = STANDARD.encode(der);
+    let body = encoded
+        .as_bytes()
+        .chunks(64)
+        .map(String::from_utf8_lossy)
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("-----BEGIN {label}-----\n{body}\n-----END {label}-----\n")
+}
+
+/// Writes a file readable only by the agent user, creating parent directories.
+///
+/// # Errors
+/// Returns an error if the directory or file cannot be written.
+pub fn write_private_file(path: &Path, contents: &str) -> Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+
+    let tmp_path = path.with_extension("tmp");
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&tmp_path)
+        .with_context(|| format!("failed to open {}", tmp_path.display()))?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path).with_context(|| format!("failed to install {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,135 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use serde_json::{json, Value};
+use x509_parser::pem::Pem;
+
+use super::{pem_encode, write_private_file};
+
+pub(super) struct AccountKey {
+    key_pair: EcdsaKeyPair,
+    rng: SystemRandom,
+}
+
+impl AccountKey {
+    pub(super) fn load_or_create(path: &Path) -> Result<Self> {
+        let rng = SystemRandom::new();
+
+        let pkcs8 = if path.is_file() {
+            let raw = fs::read(path)
+                .with_context(|| format!("failed to read ACME account key {}", path.display()))?;
+            Pem::iter_from_buffer(&raw)
+                .next()
+                .ok_or_else(|| anyhow!("ACME account key {} is empty", path.display()))?
+                .map_err(|error| anyhow!("invalid ACME account key PEM: {error}"))?
+                .contents
+        } else {
+            let document = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+                .map_err(|_| anyhow!("failed to generate ACME account key"))?;
+            write_private_file(path, &pem_encode("PRIVATE KEY", document.as_ref()))?;
+            document.as_ref().to_vec()
+        };
+
+        let key_pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &pkcs8, &rng)
+            .map_err(|error| anyhow!("invalid ACME account key: {error}"))?;
+        Ok(Self { key_pair, rng })
+    }
+
+    /// Public key as a JWK with members in the lexicographic order RFC 7638 hashes.
+    fn jwk(&self) -> String {
+        // Uncompressed SEC1 point: 0x04 || x (32 bytes) || y (32 bytes).
+        let point = self.key_pair.public_key().as_ref();
+        let x = URL_SAFE_NO_PAD.encode(&point[1..33]);
+        let y = URL_SAFE_NO_PAD.encode(&point[33..65]);
+        format!(r#"{{"crv":"P-256","kty":"EC","x":"{x}","y":"{y}"}}"#)
+    }
+
+    pub(super) fn thumbprint(&self) -> String {
+        let digest = ring::digest::digest(&ring::digest::SHA256, self.jwk().as_bytes());
+        URL_SAFE_NO_PAD.encode(digest.as_ref())
+    }
+
+    /// Builds a flattened JWS. Without `kid` the public key is embedded, as `newAccount` needs.
+    pub(super) fn sign(
+        &self,
+        url: &str,
+        nonce: &str,
+        kid: Option<&str>,
+        payload: &str,
+    ) -> Result<Value> {
+        let mut protected = json!({ "alg": "ES256", "nonce": nonce, "url": url });
+        match kid {
+            Some(kid) => protected["kid"] = json!(kid),
+            None => protected["jwk"] = serde_json::from_str(&self.jwk())?,
+        }
+
+        let protected = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&protected)?);
+        let payload = URL_SAFE_NO_PAD.encode(payload);
+        let signature = self
+            .key_pair
+            .sign(&self.rng, format!("{protected}.{payload}").as_bytes())
+            .map_err(|_| anyhow!("failed to sign ACME request"))?;
+
+        Ok(json!({
+            "protected": protected,
+            "payload": payload,
+            "signature": URL_SAFE_NO_PAD.encode(signature.as_ref()),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_FIXED};
+
+    use super::*;
+
+    #[test]
+    fn account_key_is_persisted_and_signs_verifiable_requests() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("acme/account.pem");
+        let key = AccountKey::load_or_create(&path).expect("create key");
+        let reloaded = AccountKey::load_or_create(&path).expect("reload key");
+        assert_eq!(key.thumbprint(), reloaded.thumbprint());
+        assert_eq!(
+            fs::metadata(&path).expect("metadata").permissions().mode() & 0o777,
+            0o600
+        );
+
+        let jws = key
+            .sign(
+                "https://ca.test/new-order",
+                "nonce-1",
+                Some("https://ca.test/acct/1"),
+                "{}",
+            )
+            .expect("sign");
+        let protected = URL_SAFE_NO_PAD
+            .decode(jws["protected"].as_str().expect("protected"))
+            .expect("base64");
+        let protected = serde_json::from_slice::<Value>(&protected).expect("json");
+        assert_eq!(protected["alg"], "ES256");
+        assert_eq!(protected["nonce"], "nonce-1");
+        assert_eq!(protected["kid"], "https://ca.test/acct/1");
+        assert!(protected.get("jwk").is_none());
+
+        let signing_input = format!(
+            "{}.{}",
+            jws["protected"].as_str().expect("protected"),
+            jws["payload"].as_str().expect("payload")
+        );
+        let signature = URL_SAFE_NO_PAD
+            .decode(jws["signature"].as_str().expect("signature"))
+            .expect("base64");
+        UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, key.key_pair.public_key().as_ref())
+            .verify(signing_input.as_bytes(), &signature)
+            .expect("signature verifies");
+    }
+}
@@ -0,0 +1,160 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::client::AcmeClient;
+use super::{CHALLENGE_DIRECTORY, POLL_ATTEMPTS, POLL_INTERVAL};
+use crate::deployment::dns_provider::DnsProvider;
+
+#[derive(Debug, Deserialize)]
+struct Authorization {
+    status: String,
+    identifier: Identifier,
+    challenges: Vec<Challenge>,
+}
+
+impl Authorization {
+    fn challenge(&self, kind: &str) -> Result<&Challenge> {
+        self.challenges
+            .iter()
+            .find(|challenge| challenge.kind == kind)
+            .ok_or_else(|| anyhow!("no {kind} challenge offered for {}", self.identifier.value))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Identifier {
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Challenge {
+    #[serde(rename = "type")]
+    kind: String,
+    url: String,
+    token: String,
+    error: Option<Value>,
+}
+
+impl AcmeClient {
+    pub(super) async fn complete_authorizations(
+        &mut self,
+        webroot: &Path,
+        authorization_urls: &[String],
+        token_paths: &mut Vec<PathBuf>,
+    ) -> Result<()> {
+        let challenge_dir = webroot.join(CHALLENGE_DIRECTORY);
+        fs::create_dir_all(&challenge_dir)
+            .with_context(|| format!("failed to create {}", challenge_dir.display()))?;
+
+        for authorization_url in authorization_urls {
+            let authorization = self
+                .post(authorization_url, "")
+                .await?
+                .json::<Authorization>()
+                .await?;
+            if authorization.status == "valid" {
+                continue;
+            }
+
+            let challenge = authorization.challenge("http-01")?;
+            let key_authorization = self.key_authorization(&challenge.token)?;
+
+            let token_path = challenge_dir.join(&challenge.token);
+            fs::write(&token_path, key_authorization)
+                .with_context(|| format!("failed to write {}", token_path.display()))?;
+            token_paths.push(token_path);
+
+            self.post(&challenge.url, "{}").await?;
+            self.poll_authorization(authorization_url).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Publishes every DNS-01 record first and waits for propagation once, so a wildcard and
+    /// its apex sharing `_acme-challenge.<domain>` are both visible before validation starts.
+    pub(super) async fn complete_dns_authorizations(
+        &mut self,
+        dns_provider: &dyn DnsProvider,
+        authorization_urls: &[String],
+        records: &mut Vec<(String, String)>,
+    ) -> Result<()> {
+        let mut pending = Vec::new();
+        for authorization_url in authorization_urls {
+            let authorization = self
+                .post(authorization_url, "")
+                .await?
+                .json::<Authorization>()
+                .await?;
+            if authorization.status == "valid" {
+                continue;
+            }
+
+            let challenge = authorization.challenge("dns-01")?;
+            let key_authorization = self.key_authorization(&challenge.token)?;
+            let digest = ring::digest::digest(&ring::digest::SHA256, key_authorization.as_bytes());
+            let name = format!(
+                "_acme-challenge.{}",
+                authorization.identifier.value.trim_start_matches("*.")
+            );
+            let value = URL_SAFE_NO_PAD.encode(digest.as_ref());
+
+            dns_provider.present(&name, &value).await?;
+            records.push((name, value));
+            pending.push((authorization_url, challenge.url.clone()));
+        }
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+        tokio::time::sleep(dns_provider.propagation_delay()).await;
+
+        for (authorization_url, challenge_url) in pending {
+            self.post(&challenge_url, "{}").await?;
+            self.poll_authorization(authorization_url).await?;
+        }
+
+        Ok(())
+    }
+
+    fn key_authorization(&self, token: &str) -> Result<String> {
+        if token.is_empty()
+            || !token
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric() || "-_".contains(character))
+        {
+            bail!("ACME challenge token is not base64url: {token:?}");
+        }
+        Ok(format!("{token}.{}", self.key.thumbprint()))
+    }
+
+    async fn poll_authorization(&mut self, url: &str) -> Result<()> {
+        for _ in 0..POLL_ATTEMPTS {
+            let authorization = self.post(url, "").await?.json::<Authorization>().await?;
+            match authorization.status.as_str() {
+                "valid" => return Ok(()),
+                "pending" | "processing" => tokio::time::sleep(POLL_INTERVAL).await,
+                status => {
+                    let reason = authorization
+                        .challenges
+                        .iter()
+                        .find_map(|challenge| challenge.error.as_ref())
+                        .map(ToString::to_string)
+                        .unwrap_or_default();
+                    bail!(
+                        "ACME authorization for {} is {status}: {reason}",
+                        authorization.identifier.value
+                    );
+                }
+            }
+        }
+
+        bail!("timed out waiting for ACME authorization {url}")
+    }
+}
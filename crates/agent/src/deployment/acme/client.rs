@@ -0,0 +1,231 @@
+use std::fs;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rcgen::{CertificateParams, DistinguishedName, KeyPair as CertificateKeyPair};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::account::AccountKey;
+use super::{AcmeSettings, IssuedCertificate, POLL_ATTEMPTS, POLL_INTERVAL};
+
+/// Endpoint URLs advertised by the ACME directory resource.
+#[derive(Debug, Deserialize)]
+struct Directory {
+    #[serde(rename = "newNonce")]
+    nonce: String,
+    #[serde(rename = "newAccount")]
+    account: String,
+    #[serde(rename = "newOrder")]
+    order: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Order {
+    status: String,
+    #[serde(default)]
+    authorizations: Vec<String>,
+    finalize: String,
+    certificate: Option<String>,
+    error: Option<Value>,
+}
+
+pub(super) struct AcmeClient {
+    http: reqwest::Client,
+    directory: Directory,
+    pub(super) key: AccountKey,
+    account_url: Option<String>,
+    nonce: Option<String>,
+}
+
+impl AcmeClient {
+    pub(super) async fn connect(settings: &AcmeSettings, key: AccountKey) -> Result<Self> {
+        let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(30));
+        if let Some(ca_bundle) = &settings.ca_bundle {
+            let pem = fs::read(ca_bundle).with_context(|| {
+                format!("failed to read ACME CA bundle {}", ca_bundle.display())
+            })?;
+            builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+        }
+        let http = builder.build()?;
+
+        let directory = http
+            .get(&settings.directory_url)
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("ACME directory {} unavailable", settings.directory_url))?
+            .json::<Directory>()
+            .await?;
+
+        Ok(Self {
+            http,
+            directory,
+            key,
+            account_url: None,
+            nonce: None,
+        })
+    }
+
+    pub(super) async fn register(&mut self, email: Option<&str>) -> Result<()> {
+        let mut payload = json!({ "termsOfServiceAgreed": true });
+        if let Some(email) = email {
+            payload["contact"] = json!([format!("mailto:{email}")]);
+        }
+
+        let url = self.directory.account.clone();
+        let response = self.post(&url, &payload.to_string()).await?;
+        self.account_url = Some(location(&response)?);
+        Ok(())
+    }
+
+    pub(super) async fn order(
+        &mut self,
+        settings: &AcmeSettings,
+        domains: &[String],
+    ) -> Result<IssuedCertificate> {
+        let dns_provider = if domains.iter().any(|domain| domain.starts_with("*.")) {
+            Some(settings.dns_provider.as_deref().ok_or_else(|| {
+                anyhow!("wildcard certificates need a DNS provider (dns_provider in config.json)")
+            })?)
+        } else {
+            None
+        };
+
+        let identifiers = domains
+            .iter()
+            .map(|domain| json!({ "type": "dns", "value": domain }))
+            .collect::<Vec<_>>();
+        let url = self.directory.order.clone();
+        let response = self
+            .post(&url, &json!({ "identifiers": identifiers }).to_string())
+            .await?;
+        let order_url = location(&response)?;
+        let order = response.json::<Order>().await?;
+
+        if let Some(dns_provider) = dns_provider {
+            let mut records = Vec::new();
+            let validation = self
+                .complete_dns_authorizations(dns_provider, &order.authorizations, &mut records)
+                .await;
+            for (name, value) in records {
+                if let Err(error) = dns_provider.cleanup(&name, &value).await {
+                    eprintln!("failed to remove ACME TXT record {name}: {error:#}");
+                }
+            }
+            validation?;
+        } else {
+            let mut token_paths = Vec::new();
+            let validation = self
+                .complete_authorizations(&settings.webroot, &order.authorizations, &mut token_paths)
+                .await;
+            for path in token_paths {
+                let _ = fs::remove_file(path);
+            }
+            validation?;
+        }
+
+        let order = self.poll_order(&order_url, "ready").await?;
+        let certificate_key = CertificateKeyPair::generate()?;
+        let mut params = CertificateParams::new(domains.to_vec())?;
+        params.distinguished_name = DistinguishedName::new();
+        let csr = params.serialize_request(&certificate_key)?;
+        let finalize = json!({ "csr": URL_SAFE_NO_PAD.encode(csr.der()) });
+        self.post(&order.finalize, &finalize.to_string()).await?;
+
+        let order = self.poll_order(&order_url, "valid").await?;
+        let certificate_url = order
+            .certificate
+            .ok_or_else(|| anyhow!("ACME order is valid but has no certificate URL"))?;
+        let chain_pem = self.post(&certificate_url, "").await?.text().await?;
+
+        Ok(IssuedCertificate {
+            chain_pem,
+            key_pem: certificate_key.serialize_pem(),
+        })
+    }
+
+    async fn poll_order(&mut self, url: &str, wanted: &str) -> Result<Order> {
+        for _ in 0..POLL_ATTEMPTS {
+            let order = self.post(url, "").await?.json::<Order>().await?;
+            if order.status == wanted {
+                return Ok(order);
+            }
+            if order.status == "invalid" {
+                let reason = order
+                    .error
+                    .map(|error| error.to_string())
+                    .unwrap_or_default();
+                bail!("ACME order became invalid: {reason}");
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+
+        bail!("timed out waiting for ACME order {url} to become {wanted}")
+    }
+
+    /// Sends a signed request; an empty `payload` is a POST-as-GET. A `badNonce` rejection is
+    /// retried once with the fresh nonce the CA returned.
+    pub(super) async fn post(&mut self, url: &str, payload: &str) -> Result<reqwest::Response> {
+        let mut retried = false;
+        loop {
+            let nonce = match self.nonce.take() {
+                Some(nonce) => nonce,
+                None => self.fresh_nonce().await?,
+            };
+            let body = self
+                .key
+                .sign(url, &nonce, self.account_url.as_deref(), payload)?;
+
+            let response = self
+                .http
+                .post(url)
+                .header("content-type", "application/jose+json")
+                .body(body.to_string())
+                .send()
+                .await?;
+            self.nonce = replay_nonce(&response);
+
+            if response.status().is_success() {
+                return Ok(response);
+            }
+
+            let status = response.status();
+            let problem = response.text().await.unwrap_or_default();
+            if !retried && problem.contains("urn:ietf:params:acme:error:badNonce") {
+                retried = true;
+                continue;
+            }
+            bail!("ACME request to {url} failed with {status}: {problem}");
+        }
+    }
+
+    async fn fresh_nonce(&self) -> Result<String> {
+        let response = self
+            .http
+            .head(&self.directory.nonce)
+            .send()
+            .await?
+            .error_for_status()?;
+        replay_nonce(&response).ok_or_else(|| anyhow!("ACME server returned no Replay-Nonce"))
+    }
+}
+
+fn replay_nonce(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get("replay-nonce")
+        .and_then(|value| value.to_str().ok())
+        .map(ToString::to_string)
+}
+
+pub(super) fn location(response: &reqwest::Response) -> Result<String> {
+    response
+        .headers()
+        .get("location")
+        .and_then(|value| value.to_str().ok())
+        .map(ToString::to_string)
+        .ok_or_else(|| anyhow!("ACME response to {} has no Location", response.url()))
+}
@@ -4,11 +4,13 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
 use sysinfo::System;
 
 use crate::system::PrivilegeWrapper;
 
 mod bun;
+mod podman;
 
 const MIN_RAM_BYTES: u64 = 2 * 1024 * 1024 * 1024;
 const SWAP_FILE_PATH: &str = "/opt/nanoscale/tmp/nanoscale.swap";
@@ -23,12 +25,57 @@ pub struct BuildSettings {
     pub build_command: String,
     pub output_directory: String,
     pub install_command: String,
+    pub runtime: ProjectRuntime,
+}
+
+/// Runtime requested for a project. `Auto` keeps the Node/Bun artifact detection; `Container`
+/// builds the repository's Dockerfile with podman and ignores install/build commands.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectRuntime {
+    #[default]
+    Auto,
+    Container,
+}
+
+impl ProjectRuntime {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Container => "container",
+        }
+    }
+
+    /// Parses a runtime name as stored in the database.
+    ///
+    /// # Errors
+    /// Returns an error if `value` is not a known runtime name.
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim() {
+            "" | "auto" => Ok(Self::Auto),
+            "container" => Ok(Self::Container),
+            other => bail!("unknown project runtime: {other}"),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub enum AppRuntime {
     StandaloneNode,
-    BunStart { bun_binary: String },
+    BunStart {
+        bun_binary: String,
+    },
+    Container {
+        podman_binary: String,
+        image: String,
+        image_archive: PathBuf,
+        storage_dir: PathBuf,
+    },
+    /// Plain HTML output without a server of its own.
+    StaticSite,
+    /// Any other toolchain; the project's run command is the whole story.
+    Command,
 }
 
 #[derive(Debug)]
@@ -37,46 +84,119 @@ pub struct BuildOutput {
     pub runtime: AppRuntime,
 }
 
+/// A built release not yet installed: the output directory in the checkout, or for a
+/// container the image archive staged beside it. A release command runs from here while the
+/// previous release keeps serving.
+#[derive(Debug)]
+pub struct BuildArtifacts {
+    pub dir: PathBuf,
+    pub runtime: AppRuntime,
+}
+
 impl BuildSystem {
-    /// Executes the build pipeline and installs build artifacts into the project sites directory.
+    /// Runs the install and build commands, or the container build, in the checkout and makes
+    /// sure the project's system user exists. Nothing live is touched.
     ///
     /// # Errors
-    /// Returns an error if swap provisioning fails, build commands fail, build artifacts cannot be
-    /// copied into place, permissions/ownership cannot be applied, or runtime detection fails.
-    pub fn execute(
+    /// Returns an error if swap provisioning fails, build commands fail, the output directory is
+    /// missing, runtime detection fails, or the project user cannot be created.
+    pub fn build(
         project_id: &str,
         repo_dir: &Path,
         settings: &BuildSettings,
         privilege_wrapper: &PrivilegeWrapper,
-    ) -> Result<BuildOutput> {
+    ) -> Result<BuildArtifacts> {
         Self::ensure_swap_if_low_ram(privilege_wrapper)
             .map_err(|error| anyhow::anyhow!("swap provisioning failed: {error:#}"))?;
-        Self::run_command(repo_dir, &settings.install_command, "dependency install")
-            .map_err(|error| anyhow::anyhow!("dependency install failed: {error:#}"))?;
-        Self::run_command(repo_dir, &settings.build_command, "application build")
-            .map_err(|error| anyhow::anyhow!("application build failed: {error:#}"))?;
+        Self::ensure_project_system_user(project_id, privilege_wrapper)
+            .map_err(|error| anyhow::anyhow!("project user setup failed: {error:#}"))?;
 
-        let destination_dir = PathBuf::from(format!("{SOURCE_BASE_PATH}/{project_id}/source"));
-        let artifact_source_dir =
-            Self::resolve_output_directory(repo_dir, &settings.output_directory)?;
-
-        Self::replace_directory(&artifact_source_dir, &destination_dir, privilege_wrapper)
-            .map_err(|error| anyhow::anyhow!("artifact copy failed: {error:#}"))?;
+        if settings.runtime == ProjectRuntime::Container {
+            return Self::build_container(project_id, repo_dir, privilege_wrapper);
+        }
 
-        Self::ensure_sites_directory_traversable().map_err(|error| {
-            anyhow::anyhow!("sites directory permission setup failed: {error:#}")
-        })?;
+        // Blank steps are legitimate for detected projects that need no install or build.
+        if !settings.install_command.trim().is_empty() {
+            Self::run_command(repo_dir, &settings.install_command, "dependency install")
+                .map_err(|error| anyhow::anyhow!("dependency install failed: {error:#}"))?;
+        }
+        if !settings.build_command.trim().is_empty() {
+            Self::run_command(repo_dir, &settings.build_command, "application build")
+                .map_err(|error| anyhow::anyhow!("application build failed: {error:#}"))?;
+        }
 
-        let runtime = if destination_dir.join("server.js").is_file()
-            || destination_dir.join(".next/standalone/server.js").is_file()
+        let artifact_dir = Self::resolve_output_directory(repo_dir, &settings.output_directory)?;
+        let runtime = if artifact_dir.join("server.js").is_file()
+            || artifact_dir.join(".next/standalone/server.js").is_file()
         {
             AppRuntime::StandaloneNode
-        } else {
+        } else if artifact_dir.join("package.json").is_file() {
             let bun_binary = bun::bun_binary()
                 .map_err(|error| anyhow::anyhow!("bun runtime resolution failed: {error:#}"))?;
             AppRuntime::BunStart { bun_binary }
+        } else if artifact_dir.join("index.html").is_file() {
+            // Everything in the directory is served, so the checkout itself is only published
+            // when asked for by name.
+            if settings.output_directory.trim().is_empty() {
+                bail!(
+                    "static site needs an output directory; set it to \".\" to publish the whole repository"
+                );
+            }
+            AppRuntime::StaticSite
+        } else {
+            AppRuntime::Command
+        };
+
+        Ok(BuildArtifacts {
+            dir: artifact_dir,
+            runtime,
+        })
+    }
+
+    /// Installs built artifacts into the project sites directory, replacing the previous
+    /// release.
+    ///
+    /// # Errors
+    /// Returns an error if build artifacts cannot be copied into place or permissions/ownership
+    /// cannot be applied.
+    pub fn install(
+        project_id: &str,
+        artifacts: BuildArtifacts,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<BuildOutput> {
+        let destination_dir = PathBuf::from(format!("{SOURCE_BASE_PATH}/{project_id}/source"));
+
+        let runtime = match artifacts.runtime {
+            AppRuntime::Container {
+                podman_binary,
+                image,
+                image_archive,
+                storage_dir,
+            } => {
+                Self::remove_directory(&destination_dir, privilege_wrapper)
+                    .map_err(|error| anyhow::anyhow!("artifact cleanup failed: {error:#}"))?;
+                fs::create_dir_all(&destination_dir)?;
+                let installed_archive = destination_dir.join("image.tar");
+                fs::rename(&image_archive, &installed_archive)
+                    .or_else(|_| fs::copy(&image_archive, &installed_archive).map(|_| ()))
+                    .map_err(|error| anyhow::anyhow!("image archive copy failed: {error}"))?;
+                AppRuntime::Container {
+                    podman_binary,
+                    image,
+                    image_archive: installed_archive,
+                    storage_dir,
+                }
+            }
+            runtime => {
+                Self::replace_directory(&artifacts.dir, &destination_dir, privilege_wrapper)
+                    .map_err(|error| anyhow::anyhow!("artifact copy failed: {error:#}"))?;
+                runtime
+            }
         };
 
+        Self::ensure_sites_directory_traversable().map_err(|error| {
+            anyhow::anyhow!("sites directory permission setup failed: {error:#}")
+        })?;
         Self::apply_project_ownership(project_id, &destination_dir, privilege_wrapper)
             .map_err(|error| anyhow::anyhow!("artifact ownership setup failed: {error:#}"))?;
 
@@ -86,6 +206,68 @@ impl BuildSystem {
         })
     }
 
+    /// Removes the project's image builds left in the agent user's storage. Hosts without
+    /// podman have none.
+    ///
+    /// # Errors
+    /// Returns an error if podman cannot list or remove the images.
+    pub fn remove_images(project_id: &str) -> Result<()> {
+        let Ok(podman_binary) = podman::podman_binary() else {
+            return Ok(());
+        };
+        podman::remove_project_images(&podman_binary, project_id)
+    }
+
+    /// Builds the image into an archive staged next to the checkout, loaded by the release
+    /// command and moved into the sites directory on install.
+    fn build_container(
+        project_id: &str,
+        repo_dir: &Path,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<BuildArtifacts> {
+        let podman_binary = podman::podman_binary()
+            .map_err(|error| anyhow::anyhow!("podman runtime resolution failed: {error:#}"))?;
+        let staging_dir = repo_dir
+            .parent()
+            .map_or_else(|| repo_dir.join("image"), |parent| parent.join("image"));
+        let storage_dir = PathBuf::from(format!("{SOURCE_BASE_PATH}/{project_id}/container"));
+        let image_archive = staging_dir.join("image.tar");
+
+        if staging_dir.exists() {
+            fs::remove_dir_all(&staging_dir)?;
+        }
+        fs::create_dir_all(&staging_dir)?;
+
+        let image =
+            podman::build_image_archive(&podman_binary, project_id, repo_dir, &image_archive)
+                .map_err(|error| anyhow::anyhow!("container build failed: {error:#}"))?;
+
+        Self::ensure_sites_directory_traversable().map_err(|error| {
+            anyhow::anyhow!("sites directory permission setup failed: {error:#}")
+        })?;
+        Self::ensure_project_subids(project_id, privilege_wrapper).map_err(|error| {
+            anyhow::anyhow!("subordinate id setup for rootless podman failed: {error:#}")
+        })?;
+        // Only a new, empty storage is handed over: the image layers in it belong to the
+        // user's subordinate ids, which a recursive chown would flatten.
+        if !storage_dir.exists() {
+            fs::create_dir_all(&storage_dir)?;
+            Self::apply_project_ownership(project_id, &storage_dir, privilege_wrapper).map_err(
+                |error| anyhow::anyhow!("container storage ownership setup failed: {error:#}"),
+            )?;
+        }
+
+        Ok(BuildArtifacts {
+            dir: staging_dir,
+            runtime: AppRuntime::Container {
+                podman_binary,
+                image,
+                image_archive,
+                storage_dir,
+            },
+        })
+    }
+
     fn ensure_swap_if_low_ram(privilege_wrapper: &PrivilegeWrapper) -> Result<()> {
         let mut system = System::new_all();
         system.refresh_memory();
@@ -127,6 +309,9 @@ impl BuildSystem {
 
         let executable = if program == "bun" {
             bun::bun_binary()?
+        } else if program.contains('/') && !program.starts_with('/') {
+            // Relative programs (e.g. `.venv/bin/pip`) refer to the checkout, not our cwd.
+            repo_dir.join(&program).to_string_lossy().into_owned()
         } else {
             program
         };
@@ -201,18 +386,7 @@ impl BuildSystem {
         destination_dir: &Path,
         privilege_wrapper: &PrivilegeWrapper,
     ) -> Result<()> {
-        if destination_dir.exists() {
-            match fs::remove_dir_all(destination_dir) {
-                Ok(()) => {}
-                Err(error) if error.kind() == std::io::ErrorKind::PermissionDenied => {
-                    let destination = destination_dir
-                        .to_str()
-                        .ok_or_else(|| anyhow::anyhow!("invalid destination path"))?;
-                    privilege_wrapper.run("/usr/bin/rm", &["-rf", destination])?;
-                }
-                Err(error) => return Err(error.into()),
-            }
-        }
+        Self::remove_directory(destination_dir, privilege_wrapper)?;
 
         if let Some(parent_dir) = destination_dir.parent() {
             fs::create_dir_all(parent_dir)?;
@@ -222,11 +396,37 @@ impl BuildSystem {
         Ok(())
     }
 
+    /// Removes a directory, through the privilege wrapper when it belongs to the project user.
+    pub(crate) fn remove_directory(
+        destination_dir: &Path,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        if !destination_dir.exists() {
+            return Ok(());
+        }
+
+        match fs::remove_dir_all(destination_dir) {
+            Ok(()) => Ok(()),
+            Err(error) if error.kind() == std::io::ErrorKind::PermissionDenied => {
+                let destination = destination_dir
+                    .to_str()
+                    .ok_or_else(|| anyhow::anyhow!("invalid destination path"))?;
+                privilege_wrapper.run("/usr/bin/rm", &["-rf", destination])?;
+                Ok(())
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
     fn copy_directory_recursive(source_dir: &Path, destination_dir: &Path) -> Result<()> {
         fs::create_dir_all(destination_dir)?;
 
         for entry in fs::read_dir(source_dir)? {
             let entry = entry?;
+            // Repository metadata is never part of a release.
+            if entry.file_name() == ".git" {
+                continue;
+            }
             let source_path = entry.path();
             let destination_path = destination_dir.join(entry.file_name());
             let source_metadata = fs::symlink_metadata(&source_path)?;
@@ -303,6 +503,15 @@ impl BuildSystem {
         Ok(())
     }
 
+    /// Gives the project user the subordinate uid and gid range rootless podman maps the
+    /// container's users to. The helper leaves an existing range alone.
+    fn ensure_project_subids(project_id: &str, privilege_wrapper: &PrivilegeWrapper) -> Result<()> {
+        let username = format!("nanoscale-{project_id}");
+        privilege_wrapper.run("/usr/local/sbin/nanoscale-subids", &[&username])?;
+
+        Ok(())
+    }
+
     fn apply_runtime_env(command: &mut Command) {
         command.env("PATH", RUNTIME_PATH);
     }
@@ -326,6 +535,21 @@ mod tests {
         assert!(BuildSystem::parse_command("").is_err());
     }
 
+    #[test]
+    fn project_runtime_round_trips_through_storage_names() {
+        for runtime in [ProjectRuntime::Auto, ProjectRuntime::Container] {
+            assert_eq!(
+                ProjectRuntime::parse(runtime.as_str()).expect("parse"),
+                runtime
+            );
+        }
+        assert_eq!(
+            ProjectRuntime::parse("").expect("blank"),
+            ProjectRuntime::Auto
+        );
+        assert!(ProjectRuntime::parse("lambda").is_err());
+    }
+
     #[test]
     fn resolve_output_directory_returns_repo_when_empty() {
         let tempdir = tempfile::tempdir().expect("tempdir");
@@ -381,4 +605,19 @@ mod tests {
         let symlink_meta = std::fs::symlink_metadata(dest.join("a-link")).expect("meta");
         assert!(symlink_meta.file_type().is_symlink());
     }
+
+    #[test]
+    fn replace_directory_leaves_out_git_metadata() {
+        let tempdir = tempfile::tempdir().expect("tempdir");
+        let source = tempdir.path().join("source");
+        let dest = tempdir.path().join("dest");
+        let privilege_wrapper = PrivilegeWrapper::new();
+        std::fs::create_dir_all(source.join(".git")).expect("mkdir .git");
+        std::fs::write(source.join(".git/config"), "[remote \"origin\"]").expect("write");
+        std::fs::write(source.join("index.html"), "<html></html>").expect("write");
+
+        BuildSystem::replace_directory(&source, &dest, &privilege_wrapper).expect("replace");
+        assert!(dest.join("index.html").is_file());
+        assert!(!dest.join(".git").exists());
+    }
 }
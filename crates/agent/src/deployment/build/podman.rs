@@ -0,0 +1,268 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{anyhow, bail, Result};
+
+const DOCKERFILE_CANDIDATES: [&str; 2] = ["Dockerfile", "Containerfile"];
+
+pub(super) fn podman_binary() -> Result<String> {
+    if let Ok(configured_binary) = std::env::var("NANOSCALE_PODMAN_BIN") {
+        let trimmed_binary = configured_binary.trim();
+        if !trimmed_binary.is_empty() {
+            return Ok(trimmed_binary.to_string());
+        }
+    }
+
+    for candidate in ["/usr/bin/podman", "/bin/podman", "/usr/local/bin/podman"] {
+        if Path::new(candidate).is_file() {
+            return Ok(candidate.to_string());
+        }
+    }
+
+    bail!("podman binary not found; install podman or set NANOSCALE_PODMAN_BIN")
+}
+
+/// Tag naming one build of the project's image by its id, so loading a new build leaves the
+/// image of the release still serving in place.
+pub(super) fn image_tag(project_id: &str, image_id: &str) -> Result<String> {
+    let digest = image_id.trim();
+    let digest = digest.strip_prefix("sha256:").unwrap_or(digest);
+    if digest.len() < 12
+        || !digest
+            .chars()
+            .all(|character| character.is_ascii_hexdigit())
+    {
+        bail!("podman returned an invalid image id: {image_id:?}");
+    }
+
+    Ok(format!(
+        "localhost/nanoscale-{project_id}:{}",
+        &digest[..12]
+    ))
+}
+
+pub(super) fn find_dockerfile(context_dir: &Path) -> Result<&'static str> {
+    DOCKERFILE_CANDIDATES
+        .into_iter()
+        .find(|candidate| context_dir.join(candidate).is_file())
+        .ok_or_else(|| {
+            anyhow!(
+                "container runtime requires a Dockerfile or Containerfile in {}",
+                context_dir.display()
+            )
+        })
+}
+
+/// Builds the project image and exports it as an archive the project user can load into its own
+/// rootless storage; images built by the agent user are not visible to other users, so the
+/// build is removed from the agent's storage once exported. Returns the build's tag.
+pub(super) fn build_image_archive(
+    podman_binary: &str,
+    project_id: &str,
+    context_dir: &Path,
+    archive_path: &Path,
+) -> Result<String> {
+    let dockerfile = find_dockerfile(context_dir)?;
+    let context = context_dir
+        .to_str()
+        .ok_or_else(|| anyhow!("invalid container build context path"))?;
+    let dockerfile_path = format!("{context}/{dockerfile}");
+    let archive = archive_path
+        .to_str()
+        .ok_or_else(|| anyhow!("invalid container image archive path"))?;
+    let id_path = archive_path.with_extension("id");
+    let id_file = id_path
+        .to_str()
+        .ok_or_else(|| anyhow!("invalid container image id path"))?;
+
+    run_podman(
+        podman_binary,
+        &[
+            "build",
+            "--pull=missing",
+            "--file",
+            &dockerfile_path,
+            "--iidfile",
+            id_file,
+            context,
+        ],
+        "image build",
+    )?;
+    let image_id = fs::read_to_string(&id_path)
+        .map_err(|error| anyhow!("failed to read the built image id: {error}"))?;
+    let image_id = image_id.trim();
+    let tagged = image_tag(project_id, image_id).and_then(|tag| {
+        run_podman(podman_binary, &["tag", image_id, &tag], "image tag")?;
+        Ok(tag)
+    });
+    let exported = match &tagged {
+        Ok(tag) => run_podman(
+            podman_binary,
+            &["save", "--quiet", "--output", archive, tag],
+            "image export",
+        ),
+        Err(_) => Ok(()),
+    };
+
+    // The archive is all the project user needs; the agent's copy would otherwise pile up with
+    // every build. Removing the last tag removes the image, so the id is only removed directly
+    // when tagging failed.
+    let removed = match &tagged {
+        Ok(tag) => run_podman(podman_binary, &["rmi", "--ignore", tag], "image removal"),
+        Err(_) => run_podman(
+            podman_binary,
+            &["rmi", "--ignore", image_id],
+            "image removal",
+        ),
+    };
+
+    let tag = tagged?;
+    exported?;
+    removed?;
+    Ok(tag)
+}
+
+/// Removes every build of the project left in the agent user's storage.
+pub(super) fn remove_project_images(podman_binary: &str, project_id: &str) -> Result<()> {
+    let reference = format!("reference=localhost/nanoscale-{project_id}");
+    let output = Command::new(podman_binary)
+        .args([
+            "images",
+            "--noheading",
+            "--filter",
+            &reference,
+            "--format",
+            "{{.Repository}}:{{.Tag}}",
+        ])
+        .output()
+        .map_err(|error| anyhow!("failed to execute podman image list: {error}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("podman image list failed: {stderr}");
+    }
+
+    let listed = String::from_utf8_lossy(&output.stdout);
+    let images = listed
+        .lines()
+        .map(str::trim)
+        .filter(|image| !image.is_empty())
+        .collect::<Vec<_>>();
+    if images.is_empty() {
+        return Ok(());
+    }
+
+    let mut args = vec!["rmi", "--ignore"];
+    args.extend(images);
+    run_podman(podman_binary, &args, "image removal")
+}
+
+fn run_podman(podman_binary: &str, args: &[&str], label: &str) -> Result<()> {
+    let output = Command::new(podman_binary)
+        .args(args)
+        .output()
+        .map_err(|error| anyhow!("failed to execute podman {label}: {error}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("podman {label} failed: {stderr}");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    const IMAGE_ID: &str =
+        "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    /// Writes a podman stand-in that logs its arguments and answers `build` and `images`.
+    fn fake_podman(dir: &Path) -> String {
+        let binary = dir.join("podman");
+        fs::write(
+            &binary,
+            format!(
+                "#!/bin/sh\necho \"$*\" >> {0}.log\n\
+                 case \"$1\" in\n\
+                 build) while [ \"$1\" != --iidfile ]; do shift; done; echo {IMAGE_ID} > \"$2\" ;;\n\
+                 images) echo localhost/nanoscale-p1:0123456789ab; echo localhost/nanoscale-p1:fedcba987654 ;;\n\
+                 esac\n",
+                binary.display()
+            ),
+        )
+        .expect("stub");
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).expect("chmod");
+        binary.to_str().expect("utf-8 path").to_string()
+    }
+
+    #[test]
+    fn find_dockerfile_accepts_containerfile_and_rejects_missing() {
+        let tempdir = tempfile::tempdir().expect("tempdir");
+        assert!(find_dockerfile(tempdir.path()).is_err());
+
+        std::fs::write(tempdir.path().join("Containerfile"), "FROM scratch").expect("write");
+        assert_eq!(
+            find_dockerfile(tempdir.path()).expect("containerfile"),
+            "Containerfile"
+        );
+
+        std::fs::write(tempdir.path().join("Dockerfile"), "FROM scratch").expect("write");
+        assert_eq!(
+            find_dockerfile(tempdir.path()).expect("dockerfile"),
+            "Dockerfile"
+        );
+    }
+
+    #[test]
+    fn image_tag_is_scoped_to_project_and_build() {
+        assert_eq!(
+            image_tag(
+                "p1",
+                "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef\n"
+            )
+            .expect("tag"),
+            "localhost/nanoscale-p1:0123456789ab"
+        );
+        assert!(image_tag("p1", "sha256:0123").is_err());
+        assert!(image_tag("p1", "localhost/nanoscale-p1:latest").is_err());
+    }
+
+    #[test]
+    fn build_image_archive_removes_the_build_from_agent_storage() {
+        let tempdir = tempfile::tempdir().expect("tempdir");
+        let podman = fake_podman(tempdir.path());
+        let context = tempdir.path().join("repo");
+        fs::create_dir_all(&context).expect("context");
+        fs::write(context.join("Dockerfile"), "FROM scratch").expect("write");
+
+        let tag = build_image_archive(&podman, "p1", &context, &tempdir.path().join("image.tar"))
+            .expect("build");
+
+        assert_eq!(tag, "localhost/nanoscale-p1:0123456789ab");
+        let log = fs::read_to_string(format!("{podman}.log")).expect("log");
+        let commands = log
+            .lines()
+            .map(|line| line.split(' ').next().unwrap_or_default())
+            .collect::<Vec<_>>();
+        assert_eq!(commands, ["build", "tag", "save", "rmi"]);
+        assert!(log.ends_with("rmi --ignore localhost/nanoscale-p1:0123456789ab\n"));
+    }
+
+    #[test]
+    fn remove_project_images_removes_every_listed_build() {
+        let tempdir = tempfile::tempdir().expect("tempdir");
+        let podman = fake_podman(tempdir.path());
+
+        remove_project_images(&podman, "p1").expect("remove");
+
+        let log = fs::read_to_string(format!("{podman}.log")).expect("log");
+        assert!(log.contains("--filter reference=localhost/nanoscale-p1 "));
+        assert!(log.ends_with(
+            "rmi --ignore localhost/nanoscale-p1:0123456789ab localhost/nanoscale-p1:fedcba987654\n"
+        ));
+    }
+}
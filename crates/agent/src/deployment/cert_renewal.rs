@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use x509_parser::extensions::GeneralName;
+use x509_parser::pem::Pem;
+
+use crate::deployment::acme::AcmeSettings;
+use crate::deployment::nginx::NginxGenerator;
+use crate::deployment::tls::{TlsProvisioner, CERTIFICATES_PATH};
+use crate::system::PrivilegeWrapper;
+
+const RENEWAL_INTERVAL_SECONDS: u64 = 12 * 60 * 60;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// What a certificate under `/opt/nanoscale/certs/<name>/` currently holds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CertificateInfo {
+    pub name: String,
+    pub issuer: String,
+    /// Expiry as a unix timestamp.
+    pub not_after: i64,
+    pub domains: Vec<String>,
+}
+
+/// TLS state of one routed domain as reported to the dashboard.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DomainTlsStatus {
+    pub domain: String,
+    /// Name of the certificate covering the domain, if any.
+    pub certificate_name: Option<String>,
+    pub issuer: Option<String>,
+    /// RFC 3339 expiry of the covering certificate.
+    pub not_after: Option<String>,
+    /// Why the last issuance or renewal involving the domain failed.
+    pub last_error: Option<String>,
+}
+
+/// Renews certificates that are about to expire and remembers the last TLS error per domain.
+///
+/// Clones share the error map, so the copy kept in handler state sees what the background task
+/// recorded.
+#[derive(Clone, Debug)]
+pub struct CertificateRenewer {
+    window_days: u64,
+    acme_settings: AcmeSettings,
+    last_errors: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl CertificateRenewer {
+    #[must_use]
+    pub fn new(window_days: u64, acme_settings: AcmeSettings) -> Self {
+        Self {
+            window_days,
+            acme_settings,
+            last_errors: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// ACME settings of this host, also used when deployments request certificates.
+    #[must_use]
+    pub fn acme_settings(&self) -> &AcmeSettings {
+        &self.acme_settings
+    }
+
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(RENEWAL_INTERVAL_SECONDS));
+
+            loop {
+                interval.tick().await;
+
+                let renewer = self.clone();
+                match tokio::task::spawn_blocking(move || renewer.renew_due()).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(error)) => eprintln!("certificate renewal failed: {error:#}"),
+                    Err(error) => eprintln!("certificate renewal task join error: {error}"),
+                }
+            }
+        });
+    }
+
+    /// Stores the outcome of a certificate request for `domains`; `None` clears old errors.
+    pub fn record_attempt(&self, domains: &[String], error: Option<&str>) {
+        let mut last_errors = self
+            .last_errors
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for domain in domains {
+            match error {
+                Some(error) => {
+                    last_errors.insert(domain.clone(), error.to_string());
+                }
+                None => {
+                    last_errors.remove(domain);
+                }
+            }
+        }
+    }
+
+    /// Reports which certificate covers each domain and the last error seen for it.
+    ///
+    /// # Errors
+    /// Returns an error if the live certificate directory cannot be read.
+    pub fn domain_status(&self, domains: &[String]) -> Result<Vec<DomainTlsStatus>> {
+        let certificates = list_certificates(Path::new(CERTIFICATES_PATH))?;
+        let last_errors = self
+            .last_errors
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        Ok(domains
+            .iter()
+            .map(|domain| {
+                let certificate = certificates
+                    .iter()
+                    .filter(|certificate| certificate.covers(domain))
+                    .max_by_key(|certificate| certificate.not_after);
+                DomainTlsStatus {
+                    domain: domain.clone(),
+                    certificate_name: certificate.map(|certificate| certificate.name.clone()),
+                    issuer: certificate.map(|certificate| certificate.issuer.clone()),
+                    not_after: certificate
+                        .and_then(|certificate| format_timestamp(certificate.not_after)),
+                    last_error: last_errors.get(domain).cloned(),
+                }
+            })
+            .collect())
+    }
+
+    /// Renews every certificate inside the renewal window, then reloads nginx once.
+    fn renew_due(&self) -> Result<()> {
+        let certificates = list_certificates(Path::new(CERTIFICATES_PATH))?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let now = i64::try_from(now)?;
+        let mut renewed_any = false;
+        for certificate in certificates
+            .iter()
+            .filter(|certificate| needs_renewal(certificate.not_after, now, self.window_days))
+        {
+            let result =
+                TlsProvisioner::renew(&certificate.name, &certificate.domains, &self.acme_settings);
+            match result {
+                Ok(()) => {
+                    renewed_any = true;
+                    self.record_attempt(&certificate.domains, None);
+                }
+                Err(error) => {
+                    eprintln!(
+                        "renewing certificate {} failed: {error:#}",
+                        certificate.name
+                    );
+                    self.record_attempt(&certificate.domains, Some(&format!("{error:#}")));
+                }
+            }
+        }
+
+        if renewed_any {
+            NginxGenerator::reload(&PrivilegeWrapper::new())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether a certificate expiring at `not_after` is within `window_days` of `now`.
+#[must_use]
+pub fn needs_renewal(not_after: i64, now: i64, window_days: u64) -> bool {
+    let window = i64::try_from(window_days)
+        .unwrap_or(i64::MAX)
+        .saturating_mul(SECONDS_PER_DAY);
+    not_after.saturating_sub(now) <= window
+}
+
+impl CertificateInfo {
+    /// Whether a SAN names `domain` exactly or through a single-label wildcard.
+    #[must_use]
+    pub fn covers(&self, domain: &str) -> bool {
+        self.domains
+            .iter()
+            .any(|name| match name.strip_prefix("*.") {
+                Some(parent) => domain
+                    .strip_suffix(parent)
+                    .and_then(|label| label.strip_suffix('.'))
+                    .is_some_and(|label| !label.is_empty() && !label.contains('.')),
+                None => name == domain,
+            })
+    }
+}
+
+/// Reads every certificate in `certificates_dir`. Certificates whose chain cannot be parsed are
+/// skipped with a log line rather than hiding the others.
+///
+/// # Errors
+/// Returns an error if `certificates_dir` exists but cannot be listed.
+pub fn list_certificates(certificates_dir: &Path) -> Result<Vec<CertificateInfo>> {
+    if !certificates_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(certificates_dir)
+        .with_context(|| format!("failed to list {}", certificates_dir.display()))?;
+
+    let mut certificates = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let chain_path = entry.path().join("fullchain.pem");
+        if !chain_path.is_file() {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().to_string();
+        match fs::read(&chain_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|pem| parse_certificate(&name, &pem))
+        {
+            Ok(certificate) => certificates.push(certificate),
+            Err(error) => eprintln!("skipping certificate {name}: {error:#}"),
+        }
+    }
+
+    certificates.sort_by(|left, right| left.name.cmp(&right.name));
+    Ok(certificates)
+}
+
+/// Parses the leaf (first) certificate of a PEM chain.
+///
+/// # Errors
+/// Returns an error if the PEM holds no parseable X.509 certificate.
+pub fn parse_certificate(name: &str, pem: &[u8]) -> Result<CertificateInfo> {
+    let leaf = Pem::iter_from_buffer(pem)
+        .next()
+        .ok_or_else(|| anyhow!("no certificate in PEM"))?
+        .map_err(|error| anyhow!("invalid PEM: {error}"))?;
+    let certificate = leaf
+        .parse_x509()
+        .map_err(|error| anyhow!("invalid certificate: {error}"))?;
+
+    let domains = certificate
+        .subject_alternative_name()
+        .map_err(|error| anyhow!("invalid subjectAltName: {error}"))?
+        .map(|extension| {
+            extension
+                .value
+                .general_names
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(domain) => Some(domain.to_ascii_lowercase()),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(CertificateInfo {
+        name: name.to_string(),
+        issuer: certificate.issuer().to_string(),
+        not_after: certificate.validity().not_after.timestamp(),
+        domains,
+    })
+}
+
+fn format_timestamp(timestamp: i64) -> Option<String> {
+    OffsetDateTime::from_unix_timestamp(timestamp)
+        .ok()?
+        .format(&Rfc3339)
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBujCCAWCgAwIBAgIUSntzfsVjvWhOTYZH2C8o8B+iVCEwCgYIKoZIzj0EAwIw
+GjEYMBYGA1UEAwwPYXBwLmV4YW1wbGUuY29tMCAXDTI2MTAxODEyNTU0OVoYDzIx
+MjYwOTI0MTI1NTQ5WjAaMRgwFgYDVQQDDA9hcHAuZXhhbXBsZS5jb20wWTATBgcq
+hkjOPQIBBggqhkjOPQMBBwNCAAS1XhqACyM4rcF9z4wfJZDX9HkKONTV/5XoMXT4
+bJV0mx/ulUmidi7+qslEWPyIkvvj4+qbNMdVCRXYaZJ+egYHo4GBMH8wHQYDVR0O
+BBYEFFCBL3WMzWKsIqbNQMPi71XIZBUoMB8GA1UdIwQYMBaAFFCBL3WMzWKsIqbN
+QMPi71XIZBUoMA8GA1UdEwEB/wQFMAMBAf8wLAYDVR0RBCUwI4IPYXBwLmV4YW1w
+bGUuY29tghB3d3cuY3VzdG9tZXIuY29tMAoGCCqGSM49BAMCA0gAMEUCIQCv4kum
+s1pjRVr+Pc+NczbJR3nXIU9rYPTUvwWv6vWmxAIgMG2rlPo+a4YEiXsj5opCFd7o
+RRAVG2OKcE329QJoE5k=
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn list_certificates_reads_expiry_issuer_and_domains() {
+        let store = tempfile::tempdir().expect("tempdir");
+        let certificate_dir = store.path().join("app.example.com");
+        fs::create_dir_all(&certificate_dir).expect("certificate dir");
+        fs::write(certificate_dir.join("fullchain.pem"), TEST_CERTIFICATE).expect("write chain");
+        fs::create_dir_all(store.path().join("broken")).expect("broken dir");
+        fs::write(store.path().join("broken/fullchain.pem"), "not a pem").expect("write");
+        fs::write(store.path().join("README"), "not a certificate").expect("write readme");
+
+        let certificates = list_certificates(store.path()).expect("list");
+        assert_eq!(certificates.len(), 1);
+        let certificate = &certificates[0];
+        assert_eq!(certificate.name, "app.example.com");
+        assert_eq!(certificate.issuer, "CN=app.example.com");
+        assert_eq!(
+            certificate.domains,
+            vec![
+                "app.example.com".to_string(),
+                "www.customer.com".to_string()
+            ]
+        );
+        assert_eq!(
+            format_timestamp(certificate.not_after).as_deref(),
+            Some("2126-09-24T12:55:49Z")
+        );
+
+        assert!(list_certificates(&store.path().join("missing"))
+            .expect("missing dir")
+            .is_empty());
+    }
+
+    #[test]
+    fn needs_renewal_respects_window() {
+        let now = 1_000_000_000;
+        assert!(needs_renewal(now + 10 * SECONDS_PER_DAY, now, 30));
+        assert!(needs_renewal(now - 1, now, 30));
+        assert!(!needs_renewal(now + 31 * SECONDS_PER_DAY, now, 30));
+        assert!(!needs_renewal(now + 10 * SECONDS_PER_DAY, now, 7));
+    }
+
+    #[test]
+    fn wildcard_certificates_cover_one_label() {
+        let certificate = CertificateInfo {
+            name: "_wildcard.apps.example.com".to_string(),
+            issuer: "CN=Test CA".to_string(),
+            not_after: 0,
+            domains: vec!["*.apps.example.com".to_string()],
+        };
+        assert!(certificate.covers("shop.apps.example.com"));
+        assert!(!certificate.covers("apps.example.com"));
+        assert!(!certificate.covers("a.b.apps.example.com"));
+        assert!(!certificate.covers("shopapps.example.com"));
+    }
+
+    #[test]
+    fn record_attempt_sets_and_clears_domain_errors() {
+        let renewer = CertificateRenewer::new(30, AcmeSettings::default());
+        let domains = vec!["app.example.com".to_string()];
+        renewer.record_attempt(&domains, Some("rate limited"));
+        assert_eq!(
+            renewer
+                .last_errors
+                .lock()
+                .expect("lock")
+                .get("app.example.com")
+                .map(String::as_str),
+            Some("rate limited")
+        );
+
+        renewer.record_attempt(&domains, None);
+        assert!(renewer.last_errors.lock().expect("lock").is_empty());
+    }
+}
@@ -0,0 +1,270 @@
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::deployment::build::ProjectRuntime;
+
+const NEXT_CONFIG_FILES: [&str; 4] = [
+    "next.config.js",
+    "next.config.mjs",
+    "next.config.cjs",
+    "next.config.ts",
+];
+
+/// Directories a plain HTML site is published from, in order of preference.
+const STATIC_PUBLISH_DIRS: [&str; 4] = ["public", "dist", "site", "www"];
+
+/// Build defaults proposed for a repository, buildpack style.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DetectedSettings {
+    pub framework: &'static str,
+    pub runtime: ProjectRuntime,
+    pub install_command: String,
+    pub build_command: String,
+    pub run_command: String,
+    pub output_directory: String,
+}
+
+impl DetectedSettings {
+    fn new(framework: &'static str, install: &str, build: &str, run: &str) -> Self {
+        Self {
+            framework,
+            runtime: ProjectRuntime::Auto,
+            install_command: install.to_string(),
+            build_command: build.to_string(),
+            run_command: run.to_string(),
+            output_directory: String::new(),
+        }
+    }
+}
+
+/// Inspects a checked-out repository and proposes runtime and command defaults.
+///
+/// A Dockerfile wins over language markers because it states exactly how the app runs.
+/// Returns `None` when no supported project type is recognised.
+#[must_use]
+pub fn detect(repo_dir: &Path) -> Option<DetectedSettings> {
+    if ["Dockerfile", "Containerfile"]
+        .iter()
+        .any(|name| repo_dir.join(name).is_file())
+    {
+        let mut settings = DetectedSettings::new("dockerfile", "", "", "");
+        settings.runtime = ProjectRuntime::Container;
+        return Some(settings);
+    }
+
+    if repo_dir.join("package.json").is_file() {
+        return Some(detect_node(repo_dir));
+    }
+
+    if repo_dir.join("requirements.txt").is_file() || repo_dir.join("pyproject.toml").is_file() {
+        return Some(detect_python(repo_dir));
+    }
+
+    if repo_dir.join("go.mod").is_file() {
+        return Some(DetectedSettings::new(
+            "go",
+            "go mod download",
+            "go build -o nanoscale-app .",
+            "./nanoscale-app",
+        ));
+    }
+
+    if repo_dir.join("Cargo.toml").is_file() {
+        let run_command = cargo_package_name(repo_dir)
+            .map(|name| format!("./target/release/{name}"))
+            .unwrap_or_default();
+        return Some(DetectedSettings::new(
+            "rust",
+            "cargo fetch",
+            "cargo build --release --locked",
+            &run_command,
+        ));
+    }
+
+    // A site in a publish directory wins over a root index.html; the root is proposed as "."
+    // so publishing the whole repository is always an explicit setting.
+    let publish_dir = STATIC_PUBLISH_DIRS
+        .iter()
+        .copied()
+        .find(|dir| repo_dir.join(dir).join("index.html").is_file())
+        .or_else(|| repo_dir.join("index.html").is_file().then_some("."));
+    if let Some(publish_dir) = publish_dir {
+        let mut settings = DetectedSettings::new("static", "", "", "");
+        settings.output_directory = publish_dir.to_string();
+        return Some(settings);
+    }
+
+    None
+}
+
+fn detect_node(repo_dir: &Path) -> DetectedSettings {
+    let package_manager = node_package_manager(repo_dir);
+    let install_command = match package_manager {
+        "npm" => "npm ci".to_string(),
+        manager => format!("{manager} install --frozen-lockfile"),
+    };
+
+    let scripts = fs::read_to_string(repo_dir.join("package.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .and_then(|package| package.get("scripts").cloned());
+    let has_script = |name: &str| {
+        scripts
+            .as_ref()
+            .is_some_and(|value| value.get(name).is_some())
+    };
+
+    let build_command = if has_script("build") {
+        format!("{package_manager} run build")
+    } else {
+        String::new()
+    };
+    let run_command = if has_script("start") {
+        format!("{package_manager} run start")
+    } else {
+        String::new()
+    };
+
+    let framework = if NEXT_CONFIG_FILES
+        .iter()
+        .any(|name| repo_dir.join(name).is_file())
+    {
+        "nextjs"
+    } else if package_manager == "bun" {
+        "bun"
+    } else {
+        "node"
+    };
+
+    DetectedSettings::new(framework, &install_command, &build_command, &run_command)
+}
+
+/// Lockfiles decide the package manager; Bun is the platform default when none is committed.
+fn node_package_manager(repo_dir: &Path) -> &'static str {
+    let lockfiles = [
+        ("bun.lockb", "bun"),
+        ("bun.lock", "bun"),
+        ("pnpm-lock.yaml", "pnpm"),
+        ("yarn.lock", "yarn"),
+        ("package-lock.json", "npm"),
+    ];
+
+    lockfiles
+        .into_iter()
+        .find(|(lockfile, _)| repo_dir.join(lockfile).is_file())
+        .map_or("bun", |(_, manager)| manager)
+}
+
+fn detect_python(repo_dir: &Path) -> DetectedSettings {
+    // The venv travels with the artifacts; its interpreter is a symlink to the system python.
+    let build_command = if repo_dir.join("requirements.txt").is_file() {
+        ".venv/bin/pip install -r requirements.txt"
+    } else {
+        ".venv/bin/pip install ."
+    };
+    let run_command = ["main.py", "app.py", "server.py"]
+        .into_iter()
+        .find(|entrypoint| repo_dir.join(entrypoint).is_file())
+        .map(|entrypoint| format!(".venv/bin/python {entrypoint}"))
+        .unwrap_or_default();
+
+    DetectedSettings::new(
+        "python",
+        "python3 -m venv .venv",
+        build_command,
+        &run_command,
+    )
+}
+
+fn cargo_package_name(repo_dir: &Path) -> Option<String> {
+    let manifest = fs::read_to_string(repo_dir.join("Cargo.toml")).ok()?;
+    let manifest = manifest.parse::<toml::Table>().ok()?;
+    manifest
+        .get("package")?
+        .get("name")?
+        .as_str()
+        .map(ToString::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo_with(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let tempdir = tempfile::tempdir().expect("tempdir");
+        for (name, contents) in files {
+            fs::write(tempdir.path().join(name), contents).expect("write");
+        }
+        tempdir
+    }
+
+    #[test]
+    fn detect_prefers_dockerfile_over_language_markers() {
+        let repo = repo_with(&[("Dockerfile", "FROM scratch"), ("go.mod", "module x")]);
+        let detected = detect(repo.path()).expect("detected");
+        assert_eq!(detected.framework, "dockerfile");
+        assert_eq!(detected.runtime, ProjectRuntime::Container);
+    }
+
+    #[test]
+    fn detect_next_app_uses_lockfile_package_manager() {
+        let repo = repo_with(&[
+            (
+                "package.json",
+                r#"{"scripts":{"build":"next build","start":"next start"}}"#,
+            ),
+            ("package-lock.json", "{}"),
+            ("next.config.mjs", "export default {}"),
+        ]);
+        let detected = detect(repo.path()).expect("detected");
+        assert_eq!(detected.framework, "nextjs");
+        assert_eq!(detected.install_command, "npm ci");
+        assert_eq!(detected.build_command, "npm run build");
+        assert_eq!(detected.run_command, "npm run start");
+    }
+
+    #[test]
+    fn detect_node_without_scripts_leaves_commands_blank() {
+        let repo = repo_with(&[("package.json", "{}"), ("bun.lockb", "")]);
+        let detected = detect(repo.path()).expect("detected");
+        assert_eq!(detected.framework, "bun");
+        assert_eq!(detected.install_command, "bun install --frozen-lockfile");
+        assert!(detected.build_command.is_empty());
+        assert!(detected.run_command.is_empty());
+    }
+
+    #[test]
+    fn detect_python_go_rust_and_static() {
+        let python = repo_with(&[("requirements.txt", "flask"), ("app.py", "")]);
+        let detected = detect(python.path()).expect("python");
+        assert_eq!(detected.framework, "python");
+        assert_eq!(detected.run_command, ".venv/bin/python app.py");
+
+        let go = repo_with(&[("go.mod", "module example.com/app")]);
+        assert_eq!(
+            detect(go.path()).expect("go").run_command,
+            "./nanoscale-app"
+        );
+
+        let rust = repo_with(&[("Cargo.toml", "[package]\nname = \"api\"\n")]);
+        assert_eq!(
+            detect(rust.path()).expect("rust").run_command,
+            "./target/release/api"
+        );
+
+        let site = repo_with(&[("index.html", "<html></html>")]);
+        let detected = detect(site.path()).expect("static");
+        assert_eq!(detected.framework, "static");
+        assert_eq!(detected.output_directory, ".");
+        fs::create_dir(site.path().join("public")).expect("mkdir");
+        fs::write(site.path().join("public/index.html"), "<html></html>").expect("write");
+        let detected = detect(site.path()).expect("static");
+        assert_eq!(detected.framework, "static");
+        assert_eq!(detected.output_directory, "public");
+
+        let empty = repo_with(&[("README.md", "")]);
+        assert!(detect(empty.path()).is_none());
+    }
+}
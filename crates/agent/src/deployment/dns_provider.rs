@@ -0,0 +1,384 @@
+use std::fmt;
+use std::future::Future;
+use std::io::Write;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+const NSUPDATE_BIN: &str = "/usr/bin/nsupdate";
+const DEFAULT_TTL_SECONDS: u32 = 60;
+const DEFAULT_PROPAGATION_SECONDS: u64 = 10;
+
+pub type DnsUpdateFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// Publishes the `_acme-challenge` TXT records DNS-01 validation looks up. One implementation
+/// per DNS API; tests substitute a recorder.
+pub trait DnsProvider: fmt::Debug + Send + Sync {
+    /// Adds the TXT `value` at `name`, keeping other values already published there.
+    fn present<'a>(&'a self, name: &'a str, value: &'a str) -> DnsUpdateFuture<'a>;
+
+    /// Removes the TXT `value` that `present` added at `name`.
+    fn cleanup<'a>(&'a self, name: &'a str, value: &'a str) -> DnsUpdateFuture<'a>;
+
+    /// How long to wait after publishing before asking the CA to look the records up.
+    fn propagation_delay(&self) -> Duration;
+}
+
+/// The `dns_provider` section of config.json, tagged by `type`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum DnsProviderConfig {
+    Rfc2136(Rfc2136Config),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Rfc2136Config {
+    /// Primary name server accepting dynamic updates for `zone`.
+    pub server: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    pub zone: String,
+    /// TSIG key file passed to `nsupdate -k`.
+    #[serde(default)]
+    pub key_file: Option<String>,
+    #[serde(default)]
+    pub ttl_seconds: Option<u32>,
+    #[serde(default)]
+    pub propagation_seconds: Option<u64>,
+}
+
+impl DnsProviderConfig {
+    /// Builds the provider this section describes.
+    ///
+    /// # Errors
+    /// Returns an error if the section has missing or malformed values.
+    pub fn build(&self) -> Result<Arc<dyn DnsProvider>> {
+        match self {
+            Self::Rfc2136(config) => Ok(Arc::new(Rfc2136Provider::new(config)?)),
+        }
+    }
+}
+
+/// Dynamic DNS updates (RFC 2136) sent with `nsupdate`, e.g. to BIND or `PowerDNS`.
+#[derive(Debug)]
+pub struct Rfc2136Provider {
+    nsupdate_bin: PathBuf,
+    server: String,
+    port: Option<u16>,
+    zone: String,
+    key_file: Option<PathBuf>,
+    ttl_seconds: u32,
+    propagation: Duration,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UpdateAction {
+    Add,
+    Delete,
+}
+
+impl Rfc2136Provider {
+    /// # Errors
+    /// Returns an error if the server or zone is empty or not a plain hostname.
+    pub fn new(config: &Rfc2136Config) -> Result<Self> {
+        let server = config.server.trim().to_string();
+        let zone = config
+            .zone
+            .trim()
+            .trim_end_matches('.')
+            .to_ascii_lowercase();
+        if !is_dns_name(&server) {
+            bail!("rfc2136 server is not a hostname or IP address: {server:?}");
+        }
+        if !is_dns_name(&zone) {
+            bail!("rfc2136 zone is not a domain name: {zone:?}");
+        }
+
+        Ok(Self {
+            nsupdate_bin: PathBuf::from(NSUPDATE_BIN),
+            server,
+            port: config.port,
+            zone,
+            key_file: config
+                .key_file
+                .as_deref()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from),
+            ttl_seconds: config.ttl_seconds.unwrap_or(DEFAULT_TTL_SECONDS),
+            propagation: Duration::from_secs(
+                config
+                    .propagation_seconds
+                    .unwrap_or(DEFAULT_PROPAGATION_SECONDS),
+            ),
+        })
+    }
+
+    /// The `nsupdate` script for one record change. Names and values are checked first so
+    /// nothing from an ACME response can inject extra update commands.
+    fn update_script(&self, action: UpdateAction, name: &str, value: &str) -> Result<String> {
+        let name = name.trim_end_matches('.').to_ascii_lowercase();
+        if !is_dns_name(&name) || !(name == self.zone || name.ends_with(&format!(".{}", self.zone)))
+        {
+            bail!("{name} is not inside zone {}", self.zone);
+        }
+        if value.is_empty()
+            || !value
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric() || "-_".contains(character))
+        {
+            bail!("TXT value is not base64url: {value:?}");
+        }
+
+        let server = match self.port {
+            Some(port) => format!("server {} {port}", self.server),
+            None => format!("server {}", self.server),
+        };
+        let update = match action {
+            UpdateAction::Add => {
+                format!("update add {name}. {} IN TXT \"{value}\"", self.ttl_seconds)
+            }
+            UpdateAction::Delete => format!("update delete {name}. IN TXT \"{value}\""),
+        };
+
+        Ok(format!("{server}\nzone {}.\n{update}\nsend\n", self.zone))
+    }
+
+    fn update<'a>(
+        &'a self,
+        action: UpdateAction,
+        name: &'a str,
+        value: &'a str,
+    ) -> DnsUpdateFuture<'a> {
+        Box::pin(async move {
+            let script = self.update_script(action, name, value)?;
+            let nsupdate_bin = self.nsupdate_bin.clone();
+            let key_file = self.key_file.clone();
+            tokio::task::spawn_blocking(move || run_nsupdate(&nsupdate_bin, key_file, &script))
+                .await
+                .context("nsupdate task panicked")?
+                .with_context(|| format!("RFC 2136 update for {name} failed"))
+        })
+    }
+}
+
+impl DnsProvider for Rfc2136Provider {
+    fn present<'a>(&'a self, name: &'a str, value: &'a str) -> DnsUpdateFuture<'a> {
+        self.update(UpdateAction::Add, name, value)
+    }
+
+    fn cleanup<'a>(&'a self, name: &'a str, value: &'a str) -> DnsUpdateFuture<'a> {
+        self.update(UpdateAction::Delete, name, value)
+    }
+
+    fn propagation_delay(&self) -> Duration {
+        self.propagation
+    }
+}
+
+fn run_nsupdate(nsupdate_bin: &PathBuf, key_file: Option<PathBuf>, script: &str) -> Result<()> {
+    let mut command = Command::new(nsupdate_bin);
+    if let Some(key_file) = key_file {
+        command.arg("-k").arg(key_file);
+    }
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run {}", nsupdate_bin.display()))?;
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("nsupdate stdin unavailable"))?
+        .write_all(script.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "nsupdate exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+fn is_dns_name(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "-_.:".contains(character))
+}
+
+/// Provider that only remembers what it was asked to publish.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct RecordingDnsProvider {
+    pub records: std::sync::Mutex<Vec<(String, String)>>,
+    pub cleaned: std::sync::Mutex<Vec<(String, String)>>,
+}
+
+#[cfg(test)]
+impl DnsProvider for RecordingDnsProvider {
+    fn present<'a>(&'a self, name: &'a str, value: &'a str) -> DnsUpdateFuture<'a> {
+        self.records
+            .lock()
+            .expect("records lock")
+            .push((name.to_string(), value.to_string()));
+        Box::pin(async { Ok(()) })
+    }
+
+    fn cleanup<'a>(&'a self, name: &'a str, value: &'a str) -> DnsUpdateFuture<'a> {
+        self.cleaned
+            .lock()
+            .expect("cleaned lock")
+            .push((name.to_string(), value.to_string()));
+        Box::pin(async { Ok(()) })
+    }
+
+    fn propagation_delay(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    fn config(server: &str, zone: &str) -> Rfc2136Config {
+        Rfc2136Config {
+            server: server.to_string(),
+            port: Some(5353),
+            zone: zone.to_string(),
+            key_file: None,
+            ttl_seconds: None,
+            propagation_seconds: None,
+        }
+    }
+
+    #[test]
+    fn update_script_adds_and_deletes_txt_records_in_zone() {
+        let provider =
+            Rfc2136Provider::new(&config("127.0.0.1", "Apps.Example.com.")).expect("provider");
+
+        let add = provider
+            .update_script(
+                UpdateAction::Add,
+                "_acme-challenge.apps.example.com",
+                "abc_-123",
+            )
+            .expect("add");
+        assert_eq!(
+            add,
+            "server 127.0.0.1 5353\nzone apps.example.com.\nupdate add _acme-challenge.apps.example.com. 60 IN TXT \"abc_-123\"\nsend\n"
+        );
+
+        let delete = provider
+            .update_script(
+                UpdateAction::Delete,
+                "_acme-challenge.apps.example.com.",
+                "abc",
+            )
+            .expect("delete");
+        assert!(delete.contains("update delete _acme-challenge.apps.example.com. IN TXT \"abc\""));
+    }
+
+    #[test]
+    fn update_script_rejects_injection_and_foreign_zones() {
+        let provider =
+            Rfc2136Provider::new(&config("ns1.example.com", "apps.example.com")).expect("provider");
+        assert!(provider
+            .update_script(UpdateAction::Add, "_acme-challenge.other.com", "abc")
+            .is_err());
+        assert!(provider
+            .update_script(
+                UpdateAction::Add,
+                "_acme-challenge.apps.example.com",
+                "abc\"\nsend"
+            )
+            .is_err());
+        assert!(provider
+            .update_script(
+                UpdateAction::Add,
+                "x.apps.example.com\nupdate delete",
+                "abc"
+            )
+            .is_err());
+        assert!(Rfc2136Provider::new(&config("ns1 evil", "apps.example.com")).is_err());
+        assert!(Rfc2136Provider::new(&config("ns1.example.com", " ")).is_err());
+    }
+
+    #[tokio::test]
+    async fn present_pipes_the_update_into_nsupdate() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let script_path = dir.path().join("received");
+        let fake_nsupdate = dir.path().join("nsupdate");
+        fs::write(
+            &fake_nsupdate,
+            format!(
+                "#!/bin/sh\necho \"$@\" > {0}.args\ncat > {0}\n",
+                script_path.display()
+            ),
+        )
+        .expect("write fake nsupdate");
+        fs::set_permissions(&fake_nsupdate, fs::Permissions::from_mode(0o755)).expect("chmod");
+
+        let mut provider_config = config("127.0.0.1", "apps.example.com");
+        provider_config.key_file = Some("/opt/nanoscale/config/tsig.key".to_string());
+        let mut provider = Rfc2136Provider::new(&provider_config).expect("provider");
+        provider.nsupdate_bin = fake_nsupdate;
+
+        provider
+            .present("_acme-challenge.apps.example.com", "value-1")
+            .await
+            .expect("present");
+
+        let received = fs::read_to_string(&script_path).expect("script");
+        assert!(
+            received.contains("update add _acme-challenge.apps.example.com. 60 IN TXT \"value-1\"")
+        );
+        let args = fs::read_to_string(script_path.with_extension("args")).expect("args");
+        assert_eq!(args.trim(), "-k /opt/nanoscale/config/tsig.key");
+    }
+
+    /// Runs against a local BIND accepting TSIG-signed updates, e.g. `named` with an
+    /// `update-policy` for the zone and a key generated by `tsig-keygen`.
+    #[tokio::test]
+    #[ignore = "needs BIND; set NANOSCALE_RFC2136_SERVER, NANOSCALE_RFC2136_ZONE and NANOSCALE_RFC2136_KEY"]
+    async fn present_and_cleanup_against_bind() {
+        let zone =
+            std::env::var("NANOSCALE_RFC2136_ZONE").unwrap_or_else(|_| "example.test".to_string());
+        let provider = Rfc2136Provider::new(&Rfc2136Config {
+            server: std::env::var("NANOSCALE_RFC2136_SERVER")
+                .unwrap_or_else(|_| "127.0.0.1".to_string()),
+            port: std::env::var("NANOSCALE_RFC2136_PORT")
+                .ok()
+                .and_then(|port| port.parse().ok()),
+            zone: zone.clone(),
+            key_file: std::env::var("NANOSCALE_RFC2136_KEY").ok(),
+            ttl_seconds: None,
+            propagation_seconds: None,
+        })
+        .expect("provider");
+
+        let name = format!("_acme-challenge.{zone}");
+        provider
+            .present(&name, "nanoscale-test")
+            .await
+            .expect("present");
+        provider
+            .cleanup(&name, "nanoscale-test")
+            .await
+            .expect("cleanup");
+    }
+}
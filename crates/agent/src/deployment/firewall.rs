@@ -0,0 +1,153 @@
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::system::PrivilegeWrapper;
+
+/// Agent-owned record of the firewall rules opened for each project, so they can be closed
+/// again on redeploy and teardown without parsing `ufw status`.
+pub const FIREWALL_STATE_PATH: &str = "/opt/nanoscale/data/firewall";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    #[default]
+    Tcp,
+    Udp,
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(match self {
+            Self::Tcp => "tcp",
+            Self::Udp => "udp",
+        })
+    }
+}
+
+/// An inbound port opened in ufw for a project.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FirewallRule {
+    pub port: u16,
+    pub protocol: Protocol,
+    /// Only this address may connect; anyone when unset.
+    pub from: Option<IpAddr>,
+}
+
+impl FirewallRule {
+    /// `ufw` arguments adding the rule; prefix them with `delete` to remove it again.
+    #[must_use]
+    pub fn ufw_args(&self) -> Vec<String> {
+        let mut args = vec!["allow".to_string()];
+        if let Some(from) = self.from {
+            args.extend(["from".to_string(), from.to_string()]);
+        }
+        args.extend([
+            "to".to_string(),
+            "any".to_string(),
+            "port".to_string(),
+            self.port.to_string(),
+            "proto".to_string(),
+            self.protocol.to_string(),
+        ]);
+        args
+    }
+}
+
+/// Makes `rules` the project's open ports: rules no longer wanted are deleted, new ones added,
+/// and the state file rewritten.
+///
+/// # Errors
+/// Returns an error if the state file cannot be read or written, or a `ufw` command fails.
+pub fn apply(
+    dir: &Path,
+    project_id: &str,
+    rules: &[FirewallRule],
+    privilege_wrapper: &PrivilegeWrapper,
+) -> Result<()> {
+    let previous = load(dir, project_id)?;
+    for rule in previous.iter().filter(|rule| !rules.contains(rule)) {
+        let args = rule.ufw_args();
+        let args = ["delete"]
+            .into_iter()
+            .chain(args.iter().map(String::as_str))
+            .collect::<Vec<_>>();
+        privilege_wrapper.run("/usr/local/sbin/nanoscale-ufw", &args)?;
+    }
+    for rule in rules.iter().filter(|rule| !previous.contains(rule)) {
+        let args = rule.ufw_args();
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        privilege_wrapper.run("/usr/local/sbin/nanoscale-ufw", &args)?;
+    }
+
+    let path = state_file(dir, project_id);
+    if rules.is_empty() {
+        return match fs::remove_file(&path) {
+            Err(error) if error.kind() != ErrorKind::NotFound => {
+                Err(error).with_context(|| format!("failed to remove {}", path.display()))
+            }
+            _ => Ok(()),
+        };
+    }
+    fs::create_dir_all(dir)?;
+    fs::write(&path, serde_json::to_vec(rules)?)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Closes every port opened for the project.
+///
+/// # Errors
+/// Returns an error if the state file cannot be read or a `ufw` command fails.
+pub fn remove(dir: &Path, project_id: &str, privilege_wrapper: &PrivilegeWrapper) -> Result<()> {
+    apply(dir, project_id, &[], privilege_wrapper)
+}
+
+fn state_file(dir: &Path, project_id: &str) -> PathBuf {
+    dir.join(format!("{project_id}.json"))
+}
+
+fn load(dir: &Path, project_id: &str) -> Result<Vec<FirewallRule>> {
+    let path = state_file(dir, project_id);
+    match fs::read(&path) {
+        Ok(contents) => serde_json::from_slice(&contents)
+            .with_context(|| format!("failed to parse {}", path.display())),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(error) => Err(error).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ufw_args_scope_rule_to_source_address() {
+        let rule = FirewallRule {
+            port: 3100,
+            protocol: Protocol::Tcp,
+            from: Some("10.0.0.1".parse().expect("ip")),
+        };
+        assert_eq!(
+            rule.ufw_args(),
+            ["allow", "from", "10.0.0.1", "to", "any", "port", "3100", "proto", "tcp"]
+        );
+
+        let open = FirewallRule { from: None, ..rule };
+        assert_eq!(
+            open.ufw_args(),
+            ["allow", "to", "any", "port", "3100", "proto", "tcp"]
+        );
+    }
+
+    #[test]
+    fn removing_rules_without_state_runs_nothing() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        remove(dir.path(), "p1", &PrivilegeWrapper::new()).expect("nothing to remove");
+        assert!(load(dir.path(), "p1").expect("load").is_empty());
+    }
+}
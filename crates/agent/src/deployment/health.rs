@@ -0,0 +1,98 @@
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(5);
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Polls `http://127.0.0.1:{port}{path}` until it answers with a 2xx/3xx status.
+///
+/// The request goes through the socket-activated front port, so the first attempt also starts
+/// the service.
+///
+/// # Errors
+/// Returns an error with the last observed failure once `timeout` elapses.
+pub fn wait_until_healthy(port: u16, path: &str, timeout: Duration) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        let error = match probe(port, path) {
+            Ok(status) if (200..400).contains(&status) => return Ok(()),
+            Ok(status) => anyhow!("health check {path} returned HTTP {status}"),
+            Err(error) => error,
+        };
+
+        if Instant::now() + RETRY_INTERVAL >= deadline {
+            bail!(
+                "health check did not pass within {}s: {error:#}",
+                timeout.as_secs()
+            );
+        }
+        thread::sleep(RETRY_INTERVAL);
+    }
+}
+
+fn probe(port: u16, path: &str) -> Result<u16> {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut stream = TcpStream::connect_timeout(&address, ATTEMPT_TIMEOUT)?;
+    stream.set_read_timeout(Some(ATTEMPT_TIMEOUT))?;
+    stream.set_write_timeout(Some(ATTEMPT_TIMEOUT))?;
+
+    // One write, so the request does not reach the app in fragments.
+    let request = format!(
+        "GET {path} HTTP/1.0\r\nHost: 127.0.0.1\r\nUser-Agent: nanoscale-health-check\r\n\r\n"
+    );
+    stream.write_all(request.as_bytes())?;
+
+    // Only the status line matters; bodies may be large or streamed.
+    let mut buffer = [0_u8; 64];
+    let read = stream.read(&mut buffer)?;
+    parse_status_code(&String::from_utf8_lossy(&buffer[..read]))
+}
+
+fn parse_status_code(response: &str) -> Result<u16> {
+    let status_line = response.lines().next().unwrap_or_default();
+    let mut parts = status_line.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some(version), Some(code)) if version.starts_with("HTTP/") => code
+            .parse::<u16>()
+            .map_err(|_| anyhow!("invalid HTTP status line: {status_line}")),
+        _ => bail!("invalid HTTP status line: {status_line}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn parse_status_code_reads_status_line() {
+        assert_eq!(
+            parse_status_code("HTTP/1.1 204 No Content\r\n").expect("status"),
+            204
+        );
+        assert!(parse_status_code("garbage").is_err());
+    }
+
+    #[test]
+    fn wait_until_healthy_accepts_success_response() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).expect("bind");
+        let port = listener.local_addr().expect("addr").port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let mut request = [0_u8; 256];
+            let _ = stream.read(&mut request).expect("read");
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                .expect("write");
+        });
+
+        wait_until_healthy(port, "/healthz", Duration::from_secs(5)).expect("healthy");
+        server.join().expect("server thread");
+    }
+}
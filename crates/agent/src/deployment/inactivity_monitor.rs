@@ -16,6 +16,8 @@ pub struct MonitoredProject {
     pub service_name: String,
     pub port: u16,
     pub scale_to_zero: bool,
+    /// Deliberately stopped; left alone until it is started again.
+    pub stopped: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -42,7 +44,7 @@ impl InactivityMonitor {
 
                 let projects = self.projects.read().await.clone();
                 for project in projects {
-                    if !project.scale_to_zero {
+                    if !project.scale_to_zero || project.stopped {
                         continue;
                     }
 
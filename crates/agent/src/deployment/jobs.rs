@@ -0,0 +1,168 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+mod runs;
+mod schedule;
+
+pub use runs::{parse_runs, status, trigger, JobRun, JobStatus, MAX_RUNS};
+pub use schedule::on_calendar;
+
+pub const SYSTEMD_PATH: &str = "/etc/systemd/system";
+const SITES_PATH: &str = "/opt/nanoscale/sites";
+const MAX_JOBS: usize = 16;
+const MAX_JOB_NAME_LENGTH: usize = 48;
+const DEFAULT_TIMEOUT_SECONDS: u64 = 3_600;
+const MAX_TIMEOUT_SECONDS: u64 = 86_400;
+
+/// A command run on a schedule in the app's environment, as a systemd timer and oneshot
+/// service next to the app's own units.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CronJob {
+    pub name: String,
+    /// Five-field cron expression (`minute hour day-of-month month day-of-week`) in the
+    /// server's local time, or one of `@hourly`, `@daily`, `@weekly`, `@monthly`, `@yearly`.
+    pub schedule: String,
+    pub command: String,
+    /// A run still going after this long is killed and recorded as a timeout.
+    #[serde(default = "default_timeout")]
+    pub timeout_seconds: u64,
+}
+
+const fn default_timeout() -> u64 {
+    DEFAULT_TIMEOUT_SECONDS
+}
+
+/// Checks a project's jobs before they become unit names and timers.
+///
+/// # Errors
+/// Returns an error for too many jobs, a duplicate or malformed name, an empty command, a
+/// timeout out of range, or a schedule that is not a supported cron expression.
+pub fn validate(jobs: &[CronJob]) -> Result<()> {
+    if jobs.len() > MAX_JOBS {
+        bail!("at most {MAX_JOBS} cron jobs can be scheduled");
+    }
+
+    let mut names = HashSet::new();
+    for job in jobs {
+        let name_valid = !job.name.is_empty()
+            && job.name.len() <= MAX_JOB_NAME_LENGTH
+            && job
+                .name
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric() || character == '-');
+        if !name_valid {
+            bail!(
+                "cron job name must match ^[A-Za-z0-9-]{{1,{MAX_JOB_NAME_LENGTH}}}$: {:?}",
+                job.name
+            );
+        }
+        if !names.insert(job.name.as_str()) {
+            bail!("cron job {} is declared twice", job.name);
+        }
+        if job.command.trim().is_empty() {
+            bail!("cron job {} needs a command", job.name);
+        }
+        if job.timeout_seconds == 0 || job.timeout_seconds > MAX_TIMEOUT_SECONDS {
+            bail!(
+                "cron job {} timeout_seconds must be between 1 and {MAX_TIMEOUT_SECONDS}",
+                job.name
+            );
+        }
+        on_calendar(&job.schedule).map_err(|error| anyhow!("cron job {}: {error}", job.name))?;
+    }
+
+    Ok(())
+}
+
+/// Directory the project's jobs record their runs in, writable by the project user.
+#[must_use]
+pub fn runs_dir(project_id: &str) -> PathBuf {
+    PathBuf::from(format!("{SITES_PATH}/{project_id}/jobs"))
+}
+
+/// Unit name, without suffix, of the project's job `name`.
+#[must_use]
+pub fn unit_name(project_id: &str, name: &str) -> String {
+    format!("nanoscale-{project_id}-job-{name}")
+}
+
+/// Names of the jobs whose timers are installed in `systemd_dir` for the project.
+///
+/// # Errors
+/// Returns an error if the directory cannot be read.
+pub fn installed(systemd_dir: &Path, project_id: &str) -> Result<Vec<String>> {
+    let prefix = unit_name(project_id, "");
+    let mut names = Vec::new();
+    for entry in fs::read_dir(systemd_dir)? {
+        let file_name = entry?.file_name();
+        let Some(name) = file_name
+            .to_str()
+            .and_then(|file_name| file_name.strip_prefix(&prefix))
+            .and_then(|rest| rest.strip_suffix(".timer"))
+        else {
+            continue;
+        };
+        names.push(name.to_string());
+    }
+    names.sort();
+
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(name: &str, schedule: &str) -> CronJob {
+        CronJob {
+            name: name.to_string(),
+            schedule: schedule.to_string(),
+            command: "node scripts/cleanup.js".to_string(),
+            timeout_seconds: DEFAULT_TIMEOUT_SECONDS,
+        }
+    }
+
+    #[test]
+    fn validate_rejects_bad_names_duplicates_and_timeouts() {
+        validate(&[job("nightly-cleanup", "0 3 * * *")]).expect("valid");
+
+        assert!(validate(&[job("nightly cleanup", "0 3 * * *")]).is_err());
+        assert!(validate(&[job(&"a".repeat(49), "0 3 * * *")]).is_err());
+        assert!(validate(&[job("a", "0 3 * * *"), job("a", "0 4 * * *")]).is_err());
+        assert!(validate(&[job("a", "daily")]).is_err());
+        assert!(validate(&[CronJob {
+            timeout_seconds: 0,
+            ..job("a", "0 3 * * *")
+        }])
+        .is_err());
+        assert!(validate(&[CronJob {
+            command: " ".to_string(),
+            ..job("a", "0 3 * * *")
+        }])
+        .is_err());
+    }
+
+    #[test]
+    fn installed_lists_the_project_job_timers() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        for file in [
+            "nanoscale-p1-job-cleanup.timer",
+            "nanoscale-p1-job-cleanup.service",
+            "nanoscale-p1-job-backup.timer",
+            "nanoscale-p1.service",
+            "nanoscale-p2-job-cleanup.timer",
+        ] {
+            fs::write(dir.path().join(file), "").expect("write");
+        }
+
+        assert_eq!(
+            installed(dir.path(), "p1").expect("installed"),
+            ["backup", "cleanup"]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use super::{installed, runs_dir, unit_name, SYSTEMD_PATH};
+use crate::system::PrivilegeWrapper;
+
+/// Run records kept per job; older ones are dropped as new runs finish.
+pub const MAX_RUNS: usize = 50;
+
+/// What a server knows about one installed job.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct JobStatus {
+    pub name: String,
+    /// The timer's `OnCalendar` schedule.
+    pub calendar: Option<String>,
+    pub running: bool,
+    /// Next scheduled start, as systemd prints it.
+    pub next_run: Option<String>,
+    /// Finished runs, newest first.
+    pub runs: Vec<JobRun>,
+}
+
+/// One finished run of a job.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct JobRun {
+    /// RFC 3339 start time; unknown when the run failed before starting.
+    pub started_at: Option<String>,
+    /// RFC 3339 end time.
+    pub finished_at: Option<String>,
+    /// systemd's result: `success`, `exit-code`, `signal`, `timeout`, ...
+    pub result: String,
+    pub exit_status: Option<i32>,
+}
+
+/// Parses a job's runs file, one `started finished result exit-status` line per run written
+/// by the service's `ExecStopPost`, into runs newest first.
+#[must_use]
+pub fn parse_runs(raw: &str) -> Vec<JobRun> {
+    let timestamp = |value: &str| {
+        let seconds = value.parse::<i64>().ok()?;
+        OffsetDateTime::from_unix_timestamp(seconds)
+            .ok()?
+            .format(&Rfc3339)
+            .ok()
+    };
+
+    raw.lines()
+        .rev()
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let [started, finished, result, exit_status] = fields[..] else {
+                return None;
+            };
+            Some(JobRun {
+                started_at: timestamp(started),
+                finished_at: timestamp(finished),
+                result: result.to_string(),
+                exit_status: exit_status.parse().ok(),
+            })
+        })
+        .take(MAX_RUNS)
+        .collect()
+}
+
+/// Reports the project's installed jobs with their recorded runs.
+///
+/// # Errors
+/// Returns an error if the installed units cannot be listed or systemd cannot be queried.
+pub fn status(project_id: &str, privilege_wrapper: &PrivilegeWrapper) -> Result<Vec<JobStatus>> {
+    let runs_dir = runs_dir(project_id);
+    installed(Path::new(SYSTEMD_PATH), project_id)?
+        .into_iter()
+        .map(|name| {
+            let unit = unit_name(project_id, &name);
+            let active_state = show(privilege_wrapper, "ActiveState", &format!("{unit}.service"))?;
+            let next_run = show(
+                privilege_wrapper,
+                "NextElapseUSecRealtime",
+                &format!("{unit}.timer"),
+            )?;
+            let calendar = fs::read_to_string(format!("{SYSTEMD_PATH}/{unit}.timer"))
+                .ok()
+                .and_then(|timer| {
+                    timer
+                        .lines()
+                        .find_map(|line| line.strip_prefix("OnCalendar="))
+                        .map(ToString::to_string)
+                });
+            let runs = fs::read_to_string(runs_dir.join(format!("{name}.runs")))
+                .map(|raw| parse_runs(&raw))
+                .unwrap_or_default();
+
+            Ok(JobStatus {
+                name,
+                calendar,
+                running: matches!(active_state.as_deref(), Some("activating" | "active")),
+                next_run,
+                runs,
+            })
+        })
+        .collect()
+}
+
+/// Starts a run of the project's job `name` now, without waiting for it to finish. Returns
+/// `false` when the server has no such job.
+///
+/// # Errors
+/// Returns an error if the installed units cannot be listed or systemd refuses to start the
+/// job.
+pub fn trigger(project_id: &str, name: &str, privilege_wrapper: &PrivilegeWrapper) -> Result<bool> {
+    if !installed(Path::new(SYSTEMD_PATH), project_id)?
+        .iter()
+        .any(|installed| installed == name)
+    {
+        return Ok(false);
+    }
+
+    privilege_wrapper.run(
+        "/usr/bin/systemctl",
+        &[
+            "start",
+            "--no-block",
+            &format!("{}.service", unit_name(project_id, name)),
+        ],
+    )?;
+    Ok(true)
+}
+
+fn show(
+    privilege_wrapper: &PrivilegeWrapper,
+    property: &str,
+    unit: &str,
+) -> Result<Option<String>> {
+    let output = privilege_wrapper.run(
+        "/usr/bin/systemctl",
+        &["show", &format!("--property={property}"), "--value", unit],
+    )?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    Ok((!value.is_empty() && value != "n/a").then_some(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_runs_lists_newest_first_and_skips_garbage() {
+        let runs =
+            parse_runs("1700000000 1700000005 success 0\nnot a run\n- 1700003600 exit-code 2\n");
+        assert_eq!(
+            runs,
+            [
+                JobRun {
+                    started_at: None,
+                    finished_at: Some("2023-11-14T23:13:20Z".to_string()),
+                    result: "exit-code".to_string(),
+                    exit_status: Some(2),
+                },
+                JobRun {
+                    started_at: Some("2023-11-14T22:13:20Z".to_string()),
+                    finished_at: Some("2023-11-14T22:13:25Z".to_string()),
+                    result: "success".to_string(),
+                    exit_status: Some(0),
+                },
+            ]
+        );
+    }
+}
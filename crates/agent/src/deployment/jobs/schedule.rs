@@ -0,0 +1,146 @@
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, bail, Result};
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+/// Converts a cron expression to a systemd `OnCalendar` value, e.g. `30 3 * * 1-5` to
+/// `Mon,Tue,Wed,Thu,Fri *-*-* 03:30:00`.
+///
+/// # Errors
+/// Returns an error if the expression does not have five valid fields, or restricts both the
+/// day of month and the day of week, which cron matches as either and systemd as both.
+pub fn on_calendar(schedule: &str) -> Result<String> {
+    let expression = match schedule.trim() {
+        "@hourly" => "0 * * * *",
+        "@daily" | "@midnight" => "0 0 * * *",
+        "@weekly" => "0 0 * * 0",
+        "@monthly" => "0 0 1 * *",
+        "@yearly" | "@annually" => "0 0 1 1 *",
+        other => other,
+    };
+    let fields = expression.split_whitespace().collect::<Vec<_>>();
+    let [minute, hour, day, month, weekday] = fields[..] else {
+        bail!("schedule must be a five-field cron expression: {schedule:?}");
+    };
+
+    let minute = field_values(minute, "minute", 0, 59)?;
+    let hour = field_values(hour, "hour", 0, 23)?;
+    let day = field_values(day, "day of month", 1, 31)?;
+    let month = field_values(month, "month", 1, 12)?;
+    let weekday = field_values(weekday, "day of week", 0, 7)?
+        .into_iter()
+        .map(|value| value % 7)
+        .collect::<BTreeSet<_>>();
+
+    let every_day = day.len() == 31;
+    let every_weekday = weekday.len() == 7;
+    if !every_day && !every_weekday {
+        bail!("schedule cannot restrict both the day of month and the day of week");
+    }
+
+    let weekdays = if every_weekday {
+        String::new()
+    } else {
+        let names = weekday
+            .iter()
+            .map(|value| WEEKDAYS[usize::try_from(*value).unwrap_or_default()])
+            .collect::<Vec<_>>();
+        format!("{} ", names.join(","))
+    };
+
+    Ok(format!(
+        "{weekdays}*-{}-{} {}:{}:00",
+        render_values(&month, 12),
+        render_values(&day, 31),
+        render_values(&hour, 24),
+        render_values(&minute, 60),
+    ))
+}
+
+/// Expands one cron field (`*`, `5`, `1-5`, `*/15`, `10-50/20`, comma lists) into its values.
+fn field_values(raw: &str, field: &str, min: u32, max: u32) -> Result<BTreeSet<u32>> {
+    let number = |value: &str| {
+        value
+            .parse::<u32>()
+            .map_err(|_| anyhow!("invalid {field} value {value:?}"))
+    };
+
+    let mut values = BTreeSet::new();
+    for item in raw.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, Some(number(step)?)),
+            None => (item, None),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (number(start)?, number(end)?)
+        } else {
+            let start = number(range)?;
+            (start, if step.is_some() { max } else { start })
+        };
+        if start < min || end > max || start > end {
+            bail!("{field} must be between {min} and {max}: {item:?}");
+        }
+        let step = match step {
+            Some(0) => bail!("{field} step cannot be zero: {item:?}"),
+            Some(step) => usize::try_from(step)?,
+            None => 1,
+        };
+        values.extend((start..=end).step_by(step));
+    }
+
+    Ok(values)
+}
+
+fn render_values(values: &BTreeSet<u32>, all: usize) -> String {
+    if values.len() == all {
+        return "*".to_string();
+    }
+
+    values
+        .iter()
+        .map(|value| format!("{value:02}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn on_calendar_converts_cron_expressions() {
+        for (schedule, calendar) in [
+            ("0 3 * * *", "*-*-* 03:00:00"),
+            ("*/15 * * * *", "*-*-* *:00,15,30,45:00"),
+            ("30 9 * * 1-5", "Mon,Tue,Wed,Thu,Fri *-*-* 09:30:00"),
+            ("0 0 1,15 * *", "*-*-01,15 00:00:00"),
+            ("0 12 * 6 7", "Sun *-06-* 12:00:00"),
+            ("10-50/20 8/8 * * *", "*-*-* 08,16:10,30,50:00"),
+            ("@weekly", "Sun *-*-* 00:00:00"),
+            ("@monthly", "*-*-01 00:00:00"),
+        ] {
+            assert_eq!(on_calendar(schedule).expect(schedule), calendar);
+        }
+    }
+
+    #[test]
+    fn on_calendar_rejects_invalid_expressions() {
+        for schedule in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "0 0 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "0 0 1 * 1",
+            "*-*-* 03:00:00",
+            "@reboot",
+        ] {
+            assert!(on_calendar(schedule).is_err(), "{schedule:?}");
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::deployment::processes;
+use crate::system::PrivilegeWrapper;
+
+/// Agent-owned record of the projects stopped on this server, with the process instances to
+/// bring back when they are started again. A project is stopped while its file exists.
+pub const STOPPED_STATE_PATH: &str = "/opt/nanoscale/data/stopped";
+const SYSTEMD_PATH: &str = "/etc/systemd/system";
+
+/// What to do with a deployed project's running units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProjectAction {
+    Start,
+    Stop,
+    Restart,
+}
+
+impl ProjectAction {
+    /// The action's name in routes, e.g. `/internal/projects/:id/stop`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Stop => "stop",
+            Self::Restart => "restart",
+        }
+    }
+}
+
+/// Applies `action` to the project's app and processes on this server. Servers that only
+/// route to the project's replicas have nothing to do. A `scale_to_zero` app is left to its
+/// socket: it is never started directly, and wakes on the next request.
+///
+/// # Errors
+/// Returns an error if the state file cannot be read or written, a `systemctl` command fails,
+/// or a stopped project is restarted.
+pub fn apply(
+    dir: &Path,
+    project_id: &str,
+    action: ProjectAction,
+    scale_to_zero: bool,
+    privilege_wrapper: &PrivilegeWrapper,
+) -> Result<()> {
+    if !Path::new(SYSTEMD_PATH)
+        .join(format!("nanoscale-{project_id}.service"))
+        .exists()
+    {
+        return Ok(());
+    }
+
+    match action {
+        ProjectAction::Start => start(dir, project_id, scale_to_zero, privilege_wrapper),
+        ProjectAction::Stop => stop(dir, project_id, privilege_wrapper),
+        ProjectAction::Restart => restart(dir, project_id, scale_to_zero, privilege_wrapper),
+    }
+}
+
+/// Whether the project is stopped on this server.
+#[must_use]
+pub fn is_stopped(dir: &Path, project_id: &str) -> bool {
+    state_file(dir, project_id).exists()
+}
+
+fn start(
+    dir: &Path,
+    project_id: &str,
+    scale_to_zero: bool,
+    privilege_wrapper: &PrivilegeWrapper,
+) -> Result<()> {
+    let instances = load(dir, project_id)?;
+    run_all(
+        &start_commands(project_id, scale_to_zero, &instances),
+        privilege_wrapper,
+    )?;
+
+    remove(dir, project_id)
+}
+
+/// `systemctl` calls bringing a stopped project back. A scale-to-zero app only gets its
+/// socket, so it starts on the first request rather than right away.
+fn start_commands(project_id: &str, scale_to_zero: bool, instances: &[String]) -> Vec<Vec<String>> {
+    let mut units = Vec::new();
+    if !scale_to_zero {
+        units.push(format!("nanoscale-{project_id}.service"));
+    }
+    units.push(format!("nanoscale-{project_id}.socket"));
+    units.extend(instances.iter().cloned());
+
+    units
+        .into_iter()
+        .map(|unit| vec!["enable".to_string(), "--now".to_string(), unit])
+        .collect()
+}
+
+fn stop(dir: &Path, project_id: &str, privilege_wrapper: &PrivilegeWrapper) -> Result<()> {
+    let mut instances = load(dir, project_id)?;
+    for unit in processes::enabled_instances(Path::new(SYSTEMD_PATH), project_id)? {
+        if !instances.contains(&unit) {
+            instances.push(unit);
+        }
+    }
+
+    keep_stopped(dir, project_id, &instances, privilege_wrapper)
+}
+
+fn restart(
+    dir: &Path,
+    project_id: &str,
+    scale_to_zero: bool,
+    privilege_wrapper: &PrivilegeWrapper,
+) -> Result<()> {
+    if is_stopped(dir, project_id) {
+        bail!("the project is stopped; start it instead");
+    }
+
+    let instances = processes::enabled_instances(Path::new(SYSTEMD_PATH), project_id)?;
+    run_all(
+        &restart_commands(project_id, scale_to_zero, &instances),
+        privilege_wrapper,
+    )
+}
+
+/// `systemctl` calls restarting a running project, its socket and proxy included. A
+/// scale-to-zero app is stopped instead of restarted, and the next request wakes it.
+fn restart_commands(
+    project_id: &str,
+    scale_to_zero: bool,
+    instances: &[String],
+) -> Vec<Vec<String>> {
+    let service_action = if scale_to_zero { "stop" } else { "restart" };
+    let mut commands = vec![
+        vec![
+            service_action.to_string(),
+            format!("nanoscale-{project_id}.service"),
+        ],
+        vec![
+            "stop".to_string(),
+            format!("nanoscale-{project_id}-proxy.service"),
+        ],
+        vec![
+            "restart".to_string(),
+            format!("nanoscale-{project_id}.socket"),
+        ],
+    ];
+    commands.extend(
+        instances
+            .iter()
+            .map(|unit| vec!["restart".to_string(), unit.clone()]),
+    );
+
+    commands
+}
+
+fn run_all(commands: &[Vec<String>], privilege_wrapper: &PrivilegeWrapper) -> Result<()> {
+    for command in commands {
+        let args = command.iter().map(String::as_str).collect::<Vec<_>>();
+        privilege_wrapper.run("/usr/bin/systemctl", &args)?;
+    }
+
+    Ok(())
+}
+
+/// Takes the project's app and processes down, disabled so neither its socket nor a reboot
+/// brings them back, and records `instances` as the process instances to start with it.
+///
+/// # Errors
+/// Returns an error if the state file cannot be written or a `systemctl` command fails.
+pub fn keep_stopped(
+    dir: &Path,
+    project_id: &str,
+    instances: &[String],
+    privilege_wrapper: &PrivilegeWrapper,
+) -> Result<()> {
+    let path = state_file(dir, project_id);
+    fs::create_dir_all(dir)?;
+    fs::write(&path, serde_json::to_vec(instances)?)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    for unit in processes::enabled_instances(Path::new(SYSTEMD_PATH), project_id)? {
+        privilege_wrapper.run("/usr/bin/systemctl", &["disable", "--now", &unit])?;
+    }
+    privilege_wrapper.run(
+        "/usr/bin/systemctl",
+        &[
+            "disable",
+            "--now",
+            &format!("nanoscale-{project_id}.socket"),
+        ],
+    )?;
+    privilege_wrapper.run(
+        "/usr/bin/systemctl",
+        &[
+            "disable",
+            "--now",
+            &format!("nanoscale-{project_id}.service"),
+        ],
+    )?;
+    privilege_wrapper.run(
+        "/usr/bin/systemctl",
+        &["stop", &format!("nanoscale-{project_id}-proxy.service")],
+    )?;
+
+    Ok(())
+}
+
+/// Forgets that the project is stopped, without starting anything.
+///
+/// # Errors
+/// Returns an error if the state file exists but cannot be removed.
+pub fn remove(dir: &Path, project_id: &str) -> Result<()> {
+    let path = state_file(dir, project_id);
+    match fs::remove_file(&path) {
+        Err(error) if error.kind() != ErrorKind::NotFound => {
+            Err(error).with_context(|| format!("failed to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn state_file(dir: &Path, project_id: &str) -> PathBuf {
+    dir.join(format!("{project_id}.json"))
+}
+
+fn load(dir: &Path, project_id: &str) -> Result<Vec<String>> {
+    let path = state_file(dir, project_id);
+    match fs::read(&path) {
+        Ok(contents) => serde_json::from_slice(&contents)
+            .with_context(|| format!("failed to parse {}", path.display())),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(error) => Err(error).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stopped_state_lists_the_instances_to_start_again() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        assert!(!is_stopped(dir.path(), "p1"));
+        assert!(load(dir.path(), "p1").expect("load").is_empty());
+
+        let state_dir = dir.path().join("stopped");
+        fs::create_dir_all(&state_dir).expect("state dir");
+        fs::write(
+            state_file(&state_dir, "p1"),
+            r#"["nanoscale-p1-worker@1.service"]"#,
+        )
+        .expect("write");
+        assert!(is_stopped(&state_dir, "p1"));
+        assert_eq!(
+            load(&state_dir, "p1").expect("load"),
+            vec!["nanoscale-p1-worker@1.service".to_string()]
+        );
+        assert!(restart(&state_dir, "p1", false, &PrivilegeWrapper::new()).is_err());
+
+        remove(&state_dir, "p1").expect("remove");
+        remove(&state_dir, "p1").expect("remove again");
+        assert!(!is_stopped(&state_dir, "p1"));
+    }
+
+    #[test]
+    fn start_leaves_a_scale_to_zero_app_to_its_socket() {
+        let instances = vec!["nanoscale-p1-worker@1.service".to_string()];
+        let enable = |unit: &str| vec!["enable".to_string(), "--now".to_string(), unit.to_string()];
+
+        assert_eq!(
+            start_commands("p1", false, &instances),
+            vec![
+                enable("nanoscale-p1.service"),
+                enable("nanoscale-p1.socket"),
+                enable("nanoscale-p1-worker@1.service"),
+            ]
+        );
+        assert_eq!(
+            start_commands("p1", true, &instances),
+            vec![
+                enable("nanoscale-p1.socket"),
+                enable("nanoscale-p1-worker@1.service"),
+            ]
+        );
+        assert_eq!(
+            start_commands("p1", true, &[]),
+            vec![enable("nanoscale-p1.socket")]
+        );
+    }
+
+    #[test]
+    fn restart_restarts_the_socket_and_only_stops_a_scale_to_zero_app() {
+        let instances = vec!["nanoscale-p1-worker@1.service".to_string()];
+        let command = |action: &str, unit: &str| vec![action.to_string(), unit.to_string()];
+
+        assert_eq!(
+            restart_commands("p1", false, &instances),
+            vec![
+                command("restart", "nanoscale-p1.service"),
+                command("stop", "nanoscale-p1-proxy.service"),
+                command("restart", "nanoscale-p1.socket"),
+                command("restart", "nanoscale-p1-worker@1.service"),
+            ]
+        );
+        assert_eq!(
+            restart_commands("p1", true, &[]),
+            vec![
+                command("stop", "nanoscale-p1.service"),
+                command("stop", "nanoscale-p1-proxy.service"),
+                command("restart", "nanoscale-p1.socket"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::deployment::build::ProjectRuntime;
+use crate::deployment::jobs::{self, CronJob};
+use crate::deployment::routing::RoutingConfig;
+use crate::deployment::systemd::{env_file, ResourceLimits};
+
+const TOML_MANIFEST: &str = "nanoscale.toml";
+const JSON_MANIFEST: &str = "nanoscale.json";
+const DEFAULT_HEALTH_CHECK_TIMEOUT_SECONDS: u64 = 60;
+const MAX_HEALTH_CHECK_TIMEOUT_SECONDS: u64 = 600;
+/// How long the release command may run when the manifest does not say.
+pub const DEFAULT_RELEASE_TIMEOUT_SECONDS: u64 = 600;
+const MAX_RELEASE_TIMEOUT_SECONDS: u64 = 3600;
+
+/// Repository-owned deployment settings read from `nanoscale.toml` (or `nanoscale.json`).
+///
+/// Only non-secret configuration belongs here; secrets stay in the orchestrator database.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectManifest {
+    pub runtime: Option<ProjectRuntime>,
+    #[serde(default)]
+    pub build: ManifestBuild,
+    #[serde(default)]
+    pub run: ManifestRun,
+    #[serde(default)]
+    pub release: ManifestRelease,
+    pub health_check: Option<HealthCheck>,
+    #[serde(default)]
+    pub resources: ResourceLimits,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub cron: Vec<CronJob>,
+    #[serde(default)]
+    pub routing: RoutingConfig,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestBuild {
+    pub install_command: Option<String>,
+    pub build_command: Option<String>,
+    pub output_directory: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestRun {
+    pub command: Option<String>,
+}
+
+/// Command run once per deployment, after the build and before the new release goes live.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestRelease {
+    pub command: Option<String>,
+    pub timeout_seconds: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HealthCheck {
+    pub path: String,
+    #[serde(default = "default_health_check_timeout")]
+    pub timeout_seconds: u64,
+}
+
+const fn default_health_check_timeout() -> u64 {
+    DEFAULT_HEALTH_CHECK_TIMEOUT_SECONDS
+}
+
+/// Reads and validates the manifest at the repository root, if there is one.
+///
+/// Returns the manifest together with the file name it was read from.
+///
+/// # Errors
+/// Returns an error if the manifest cannot be read, does not parse, or fails validation.
+pub fn load(repo_dir: &Path) -> Result<Option<(ProjectManifest, &'static str)>> {
+    let (file_name, raw) = if repo_dir.join(TOML_MANIFEST).is_file() {
+        (
+            TOML_MANIFEST,
+            fs::read_to_string(repo_dir.join(TOML_MANIFEST)),
+        )
+    } else if repo_dir.join(JSON_MANIFEST).is_file() {
+        (
+            JSON_MANIFEST,
+            fs::read_to_string(repo_dir.join(JSON_MANIFEST)),
+        )
+    } else {
+        return Ok(None);
+    };
+    let raw = raw.with_context(|| format!("failed to read {file_name}"))?;
+
+    let manifest = if file_name == TOML_MANIFEST {
+        toml::from_str::<ProjectManifest>(&raw).with_context(|| format!("invalid {file_name}"))?
+    } else {
+        serde_json::from_str::<ProjectManifest>(&raw)
+            .with_context(|| format!("invalid {file_name}"))?
+    };
+    manifest
+        .validate()
+        .with_context(|| format!("invalid {file_name}"))?;
+
+    Ok(Some((manifest, file_name)))
+}
+
+impl ProjectManifest {
+    fn validate(&self) -> Result<()> {
+        if self.resources.memory_max_mb == Some(0) || self.resources.cpu_quota_percent == Some(0) {
+            bail!("resources limits must be greater than zero");
+        }
+
+        if let Some(health_check) = &self.health_check {
+            if !health_check.path.starts_with('/')
+                || health_check.path.chars().any(char::is_whitespace)
+            {
+                bail!("health_check.path must be an absolute URL path without whitespace");
+            }
+            if health_check.timeout_seconds == 0
+                || health_check.timeout_seconds > MAX_HEALTH_CHECK_TIMEOUT_SECONDS
+            {
+                bail!(
+                    "health_check.timeout_seconds must be between 1 and {MAX_HEALTH_CHECK_TIMEOUT_SECONDS}"
+                );
+            }
+        }
+
+        if let Some(timeout_seconds) = self.release.timeout_seconds {
+            if timeout_seconds == 0 || timeout_seconds > MAX_RELEASE_TIMEOUT_SECONDS {
+                bail!(
+                    "release.timeout_seconds must be between 1 and {MAX_RELEASE_TIMEOUT_SECONDS}"
+                );
+            }
+        }
+
+        for key in self.env.keys() {
+            env_file::validate_key(key)?;
+        }
+
+        self.routing.validate()?;
+
+        jobs::validate(&self.cron)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_parses_toml_manifest() {
+        let repo = tempfile::tempdir().expect("tempdir");
+        fs::write(
+            repo.path().join(TOML_MANIFEST),
+            r#"
+runtime = "auto"
+
+[build]
+install_command = "bun install"
+build_command = "bun run build"
+
+[run]
+command = "bun run start"
+
+[release]
+command = "bunx prisma migrate deploy"
+timeout_seconds = 300
+
+[health_check]
+path = "/healthz"
+
+[resources]
+memory_max_mb = 256
+
+[env]
+LOG_LEVEL = "info"
+
+[[cron]]
+name = "nightly-cleanup"
+schedule = "0 3 * * *"
+command = "bun run cleanup"
+timeout_seconds = 900
+"#,
+        )
+        .expect("write");
+
+        let (manifest, file_name) = load(repo.path()).expect("load").expect("present");
+        assert_eq!(file_name, TOML_MANIFEST);
+        assert_eq!(manifest.runtime, Some(ProjectRuntime::Auto));
+        assert_eq!(manifest.run.command.as_deref(), Some("bun run start"));
+        assert_eq!(
+            manifest.release,
+            ManifestRelease {
+                command: Some("bunx prisma migrate deploy".to_string()),
+                timeout_seconds: Some(300),
+            }
+        );
+        assert_eq!(
+            manifest.health_check.expect("health check").timeout_seconds,
+            DEFAULT_HEALTH_CHECK_TIMEOUT_SECONDS
+        );
+        assert_eq!(manifest.resources.memory_max_mb, Some(256));
+        assert_eq!(
+            manifest.env.get("LOG_LEVEL").map(String::as_str),
+            Some("info")
+        );
+        assert_eq!(manifest.cron.len(), 1);
+        assert_eq!(manifest.cron[0].timeout_seconds, 900);
+    }
+
+    #[test]
+    fn load_accepts_json_and_reports_missing_manifest() {
+        let repo = tempfile::tempdir().expect("tempdir");
+        assert!(load(repo.path()).expect("load").is_none());
+
+        fs::write(
+            repo.path().join(JSON_MANIFEST),
+            r#"{"runtime":"container","resources":{"cpu_quota_percent":50}}"#,
+        )
+        .expect("write");
+        let (manifest, file_name) = load(repo.path()).expect("load").expect("present");
+        assert_eq!(file_name, JSON_MANIFEST);
+        assert_eq!(manifest.runtime, Some(ProjectRuntime::Container));
+        assert_eq!(manifest.resources.cpu_quota_percent, Some(50));
+    }
+
+    #[test]
+    fn load_rejects_unknown_keys_and_invalid_values() {
+        let repo = tempfile::tempdir().expect("tempdir");
+        let manifest_path = repo.path().join(TOML_MANIFEST);
+
+        fs::write(&manifest_path, "[build]\ninstal_command = \"npm ci\"\n").expect("write");
+        assert!(load(repo.path()).is_err());
+
+        fs::write(&manifest_path, "[env]\n\"BAD-KEY\" = \"x\"\n").expect("write");
+        assert!(load(repo.path()).is_err());
+
+        fs::write(&manifest_path, "[health_check]\npath = \"healthz\"\n").expect("write");
+        assert!(load(repo.path()).is_err());
+
+        fs::write(
+            &manifest_path,
+            "[release]\ncommand = \"bin/migrate\"\ntimeout_seconds = 0\n",
+        )
+        .expect("write");
+        assert!(load(repo.path()).is_err());
+
+        fs::write(
+            &manifest_path,
+            "[[cron]]\nname = \"cleanup\"\nschedule = \"*-*-* 03:00:00\"\ncommand = \"bun run cleanup\"\n",
+        )
+        .expect("write");
+        assert!(load(repo.path()).is_err());
+    }
+}
@@ -1,7 +1,26 @@
+pub mod access;
+pub mod acme;
 pub mod build;
+pub mod cert_renewal;
+pub mod detect;
+pub mod dns_provider;
+pub mod firewall;
 pub mod git;
+pub mod health;
 pub mod inactivity_monitor;
+pub mod jobs;
+pub mod lifecycle;
+pub mod manifest;
 pub mod nginx;
+pub mod pages;
+pub mod pipeline;
+pub mod private_network;
+pub mod processes;
+pub mod replicas;
+pub mod rollback;
+pub mod routing;
+pub mod streams;
 pub mod systemd;
 pub mod teardown;
 pub mod tls;
+pub mod volumes;
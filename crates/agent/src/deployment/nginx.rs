@@ -1,14 +1,25 @@
-use std::fs;
-use std::path::PathBuf;
+use std::path::Path;
 
-use anyhow::{anyhow, bail, Result};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
 
 use crate::system::PrivilegeWrapper;
 
-use crate::deployment::tls::ACME_WEBROOT_PATH;
+use crate::deployment::access::AccessControl;
+use crate::deployment::routing::RoutingConfig;
+use crate::deployment::streams::{self, StreamPort};
+
+mod coldstart;
+mod install;
+mod limits;
+mod locations;
+mod templates;
 
-const TMP_BASE_PATH: &str = "/opt/nanoscale/tmp";
 const NGINX_SITES_ENABLED: &str = "/etc/nginx/sites-enabled";
+/// http-level includes; holds the `limit_*_zone` definitions a site's locations refer to.
+const NGINX_CONF_D: &str = "/etc/nginx/conf.d";
+/// Included inside the top-level `stream` block; holds each project's TCP/UDP servers.
+const NGINX_STREAMS_ENABLED: &str = "/etc/nginx/streams-enabled";
 
 #[derive(Debug)]
 pub struct NginxGenerator;
@@ -16,92 +27,206 @@ pub struct NginxGenerator;
 #[derive(Clone, Copy, Debug)]
 pub enum NginxTlsMode<'a> {
     Disabled,
-    Enabled { domain: &'a str },
+    /// One certificate, named after `domain`, covers every routed domain.
+    Enabled {
+        domain: &'a str,
+    },
+    /// `domain` is served with the base domain's wildcard `certificate`; the other domains use
+    /// the project certificate `others`. `None` leaves that part on plain HTTP.
+    Wildcard {
+        domain: &'a str,
+        certificate: Option<&'a str>,
+        others: Option<&'a str>,
+    },
+}
+
+/// A hostname routed to a project and what nginx does with requests for it.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RoutedDomain {
+    pub domain: String,
+    /// `None` serves the project; otherwise requests are redirected to the primary domain.
+    #[serde(default)]
+    pub redirect: Option<DomainRedirect>,
+    /// The canonical domain redirects point at. Defaults to the first served domain.
+    #[serde(default)]
+    pub primary: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DomainRedirect {
+    /// 301 (permanent) or 308 (permanent, method preserving).
+    pub status: u16,
+    /// Keep the request path and query (`$request_uri`) instead of redirecting to `/`.
+    #[serde(default = "default_preserve_path")]
+    pub preserve_path: bool,
+}
+
+const fn default_preserve_path() -> bool {
+    true
+}
+
+impl RoutedDomain {
+    #[must_use]
+    pub fn serve(domain: impl Into<String>) -> Self {
+        Self {
+            domain: domain.into(),
+            redirect: None,
+            primary: false,
+        }
+    }
 }
 
 impl NginxGenerator {
     /// Generates an nginx site config and installs it into `sites-enabled`, then reloads nginx.
     ///
+    /// `domains` are the verified hostnames routed to the project. Served domains share one
+    /// server block together with a local fallback name; each redirecting domain gets its own
+    /// block pointing at the primary domain. `routing` adds the project's body size, timeout,
+    /// WebSocket, header, gzip and cache options to every block that proxies to the app, and
+    /// `access` restricts those blocks by IP and basic auth (the htpasswd file must already be
+    /// installed under [`HTPASSWD_PATH`](crate::deployment::access::HTPASSWD_PATH)).
+    ///
+    /// A rate limit also installs the project's zones into `conf.d`. The new files are checked
+    /// with `nginx -t` against a staged copy of the config before they are moved into place. If
+    /// nginx rejects them, nothing is installed, so the running config stays valid for every
+    /// other site on the host.
+    ///
     /// # Errors
-    /// Returns an error if the config cannot be written, the temp path is invalid, or privileged
-    /// install/reload commands fail.
+    /// Returns an error if a domain is invalid, the config cannot be written, the temp path is
+    /// invalid, nginx rejects the config, or privileged install/reload commands fail.
+    #[allow(clippy::too_many_arguments)]
     pub fn generate_and_install(
         project_id: &str,
         port: u16,
-        domain: Option<&str>,
+        backend_port: u16,
+        domains: &[RoutedDomain],
         tls_mode: NginxTlsMode<'_>,
+        routing: &RoutingConfig,
+        access: &AccessControl,
+        upstream: &[String],
         privilege_wrapper: &PrivilegeWrapper,
     ) -> Result<()> {
         let site_name = format!("nanoscale-{project_id}");
-        let server_name = Self::server_name(project_id, domain);
-        let tmp_conf_enabled_path =
-            PathBuf::from(format!("{TMP_BASE_PATH}/{site_name}.enabled.conf"));
-
-        if let Some(parent_dir) = tmp_conf_enabled_path.parent() {
-            fs::create_dir_all(parent_dir)?;
-        }
-
-        let conf_text = match tls_mode {
-            NginxTlsMode::Disabled => Self::nginx_http_template(&server_name, port),
-            NginxTlsMode::Enabled { domain } => {
-                Self::nginx_https_template(&server_name, domain, port)
-            }
-        };
-        fs::write(&tmp_conf_enabled_path, conf_text)?;
-
-        let target_enabled_conf_path = format!("{NGINX_SITES_ENABLED}/{site_name}.conf");
-
-        let tmp_enabled_conf_string = tmp_conf_enabled_path
-            .to_str()
-            .ok_or_else(|| anyhow!("invalid nginx temp enabled path"))?;
-
-        privilege_wrapper.run(
-            "/usr/bin/mv",
-            &[tmp_enabled_conf_string, &target_enabled_conf_path],
+        let conf_text = Self::render(
+            project_id,
+            port,
+            backend_port,
+            domains,
+            tls_mode,
+            routing,
+            access,
+            upstream,
         )?;
-        privilege_wrapper.run("/usr/sbin/service", &["nginx", "reload"])?;
+        // The zones include sorts before the site in nginx.conf, so it is checked and installed
+        // with it.
+        let files = [
+            (
+                format!("{NGINX_CONF_D}/{site_name}-zones.conf"),
+                Self::render_zones(project_id, routing),
+            ),
+            (
+                format!("{NGINX_SITES_ENABLED}/{site_name}.conf"),
+                Some(conf_text),
+            ),
+        ];
 
-        Ok(())
+        Self::install_checked(&site_name, &files, privilege_wrapper)
     }
 
-    fn server_name(project_id: &str, domain: Option<&str>) -> String {
-        let compact_id = project_id.replace('-', "");
-        let short_id = compact_id.chars().take(12).collect::<String>();
-        let fallback = format!("ns-{short_id}.local");
-
-        let domain = domain.map(str::trim).filter(|value| !value.is_empty());
-        match domain {
-            Some(domain) => format!("{domain} {fallback}"),
-            None => fallback,
+    /// Installs the project's TCP/UDP ports as nginx `stream` servers, or removes them when
+    /// `streams` is empty. Checked like a site before it is installed.
+    ///
+    /// # Errors
+    /// Returns an error if the config cannot be written, nginx rejects it, or privileged
+    /// install/reload commands fail.
+    pub fn install_streams(
+        project_id: &str,
+        streams: &[StreamPort],
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        let name = format!("nanoscale-{project_id}");
+        let target = format!("{NGINX_STREAMS_ENABLED}/{name}.conf");
+        if streams.is_empty() && !Path::new(&target).exists() {
+            return Ok(());
         }
-    }
 
-    fn nginx_http_template(server_name: &str, port: u16) -> String {
-        let backend_port = backend_port(port).unwrap_or(port);
-        format!(
-            "server {{\n    listen 80;\n    server_name {server_name};\n\n    location ^~ /.well-known/acme-challenge/ {{\n        root {ACME_WEBROOT_PATH};\n    }}\n\n    location / {{\n        proxy_http_version 1.1;\n        proxy_set_header Host $host;\n        proxy_set_header X-Real-IP $remote_addr;\n        proxy_set_header X-Forwarded-For $proxy_add_x_forwarded_for;\n        proxy_set_header X-Forwarded-Proto $scheme;\n\n        proxy_connect_timeout 2s;\n        proxy_pass http://127.0.0.1:{backend_port};\n\n        proxy_intercept_errors on;\n        error_page 502 503 504 = @nanoscale_coldstart;\n    }}\n\n    location @nanoscale_coldstart {{\n        proxy_http_version 1.1;\n        proxy_set_header Host $host;\n        proxy_set_header X-Real-IP $remote_addr;\n        proxy_set_header X-Forwarded-For $proxy_add_x_forwarded_for;\n        proxy_set_header X-Forwarded-Proto $scheme;\n\n        proxy_next_upstream error timeout;\n        proxy_next_upstream_tries 120;\n        proxy_next_upstream_timeout 60s;\n        proxy_connect_timeout 2s;\n\n        proxy_pass http://127.0.0.1:{port};\n    }}\n}}\n"
+        Self::install_checked(
+            &format!("{name} streams"),
+            &[(target, streams::render(project_id, streams))],
+            privilege_wrapper,
         )
     }
+}
 
-    fn nginx_https_template(server_name: &str, domain: &str, port: u16) -> String {
-        let cert_path = format!("/etc/letsencrypt/live/{domain}/fullchain.pem");
-        let key_path = format!("/etc/letsencrypt/live/{domain}/privkey.pem");
-        let backend_port = backend_port(port).unwrap_or(port);
+/// The domain redirects point at: the served domain marked primary, else the first served one.
+#[must_use]
+pub fn primary_domain(domains: &[RoutedDomain]) -> Option<&str> {
+    let mut served = domains.iter().filter(|domain| domain.redirect.is_none());
+    served
+        .clone()
+        .find(|domain| domain.primary)
+        .or_else(|| served.next())
+        .map(|domain| domain.domain.as_str())
+}
 
-        format!(
-            "server {{\n    listen 80;\n    server_name {server_name};\n\n    location ^~ /.well-known/acme-challenge/ {{\n        root {ACME_WEBROOT_PATH};\n    }}\n\n    location / {{\n        return 301 https://$host$request_uri;\n    }}\n}}\n\nserver {{\n    listen 443 ssl;\n    server_name {server_name};\n\n    ssl_certificate {cert_path};\n    ssl_certificate_key {key_path};\n\n    location / {{\n        proxy_http_version 1.1;\n        proxy_set_header Host $host;\n        proxy_set_header X-Real-IP $remote_addr;\n        proxy_set_header X-Forwarded-For $proxy_add_x_forwarded_for;\n        proxy_set_header X-Forwarded-Proto $scheme;\n\n        proxy_connect_timeout 2s;\n        proxy_pass http://127.0.0.1:{backend_port};\n\n        proxy_intercept_errors on;\n        error_page 502 503 504 = @nanoscale_coldstart;\n    }}\n\n    location @nanoscale_coldstart {{\n        proxy_http_version 1.1;\n        proxy_set_header Host $host;\n        proxy_set_header X-Real-IP $remote_addr;\n        proxy_set_header X-Forwarded-For $proxy_add_x_forwarded_for;\n        proxy_set_header X-Forwarded-Proto $scheme;\n\n        proxy_next_upstream error timeout;\n        proxy_next_upstream_tries 120;\n        proxy_next_upstream_timeout 60s;\n        proxy_connect_timeout 2s;\n\n        proxy_pass http://127.0.0.1:{port};\n    }}\n}}\n"
-        )
+/// Checks every routed domain before it reaches an nginx config.
+///
+/// # Errors
+/// Returns an error for malformed or duplicate hostnames, unsupported redirect codes, a
+/// redirecting primary domain, or more than one primary domain.
+pub fn validate_routed_domains(domains: &[RoutedDomain]) -> Result<()> {
+    let mut seen = Vec::with_capacity(domains.len());
+    for routed in domains {
+        validate_domain_name(&routed.domain)?;
+        if seen.contains(&routed.domain.as_str()) {
+            bail!("domain {} is listed twice", routed.domain);
+        }
+        seen.push(&routed.domain);
+
+        if let Some(redirect) = routed.redirect {
+            if !matches!(redirect.status, 301 | 308) {
+                bail!(
+                    "redirect status for {} must be 301 or 308, got {}",
+                    routed.domain,
+                    redirect.status
+                );
+            }
+            if routed.primary {
+                bail!("primary domain {} cannot redirect", routed.domain);
+            }
+        }
     }
+
+    if domains.iter().filter(|domain| domain.primary).count() > 1 {
+        bail!("only one domain can be primary");
+    }
+
+    Ok(())
 }
 
-fn backend_port(front_port: u16) -> Result<u16> {
-    let candidate = u32::from(front_port) + 10_000;
-    if candidate > u32::from(u16::MAX) {
-        bail!("cannot derive backend port from {front_port}; {candidate} exceeds 65535");
+/// Accepts lowercase DNS hostnames only: dot-separated labels of letters, digits and inner
+/// hyphens. Anything else could break out of the nginx directive it is written into.
+///
+/// # Errors
+/// Returns an error naming the invalid domain.
+pub fn validate_domain_name(domain: &str) -> Result<()> {
+    let labels = domain.split('.').collect::<Vec<_>>();
+    let valid = domain.len() <= 253
+        && labels.len() >= 2
+        && labels.iter().all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|character| {
+                    character.is_ascii_lowercase() || character.is_ascii_digit() || character == '-'
+                })
+        });
+
+    if !valid {
+        bail!("invalid domain name: {domain:?}");
     }
 
-    u16::try_from(candidate)
-        .map_err(|error| anyhow!("cannot derive backend port from {front_port}: {error}"))
+    Ok(())
 }
 
 #[cfg(test)]
@@ -109,42 +234,47 @@ mod tests {
     use super::*;
 
     #[test]
-    fn server_name_includes_domain_and_fallback() {
-        let name = NginxGenerator::server_name(
-            "123e4567-e89b-12d3-a456-426614174000",
-            Some("app.example.com"),
-        );
-        assert!(name.contains("app.example.com"));
-        assert!(name.contains("ns-"));
-        assert!(name.contains(".local"));
-    }
-
-    #[test]
-    fn server_name_falls_back_when_domain_missing_or_blank() {
-        let missing = NginxGenerator::server_name("p1", None);
-        let blank = NginxGenerator::server_name("p1", Some("   "));
-        assert_eq!(missing, blank);
-        assert!(std::path::Path::new(&missing)
-            .extension()
-            .is_some_and(|ext| ext.eq_ignore_ascii_case("local")));
-    }
+    fn validate_routed_domains_rejects_bad_names_and_redirects() {
+        let redirect = |status| {
+            Some(DomainRedirect {
+                status,
+                preserve_path: true,
+            })
+        };
 
-    #[test]
-    fn http_template_contains_acme_root_and_proxy_pass() {
-        let template = NginxGenerator::nginx_http_template("example", 3100);
-        assert!(template.contains(ACME_WEBROOT_PATH));
-        assert!(template.contains("proxy_pass http://127.0.0.1:13100"));
-        assert!(template.contains("error_page 502 503 504 = @nanoscale_coldstart"));
-        assert!(template.contains("proxy_pass http://127.0.0.1:3100"));
-    }
+        assert!(validate_routed_domains(&[RoutedDomain::serve("app.example.com;")]).is_err());
+        assert!(validate_routed_domains(&[RoutedDomain::serve("App.example.com")]).is_err());
+        assert!(validate_routed_domains(&[
+            RoutedDomain::serve("a.example.com"),
+            RoutedDomain::serve("a.example.com"),
+        ])
+        .is_err());
+        assert!(validate_routed_domains(&[RoutedDomain {
+            redirect: redirect(302),
+            ..RoutedDomain::serve("a.example.com")
+        }])
+        .is_err());
+        assert!(validate_routed_domains(&[RoutedDomain {
+            redirect: redirect(301),
+            primary: true,
+            ..RoutedDomain::serve("a.example.com")
+        }])
+        .is_err());
 
-    #[test]
-    fn https_template_contains_cert_paths_and_redirect() {
-        let template = NginxGenerator::nginx_https_template("example", "app.example.com", 3100);
-        assert!(template.contains("/etc/letsencrypt/live/app.example.com/fullchain.pem"));
-        assert!(template.contains("return 301 https://$host$request_uri"));
-        assert!(template.contains("proxy_pass http://127.0.0.1:13100"));
-        assert!(template.contains("error_page 502 503 504 = @nanoscale_coldstart"));
-        assert!(template.contains("proxy_pass http://127.0.0.1:3100"));
+        let only_redirects = [RoutedDomain {
+            redirect: redirect(301),
+            ..RoutedDomain::serve("a.example.com")
+        }];
+        assert!(NginxGenerator::render(
+            "p1",
+            3100,
+            13_100,
+            &only_redirects,
+            NginxTlsMode::Disabled,
+            &RoutingConfig::default(),
+            &AccessControl::default(),
+            &[]
+        )
+        .is_err());
     }
 }
@@ -0,0 +1,196 @@
+use std::fmt::Write as _;
+
+use crate::deployment::pages::{MAINTENANCE_PAGE_FILE, WAKING_PAGE_FILE};
+
+use super::locations::SiteOptions;
+
+pub(super) const MAINTENANCE_RETRY_AFTER_SECONDS: u32 = 300;
+
+/// Where the app locations send requests and what they do when that fails.
+pub(super) struct Backend {
+    /// `proxy_pass` target without the scheme.
+    pub(super) target: String,
+    /// Directives closing every app location.
+    pub(super) on_error: String,
+    /// Named locations the app locations fall back to.
+    pub(super) fallbacks: Vec<String>,
+}
+
+/// A lone replica: requests go straight to the app's backend port, and failures fall back to
+/// `@nanoscale_coldstart`, which wakes the app through its socket on `port`. With a waking
+/// page the fallback stops retrying the connection after `wait_seconds` and answers with the
+/// page instead; a request the app has accepted keeps the configured read timeout.
+pub(super) fn coldstart_backend(
+    port: u16,
+    options: &SiteOptions,
+    proxy_headers: &str,
+    read_timeout: &str,
+    response_headers: &str,
+) -> Backend {
+    let backend_port = options.backend_port;
+    let (timeouts, waking) = match &options.routing.waking_page {
+        Some(waking_page) => {
+            let wait = waking_page.wait_seconds;
+            (
+                format!("        proxy_next_upstream_timeout {wait}s;\n        proxy_connect_timeout 2s;\n{read_timeout}        error_page 502 504 =503 @nanoscale_waking;\n"),
+                Some(status_page_location(
+                    "@nanoscale_waking",
+                    &options.pages,
+                    WAKING_PAGE_FILE,
+                    waking_page.refresh_seconds,
+                )),
+            )
+        }
+        None => (
+            format!("        proxy_next_upstream_timeout 60s;\n        proxy_connect_timeout 2s;\n{read_timeout}"),
+            None,
+        ),
+    };
+    // nginx only follows the fallback's own error_page into the waking page when the location
+    // the request failed in allows a second redirect.
+    let recursive_error_pages = if waking.is_some() {
+        "        recursive_error_pages on;\n"
+    } else {
+        ""
+    };
+
+    Backend {
+        target: format!("127.0.0.1:{backend_port}"),
+        on_error: format!("        proxy_intercept_errors on;\n        error_page 502 503 504 = @nanoscale_coldstart;\n{recursive_error_pages}"),
+        fallbacks: [format!(
+            "    location @nanoscale_coldstart {{\n{proxy_headers}\n        proxy_next_upstream error timeout;\n        proxy_next_upstream_tries 120;\n{timeouts}\n        proxy_pass http://127.0.0.1:{port};\n{response_headers}    }}\n"
+        )]
+        .into_iter()
+        .chain(waking)
+        .collect(),
+    }
+}
+
+pub(super) fn upstream_name(zone: &str) -> String {
+    format!("{zone}_app")
+}
+
+/// The `upstream` balancing over the project's replicas, or nothing for a lone replica. A
+/// replica failing three times in ten seconds is skipped for the next ten.
+pub(super) fn upstream_block(options: &SiteOptions) -> String {
+    if options.upstream.is_empty() {
+        return String::new();
+    }
+
+    let mut servers = String::new();
+    for server in &options.upstream {
+        let _ = writeln!(servers, "    server {server} max_fails=3 fail_timeout=10s;");
+    }
+    format!(
+        "upstream {} {{\n{servers}}}\n\n",
+        upstream_name(&options.zone)
+    )
+}
+
+/// Sends requests to `@nanoscale_maintenance` while the maintenance page exists. 418 never comes
+/// from the app here, so it cannot be mistaken for a cold start.
+pub(super) fn maintenance_check(pages: &str) -> String {
+    format!(
+        "        if (-f {pages}/{MAINTENANCE_PAGE_FILE}) {{\n            return 418;\n        }}\n        error_page 418 =503 @nanoscale_maintenance;\n\n"
+    )
+}
+
+/// A named location serving `file` from the project's status page directory; the status comes
+/// from the `error_page` that redirected here.
+pub(super) fn status_page_location(
+    name: &str,
+    pages: &str,
+    file: &str,
+    retry_after: u32,
+) -> String {
+    format!(
+        "    location {name} {{\n        root {pages};\n        add_header Retry-After {retry_after} always;\n        add_header Cache-Control \"no-store\" always;\n        try_files /{file} =503;\n    }}\n"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::deployment::access::AccessControl;
+    use crate::deployment::nginx::{NginxGenerator, NginxTlsMode, RoutedDomain};
+    use crate::deployment::routing::{CachePath, RoutingConfig, WakingPage};
+
+    #[test]
+    fn snapshot_http_site_balancing_over_replicas() {
+        insta::assert_snapshot!(NginxGenerator::render(
+            "p1",
+            3100,
+            13_100,
+            &[RoutedDomain::serve("app.example.com")],
+            NginxTlsMode::Disabled,
+            &RoutingConfig::default(),
+            &AccessControl::default(),
+            &[
+                "127.0.0.1:3100".to_string(),
+                "10.0.0.2:3100".to_string(),
+                "10.0.0.3:3100".to_string(),
+            ],
+        )
+        .expect("render"));
+    }
+
+    #[test]
+    fn snapshot_http_site_with_waking_page() {
+        let routing = RoutingConfig {
+            read_timeout_seconds: Some(300),
+            waking_page: Some(WakingPage {
+                wait_seconds: 8,
+                refresh_seconds: 4,
+                ..WakingPage::default()
+            }),
+            ..RoutingConfig::default()
+        };
+        insta::assert_snapshot!(NginxGenerator::render(
+            "p1",
+            3100,
+            13_100,
+            &[RoutedDomain::serve("app.example.com")],
+            NginxTlsMode::Disabled,
+            &routing,
+            &AccessControl::default(),
+            &[]
+        )
+        .expect("render"));
+    }
+
+    #[test]
+    fn maintenance_check_guards_app_locations_but_not_acme_challenge() {
+        let routing = RoutingConfig {
+            cache_paths: vec![CachePath {
+                path: "/assets/".to_string(),
+                max_age_seconds: 600,
+            }],
+            ..RoutingConfig::default()
+        };
+        let conf = NginxGenerator::render(
+            "p1",
+            3100,
+            13_100,
+            &[RoutedDomain::serve("app.example.com")],
+            NginxTlsMode::Enabled {
+                domain: "app.example.com",
+            },
+            &routing,
+            &AccessControl::default(),
+            &[],
+        )
+        .expect("render");
+
+        assert_eq!(
+            conf.matches("if (-f /opt/nanoscale/pages/p1/maintenance.html) {")
+                .count(),
+            2
+        );
+        assert!(conf.contains(
+            "    location ^~ /.well-known/acme-challenge/ {\n        root /opt/nanoscale/acme;\n    }"
+        ));
+        assert!(conf.contains(
+            "    location @nanoscale_maintenance {\n        root /opt/nanoscale/pages/p1;"
+        ));
+        assert!(!conf.contains("@nanoscale_waking"));
+    }
+}
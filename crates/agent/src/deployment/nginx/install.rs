@@ -0,0 +1,204 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::system::PrivilegeWrapper;
+
+use super::NginxGenerator;
+
+const TMP_BASE_PATH: &str = "/opt/nanoscale/tmp";
+
+/// Staged copies of the files about to be installed, laid out like the nginx directories; the
+/// check helper tests them against the live config.
+const NGINX_STAGE_PATH: &str = "/opt/nanoscale/tmp/nginx-stage";
+
+/// Serialises staging, checks, installs and reloads so one deployment cannot install or reload
+/// a config another one is still checking.
+static NGINX_INSTALL_LOCK: Mutex<()> = Mutex::new(());
+
+impl NginxGenerator {
+    /// Checks `files` against the live config with `nginx -t` on a staged copy, then installs
+    /// them and reloads. A rejected config is never moved into place, so nginx keeps running
+    /// with the previous files.
+    pub(super) fn install_checked(
+        name: &str,
+        files: &[(String, Option<String>)],
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        let _guard = NGINX_INSTALL_LOCK
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        Self::stage(Path::new(NGINX_STAGE_PATH), files)?;
+        if let Err(error) = privilege_wrapper.run("/usr/local/sbin/nanoscale-nginx-check", &[]) {
+            return Err(anyhow!(
+                "nginx rejected the config for {name} (nothing was installed): {}",
+                nginx_error(&error)
+            ));
+        }
+
+        for (target, contents) in files {
+            Self::replace_file(target, contents.as_deref(), privilege_wrapper)?;
+        }
+        privilege_wrapper.run("/usr/sbin/service", &["nginx", "reload"])?;
+
+        Ok(())
+    }
+
+    /// Reloads nginx for changes made outside [`Self::install_checked`], such as renewed
+    /// certificates or removed sites. Takes the install lock, so the reload never picks up a
+    /// site another deployment is halfway through installing.
+    ///
+    /// # Errors
+    /// Returns an error if the privileged reload fails.
+    pub fn reload(privilege_wrapper: &PrivilegeWrapper) -> Result<()> {
+        let _guard = NGINX_INSTALL_LOCK
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        privilege_wrapper.run("/usr/sbin/service", &["nginx", "reload"])?;
+
+        Ok(())
+    }
+
+    /// Writes `files` under `stage_dir` as `<nginx dir>/<file>`, replacing any earlier stage. A
+    /// file about to be removed is staged empty, which nginx reads like a missing one.
+    fn stage(stage_dir: &Path, files: &[(String, Option<String>)]) -> Result<()> {
+        match fs::remove_dir_all(stage_dir) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => return Err(error.into()),
+            _ => {}
+        }
+        for (target, contents) in files {
+            let target = Path::new(target);
+            let (Some(dir), Some(file_name)) = (
+                target.parent().and_then(Path::file_name),
+                target.file_name(),
+            ) else {
+                bail!("invalid nginx config path {}", target.display());
+            };
+            let nginx_dir = stage_dir.join(dir);
+            fs::create_dir_all(&nginx_dir)?;
+            fs::write(
+                nginx_dir.join(file_name),
+                contents.as_deref().unwrap_or_default(),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Installs `contents` at `target` through an agent-owned temp file, or removes `target`
+    /// when there is nothing to install.
+    fn replace_file(
+        target: &str,
+        contents: Option<&str>,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        match contents {
+            Some(contents) => {
+                let file_name = Path::new(target)
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .ok_or_else(|| anyhow!("invalid nginx config path {target}"))?;
+                let tmp_path = PathBuf::from(format!("{TMP_BASE_PATH}/{file_name}.enabled.conf"));
+                Self::install_file(&tmp_path, contents, target, privilege_wrapper)
+            }
+            None if Path::new(target).exists() => privilege_wrapper
+                .run("/usr/bin/rm", &["-f", target])
+                .map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes `conf_text` to the agent-owned temp path and moves it over `target`.
+    fn install_file(
+        tmp_path: &Path,
+        conf_text: &str,
+        target: &str,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        if let Some(parent_dir) = tmp_path.parent() {
+            fs::create_dir_all(parent_dir)?;
+        }
+
+        fs::write(tmp_path, conf_text)?;
+
+        let tmp_path_string = tmp_path
+            .to_str()
+            .ok_or_else(|| anyhow!("invalid nginx temp enabled path"))?;
+
+        privilege_wrapper.run("/usr/bin/mv", &[tmp_path_string, target])?;
+        Ok(())
+    }
+}
+
+/// The `nginx -t` diagnostics (`[emerg] ...` lines) from a failed privileged run, falling back to
+/// the whole error when nginx printed nothing recognisable.
+fn nginx_error(error: &anyhow::Error) -> String {
+    let message = format!("{error:#}");
+    let diagnostics = message
+        .lines()
+        .flat_map(|line| line.split("nginx: "))
+        .filter(|part| part.starts_with('['))
+        .map(str::trim)
+        .collect::<Vec<_>>();
+
+    if diagnostics.is_empty() {
+        message
+    } else {
+        diagnostics.join("; ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stage_lays_out_files_like_the_nginx_directories() {
+        let tempdir = tempfile::tempdir().expect("tempdir");
+        let stage_dir = tempdir.path().join("nginx-stage");
+        fs::create_dir_all(stage_dir.join("conf.d")).expect("mkdir");
+        fs::write(stage_dir.join("conf.d/nanoscale-old-zones.conf"), "stale").expect("write");
+
+        NginxGenerator::stage(
+            &stage_dir,
+            &[
+                (
+                    "/etc/nginx/conf.d/nanoscale-p1-zones.conf".to_string(),
+                    None,
+                ),
+                (
+                    "/etc/nginx/sites-enabled/nanoscale-p1.conf".to_string(),
+                    Some("server {}\n".to_string()),
+                ),
+            ],
+        )
+        .expect("stage");
+
+        assert_eq!(
+            fs::read_to_string(stage_dir.join("sites-enabled/nanoscale-p1.conf")).expect("site"),
+            "server {}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(stage_dir.join("conf.d/nanoscale-p1-zones.conf")).expect("zones"),
+            ""
+        );
+        assert!(!stage_dir.join("conf.d/nanoscale-old-zones.conf").exists());
+    }
+
+    #[test]
+    fn nginx_error_extracts_emerg_lines() {
+        let error = anyhow!(
+            "privileged command failed: /usr/sbin/nginx [\"-t\"]; stdout: ; stderr: nginx: [emerg] cannot load certificate \"/opt/nanoscale/certs/a.com/fullchain.pem\"\nnginx: configuration file /etc/nginx/nginx.conf test failed\n"
+        );
+        assert_eq!(
+            nginx_error(&error),
+            "[emerg] cannot load certificate \"/opt/nanoscale/certs/a.com/fullchain.pem\""
+        );
+
+        let other = anyhow!("sudo: a password is required");
+        assert_eq!(nginx_error(&other), "sudo: a password is required");
+    }
+}
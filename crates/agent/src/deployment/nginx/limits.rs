@@ -0,0 +1,126 @@
+use crate::deployment::routing::{RateLimit, RoutingConfig};
+
+use super::NginxGenerator;
+
+impl NginxGenerator {
+    /// The http-level `limit_req_zone`/`limit_conn_zone` definitions the site refers to, or
+    /// `None` when the project has no rate limit.
+    pub(super) fn render_zones(project_id: &str, routing: &RoutingConfig) -> Option<String> {
+        let rate_limit = routing.rate_limit.as_ref()?;
+        let zone = zone_name(project_id);
+        let req_zone = format!(
+            "limit_req_zone $binary_remote_addr zone={zone}_req:10m rate={}r/s;\n",
+            rate_limit.requests_per_second
+        );
+        let conn_zone = rate_limit
+            .max_connections
+            .map(|_| format!("limit_conn_zone $binary_remote_addr zone={zone}_conn:10m;\n"))
+            .unwrap_or_default();
+
+        Some(req_zone + &conn_zone)
+    }
+}
+
+pub(super) fn zone_name(project_id: &str) -> String {
+    format!("nanoscale_{}", project_id.replace('-', "_"))
+}
+
+/// `limit_req`/`limit_conn` directives for a location, followed by a blank line.
+pub(super) fn limit_directives(zone: &str, rate_limit: &RateLimit) -> String {
+    let status = rate_limit.status;
+    let mut directives = vec![if rate_limit.burst > 0 {
+        format!(
+            "limit_req zone={zone}_req burst={} nodelay;",
+            rate_limit.burst
+        )
+    } else {
+        format!("limit_req zone={zone}_req;")
+    }];
+    directives.push(format!("limit_req_status {status};"));
+    if let Some(max_connections) = rate_limit.max_connections {
+        directives.push(format!("limit_conn {zone}_conn {max_connections};"));
+        directives.push(format!("limit_conn_status {status};"));
+    }
+
+    directives
+        .iter()
+        .map(|directive| ["        ", directive, "\n"].concat())
+        .chain(["\n".to_string()])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deployment::access::AccessControl;
+    use crate::deployment::nginx::{NginxTlsMode, RoutedDomain};
+    use crate::deployment::routing::CachePath;
+
+    #[test]
+    fn snapshot_http_site_with_path_rate_limit() {
+        let routing = RoutingConfig {
+            cache_paths: vec![CachePath {
+                path: "/api/static/".to_string(),
+                max_age_seconds: 600,
+            }],
+            rate_limit: Some(RateLimit {
+                requests_per_second: 10,
+                burst: 20,
+                max_connections: Some(5),
+                paths: vec!["/api/".to_string()],
+                status: 429,
+            }),
+            ..RoutingConfig::default()
+        };
+        let zones = NginxGenerator::render_zones("123e4567-e89b", &routing).expect("zones");
+        assert_eq!(
+            zones,
+            "limit_req_zone $binary_remote_addr zone=nanoscale_123e4567_e89b_req:10m rate=10r/s;\nlimit_conn_zone $binary_remote_addr zone=nanoscale_123e4567_e89b_conn:10m;\n"
+        );
+        insta::assert_snapshot!(NginxGenerator::render(
+            "123e4567-e89b",
+            3100,
+            13_100,
+            &[RoutedDomain::serve("app.example.com")],
+            NginxTlsMode::Disabled,
+            &routing,
+            &AccessControl::default(),
+            &[]
+        )
+        .expect("render"));
+    }
+
+    #[test]
+    fn site_wide_rate_limit_covers_root_location_without_zones_for_connections() {
+        let routing = RoutingConfig {
+            rate_limit: Some(RateLimit {
+                requests_per_second: 5,
+                burst: 0,
+                max_connections: None,
+                paths: Vec::new(),
+                status: 503,
+            }),
+            ..RoutingConfig::default()
+        };
+        let conf = NginxGenerator::render(
+            "p1",
+            3100,
+            13_100,
+            &[RoutedDomain::serve("app.example.com")],
+            NginxTlsMode::Disabled,
+            &routing,
+            &AccessControl::default(),
+            &[],
+        )
+        .expect("render");
+
+        assert!(conf.contains(
+            "        error_page 418 =503 @nanoscale_maintenance;\n\n        limit_req zone=nanoscale_p1_req;\n        limit_req_status 503;\n\n"
+        ));
+        assert!(!conf.contains("limit_conn"));
+        assert!(!NginxGenerator::render_zones("p1", &routing)
+            .expect("zones")
+            .contains("limit_conn_zone"));
+        assert!(NginxGenerator::render_zones("p1", &RoutingConfig::default()).is_none());
+    }
+}
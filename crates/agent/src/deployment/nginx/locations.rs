@@ -0,0 +1,293 @@
+use std::path::Path;
+
+use crate::deployment::access::{htpasswd_file, AccessControl, HTPASSWD_PATH};
+use crate::deployment::pages::{pages_dir, MAINTENANCE_PAGE_FILE, PAGES_PATH};
+use crate::deployment::routing::RoutingConfig;
+
+use super::coldstart::{
+    coldstart_backend, maintenance_check, status_page_location, upstream_name, Backend,
+    MAINTENANCE_RETRY_AFTER_SECONDS,
+};
+use super::limits::{limit_directives, zone_name};
+
+/// Per-project options rendered into every server block that proxies to the app.
+#[derive(Debug)]
+pub(super) struct SiteOptions {
+    pub(super) routing: RoutingConfig,
+    /// Access directives for the app's locations; empty when the project is public.
+    pub(super) access: String,
+    /// The same directives for the ACME challenge location; empty when it is exempt.
+    pub(super) acme_access: String,
+    /// Prefix of the project's rate limit zone names.
+    pub(super) zone: String,
+    /// The project's status page directory under [`PAGES_PATH`].
+    pub(super) pages: String,
+    /// `ip:port` of the project's replicas; requests are balanced over them through an
+    /// `upstream` when non-empty, and sent to the local app otherwise.
+    pub(super) upstream: Vec<String>,
+    /// Port the local app listens on behind its socket.
+    pub(super) backend_port: u16,
+}
+
+impl SiteOptions {
+    pub(super) fn new(
+        project_id: &str,
+        routing: &RoutingConfig,
+        access: &AccessControl,
+        upstream: &[String],
+        backend_port: u16,
+    ) -> Self {
+        let directives =
+            access.location_directives(&htpasswd_file(Path::new(HTPASSWD_PATH), project_id));
+        Self {
+            routing: routing.clone(),
+            acme_access: if access.rules.exempt_acme_challenge {
+                String::new()
+            } else {
+                directives.clone()
+            },
+            access: directives,
+            zone: zone_name(project_id),
+            pages: pages_dir(Path::new(PAGES_PATH), project_id)
+                .display()
+                .to_string(),
+            upstream: upstream.to_vec(),
+            backend_port,
+        }
+    }
+}
+
+/// Server-level directives for body size and compression, one indented line each.
+pub(super) fn server_directives(routing: &RoutingConfig) -> String {
+    let mut directives = Vec::new();
+    if let Some(max_body_size_mb) = routing.max_body_size_mb {
+        directives.push(format!("client_max_body_size {max_body_size_mb}m;"));
+    }
+    match routing.gzip {
+        Some(true) => directives.extend(
+            [
+                "gzip on;",
+                "gzip_vary on;",
+                "gzip_proxied any;",
+                "gzip_min_length 1024;",
+                "gzip_types text/plain text/css text/xml application/javascript application/json application/xml image/svg+xml;",
+            ]
+            .map(ToString::to_string),
+        ),
+        Some(false) => directives.push("gzip off;".to_string()),
+        None => {}
+    }
+
+    directives
+        .iter()
+        .map(|directive| ["    ", directive, "\n"].concat())
+        .collect()
+}
+
+/// The app's locations: one per cached or rate-limited path prefix, then `/` and the backend's
+/// fallbacks. Access rules and rate limits guard the locations clients can reach; fallbacks are
+/// only entered from one of them.
+///
+/// Each app location answers with the maintenance page while it exists on disk. The check runs
+/// before access and rate limiting, and the ACME challenge location has no such check.
+pub(super) fn proxy_locations(port: u16, options: &SiteOptions) -> String {
+    let routing = &options.routing;
+    let access = if options.access.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", options.access)
+    };
+    let mut proxy_headers = String::from(
+        "        proxy_http_version 1.1;\n        proxy_set_header Host $host;\n        proxy_set_header X-Real-IP $remote_addr;\n        proxy_set_header X-Forwarded-For $proxy_add_x_forwarded_for;\n        proxy_set_header X-Forwarded-Proto $scheme;\n",
+    );
+    if routing.websockets == Some(true) {
+        proxy_headers.push_str(
+            "        proxy_set_header Upgrade $http_upgrade;\n        proxy_set_header Connection \"upgrade\";\n",
+        );
+    }
+    let read_timeout = routing
+        .read_timeout_seconds
+        .map(|seconds| format!("        proxy_read_timeout {seconds}s;\n"))
+        .unwrap_or_default();
+    let response_headers = |cache_max_age: Option<u32>| -> String {
+        routing
+            .headers
+            .iter()
+            .map(|(name, value)| format!("        add_header {name} \"{value}\" always;\n"))
+            .chain(cache_max_age.map(|max_age| {
+                format!("        add_header Cache-Control \"public, max-age={max_age}\" always;\n")
+            }))
+            .collect::<String>()
+    };
+    let limits = |path: Option<&str>| {
+        routing
+            .rate_limit
+            .as_ref()
+            .filter(|rate_limit| rate_limit.applies_to(path))
+            .map(|rate_limit| limit_directives(&options.zone, rate_limit))
+            .unwrap_or_default()
+    };
+    let pages = &options.pages;
+    let maintenance = maintenance_check(pages);
+    let backend = if options.upstream.is_empty() {
+        coldstart_backend(
+            port,
+            options,
+            &proxy_headers,
+            &read_timeout,
+            &response_headers(None),
+        )
+    } else {
+        Backend {
+            target: upstream_name(&options.zone),
+            on_error: "        proxy_next_upstream error timeout http_502 http_503 http_504;\n"
+                .to_string(),
+            fallbacks: Vec::new(),
+        }
+    };
+    let app_location = |path: Option<&str>, cache_max_age: Option<u32>| {
+        let location = path.map_or_else(|| "/".to_string(), |path| format!("^~ {path}"));
+        format!(
+            "    location {location} {{\n{maintenance}{access}{}{proxy_headers}\n        proxy_connect_timeout 2s;\n{read_timeout}        proxy_pass http://{};\n{}\n{}    }}\n",
+            limits(path),
+            backend.target,
+            response_headers(cache_max_age),
+            backend.on_error,
+        )
+    };
+    let limited_paths = routing
+        .rate_limit
+        .iter()
+        .flat_map(|rate_limit| &rate_limit.paths)
+        .filter(|path| {
+            !routing
+                .cache_paths
+                .iter()
+                .any(|cache_path| cache_path.path == **path)
+        });
+
+    routing
+        .cache_paths
+        .iter()
+        .map(|cache_path| app_location(Some(&cache_path.path), Some(cache_path.max_age_seconds)))
+        .chain(limited_paths.map(|path| app_location(Some(path), None)))
+        .chain([app_location(None, None)])
+        .chain(backend.fallbacks)
+        .chain([status_page_location(
+            "@nanoscale_maintenance",
+            pages,
+            MAINTENANCE_PAGE_FILE,
+            MAINTENANCE_RETRY_AFTER_SECONDS,
+        )])
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deployment::nginx::NginxGenerator;
+    use crate::deployment::routing::CachePath;
+    use crate::deployment::tls::ACME_WEBROOT_PATH;
+
+    fn full_routing() -> RoutingConfig {
+        RoutingConfig {
+            max_body_size_mb: Some(50),
+            read_timeout_seconds: Some(300),
+            websockets: Some(true),
+            gzip: Some(true),
+            headers: [
+                (
+                    "Strict-Transport-Security".to_string(),
+                    "max-age=31536000; includeSubDomains".to_string(),
+                ),
+                (
+                    "Content-Security-Policy".to_string(),
+                    "default-src 'self'".to_string(),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            cache_paths: vec![CachePath {
+                path: "/assets/".to_string(),
+                max_age_seconds: 31_536_000,
+            }],
+            rate_limit: None,
+            waking_page: None,
+        }
+    }
+
+    fn site_options(routing: &RoutingConfig) -> SiteOptions {
+        SiteOptions::new("p1", routing, &AccessControl::default(), &[], 13_100)
+    }
+
+    #[test]
+    fn http_template_contains_acme_root_and_proxy_pass() {
+        let template = NginxGenerator::nginx_http_template(
+            "example",
+            3100,
+            &site_options(&RoutingConfig::default()),
+        );
+        assert!(template.contains(ACME_WEBROOT_PATH));
+        assert!(template.contains("proxy_pass http://127.0.0.1:13100"));
+        assert!(template.contains("error_page 502 503 504 = @nanoscale_coldstart"));
+        assert!(template.contains("proxy_pass http://127.0.0.1:3100"));
+    }
+
+    #[test]
+    fn https_template_contains_cert_paths_and_redirect() {
+        let template = NginxGenerator::nginx_https_template(
+            "example",
+            "app.example.com",
+            3100,
+            &site_options(&RoutingConfig::default()),
+        );
+        assert!(template.contains("/opt/nanoscale/certs/app.example.com/fullchain.pem"));
+        assert!(template.contains("return 301 https://$host$request_uri"));
+        assert!(template.contains("proxy_pass http://127.0.0.1:13100"));
+        assert!(template.contains("error_page 502 503 504 = @nanoscale_coldstart"));
+        assert!(template.contains("proxy_pass http://127.0.0.1:3100"));
+    }
+
+    #[test]
+    fn snapshot_http_site_with_default_routing() {
+        insta::assert_snapshot!(NginxGenerator::nginx_http_template(
+            "app.example.com ns-p1.local",
+            3100,
+            &site_options(&RoutingConfig::default()),
+        ));
+    }
+
+    #[test]
+    fn snapshot_http_site_with_all_routing_options() {
+        insta::assert_snapshot!(NginxGenerator::nginx_http_template(
+            "app.example.com ns-p1.local",
+            3100,
+            &site_options(&full_routing()),
+        ));
+    }
+
+    #[test]
+    fn snapshot_https_site_with_all_routing_options() {
+        insta::assert_snapshot!(NginxGenerator::nginx_https_template(
+            "app.example.com ns-p1.local",
+            "app.example.com",
+            3100,
+            &site_options(&full_routing()),
+        ));
+    }
+
+    #[test]
+    fn snapshot_https_site_with_gzip_disabled() {
+        let routing = RoutingConfig {
+            gzip: Some(false),
+            ..RoutingConfig::default()
+        };
+        insta::assert_snapshot!(NginxGenerator::nginx_https_template(
+            "app.example.com ns-p1.local",
+            "app.example.com",
+            3100,
+            &site_options(&routing),
+        ));
+    }
+}
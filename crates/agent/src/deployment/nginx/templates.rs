@@ -0,0 +1,168 @@
+use anyhow::{anyhow, Result};
+
+use crate::deployment::access::AccessControl;
+use crate::deployment::routing::RoutingConfig;
+use crate::deployment::tls::{ACME_WEBROOT_PATH, CERTIFICATES_PATH};
+
+use super::coldstart::upstream_block;
+use super::locations::{proxy_locations, server_directives, SiteOptions};
+use super::{
+    primary_domain, validate_routed_domains, DomainRedirect, NginxGenerator, NginxTlsMode,
+    RoutedDomain,
+};
+
+impl NginxGenerator {
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn render(
+        project_id: &str,
+        port: u16,
+        backend_port: u16,
+        domains: &[RoutedDomain],
+        tls_mode: NginxTlsMode<'_>,
+        routing: &RoutingConfig,
+        access: &AccessControl,
+        upstream: &[String],
+    ) -> Result<String> {
+        validate_routed_domains(domains)?;
+        routing.validate()?;
+        access.validate()?;
+        let options = SiteOptions::new(project_id, routing, access, upstream, backend_port);
+
+        let served = domains
+            .iter()
+            .filter(|domain| domain.redirect.is_none())
+            .map(|domain| domain.domain.clone())
+            .collect::<Vec<_>>();
+        let site = |server_name: &str, certificate: Option<&str>| match certificate {
+            Some(certificate) => {
+                Self::nginx_https_template(server_name, certificate, port, &options)
+            }
+            None => Self::nginx_http_template(server_name, port, &options),
+        };
+
+        let mut conf_text = upstream_block(&options);
+        conf_text.push_str(&match tls_mode {
+            NginxTlsMode::Disabled => site(&Self::server_name(project_id, &served), None),
+            NginxTlsMode::Enabled { domain } => {
+                site(&Self::server_name(project_id, &served), Some(domain))
+            }
+            NginxTlsMode::Wildcard {
+                domain,
+                certificate,
+                others,
+            } => {
+                let (wildcard, rest) = served
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|served| served == domain);
+                let mut conf_text = site(&Self::server_name(project_id, &wildcard), certificate);
+                if !rest.is_empty() {
+                    conf_text.push('\n');
+                    conf_text.push_str(&site(&rest.join(" "), others));
+                }
+                conf_text
+            }
+        });
+
+        let redirects = domains
+            .iter()
+            .filter_map(|domain| domain.redirect.map(|redirect| (domain, redirect)));
+        for (domain, redirect) in redirects {
+            let primary = primary_domain(domains)
+                .ok_or_else(|| anyhow!("{} redirects but no domain is served", domain.domain))?;
+            let cert_domain = match tls_mode {
+                NginxTlsMode::Disabled => None,
+                NginxTlsMode::Enabled { domain } => Some(domain),
+                NginxTlsMode::Wildcard { others, .. } => others,
+            };
+            conf_text.push('\n');
+            conf_text.push_str(&Self::nginx_redirect_template(
+                &domain.domain,
+                primary,
+                redirect,
+                cert_domain,
+            ));
+        }
+
+        Ok(conf_text)
+    }
+
+    fn server_name(project_id: &str, domains: &[String]) -> String {
+        let compact_id = project_id.replace('-', "");
+        let short_id = compact_id.chars().take(12).collect::<String>();
+        let fallback = format!("ns-{short_id}.local");
+
+        domains
+            .iter()
+            .map(|domain| domain.trim())
+            .filter(|domain| !domain.is_empty())
+            .chain([fallback.as_str()])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub(super) fn nginx_http_template(
+        server_name: &str,
+        port: u16,
+        options: &SiteOptions,
+    ) -> String {
+        let server_directives = server_directives(&options.routing);
+        let acme_access = &options.acme_access;
+        let locations = proxy_locations(port, options);
+        format!(
+            "server {{\n    listen 80;\n    server_name {server_name};\n{server_directives}\n    location ^~ /.well-known/acme-challenge/ {{\n{acme_access}        root {ACME_WEBROOT_PATH};\n    }}\n\n{locations}}}\n"
+        )
+    }
+
+    pub(super) fn nginx_https_template(
+        server_name: &str,
+        domain: &str,
+        port: u16,
+        options: &SiteOptions,
+    ) -> String {
+        let cert_path = format!("{CERTIFICATES_PATH}/{domain}/fullchain.pem");
+        let key_path = format!("{CERTIFICATES_PATH}/{domain}/privkey.pem");
+        let server_directives = server_directives(&options.routing);
+        let acme_access = &options.acme_access;
+        let locations = proxy_locations(port, options);
+
+        format!(
+            "server {{\n    listen 80;\n    server_name {server_name};\n\n    location ^~ /.well-known/acme-challenge/ {{\n{acme_access}        root {ACME_WEBROOT_PATH};\n    }}\n\n    location / {{\n        return 301 https://$host$request_uri;\n    }}\n}}\n\nserver {{\n    listen 443 ssl;\n    server_name {server_name};\n\n    ssl_certificate {cert_path};\n    ssl_certificate_key {key_path};\n{server_directives}\n{locations}}}\n"
+        )
+    }
+
+    /// Server blocks answering `domain` with a redirect to `primary`. With a certificate the
+    /// HTTPS listener redirects too, so old links keep working over both schemes.
+    fn nginx_redirect_template(
+        domain: &str,
+        primary: &str,
+        redirect: DomainRedirect,
+        cert_domain: Option<&str>,
+    ) -> String {
+        let scheme = if cert_domain.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        let path = if redirect.preserve_path {
+            "$request_uri"
+        } else {
+            "/"
+        };
+        let status = redirect.status;
+        let target = format!("{scheme}://{primary}{path}");
+
+        let http_block = format!(
+            "server {{\n    listen 80;\n    server_name {domain};\n\n    location ^~ /.well-known/acme-challenge/ {{\n        root {ACME_WEBROOT_PATH};\n    }}\n\n    location / {{\n        return {status} {target};\n    }}\n}}\n"
+        );
+
+        match cert_domain {
+            None => http_block,
+            Some(cert_domain) => format!(
+                "{http_block}\nserver {{\n    listen 443 ssl;\n    server_name {domain};\n\n    ssl_certificate {CERTIFICATES_PATH}/{cert_domain}/fullchain.pem;\n    ssl_certificate_key {CERTIFICATES_PATH}/{cert_domain}/privkey.pem;\n\n    location / {{\n        return {status} {target};\n    }}\n}}\n"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
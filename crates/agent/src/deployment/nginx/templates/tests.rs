@@ -0,0 +1,243 @@
+use super::*;
+use crate::deployment::access::{AccessRules, BasicAuthUser};
+use crate::deployment::routing::RoutingConfig;
+
+#[test]
+fn server_name_includes_domain_and_fallback() {
+    let name = NginxGenerator::server_name(
+        "123e4567-e89b-12d3-a456-426614174000",
+        &[
+            "app.example.com".to_string(),
+            "www.customer.com".to_string(),
+        ],
+    );
+    assert!(name.starts_with("app.example.com www.customer.com "));
+    assert!(name.contains("ns-"));
+    assert!(name.contains(".local"));
+}
+
+#[test]
+fn server_name_falls_back_when_domain_missing_or_blank() {
+    let missing = NginxGenerator::server_name("p1", &[]);
+    let blank = NginxGenerator::server_name("p1", &["   ".to_string()]);
+    assert_eq!(missing, blank);
+    assert!(std::path::Path::new(&missing)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("local")));
+}
+
+#[test]
+fn render_adds_redirect_blocks_towards_primary_domain() {
+    let domains = [
+        RoutedDomain::serve("app.example.com"),
+        RoutedDomain {
+            primary: true,
+            ..RoutedDomain::serve("customer.com")
+        },
+        RoutedDomain {
+            redirect: Some(DomainRedirect {
+                status: 308,
+                preserve_path: true,
+            }),
+            ..RoutedDomain::serve("www.customer.com")
+        },
+        RoutedDomain {
+            redirect: Some(DomainRedirect {
+                status: 301,
+                preserve_path: false,
+            }),
+            ..RoutedDomain::serve("old-brand.com")
+        },
+    ];
+
+    let http = NginxGenerator::render(
+        "p1",
+        3100,
+        13_100,
+        &domains,
+        NginxTlsMode::Disabled,
+        &RoutingConfig::default(),
+        &AccessControl::default(),
+        &[],
+    )
+    .expect("render");
+    assert!(http.contains("server_name app.example.com customer.com ns-p1.local;"));
+    assert!(http.contains("server_name www.customer.com;"));
+    assert!(http.contains("return 308 http://customer.com$request_uri;"));
+    assert!(http.contains("return 301 http://customer.com/;"));
+    assert!(!http.contains("listen 443"));
+
+    let https = NginxGenerator::render(
+        "p1",
+        3100,
+        13_100,
+        &domains,
+        NginxTlsMode::Enabled {
+            domain: "app.example.com",
+        },
+        &RoutingConfig::default(),
+        &AccessControl::default(),
+        &[],
+    )
+    .expect("render");
+    assert!(https.contains("return 308 https://customer.com$request_uri;"));
+    assert_eq!(https.matches("listen 443 ssl;").count(), 3);
+    assert_eq!(
+        https
+            .matches("ssl_certificate /opt/nanoscale/certs/app.example.com/fullchain.pem;")
+            .count(),
+        3
+    );
+}
+
+#[test]
+fn render_serves_base_domain_with_wildcard_certificate() {
+    let domains = [
+        RoutedDomain::serve("app.apps.example.com"),
+        RoutedDomain::serve("customer.com"),
+        RoutedDomain {
+            redirect: Some(DomainRedirect {
+                status: 301,
+                preserve_path: true,
+            }),
+            ..RoutedDomain::serve("www.customer.com")
+        },
+    ];
+
+    let conf = NginxGenerator::render(
+        "p1",
+        3100,
+        13_100,
+        &domains,
+        NginxTlsMode::Wildcard {
+            domain: "app.apps.example.com",
+            certificate: Some("_wildcard.apps.example.com"),
+            others: Some("customer.com"),
+        },
+        &RoutingConfig::default(),
+        &AccessControl::default(),
+        &[],
+    )
+    .expect("render");
+    assert!(conf.contains("server_name app.apps.example.com ns-p1.local;"));
+    assert!(conf.contains("/opt/nanoscale/certs/_wildcard.apps.example.com/fullchain.pem"));
+    assert!(conf.contains("server_name customer.com;"));
+    assert!(conf.contains("/opt/nanoscale/certs/customer.com/fullchain.pem"));
+    assert!(conf.contains("return 301 https://app.apps.example.com$request_uri;"));
+    assert_eq!(conf.matches("listen 443 ssl;").count(), 3);
+
+    let base_only = NginxGenerator::render(
+        "p1",
+        3100,
+        13_100,
+        &domains[..1],
+        NginxTlsMode::Wildcard {
+            domain: "app.apps.example.com",
+            certificate: Some("_wildcard.apps.example.com"),
+            others: None,
+        },
+        &RoutingConfig::default(),
+        &AccessControl::default(),
+        &[],
+    )
+    .expect("render");
+    assert_eq!(base_only.matches("listen 443 ssl;").count(), 1);
+
+    let custom_without_cert = NginxGenerator::render(
+        "p1",
+        3100,
+        13_100,
+        &domains[..2],
+        NginxTlsMode::Wildcard {
+            domain: "app.apps.example.com",
+            certificate: Some("_wildcard.apps.example.com"),
+            others: None,
+        },
+        &RoutingConfig::default(),
+        &AccessControl::default(),
+        &[],
+    )
+    .expect("render");
+    assert_eq!(custom_without_cert.matches("listen 443 ssl;").count(), 1);
+    assert!(custom_without_cert.contains("server_name customer.com;"));
+}
+
+#[test]
+fn render_rejects_invalid_routing() {
+    let routing = RoutingConfig {
+        headers: [("X-Test".to_string(), "a\"; return 302 x".to_string())]
+            .into_iter()
+            .collect(),
+        ..RoutingConfig::default()
+    };
+    assert!(NginxGenerator::render(
+        "p1",
+        3100,
+        13_100,
+        &[RoutedDomain::serve("app.example.com")],
+        NginxTlsMode::Disabled,
+        &routing,
+        &AccessControl::default(),
+        &[]
+    )
+    .is_err());
+}
+
+#[test]
+fn snapshot_http_site_with_access_control() {
+    let access = AccessControl {
+        rules: AccessRules {
+            allow: vec!["203.0.113.0/24".to_string()],
+            deny: vec!["203.0.113.9".to_string()],
+            exempt_acme_challenge: false,
+        },
+        users: vec![BasicAuthUser {
+            username: "preview".to_string(),
+            password_hash: "$6$salt$hash".to_string(),
+        }],
+    };
+    insta::assert_snapshot!(NginxGenerator::render(
+        "p1",
+        3100,
+        13_100,
+        &[RoutedDomain::serve("app.example.com")],
+        NginxTlsMode::Disabled,
+        &RoutingConfig::default(),
+        &access,
+        &[]
+    )
+    .expect("render"));
+}
+
+#[test]
+fn access_control_leaves_acme_challenge_open_by_default() {
+    let access = AccessControl {
+        users: vec![BasicAuthUser {
+            username: "preview".to_string(),
+            password_hash: "$6$salt$hash".to_string(),
+        }],
+        ..AccessControl::default()
+    };
+    let conf = NginxGenerator::render(
+        "p1",
+        3100,
+        13_100,
+        &[RoutedDomain::serve("app.example.com")],
+        NginxTlsMode::Enabled {
+            domain: "app.example.com",
+        },
+        &RoutingConfig::default(),
+        &access,
+        &[],
+    )
+    .expect("render");
+
+    assert!(conf.contains(
+        "    location ^~ /.well-known/acme-challenge/ {\n        root /opt/nanoscale/acme;"
+    ));
+    assert_eq!(
+        conf.matches("auth_basic_user_file /opt/nanoscale/htpasswd/p1;")
+            .count(),
+        1
+    );
+}
@@ -0,0 +1,224 @@
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::deployment::routing::{validate_page_text, WakingPage};
+
+/// Directory holding one directory of status pages per project. nginx serves the pages itself,
+/// so they stay reachable while the app is asleep or in maintenance.
+pub const PAGES_PATH: &str = "/opt/nanoscale/pages";
+/// Shown while the app boots; only present when the project has a waking page.
+pub const WAKING_PAGE_FILE: &str = "waking.html";
+/// Shown instead of the app; its presence is what puts the site into maintenance, so toggling
+/// maintenance never needs an nginx reload.
+pub const MAINTENANCE_PAGE_FILE: &str = "maintenance.html";
+
+const MAX_MAINTENANCE_MESSAGE_LENGTH: usize = 2_000;
+const DEFAULT_WAKING_TITLE: &str = "Waking up";
+const DEFAULT_WAKING_MESSAGE: &str =
+    "This site was asleep and is starting now. The page reloads on its own in a moment.";
+const DEFAULT_MAINTENANCE_MESSAGE: &str =
+    "This site is down for maintenance and will be back shortly.";
+
+/// Whether a project answers with its maintenance page instead of the app.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MaintenanceMode {
+    pub enabled: bool,
+    /// Text of the maintenance page; a generic notice is shown when unset.
+    pub message: Option<String>,
+}
+
+impl MaintenanceMode {
+    /// Checks the message before it is written into the page.
+    ///
+    /// # Errors
+    /// Returns an error when the message is blank, too long, or has control characters.
+    pub fn validate(&self) -> Result<()> {
+        validate_page_text(
+            "maintenance message",
+            self.message.as_deref(),
+            MAX_MAINTENANCE_MESSAGE_LENGTH,
+        )
+    }
+}
+
+/// Path of the status page directory for `project_id` under `dir`.
+#[must_use]
+pub fn pages_dir(dir: &Path, project_id: &str) -> PathBuf {
+    dir.join(project_id)
+}
+
+/// The waking page: auto-refreshing so the visitor lands on the app once it is up.
+#[must_use]
+pub fn render_waking_page(page: &WakingPage) -> String {
+    render_page(
+        page.title.as_deref().unwrap_or(DEFAULT_WAKING_TITLE),
+        page.message.as_deref().unwrap_or(DEFAULT_WAKING_MESSAGE),
+        Some(page.refresh_seconds),
+    )
+}
+
+#[must_use]
+pub fn render_maintenance_page(maintenance: &MaintenanceMode) -> String {
+    render_page(
+        "Down for maintenance",
+        maintenance
+            .message
+            .as_deref()
+            .unwrap_or(DEFAULT_MAINTENANCE_MESSAGE),
+        None,
+    )
+}
+
+/// Writes the project's status pages: the waking page when configured, and the maintenance
+/// page when maintenance is enabled. Pages that are not wanted are removed.
+///
+/// # Errors
+/// Returns an error if the directory or a page cannot be written or removed.
+pub fn install_pages(
+    dir: &Path,
+    project_id: &str,
+    waking_page: Option<&WakingPage>,
+    maintenance: &MaintenanceMode,
+) -> Result<()> {
+    let project_dir = pages_dir(dir, project_id);
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o755)
+        .create(&project_dir)
+        .with_context(|| format!("failed to create {}", project_dir.display()))?;
+
+    let waking_path = project_dir.join(WAKING_PAGE_FILE);
+    match waking_page {
+        Some(page) => write_page(&waking_path, &render_waking_page(page))?,
+        None => remove_page(&waking_path)?,
+    }
+
+    set_maintenance(dir, project_id, maintenance)
+}
+
+/// Turns maintenance on or off for a deployed project by writing or removing its page.
+///
+/// # Errors
+/// Returns an error if the project has no page directory or the page cannot be changed.
+pub fn set_maintenance(dir: &Path, project_id: &str, maintenance: &MaintenanceMode) -> Result<()> {
+    let path = pages_dir(dir, project_id).join(MAINTENANCE_PAGE_FILE);
+    if maintenance.enabled {
+        write_page(&path, &render_maintenance_page(maintenance))
+    } else {
+        remove_page(&path)
+    }
+}
+
+/// Removes the project's status pages if there are any.
+///
+/// # Errors
+/// Returns an error if the directory exists but cannot be removed.
+pub fn remove_pages(dir: &Path, project_id: &str) -> Result<()> {
+    let path = pages_dir(dir, project_id);
+    match fs::remove_dir_all(&path) {
+        Err(error) if error.kind() != ErrorKind::NotFound => {
+            Err(error).with_context(|| format!("failed to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn render_page(title: &str, message: &str, refresh_seconds: Option<u32>) -> String {
+    let title = escape_html(title);
+    let message = escape_html(message);
+    let refresh = refresh_seconds
+        .map(|seconds| format!("  <meta http-equiv=\"refresh\" content=\"{seconds}\">\n"))
+        .unwrap_or_default();
+
+    format!(
+        "<!doctype html>\n<html lang=\"en\">\n<head>\n  <meta charset=\"utf-8\">\n  <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n{refresh}  <title>{title}</title>\n  <style>\n    body {{ margin: 0; min-height: 100vh; display: grid; place-items: center; font-family: system-ui, sans-serif; background: #f6f7f9; color: #1f2933; }}\n    main {{ max-width: 32rem; padding: 2rem; text-align: center; }}\n    p {{ line-height: 1.5; color: #52606d; white-space: pre-line; }}\n  </style>\n</head>\n<body>\n  <main>\n    <h1>{title}</h1>\n    <p>{message}</p>\n  </main>\n</body>\n</html>\n"
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.chars()
+        .map(|character| match character {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&#39;".to_string(),
+            character => character.to_string(),
+        })
+        .collect()
+}
+
+/// Replaces `path` atomically with a world-readable page.
+fn write_page(path: &Path, contents: &str) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o644)
+        .open(&tmp_path)
+        .with_context(|| format!("failed to open {}", tmp_path.display()))?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path).with_context(|| format!("failed to install {}", path.display()))
+}
+
+fn remove_page(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != ErrorKind::NotFound => {
+            Err(error).with_context(|| format!("failed to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waking_page_refreshes_and_escapes_text() {
+        let page = render_waking_page(&WakingPage {
+            title: Some("Acme <Shop>".to_string()),
+            refresh_seconds: 7,
+            ..WakingPage::default()
+        });
+
+        assert!(page.contains("<meta http-equiv=\"refresh\" content=\"7\">"));
+        assert!(page.contains("<h1>Acme &lt;Shop&gt;</h1>"));
+        assert!(!render_maintenance_page(&MaintenanceMode::default()).contains("refresh"));
+    }
+
+    #[test]
+    fn install_pages_writes_wanted_pages_and_toggles_maintenance() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let project_dir = pages_dir(dir.path(), "p1");
+        let maintenance = MaintenanceMode {
+            enabled: true,
+            message: Some("Back at 10:00 UTC".to_string()),
+        };
+
+        install_pages(dir.path(), "p1", Some(&WakingPage::default()), &maintenance)
+            .expect("install");
+        assert!(project_dir.join(WAKING_PAGE_FILE).exists());
+        assert!(fs::read_to_string(project_dir.join(MAINTENANCE_PAGE_FILE))
+            .expect("maintenance page")
+            .contains("Back at 10:00 UTC"));
+
+        set_maintenance(dir.path(), "p1", &MaintenanceMode::default()).expect("disable");
+        assert!(!project_dir.join(MAINTENANCE_PAGE_FILE).exists());
+
+        install_pages(dir.path(), "p1", None, &MaintenanceMode::default()).expect("reinstall");
+        assert!(!project_dir.join(WAKING_PAGE_FILE).exists());
+
+        remove_pages(dir.path(), "p1").expect("remove");
+        assert!(!project_dir.exists());
+        remove_pages(dir.path(), "p1").expect("already removed");
+    }
+}
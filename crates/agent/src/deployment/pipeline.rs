@@ -0,0 +1,252 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+use crate::deployment::access::AccessControl;
+use crate::deployment::acme::AcmeSettings;
+use crate::deployment::build::{BuildSettings, BuildSystem, ProjectRuntime};
+use crate::deployment::jobs::CronJob;
+use crate::deployment::nginx::RoutedDomain;
+use crate::deployment::pages::MaintenanceMode;
+use crate::deployment::private_network::{self, PrivateNetwork, HOSTS_PATH};
+use crate::deployment::processes::ProcessType;
+use crate::deployment::replicas::ReplicaRole;
+use crate::deployment::routing::RoutingConfig;
+use crate::deployment::streams::{self, StreamPort};
+use crate::deployment::systemd::{ResourceLimits, ServiceSettings};
+use crate::deployment::volumes::{self, Volume, VOLUMES_PATH};
+use crate::system::PrivilegeWrapper;
+
+mod checkout;
+mod release;
+mod settings;
+mod site;
+mod workloads;
+
+pub use checkout::{repo_paths, validate_root_directory};
+
+/// Everything a host needs to deploy one project; shared by the orchestrator's local
+/// worker endpoint and remote workers so both run the exact same pipeline.
+#[derive(Debug)]
+pub struct DeploymentSpec {
+    pub project_id: String,
+    pub repo_url: String,
+    pub branch: String,
+    pub build_command: String,
+    pub install_command: String,
+    pub run_command: String,
+    pub output_directory: String,
+    pub port: u16,
+    /// Port the app listens on behind the socket proxy on `port`.
+    pub backend_port: u16,
+    pub domain: Option<String>,
+    /// Verified custom domains routed and certified alongside `domain`, each either served or
+    /// redirected to the primary domain.
+    pub custom_domains: Vec<RoutedDomain>,
+    pub tls_email: Option<String>,
+    /// The orchestrator's base domain; `domain` under it uses the shared wildcard certificate
+    /// when the host has a DNS provider.
+    pub base_domain: Option<String>,
+    /// ACME settings of the host running the deployment.
+    pub acme: AcmeSettings,
+    pub env_vars: Vec<(String, String)>,
+    pub runtime: ProjectRuntime,
+    pub resource_limits: ResourceLimits,
+    /// nginx options for the project's site.
+    pub routing: RoutingConfig,
+    /// IP rules and basic auth users guarding the project's site.
+    pub access: AccessControl,
+    /// Whether the site answers with its maintenance page instead of the app.
+    pub maintenance: MaintenanceMode,
+    /// This deployment's place in the project's replica set.
+    pub replica: ReplicaRole,
+    /// Services the app may call and the servers that may call it.
+    pub network: PrivateNetwork,
+    /// Public TCP/UDP ports forwarded to the app by nginx `stream` servers.
+    pub stream_ports: Vec<StreamPort>,
+    /// Scheduled jobs set in the dashboard; the manifest's `[[cron]]` jobs are added to them.
+    pub jobs: Vec<CronJob>,
+    /// Process types run next to the web process, set in the dashboard; the Procfile's are
+    /// added to them.
+    pub processes: Vec<ProcessType>,
+    /// Whether this is the project's own server, which alone runs its scheduled jobs and worker
+    /// processes, so they do not run again on every replica.
+    pub primary: bool,
+    /// Persistent directories mounted into the app, kept across deploys.
+    pub volumes: Vec<Volume>,
+    /// Command run before the new release goes live; empty for none.
+    pub release_command: String,
+    /// How long the release command may run; `None` for the default.
+    pub release_timeout_seconds: Option<u64>,
+    /// Whether this server runs the release command, which runs once per rollout.
+    pub runs_release: bool,
+    /// Whether the project was stopped; its units are replaced but stay down.
+    pub stopped: bool,
+    /// Repository subdirectory the project lives in; empty for the repository root.
+    pub root_directory: String,
+}
+
+/// Human-readable record of one deployment, returned to the caller whether it succeeds or not.
+#[derive(Debug, Default)]
+pub struct DeploymentLog {
+    lines: Vec<String>,
+}
+
+impl DeploymentLog {
+    pub fn push(&mut self, line: impl Into<String>) {
+        self.lines.push(line.into());
+    }
+
+    #[must_use]
+    pub fn into_lines(self) -> Vec<String> {
+        self.lines
+    }
+}
+
+/// Result of a successful deployment.
+#[derive(Debug)]
+pub struct DeploymentOutcome {
+    /// Full SHA of the commit that was built; `None` for an edge node, which builds nothing.
+    pub commit_sha: Option<String>,
+    pub tls_summary: String,
+    /// Certificates requested during the deployment, so the host can track per-domain TLS errors.
+    pub certificates: Vec<CertificateAttempt>,
+}
+
+impl DeploymentOutcome {
+    /// One-line status for the deployment response.
+    #[must_use]
+    pub fn summary(&self) -> String {
+        match &self.commit_sha {
+            Some(commit_sha) => format!(
+                "Checked out {commit_sha}. Build pipeline, systemd generation, and nginx configuration completed. {}.",
+                self.tls_summary
+            ),
+            None => format!("nginx configuration completed. {}.", self.tls_summary),
+        }
+    }
+}
+
+/// Outcome of one certificate request made during a deployment.
+#[derive(Debug)]
+pub struct CertificateAttempt {
+    pub domains: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// Clones, builds and installs a project, returning the deployed commit and a short TLS status
+/// summary.
+///
+/// Blocking; callers run it on a blocking task.
+///
+/// Settings are resolved in this order once the repository is checked out: the repository
+/// manifest, then the dashboard settings in `spec`, then
+/// [`detect`](crate::deployment::detect::detect) for anything still blank. A `Procfile` `web`
+/// entry takes precedence over detection. Env vars, cron jobs and process types are merged,
+/// with dashboard values winning so secrets cannot be overridden from the repository.
+///
+/// A release failing its health check is replaced by the previous one again, so a failed
+/// deploy leaves the previous release serving.
+///
+/// # Errors
+/// Returns an error if validation, clone, manifest, build, the release command, systemd, health
+/// check, or nginx installation fails. TLS provisioning failures are reported in the summary instead.
+pub fn run(mut spec: DeploymentSpec, log: &mut DeploymentLog) -> Result<DeploymentOutcome> {
+    spec.replica.validate()?;
+    spec.network.validate()?;
+    if !spec.replica.runs_app() {
+        return site::route_to_replicas(&spec, log);
+    }
+    streams::validate(&spec.stream_ports, spec.port)?;
+    if spec.backend_port == spec.port
+        || spec.stream_ports.iter().any(|stream| {
+            stream.port == spec.backend_port || stream.target_port == spec.backend_port
+        })
+    {
+        bail!(
+            "backend port {} is already used by the project",
+            spec.backend_port
+        );
+    }
+    if !spec.stream_ports.is_empty() && matches!(spec.replica, ReplicaRole::Ingress { .. }) {
+        bail!("TCP/UDP ports cannot be exposed by a project running on several servers");
+    }
+
+    let (project_dir, commit_sha) = checkout::checkout_project(&spec, log)?;
+    let health_check = settings::resolve(&mut spec, &project_dir, log)?;
+
+    let privilege_wrapper = PrivilegeWrapper::new();
+    let build_settings = BuildSettings {
+        build_command: std::mem::take(&mut spec.build_command),
+        output_directory: std::mem::take(&mut spec.output_directory),
+        install_command: std::mem::take(&mut spec.install_command),
+        runtime: spec.runtime,
+    };
+
+    let artifacts = BuildSystem::build(
+        &spec.project_id,
+        &project_dir,
+        &build_settings,
+        &privilege_wrapper,
+    )
+    .context("build pipeline failed")?;
+    log.push("Build completed");
+
+    let volume_mounts = volumes::install(
+        Path::new(VOLUMES_PATH),
+        &spec.project_id,
+        &spec.volumes,
+        &privilege_wrapper,
+    )
+    .context("failed to set up volumes")?;
+
+    let hosts_file =
+        private_network::install_hosts(Path::new(HOSTS_PATH), &spec.project_id, &spec.network)
+            .context("failed to install the private services hosts file")?;
+    let service_settings = ServiceSettings {
+        run_command: &spec.run_command,
+        port: spec.port,
+        backend_port: spec.backend_port,
+        env_vars: &spec.env_vars,
+        resource_limits: spec.resource_limits,
+        network_socket: spec.replica.is_member() || spec.network.reachable,
+        private_network: &spec.network,
+        hosts_file: hosts_file.as_deref(),
+        stream_ports: &spec.stream_ports,
+        volumes: &volume_mounts,
+    };
+
+    let build_output = release::install(
+        &spec,
+        artifacts,
+        &service_settings,
+        health_check.as_ref(),
+        &privilege_wrapper,
+        log,
+    )?;
+    workloads::start(
+        &spec,
+        &build_output,
+        &service_settings,
+        &privilege_wrapper,
+        log,
+    )?;
+    workloads::expose_ports(&spec, &privilege_wrapper, log)?;
+
+    let (tls_summary, certificates) = match &spec.replica {
+        ReplicaRole::Member { ingress_ip } => (
+            format!("Serving port {} to the ingress at {ingress_ip}", spec.port),
+            Vec::new(),
+        ),
+        ReplicaRole::Single | ReplicaRole::Ingress { .. } | ReplicaRole::Edge { .. } => {
+            site::install_routing(&spec, &privilege_wrapper, log)?
+        }
+    };
+    log.push(tls_summary.clone());
+
+    Ok(DeploymentOutcome {
+        commit_sha: Some(commit_sha),
+        tls_summary,
+        certificates,
+    })
+}
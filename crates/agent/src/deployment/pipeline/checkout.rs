@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+
+use super::{DeploymentLog, DeploymentSpec};
+use crate::deployment::git::Git;
+
+/// Validates a monorepo root directory: relative, inside the repository, plain path characters.
+///
+/// # Errors
+/// Returns an error if the path is absolute, escapes the repository, or has unsupported characters.
+pub fn validate_root_directory(root_directory: &str) -> Result<()> {
+    let trimmed = root_directory.trim().trim_end_matches('/');
+    if trimmed.is_empty() {
+        return Ok(());
+    }
+
+    let valid_characters = trimmed.chars().all(|character| {
+        character.is_ascii_alphanumeric() || matches!(character, '/' | '-' | '_' | '.')
+    });
+    let escapes = trimmed.starts_with('/')
+        || trimmed
+            .split('/')
+            .any(|segment| segment.is_empty() || segment == "." || segment == "..");
+
+    if !valid_characters || escapes {
+        bail!("root directory must be a relative path inside the repository: {root_directory:?}");
+    }
+
+    Ok(())
+}
+
+#[must_use]
+pub fn repo_paths(project_id: &str) -> (PathBuf, PathBuf) {
+    let repo_dir = PathBuf::from(format!("/opt/nanoscale/tmp/{project_id}/source"));
+    let parent_dir = repo_dir
+        .parent()
+        .map_or_else(|| PathBuf::from("/opt/nanoscale/tmp"), PathBuf::from);
+    (repo_dir, parent_dir)
+}
+
+/// Fetches the requested ref into a fresh repository and returns the directory the project
+/// builds from together with the checked-out commit SHA.
+pub(super) fn checkout_project(
+    spec: &DeploymentSpec,
+    log: &mut DeploymentLog,
+) -> Result<(PathBuf, String)> {
+    let (repo_dir, parent_dir) = repo_paths(&spec.project_id);
+
+    Git::validate_repo_url(&spec.repo_url).context("repo URL validation failed")?;
+    Git::validate_ref(&spec.branch).context("git ref validation failed")?;
+    validate_root_directory(&spec.root_directory).context("root directory validation failed")?;
+
+    std::fs::create_dir_all(&parent_dir).context("failed to create repo parent directory")?;
+
+    if repo_dir.exists() {
+        std::fs::remove_dir_all(&repo_dir).context("failed to clean existing repo directory")?;
+    }
+
+    Git::fetch_ref(&spec.repo_url, &spec.branch, &repo_dir).context("git fetch step failed")?;
+    let commit_sha = Git::head_commit(&repo_dir).context("failed to resolve checked-out commit")?;
+    log.push(format!("Checked out {} at {commit_sha}", spec.branch));
+
+    let root_directory = spec.root_directory.trim().trim_end_matches('/');
+    let project_dir = if root_directory.is_empty() {
+        repo_dir.clone()
+    } else {
+        repo_dir.join(root_directory)
+    };
+    if !project_dir.is_dir() {
+        bail!("root directory not found in repository: {root_directory}");
+    }
+
+    Ok((project_dir, commit_sha))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repo_paths_build_expected_paths() {
+        let (repo_dir, parent_dir) = repo_paths("p1");
+        assert!(repo_dir
+            .to_string_lossy()
+            .ends_with("/opt/nanoscale/tmp/p1/source"));
+        assert!(parent_dir
+            .to_string_lossy()
+            .ends_with("/opt/nanoscale/tmp/p1"));
+    }
+
+    #[test]
+    fn validate_root_directory_rejects_escapes() {
+        validate_root_directory("").expect("root");
+        validate_root_directory("apps/web/").expect("nested");
+        assert!(validate_root_directory("/etc").is_err());
+        assert!(validate_root_directory("apps/../..").is_err());
+        assert!(validate_root_directory("apps//web").is_err());
+        assert!(validate_root_directory("apps/web;rm").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use super::{DeploymentLog, DeploymentSpec};
+use crate::deployment::build::{BuildArtifacts, BuildOutput, BuildSystem};
+use crate::deployment::health;
+use crate::deployment::manifest::{HealthCheck, DEFAULT_RELEASE_TIMEOUT_SECONDS};
+use crate::deployment::rollback::{PreviousRelease, SITES_PATH, SYSTEMD_PATH};
+use crate::deployment::systemd::{ServiceSettings, SystemdGenerator};
+use crate::system::PrivilegeWrapper;
+
+/// Loads the container image and runs the release command, then cuts over to the new
+/// release. A cut over that fails, its health check included, puts the previous release back.
+pub(super) fn install(
+    spec: &DeploymentSpec,
+    artifacts: BuildArtifacts,
+    service_settings: &ServiceSettings<'_>,
+    health_check: Option<&HealthCheck>,
+    privilege_wrapper: &PrivilegeWrapper,
+    log: &mut DeploymentLog,
+) -> Result<BuildOutput> {
+    SystemdGenerator::load_image(
+        &spec.project_id,
+        &artifacts.runtime,
+        service_settings,
+        privilege_wrapper,
+    )
+    .context("failed to load the container image")?;
+
+    let release_command = spec.release_command.trim();
+    if spec.runs_release && !release_command.is_empty() {
+        if let Err(error) = SystemdGenerator::run_release(
+            &spec.project_id,
+            &artifacts.dir,
+            &artifacts.runtime,
+            service_settings,
+            release_command,
+            spec.release_timeout_seconds
+                .unwrap_or(DEFAULT_RELEASE_TIMEOUT_SECONDS),
+            privilege_wrapper,
+        ) {
+            log.push(format!("Release command failed: {error:#}"));
+            return Err(error.context("release phase failed; the previous release keeps running"));
+        }
+        log.push(format!("Release command succeeded: {release_command}"));
+    } else {
+        SystemdGenerator::remove_release(&spec.project_id, privilege_wrapper)
+            .context("failed to remove the release command unit")?;
+    }
+
+    let previous = PreviousRelease::set_aside(
+        Path::new(SITES_PATH),
+        Path::new(SYSTEMD_PATH),
+        &spec.project_id,
+        privilege_wrapper,
+    )
+    .context("failed to set the previous release aside")?;
+    match cut_over(
+        spec,
+        artifacts,
+        service_settings,
+        health_check,
+        privilege_wrapper,
+        log,
+    ) {
+        Ok(build_output) => {
+            if let Some(previous) = previous {
+                if let Err(error) = previous.discard(privilege_wrapper) {
+                    log.push(format!("Could not remove the previous release: {error:#}"));
+                }
+            }
+            Ok(build_output)
+        }
+        Err(error) => {
+            if let Some(previous) = previous {
+                match previous.restore(spec.stopped, privilege_wrapper) {
+                    Ok(()) => log.push("Restored the previous release"),
+                    Err(restore_error) => log.push(format!(
+                        "Could not restore the previous release: {restore_error:#}"
+                    )),
+                }
+            }
+            Err(error)
+        }
+    }
+}
+
+/// Installs the new release and switches the app's service to it, then waits for the health
+/// check. Processes and jobs are only moved to a release that passed it.
+fn cut_over(
+    spec: &DeploymentSpec,
+    artifacts: BuildArtifacts,
+    service_settings: &ServiceSettings<'_>,
+    health_check: Option<&HealthCheck>,
+    privilege_wrapper: &PrivilegeWrapper,
+    log: &mut DeploymentLog,
+) -> Result<BuildOutput> {
+    let build_output = BuildSystem::install(&spec.project_id, artifacts, privilege_wrapper)
+        .context("build pipeline failed")?;
+
+    SystemdGenerator::generate_and_install(
+        &spec.project_id,
+        &build_output.source_dir,
+        &build_output.runtime,
+        service_settings,
+        spec.stopped,
+        privilege_wrapper,
+    )
+    .context("systemd generation failed")?;
+    log.push("Installed systemd units");
+
+    if let Some(health_check) = health_check.filter(|_| !spec.stopped) {
+        health::wait_until_healthy(
+            spec.port,
+            &health_check.path,
+            Duration::from_secs(health_check.timeout_seconds),
+        )
+        .context("health check failed")?;
+        log.push(format!("Health check {} passed", health_check.path));
+    }
+
+    Ok(build_output)
+}
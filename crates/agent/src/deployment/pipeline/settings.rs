@@ -0,0 +1,191 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use super::{DeploymentLog, DeploymentSpec};
+use crate::deployment::build::ProjectRuntime;
+use crate::deployment::detect;
+use crate::deployment::jobs;
+use crate::deployment::manifest::{self, HealthCheck, ProjectManifest};
+use crate::deployment::processes::{self, WEB_PROCESS};
+use crate::deployment::volumes;
+
+/// Applies the repository's manifest and Procfile to `spec`, fills what is still blank from
+/// detection and checks the result. Returns the manifest's health check.
+pub(super) fn resolve(
+    spec: &mut DeploymentSpec,
+    project_dir: &Path,
+    log: &mut DeploymentLog,
+) -> Result<Option<HealthCheck>> {
+    let health_check = match manifest::load(project_dir) {
+        Ok(Some((project_manifest, file_name))) => {
+            log.push(format!("Loaded {file_name}"));
+            apply_manifest(spec, project_manifest, log)
+        }
+        Ok(None) => None,
+        Err(error) => {
+            log.push(format!("Manifest error: {error:#}"));
+            return Err(error);
+        }
+    };
+
+    let procfile = match processes::load_procfile(project_dir) {
+        Ok(procfile) => procfile,
+        Err(error) => {
+            log.push(format!("Procfile error: {error:#}"));
+            return Err(error);
+        }
+    };
+    let run_command_blank = spec.run_command.trim().is_empty();
+    fill_blank_settings(spec, project_dir, log);
+    if let Some(procfile) = procfile {
+        log.push("Loaded Procfile");
+        apply_procfile(spec, procfile, run_command_blank, log);
+    }
+    jobs::validate(&spec.jobs)?;
+    processes::validate(&spec.processes)?;
+    volumes::validate(&spec.volumes)?;
+    if let Some(process) = spec
+        .processes
+        .iter()
+        .find(|process| process.replicas > 0 && process.command.trim().is_empty())
+    {
+        bail!(
+            "process {} has no command: set one or add it to the Procfile",
+            process.name
+        );
+    }
+    for (key, value) in spec.network.env_vars() {
+        if !spec.env_vars.iter().any(|(existing, _)| *existing == key) {
+            spec.env_vars.push((key, value));
+        }
+    }
+
+    Ok(health_check)
+}
+
+fn apply_manifest(
+    spec: &mut DeploymentSpec,
+    project_manifest: ProjectManifest,
+    log: &mut DeploymentLog,
+) -> Option<HealthCheck> {
+    if let Some(runtime) = project_manifest.runtime {
+        spec.runtime = runtime;
+    }
+
+    for (field, value) in [
+        (
+            &mut spec.install_command,
+            project_manifest.build.install_command,
+        ),
+        (
+            &mut spec.build_command,
+            project_manifest.build.build_command,
+        ),
+        (
+            &mut spec.output_directory,
+            project_manifest.build.output_directory,
+        ),
+        (&mut spec.run_command, project_manifest.run.command),
+        (&mut spec.release_command, project_manifest.release.command),
+    ] {
+        if let Some(value) = value {
+            *field = value;
+        }
+    }
+    if let Some(timeout_seconds) = project_manifest.release.timeout_seconds {
+        spec.release_timeout_seconds = Some(timeout_seconds);
+    }
+
+    if let Some(memory_max_mb) = project_manifest.resources.memory_max_mb {
+        spec.resource_limits.memory_max_mb = Some(memory_max_mb);
+    }
+    if let Some(cpu_quota_percent) = project_manifest.resources.cpu_quota_percent {
+        spec.resource_limits.cpu_quota_percent = Some(cpu_quota_percent);
+    }
+    spec.routing.merge(project_manifest.routing);
+
+    for (key, value) in project_manifest.env {
+        if spec.env_vars.iter().any(|(existing, _)| *existing == key) {
+            log.push(format!(
+                "Env var {key} from manifest ignored: set in dashboard"
+            ));
+            continue;
+        }
+        spec.env_vars.push((key, value));
+    }
+
+    for job in project_manifest.cron {
+        if spec.jobs.iter().any(|existing| existing.name == job.name) {
+            log.push(format!(
+                "Cron job {} from manifest ignored: set in dashboard",
+                job.name
+            ));
+            continue;
+        }
+        spec.jobs.push(job);
+    }
+
+    project_manifest.health_check
+}
+
+/// Runs the Procfile's `web` command when no run command was set before detection, and adds
+/// its other processes to the dashboard's.
+fn apply_procfile(
+    spec: &mut DeploymentSpec,
+    procfile: Vec<(String, String)>,
+    run_command_blank: bool,
+    log: &mut DeploymentLog,
+) {
+    if let Some((_, command)) = procfile.iter().find(|(name, _)| name == WEB_PROCESS) {
+        if run_command_blank {
+            spec.run_command.clone_from(command);
+        } else {
+            log.push("Procfile web process ignored: run command set");
+        }
+    }
+
+    for name in processes::merge_procfile(&mut spec.processes, procfile) {
+        log.push(format!(
+            "Process {name} from Procfile ignored: set in dashboard"
+        ));
+    }
+}
+
+fn fill_blank_settings(spec: &mut DeploymentSpec, repo_dir: &Path, log: &mut DeploymentLog) {
+    if spec.runtime == ProjectRuntime::Container {
+        return;
+    }
+
+    let Some(detected) = detect::detect(repo_dir) else {
+        return;
+    };
+    log.push(format!("Detected {} project", detected.framework));
+
+    // Only a project with no commands at all is switched to a detected Dockerfile; explicit
+    // commands mean the user wants the native pipeline.
+    let all_blank = [
+        &spec.install_command,
+        &spec.build_command,
+        &spec.run_command,
+    ]
+    .iter()
+    .all(|command| command.trim().is_empty());
+    if all_blank {
+        spec.runtime = detected.runtime;
+    }
+
+    for (field, proposed) in [
+        (&mut spec.install_command, detected.install_command),
+        (&mut spec.build_command, detected.build_command),
+        (&mut spec.run_command, detected.run_command),
+        (&mut spec.output_directory, detected.output_directory),
+    ] {
+        if field.trim().is_empty() {
+            *field = proposed;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,182 @@
+use super::*;
+use crate::deployment::access::AccessControl;
+use crate::deployment::acme::AcmeSettings;
+use crate::deployment::jobs::CronJob;
+use crate::deployment::pages::MaintenanceMode;
+use crate::deployment::private_network::PrivateNetwork;
+use crate::deployment::processes::ProcessType;
+use crate::deployment::replicas::ReplicaRole;
+use crate::deployment::routing::RoutingConfig;
+use crate::deployment::systemd::ResourceLimits;
+
+fn spec() -> DeploymentSpec {
+    DeploymentSpec {
+        project_id: "p1".to_string(),
+        repo_url: "https://example.com/repo.git".to_string(),
+        branch: "main".to_string(),
+        build_command: String::new(),
+        install_command: String::new(),
+        run_command: String::new(),
+        output_directory: String::new(),
+        port: 3100,
+        backend_port: 20_000,
+        domain: None,
+        custom_domains: Vec::new(),
+        tls_email: None,
+        base_domain: None,
+        acme: AcmeSettings::default(),
+        env_vars: vec![],
+        runtime: ProjectRuntime::Auto,
+        resource_limits: ResourceLimits::default(),
+        routing: RoutingConfig::default(),
+        access: AccessControl::default(),
+        maintenance: MaintenanceMode::default(),
+        replica: ReplicaRole::default(),
+        network: PrivateNetwork::default(),
+        stream_ports: Vec::new(),
+        jobs: Vec::new(),
+        processes: Vec::new(),
+        primary: true,
+        volumes: Vec::new(),
+        release_command: String::new(),
+        release_timeout_seconds: None,
+        runs_release: true,
+        stopped: false,
+        root_directory: String::new(),
+    }
+}
+
+#[test]
+fn fill_blank_settings_keeps_explicit_commands() {
+    let repo = tempfile::tempdir().expect("tempdir");
+    std::fs::write(repo.path().join("go.mod"), "module example.com/app").expect("write");
+
+    let mut spec = DeploymentSpec {
+        build_command: "go build -o server ./cmd/server".to_string(),
+        ..spec()
+    };
+    fill_blank_settings(&mut spec, repo.path(), &mut DeploymentLog::default());
+
+    assert_eq!(spec.install_command, "go mod download");
+    assert_eq!(spec.build_command, "go build -o server ./cmd/server");
+    assert_eq!(spec.run_command, "./nanoscale-app");
+    assert_eq!(spec.runtime, ProjectRuntime::Auto);
+}
+
+#[test]
+fn apply_manifest_overrides_commands_and_keeps_dashboard_env() {
+    let mut spec = DeploymentSpec {
+        build_command: "bun run build".to_string(),
+        install_command: "bun install".to_string(),
+        run_command: "bun run start".to_string(),
+        env_vars: vec![("API_KEY".to_string(), "secret".to_string())],
+        ..spec()
+    };
+    let project_manifest = ProjectManifest {
+        run: manifest::ManifestRun {
+            command: Some("bun run serve".to_string()),
+        },
+        release: manifest::ManifestRelease {
+            command: Some("bunx prisma migrate deploy".to_string()),
+            timeout_seconds: Some(120),
+        },
+        resources: ResourceLimits {
+            memory_max_mb: Some(128),
+            cpu_quota_percent: None,
+        },
+        env: [
+            ("API_KEY".to_string(), "from-repo".to_string()),
+            ("LOG_LEVEL".to_string(), "debug".to_string()),
+        ]
+        .into_iter()
+        .collect(),
+        routing: RoutingConfig {
+            max_body_size_mb: Some(50),
+            ..RoutingConfig::default()
+        },
+        cron: vec![
+            CronJob {
+                name: "cleanup".to_string(),
+                schedule: "0 4 * * *".to_string(),
+                command: "bun run cleanup".to_string(),
+                timeout_seconds: 60,
+            },
+            CronJob {
+                name: "report".to_string(),
+                schedule: "@weekly".to_string(),
+                command: "bun run report".to_string(),
+                timeout_seconds: 60,
+            },
+        ],
+        ..ProjectManifest::default()
+    };
+    spec.routing.websockets = Some(true);
+    spec.jobs = vec![CronJob {
+        name: "cleanup".to_string(),
+        schedule: "0 3 * * *".to_string(),
+        command: "node scripts/cleanup.js".to_string(),
+        timeout_seconds: 600,
+    }];
+
+    let mut log = DeploymentLog::default();
+    assert!(apply_manifest(&mut spec, project_manifest, &mut log).is_none());
+
+    assert_eq!(spec.run_command, "bun run serve");
+    assert_eq!(spec.build_command, "bun run build");
+    assert_eq!(spec.release_command, "bunx prisma migrate deploy");
+    assert_eq!(spec.release_timeout_seconds, Some(120));
+    assert_eq!(spec.resource_limits.memory_max_mb, Some(128));
+    assert_eq!(spec.routing.max_body_size_mb, Some(50));
+    assert_eq!(spec.routing.websockets, Some(true));
+    assert_eq!(
+        spec.env_vars,
+        vec![
+            ("API_KEY".to_string(), "secret".to_string()),
+            ("LOG_LEVEL".to_string(), "debug".to_string()),
+        ]
+    );
+    assert_eq!(
+        spec.jobs
+            .iter()
+            .map(|job| (job.name.as_str(), job.schedule.as_str()))
+            .collect::<Vec<_>>(),
+        [("cleanup", "0 3 * * *"), ("report", "@weekly")]
+    );
+    let lines = log.into_lines();
+    assert!(lines.iter().any(|line| line.contains("API_KEY")));
+    assert!(lines.iter().any(|line| line.contains("Cron job cleanup")));
+}
+
+#[test]
+fn apply_procfile_runs_web_when_no_command_was_set_and_adds_processes() {
+    let mut spec = DeploymentSpec {
+        run_command: "npm run start".to_string(),
+        processes: vec![ProcessType {
+            name: "worker".to_string(),
+            command: String::new(),
+            replicas: 3,
+            resources: ResourceLimits::default(),
+        }],
+        ..spec()
+    };
+    let procfile = vec![
+        ("web".to_string(), "node server.js".to_string()),
+        ("worker".to_string(), "node worker.js".to_string()),
+    ];
+
+    let mut log = DeploymentLog::default();
+    apply_procfile(&mut spec, procfile.clone(), true, &mut log);
+    assert_eq!(spec.run_command, "node server.js");
+    assert_eq!(spec.processes[0].command, "node worker.js");
+    assert_eq!(spec.processes[0].replicas, 3);
+
+    spec.run_command = "bun run serve".to_string();
+    apply_procfile(&mut spec, procfile, false, &mut log);
+    assert_eq!(spec.run_command, "bun run serve");
+    assert_eq!(spec.processes.len(), 1);
+    let lines = log.into_lines();
+    assert!(lines
+        .iter()
+        .any(|line| line.contains("Procfile web process ignored")));
+    assert!(lines.iter().any(|line| line.contains("Process worker")));
+}
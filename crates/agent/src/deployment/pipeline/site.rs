@@ -0,0 +1,187 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use super::{CertificateAttempt, DeploymentLog, DeploymentOutcome, DeploymentSpec};
+use crate::deployment::access::{self, HTPASSWD_PATH};
+use crate::deployment::nginx::{NginxGenerator, NginxTlsMode, RoutedDomain};
+use crate::deployment::pages::{self, PAGES_PATH};
+use crate::deployment::tls::TlsProvisioner;
+use crate::system::PrivilegeWrapper;
+
+/// Deploys an edge node: only the nginx site, status pages and certificates, with the app
+/// reached over the network on the replicas.
+pub(super) fn route_to_replicas(
+    spec: &DeploymentSpec,
+    log: &mut DeploymentLog,
+) -> Result<DeploymentOutcome> {
+    let (tls_summary, certificates) = install_routing(spec, &PrivilegeWrapper::new(), log)?;
+    log.push(format!(
+        "Routing to {} replicas",
+        spec.replica.upstream_servers(spec.port).len()
+    ));
+    log.push(tls_summary.clone());
+
+    Ok(DeploymentOutcome {
+        commit_sha: None,
+        tls_summary,
+        certificates,
+    })
+}
+
+/// Installs the nginx site for every routed domain and, when possible, certificates covering
+/// them. Returns the TLS summary and the certificate attempts made; certificate failures, and
+/// an HTTPS site nginx rejects, leave the affected domains on plain HTTP.
+#[allow(clippy::too_many_lines)]
+pub(super) fn install_routing(
+    spec: &DeploymentSpec,
+    privilege_wrapper: &PrivilegeWrapper,
+    log: &mut DeploymentLog,
+) -> Result<(String, Vec<CertificateAttempt>)> {
+    let routed = spec
+        .domain
+        .iter()
+        .map(RoutedDomain::serve)
+        .chain(spec.custom_domains.iter().cloned())
+        .collect::<Vec<_>>();
+    let domains = routed
+        .iter()
+        .map(|routed| routed.domain.clone())
+        .collect::<Vec<_>>();
+
+    access::install_htpasswd(
+        Path::new(HTPASSWD_PATH),
+        &spec.project_id,
+        &spec.access.users,
+    )
+    .context("failed to install basic auth users")?;
+    pages::install_pages(
+        Path::new(PAGES_PATH),
+        &spec.project_id,
+        spec.routing.waking_page.as_ref(),
+        &spec.maintenance,
+    )
+    .context("failed to install status pages")?;
+    if let Err(error) = NginxGenerator::generate_and_install(
+        &spec.project_id,
+        spec.port,
+        spec.backend_port,
+        &routed,
+        NginxTlsMode::Disabled,
+        &spec.routing,
+        &spec.access,
+        &spec.replica.upstream_servers(spec.port),
+        privilege_wrapper,
+    ) {
+        log.push(format!("nginx: {error:#}"));
+        return Err(error.context("nginx generation failed"));
+    }
+
+    let (primary_domain, email) = match (domains.first(), spec.tls_email.as_deref()) {
+        (Some(primary_domain), Some(email)) => (primary_domain, email),
+        (Some(_), None) => {
+            return Ok((
+                "TLS skipped: NANOSCALE_TLS_EMAIL not configured".to_string(),
+                Vec::new(),
+            ))
+        }
+        _ => return Ok(("TLS skipped: no domain assigned".to_string(), Vec::new())),
+    };
+
+    let mut attempts = Vec::new();
+    let wildcard_certificate;
+    let tls_mode = if let Some((domain, base_domain)) = wildcard_target(spec) {
+        let wildcard = TlsProvisioner::ensure_wildcard_certificate(base_domain, email, &spec.acme);
+        attempts.push(certificate_attempt(
+            vec![domain.to_string()],
+            wildcard.as_ref().err(),
+        ));
+        wildcard_certificate = wildcard.ok();
+
+        let others = domains
+            .iter()
+            .filter(|other| *other != domain)
+            .cloned()
+            .collect::<Vec<_>>();
+        let others_ok = !others.is_empty() && {
+            let result = TlsProvisioner::ensure_certificate(&others, email, &spec.acme);
+            let ok = result.is_ok();
+            attempts.push(certificate_attempt(others.clone(), result.err().as_ref()));
+            ok
+        };
+
+        NginxTlsMode::Wildcard {
+            domain,
+            certificate: wildcard_certificate.as_deref(),
+            others: domains
+                .iter()
+                .find(|other| *other != domain)
+                .filter(|_| others_ok)
+                .map(String::as_str),
+        }
+    } else {
+        let result = TlsProvisioner::ensure_certificate(&domains, email, &spec.acme);
+        let ok = result.is_ok();
+        attempts.push(certificate_attempt(domains.clone(), result.err().as_ref()));
+        if ok {
+            NginxTlsMode::Enabled {
+                domain: primary_domain,
+            }
+        } else {
+            NginxTlsMode::Disabled
+        }
+    };
+
+    let errors = attempts
+        .iter()
+        .filter_map(|attempt| attempt.error.as_deref())
+        .collect::<Vec<_>>();
+    for error in &errors {
+        eprintln!("TLS provisioning failed for {primary_domain}: {error}");
+    }
+
+    if !matches!(tls_mode, NginxTlsMode::Disabled) {
+        if let Err(error) = NginxGenerator::generate_and_install(
+            &spec.project_id,
+            spec.port,
+            spec.backend_port,
+            &routed,
+            tls_mode,
+            &spec.routing,
+            &spec.access,
+            &spec.replica.upstream_servers(spec.port),
+            privilege_wrapper,
+        ) {
+            log.push(format!("nginx: {error:#}"));
+            return Ok((
+                format!("TLS config rejected by nginx, serving HTTP only: {error:#}"),
+                attempts,
+            ));
+        }
+    }
+
+    let tls_summary = if errors.is_empty() {
+        "TLS enabled".to_string()
+    } else {
+        format!("TLS provisioning failed: {}", errors.join("; "))
+    };
+    Ok((tls_summary, attempts))
+}
+
+/// The project domain and base domain when `domain` is a direct subdomain of the base domain
+/// and this host can publish DNS-01 records for its wildcard certificate.
+fn wildcard_target(spec: &DeploymentSpec) -> Option<(&str, &str)> {
+    let domain = spec.domain.as_deref()?;
+    let base_domain = spec.base_domain.as_deref()?;
+    spec.acme.dns_provider.as_ref()?;
+
+    let label = domain.strip_suffix(base_domain)?.strip_suffix('.')?;
+    (!label.is_empty() && !label.contains('.')).then_some((domain, base_domain))
+}
+
+fn certificate_attempt(domains: Vec<String>, error: Option<&anyhow::Error>) -> CertificateAttempt {
+    CertificateAttempt {
+        domains,
+        error: error.map(|error| format!("{error:#}")),
+    }
+}
@@ -0,0 +1,146 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use super::{DeploymentLog, DeploymentSpec};
+use crate::deployment::build::BuildOutput;
+use crate::deployment::firewall::{self, FirewallRule, Protocol, FIREWALL_STATE_PATH};
+use crate::deployment::lifecycle::{self, STOPPED_STATE_PATH};
+use crate::deployment::nginx::NginxGenerator;
+use crate::deployment::private_network::PrivateService;
+use crate::deployment::processes;
+use crate::deployment::replicas::ReplicaRole;
+use crate::deployment::streams::{self, StreamPort};
+use crate::deployment::systemd::{ServiceSettings, SystemdGenerator};
+use crate::system::PrivilegeWrapper;
+
+/// Moves the project's processes and scheduled jobs to the new release, on the primary server
+/// only, and removes the container images it no longer uses.
+pub(super) fn start(
+    spec: &DeploymentSpec,
+    build_output: &BuildOutput,
+    service_settings: &ServiceSettings<'_>,
+    privilege_wrapper: &PrivilegeWrapper,
+    log: &mut DeploymentLog,
+) -> Result<()> {
+    let processes = if spec.primary {
+        spec.processes.as_slice()
+    } else {
+        &[]
+    };
+    SystemdGenerator::install_processes(
+        &spec.project_id,
+        &build_output.source_dir,
+        &build_output.runtime,
+        service_settings,
+        processes,
+        spec.stopped,
+        privilege_wrapper,
+    )
+    .context("process installation failed")?;
+    if spec.stopped {
+        lifecycle::keep_stopped(
+            Path::new(STOPPED_STATE_PATH),
+            &spec.project_id,
+            &processes::instance_units(&spec.project_id, processes),
+            privilege_wrapper,
+        )
+        .context("failed to keep the project stopped")?;
+        log.push("Project is stopped: the new release starts with it");
+    } else {
+        lifecycle::remove(Path::new(STOPPED_STATE_PATH), &spec.project_id)?;
+        let started = processes
+            .iter()
+            .filter(|process| process.replicas > 0)
+            .map(|process| format!("{} x{}", process.name, process.replicas))
+            .collect::<Vec<_>>();
+        if !started.is_empty() {
+            log.push(format!("Started processes: {}", started.join(", ")));
+        }
+    }
+
+    let scheduled = if spec.primary {
+        spec.jobs.as_slice()
+    } else {
+        &[]
+    };
+    SystemdGenerator::install_jobs(
+        &spec.project_id,
+        &build_output.source_dir,
+        &build_output.runtime,
+        service_settings,
+        scheduled,
+        privilege_wrapper,
+    )
+    .context("cron job installation failed")?;
+    if !scheduled.is_empty() {
+        log.push(format!(
+            "Scheduled cron jobs: {}",
+            scheduled
+                .iter()
+                .map(|job| job.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    if let Err(error) = SystemdGenerator::prune_images(
+        &spec.project_id,
+        &build_output.runtime,
+        service_settings,
+        privilege_wrapper,
+    ) {
+        log.push(format!("Could not remove old container images: {error:#}"));
+    }
+
+    Ok(())
+}
+
+/// Forwards the project's TCP/UDP ports and opens its ports in the firewall.
+pub(super) fn expose_ports(
+    spec: &DeploymentSpec,
+    privilege_wrapper: &PrivilegeWrapper,
+    log: &mut DeploymentLog,
+) -> Result<()> {
+    NginxGenerator::install_streams(&spec.project_id, &spec.stream_ports, privilege_wrapper)
+        .context("failed to expose TCP/UDP ports")?;
+    let mut rules = spec.network.firewall_rules(spec.port);
+    if let ReplicaRole::Member { ingress_ip } = &spec.replica {
+        rules.push(FirewallRule {
+            port: spec.port,
+            protocol: Protocol::Tcp,
+            from: Some(ingress_ip.parse()?),
+        });
+    }
+    rules.extend(streams::firewall_rules(&spec.stream_ports));
+    firewall::apply(
+        Path::new(FIREWALL_STATE_PATH),
+        &spec.project_id,
+        &rules,
+        privilege_wrapper,
+    )
+    .context("failed to open the project's ports in the firewall")?;
+    if !spec.stream_ports.is_empty() {
+        log.push(format!(
+            "Exposed ports: {}",
+            spec.stream_ports
+                .iter()
+                .map(StreamPort::describe)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    if !spec.network.services.is_empty() {
+        log.push(format!(
+            "Private services: {}",
+            spec.network
+                .services
+                .iter()
+                .map(PrivateService::hostname)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,237 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{ErrorKind, Write as _};
+use std::net::IpAddr;
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::deployment::firewall::{FirewallRule, Protocol};
+
+/// Directory holding one hosts file per project, mounted over the app's `/etc/hosts`.
+pub const HOSTS_PATH: &str = "/opt/nanoscale/hosts";
+/// Suffix of every private service hostname.
+pub const INTERNAL_DOMAIN: &str = "internal";
+
+const SYSTEM_HOSTS_FILE: &str = "/etc/hosts";
+const MAX_SERVICE_NAME_LENGTH: usize = 63;
+
+/// Project-to-project networking for one deployment.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PrivateNetwork {
+    /// Other projects' services this app may call.
+    pub services: Vec<PrivateService>,
+    /// Whether other projects may call this app; its socket then listens on every interface.
+    pub reachable: bool,
+    /// Servers whose projects may call this app, the only remote sources let through to it.
+    pub allowed_sources: Vec<IpAddr>,
+}
+
+/// A service reachable as `<name>.internal:<port>`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PrivateService {
+    pub name: String,
+    pub ip: IpAddr,
+    pub port: u16,
+}
+
+impl PrivateService {
+    #[must_use]
+    pub fn hostname(&self) -> String {
+        format!("{}.{INTERNAL_DOMAIN}", self.name)
+    }
+}
+
+impl PrivateNetwork {
+    /// Checks service names before they reach the hosts file and env vars.
+    ///
+    /// # Errors
+    /// Returns an error for an invalid service name or port.
+    pub fn validate(&self) -> Result<()> {
+        for service in &self.services {
+            validate_service_name(&service.name)?;
+            if service.port == 0 {
+                bail!("private service {} has no port", service.name);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// `NANOSCALE_SERVICE_<NAME>_URL` for each callable service, e.g.
+    /// `NANOSCALE_SERVICE_BILLING_API_URL=http://billing-api.internal:3105`.
+    #[must_use]
+    pub fn env_vars(&self) -> Vec<(String, String)> {
+        self.services
+            .iter()
+            .map(|service| {
+                (
+                    format!(
+                        "NANOSCALE_SERVICE_{}_URL",
+                        service.name.to_ascii_uppercase().replace('-', "_")
+                    ),
+                    format!("http://{}:{}", service.hostname(), service.port),
+                )
+            })
+            .collect()
+    }
+
+    /// ufw rules letting the allowed servers reach the app's socket on `port`.
+    #[must_use]
+    pub fn firewall_rules(&self, port: u16) -> Vec<FirewallRule> {
+        self.allowed_sources
+            .iter()
+            .filter(|source| !source.is_loopback())
+            .map(|source| FirewallRule {
+                port,
+                protocol: Protocol::Tcp,
+                from: Some(*source),
+            })
+            .collect()
+    }
+}
+
+/// Checks a private service name: one lowercase DNS label.
+///
+/// # Errors
+/// Returns an error if the name is empty, too long, or not a lowercase DNS label.
+pub fn validate_service_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_SERVICE_NAME_LENGTH
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+        && name.chars().all(|character| {
+            character.is_ascii_lowercase() || character.is_ascii_digit() || character == '-'
+        });
+    if !valid {
+        bail!("service name must be a lowercase DNS label of letters, digits and '-': {name:?}");
+    }
+
+    Ok(())
+}
+
+#[must_use]
+pub fn hosts_file(dir: &Path, project_id: &str) -> PathBuf {
+    dir.join(project_id)
+}
+
+/// The system hosts file followed by one line per callable service.
+#[must_use]
+pub fn render_hosts(system_hosts: &str, network: &PrivateNetwork) -> String {
+    let mut hosts = system_hosts.trim_end().to_string();
+    hosts.push_str("\n\n# NanoScale private services\n");
+    for service in &network.services {
+        let _ = writeln!(hosts, "{} {}", service.ip, service.hostname());
+    }
+    hosts
+}
+
+/// Writes the project's hosts file when it may call other services, and removes it otherwise.
+/// Returns the file to mount over `/etc/hosts`, if any.
+///
+/// # Errors
+/// Returns an error if the system hosts file cannot be read or the project's cannot be written.
+pub fn install_hosts(
+    dir: &Path,
+    project_id: &str,
+    network: &PrivateNetwork,
+) -> Result<Option<PathBuf>> {
+    let path = hosts_file(dir, project_id);
+    if network.services.is_empty() {
+        remove_hosts(dir, project_id)?;
+        return Ok(None);
+    }
+
+    let system_hosts = fs::read_to_string(SYSTEM_HOSTS_FILE)
+        .with_context(|| format!("failed to read {SYSTEM_HOSTS_FILE}"))?;
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o755)
+        .create(dir)
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+    let tmp_path = path.with_extension("tmp");
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o644)
+        .open(&tmp_path)
+        .with_context(|| format!("failed to open {}", tmp_path.display()))?;
+    file.write_all(render_hosts(&system_hosts, network).as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, &path)
+        .with_context(|| format!("failed to install {}", path.display()))?;
+
+    Ok(Some(path))
+}
+
+/// Removes the project's hosts file if there is one.
+///
+/// # Errors
+/// Returns an error if the file exists but cannot be removed.
+pub fn remove_hosts(dir: &Path, project_id: &str) -> Result<()> {
+    let path = hosts_file(dir, project_id);
+    match fs::remove_file(&path) {
+        Err(error) if error.kind() != ErrorKind::NotFound => {
+            Err(error).with_context(|| format!("failed to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network() -> PrivateNetwork {
+        PrivateNetwork {
+            services: vec![PrivateService {
+                name: "billing-api".to_string(),
+                ip: "10.0.0.2".parse().expect("ip"),
+                port: 3105,
+            }],
+            reachable: true,
+            allowed_sources: vec![
+                "10.0.0.3".parse().expect("ip"),
+                "127.0.0.1".parse().expect("ip"),
+            ],
+        }
+    }
+
+    #[test]
+    fn services_become_hosts_entries_and_env_vars() {
+        let network = network();
+        network.validate().expect("valid");
+
+        let hosts = render_hosts("127.0.0.1 localhost\n", &network);
+        assert!(hosts.starts_with("127.0.0.1 localhost\n"));
+        assert!(hosts.ends_with("10.0.0.2 billing-api.internal\n"));
+        assert_eq!(
+            network.env_vars(),
+            [(
+                "NANOSCALE_SERVICE_BILLING_API_URL".to_string(),
+                "http://billing-api.internal:3105".to_string()
+            )]
+        );
+        assert_eq!(
+            network.firewall_rules(3100),
+            [FirewallRule {
+                port: 3100,
+                protocol: Protocol::Tcp,
+                from: Some("10.0.0.3".parse().expect("ip")),
+            }]
+        );
+    }
+
+    #[test]
+    fn validate_service_name_accepts_only_dns_labels() {
+        validate_service_name("api").expect("plain");
+        validate_service_name("billing-api2").expect("dashes and digits");
+        for name in ["", "-api", "api-", "Api", "api.internal", "api service"] {
+            assert!(validate_service_name(name).is_err(), "{name:?}");
+        }
+    }
+}
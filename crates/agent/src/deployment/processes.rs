@@ -0,0 +1,246 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::deployment::systemd::ResourceLimits;
+
+mod procfile;
+
+pub use procfile::{load_procfile, merge_procfile};
+
+/// The process served through the project's socket; its command, replicas and limits are the
+/// project's own settings.
+pub const WEB_PROCESS: &str = "web";
+const MAX_PROCESSES: usize = 8;
+const MAX_PROCESS_NAME_LENGTH: usize = 32;
+const MAX_PROCESS_REPLICAS: u32 = 16;
+
+/// A long-running process next to the web server, such as a queue consumer, run as its own
+/// systemd template unit with one instance per replica.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProcessType {
+    pub name: String,
+    /// Empty to run the Procfile's command for `name`.
+    #[serde(default)]
+    pub command: String,
+    /// Instances run on the project's server; 0 keeps a Procfile process from running.
+    #[serde(default = "default_replicas")]
+    pub replicas: u32,
+    #[serde(default)]
+    pub resources: ResourceLimits,
+}
+
+const fn default_replicas() -> u32 {
+    1
+}
+
+/// Checks a project's process types before they become unit names.
+///
+/// # Errors
+/// Returns an error for too many processes, a duplicate or malformed name, `web`, or a replica
+/// count out of range.
+pub fn validate(processes: &[ProcessType]) -> Result<()> {
+    if processes.len() > MAX_PROCESSES {
+        bail!("at most {MAX_PROCESSES} process types can be defined");
+    }
+
+    let mut names = HashSet::new();
+    for process in processes {
+        validate_name(&process.name)?;
+        if process.name == WEB_PROCESS {
+            bail!("the web process is configured by the project's run command and replicas");
+        }
+        if !names.insert(process.name.as_str()) {
+            bail!("process {} is declared twice", process.name);
+        }
+        if process.replicas > MAX_PROCESS_REPLICAS {
+            bail!(
+                "process {} replicas must be between 0 and {MAX_PROCESS_REPLICAS}",
+                process.name
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_PROCESS_NAME_LENGTH
+        && name.chars().all(|character| {
+            character.is_ascii_alphanumeric() || character == '-' || character == '_'
+        });
+    if !valid {
+        bail!("process name must match ^[A-Za-z0-9_-]{{1,{MAX_PROCESS_NAME_LENGTH}}}$: {name:?}");
+    }
+
+    Ok(())
+}
+
+/// Unit name, without suffix, of the project's process `name`; instances add `@<n>`.
+#[must_use]
+pub fn unit_name(project_id: &str, name: &str) -> String {
+    format!("nanoscale-{project_id}-{name}")
+}
+
+/// Instance units the processes run, e.g. `nanoscale-<id>-worker@1.service`, in order.
+#[must_use]
+pub fn instance_units(project_id: &str, processes: &[ProcessType]) -> Vec<String> {
+    processes
+        .iter()
+        .flat_map(|process| {
+            let unit_name = unit_name(project_id, &process.name);
+            (1..=process.replicas).map(move |instance| format!("{unit_name}@{instance}.service"))
+        })
+        .collect()
+}
+
+/// Names of the processes whose template units are installed in `systemd_dir` for the project.
+///
+/// # Errors
+/// Returns an error if the directory cannot be read.
+pub fn installed(systemd_dir: &Path, project_id: &str) -> Result<Vec<String>> {
+    let prefix = unit_name(project_id, "");
+    let mut names = Vec::new();
+    for entry in fs::read_dir(systemd_dir)? {
+        let file_name = entry?.file_name();
+        let Some(name) = file_name
+            .to_str()
+            .and_then(|file_name| file_name.strip_prefix(&prefix))
+            .and_then(|rest| rest.strip_suffix("@.service"))
+        else {
+            continue;
+        };
+        names.push(name.to_string());
+    }
+    names.sort();
+
+    Ok(names)
+}
+
+/// Enabled instance units of the project's processes, e.g. `nanoscale-<id>-worker@2.service`,
+/// read from the `multi-user.target.wants` links in `systemd_dir`.
+///
+/// # Errors
+/// Returns an error if the directory exists but cannot be read.
+pub fn enabled_instances(systemd_dir: &Path, project_id: &str) -> Result<Vec<String>> {
+    let prefix = unit_name(project_id, "");
+    let entries = match fs::read_dir(systemd_dir.join("multi-user.target.wants")) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error.into()),
+    };
+
+    let mut units = Vec::new();
+    for entry in entries {
+        let file_name = entry?.file_name();
+        let Some(unit) = file_name.to_str() else {
+            continue;
+        };
+        let is_instance = unit
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(".service"))
+            .and_then(|rest| rest.split_once('@'))
+            .is_some_and(|(_, instance)| !instance.is_empty());
+        if is_instance {
+            units.push(unit.to_string());
+        }
+    }
+    units.sort();
+
+    Ok(units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(super) fn process(name: &str, command: &str) -> ProcessType {
+        ProcessType {
+            name: name.to_string(),
+            command: command.to_string(),
+            replicas: 1,
+            resources: ResourceLimits::default(),
+        }
+    }
+
+    #[test]
+    fn validate_rejects_web_duplicates_and_too_many_replicas() {
+        validate(&[process("worker", "bun run worker"), process("clock_1", "")]).expect("valid");
+
+        assert!(validate(&[process("web", "bun run start")]).is_err());
+        assert!(validate(&[process("worker", "a"), process("worker", "b")]).is_err());
+        assert!(validate(&[process("bad name", "a")]).is_err());
+        assert!(validate(&[process("worker@1", "a")]).is_err());
+        let mut busy = process("worker", "a");
+        busy.replicas = MAX_PROCESS_REPLICAS + 1;
+        assert!(validate(&[busy]).is_err());
+    }
+
+    #[test]
+    fn instance_units_name_every_replica() {
+        let processes = [
+            ProcessType {
+                replicas: 2,
+                ..process("worker", "a")
+            },
+            ProcessType {
+                replicas: 0,
+                ..process("mailer", "b")
+            },
+            process("clock", "c"),
+        ];
+        assert_eq!(
+            instance_units("p1", &processes),
+            vec![
+                "nanoscale-p1-worker@1.service".to_string(),
+                "nanoscale-p1-worker@2.service".to_string(),
+                "nanoscale-p1-clock@1.service".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn installed_and_enabled_instances_list_the_project_processes() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let wants = dir.path().join("multi-user.target.wants");
+        fs::create_dir(&wants).expect("wants");
+        for file in [
+            "nanoscale-p1.service",
+            "nanoscale-p1-proxy.service",
+            "nanoscale-p1-worker@.service",
+            "nanoscale-p1-job-cleanup.service",
+            "nanoscale-p2-worker@.service",
+        ] {
+            fs::write(dir.path().join(file), "").expect("write");
+        }
+        for file in [
+            "nanoscale-p1.service",
+            "nanoscale-p1-worker@1.service",
+            "nanoscale-p1-worker@2.service",
+            "nanoscale-p2-worker@1.service",
+        ] {
+            fs::write(wants.join(file), "").expect("write");
+        }
+
+        assert_eq!(
+            installed(dir.path(), "p1").expect("installed"),
+            vec!["worker".to_string()]
+        );
+        assert_eq!(
+            enabled_instances(dir.path(), "p1").expect("instances"),
+            vec![
+                "nanoscale-p1-worker@1.service".to_string(),
+                "nanoscale-p1-worker@2.service".to_string(),
+            ]
+        );
+        assert!(enabled_instances(&dir.path().join("missing"), "p1")
+            .expect("missing")
+            .is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+use super::{default_replicas, validate_name, ProcessType, WEB_PROCESS};
+use crate::deployment::systemd::ResourceLimits;
+
+const PROCFILE: &str = "Procfile";
+
+impl ProcessType {
+    fn from_procfile(name: String, command: String) -> Self {
+        Self {
+            name,
+            command,
+            replicas: default_replicas(),
+            resources: ResourceLimits::default(),
+        }
+    }
+}
+
+/// Reads the `Procfile` in `project_dir`, if there is one, as `(name, command)` pairs in file
+/// order.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or a line is not `name: command` with a valid,
+/// unique name.
+pub fn load_procfile(project_dir: &Path) -> Result<Option<Vec<(String, String)>>> {
+    match fs::read_to_string(project_dir.join(PROCFILE)) {
+        Ok(raw) => parse_procfile(&raw).map(Some),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error).context("failed to read Procfile"),
+    }
+}
+
+fn parse_procfile(raw: &str) -> Result<Vec<(String, String)>> {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for (index, line) in raw.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((name, command)) = line.split_once(':') else {
+            bail!("Procfile line {} must be `name: command`", index + 1);
+        };
+        let (name, command) = (name.trim(), command.trim());
+        validate_name(name).with_context(|| format!("Procfile line {}", index + 1))?;
+        if command.is_empty() {
+            bail!("Procfile process {name} needs a command");
+        }
+        if entries.iter().any(|(existing, _)| existing == name) {
+            bail!("Procfile process {name} is declared twice");
+        }
+        entries.push((name.to_string(), command.to_string()));
+    }
+
+    Ok(entries)
+}
+
+/// Adds the Procfile's non-web processes to the configured ones. A configured process keeps
+/// its command when it has one and takes the Procfile's otherwise. Returns the names of the
+/// Procfile processes whose command was overridden.
+pub fn merge_procfile(
+    processes: &mut Vec<ProcessType>,
+    procfile: Vec<(String, String)>,
+) -> Vec<String> {
+    let mut overridden = Vec::new();
+    for (name, command) in procfile {
+        if name == WEB_PROCESS {
+            continue;
+        }
+        match processes.iter_mut().find(|process| process.name == name) {
+            Some(process) if process.command.trim().is_empty() => process.command = command,
+            Some(_) => overridden.push(name),
+            None => processes.push(ProcessType::from_procfile(name, command)),
+        }
+    }
+
+    overridden
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deployment::processes::tests::process;
+
+    #[test]
+    fn parse_procfile_reads_named_commands() {
+        let entries = parse_procfile(
+            "# processes\nweb: bun run start\n\nworker:  bun run worker --queue=default\n",
+        )
+        .expect("parse");
+        assert_eq!(
+            entries,
+            vec![
+                ("web".to_string(), "bun run start".to_string()),
+                (
+                    "worker".to_string(),
+                    "bun run worker --queue=default".to_string()
+                ),
+            ]
+        );
+
+        assert!(parse_procfile("worker bun run worker").is_err());
+        assert!(parse_procfile("worker:").is_err());
+        assert!(parse_procfile("worker: a\nworker: b").is_err());
+    }
+
+    #[test]
+    fn merge_procfile_keeps_configured_commands_and_fills_blank_ones() {
+        let mut processes = vec![
+            process("worker", "bun run worker --fast"),
+            ProcessType {
+                replicas: 3,
+                ..process("mailer", "")
+            },
+        ];
+        let overridden = merge_procfile(
+            &mut processes,
+            vec![
+                ("web".to_string(), "bun run start".to_string()),
+                ("worker".to_string(), "bun run worker".to_string()),
+                ("mailer".to_string(), "bun run mailer".to_string()),
+                ("clock".to_string(), "bun run clock".to_string()),
+            ],
+        );
+
+        assert_eq!(overridden, vec!["worker".to_string()]);
+        assert_eq!(processes[0].command, "bun run worker --fast");
+        assert_eq!(processes[1].command, "bun run mailer");
+        assert_eq!(processes[1].replicas, 3);
+        assert_eq!(processes[2], process("clock", "bun run clock"));
+        assert!(processes.iter().all(|process| process.name != "web"));
+    }
+}
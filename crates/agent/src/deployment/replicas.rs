@@ -0,0 +1,157 @@
+use std::net::{IpAddr, SocketAddr};
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Most replicas a project can run; each one needs its own server.
+pub const MAX_REPLICAS: u32 = 16;
+
+/// How one deployment takes part in its project's replica set.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "role", rename_all = "snake_case")]
+pub enum ReplicaRole {
+    /// The project's only replica: nginx proxies to it on loopback and it scales to zero.
+    #[default]
+    Single,
+    /// Serves the project's domains and balances over its own replica and `peers`, the
+    /// `ip:port` addresses of the replicas on other servers.
+    Ingress { peers: Vec<String> },
+    /// Runs the app for the ingress at `ingress_ip`, the only address let through to its port.
+    Member { ingress_ip: String },
+    /// Serves the project's domains on a dedicated ingress node without running the app,
+    /// balancing over `backends`, the `ip:port` addresses of the replicas.
+    Edge { backends: Vec<String> },
+}
+
+impl ReplicaRole {
+    /// Checks the addresses before they reach nginx or the firewall.
+    ///
+    /// # Errors
+    /// Returns an error for an address that does not parse, or an ingress without peers.
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Self::Single => {}
+            Self::Ingress { peers: addresses }
+            | Self::Edge {
+                backends: addresses,
+            } => {
+                if addresses.is_empty() {
+                    bail!("an ingress needs at least one replica to balance over");
+                }
+                for address in addresses {
+                    address.parse::<SocketAddr>().map_err(|_| {
+                        anyhow!("replica address must be an ip:port address: {address:?}")
+                    })?;
+                }
+            }
+            Self::Member { ingress_ip } => {
+                ingress_ip
+                    .parse::<IpAddr>()
+                    .map_err(|_| anyhow!("ingress address must be an IP: {ingress_ip:?}"))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Only a lone replica may be stopped when idle; the ingress cannot wake a peer on
+    /// another server, so replicated projects stay running.
+    #[must_use]
+    pub const fn scales_to_zero(&self) -> bool {
+        matches!(self, Self::Single)
+    }
+
+    /// Members are reached from the ingress over the network instead of through loopback.
+    #[must_use]
+    pub const fn is_member(&self) -> bool {
+        matches!(self, Self::Member { .. })
+    }
+
+    /// Edge nodes only route; there is no app to build or run.
+    #[must_use]
+    pub const fn runs_app(&self) -> bool {
+        !matches!(self, Self::Edge { .. })
+    }
+
+    /// Servers of the nginx `upstream` in front of the app, the local replica's socket on `port`
+    /// included; empty when nginx talks to a lone replica directly.
+    #[must_use]
+    pub fn upstream_servers(&self, port: u16) -> Vec<String> {
+        match self {
+            Self::Single | Self::Member { .. } => Vec::new(),
+            Self::Ingress { peers } => std::iter::once(format!("127.0.0.1:{port}"))
+                .chain(peers.iter().cloned())
+                .collect(),
+            Self::Edge { backends } => backends.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_checks_addresses() {
+        ReplicaRole::Single.validate().expect("single");
+        ReplicaRole::Ingress {
+            peers: vec![
+                "10.0.0.2:3100".to_string(),
+                "[2001:db8::2]:3100".to_string(),
+            ],
+        }
+        .validate()
+        .expect("ingress");
+        assert!(ReplicaRole::Ingress { peers: Vec::new() }
+            .validate()
+            .is_err());
+        assert!(ReplicaRole::Ingress {
+            peers: vec!["10.0.0.2:3100; return 200".to_string()],
+        }
+        .validate()
+        .is_err());
+        assert!(ReplicaRole::Member {
+            ingress_ip: "ingress.local".to_string(),
+        }
+        .validate()
+        .is_err());
+        assert!(ReplicaRole::Edge {
+            backends: Vec::new(),
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn upstream_includes_local_socket_only_when_the_app_runs_here() {
+        let peers = vec!["10.0.0.2:3100".to_string()];
+        assert!(ReplicaRole::Single.upstream_servers(3100).is_empty());
+        assert_eq!(
+            ReplicaRole::Ingress {
+                peers: peers.clone(),
+            }
+            .upstream_servers(3100),
+            ["127.0.0.1:3100", "10.0.0.2:3100"]
+        );
+        assert_eq!(
+            ReplicaRole::Edge {
+                backends: peers.clone(),
+            }
+            .upstream_servers(3100),
+            peers
+        );
+    }
+
+    #[test]
+    fn only_single_replicas_scale_to_zero() {
+        assert!(ReplicaRole::Single.scales_to_zero());
+        assert!(!ReplicaRole::Member {
+            ingress_ip: "10.0.0.1".to_string(),
+        }
+        .scales_to_zero());
+        assert!(!ReplicaRole::Edge {
+            backends: vec!["10.0.0.2:3100".to_string()],
+        }
+        .scales_to_zero());
+    }
+}
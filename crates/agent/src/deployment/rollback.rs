@@ -0,0 +1,145 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::deployment::build::BuildSystem;
+use crate::deployment::systemd::{ServiceUnits, SystemdGenerator};
+use crate::system::PrivilegeWrapper;
+
+/// Installed releases, one `{id}/source` directory per project.
+pub const SITES_PATH: &str = "/opt/nanoscale/sites";
+pub const SYSTEMD_PATH: &str = "/etc/systemd/system";
+
+/// The release a deploy replaces, kept until the new one passes its health check so a failed
+/// check can put it back. The env file is the project's configuration rather than part of a
+/// release, so it keeps the new values.
+#[derive(Debug)]
+pub struct PreviousRelease {
+    project_id: String,
+    source_dir: PathBuf,
+    kept_dir: PathBuf,
+    units: ServiceUnits,
+}
+
+impl PreviousRelease {
+    /// Moves the project's installed release in `sites_dir` aside and records its service
+    /// units from `systemd_dir`. `None` before the project's first deploy.
+    ///
+    /// # Errors
+    /// Returns an error if the units cannot be read or the release cannot be moved.
+    pub fn set_aside(
+        sites_dir: &Path,
+        systemd_dir: &Path,
+        project_id: &str,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<Option<Self>> {
+        let project_dir = sites_dir.join(project_id);
+        let source_dir = project_dir.join("source");
+        let Some(units) = ServiceUnits::installed(systemd_dir, project_id)? else {
+            return Ok(None);
+        };
+        if !source_dir.is_dir() {
+            return Ok(None);
+        }
+
+        let kept_dir = project_dir.join("previous");
+        BuildSystem::remove_directory(&kept_dir, privilege_wrapper)?;
+        fs::rename(&source_dir, &kept_dir)?;
+
+        Ok(Some(Self {
+            project_id: project_id.to_string(),
+            source_dir,
+            kept_dir,
+            units,
+        }))
+    }
+
+    /// Puts the release back in place of the new one and restarts the service on its units.
+    ///
+    /// # Errors
+    /// Returns an error if the new release cannot be removed or the units cannot be installed.
+    pub fn restore(self, stopped: bool, privilege_wrapper: &PrivilegeWrapper) -> Result<()> {
+        self.put_back(privilege_wrapper)?;
+        SystemdGenerator::restore_service(&self.project_id, &self.units, stopped, privilege_wrapper)
+    }
+
+    /// Deletes the release once the new one serves.
+    ///
+    /// # Errors
+    /// Returns an error if the directory cannot be removed.
+    pub fn discard(self, privilege_wrapper: &PrivilegeWrapper) -> Result<()> {
+        BuildSystem::remove_directory(&self.kept_dir, privilege_wrapper)
+    }
+
+    fn put_back(&self, privilege_wrapper: &PrivilegeWrapper) -> Result<()> {
+        BuildSystem::remove_directory(&self.source_dir, privilege_wrapper)?;
+        fs::rename(&self.kept_dir, &self.source_dir)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_check_puts_the_previous_release_back() {
+        let tempdir = tempfile::tempdir().expect("tempdir");
+        let sites_dir = tempdir.path().join("sites");
+        let systemd_dir = tempdir.path().join("systemd");
+        let source_dir = sites_dir.join("p1/source");
+        let privilege_wrapper = PrivilegeWrapper::new();
+        fs::create_dir_all(&source_dir).expect("mkdir source");
+        fs::create_dir_all(&systemd_dir).expect("mkdir systemd");
+        fs::write(source_dir.join("server.js"), "old").expect("write");
+        for (unit, contents) in [
+            ("nanoscale-p1.service", "old service"),
+            ("nanoscale-p1.socket", "old socket"),
+            ("nanoscale-p1-proxy.service", "old proxy"),
+        ] {
+            fs::write(systemd_dir.join(unit), contents).expect("write unit");
+        }
+
+        let previous =
+            PreviousRelease::set_aside(&sites_dir, &systemd_dir, "p1", &privilege_wrapper)
+                .expect("set aside")
+                .expect("installed release");
+        assert!(!source_dir.exists());
+        fs::create_dir_all(&source_dir).expect("mkdir new source");
+        fs::write(source_dir.join("server.js"), "new").expect("write");
+        fs::write(source_dir.join("added.js"), "new").expect("write");
+
+        previous.put_back(&privilege_wrapper).expect("put back");
+        assert_eq!(
+            fs::read_to_string(source_dir.join("server.js")).expect("read"),
+            "old"
+        );
+        assert!(!source_dir.join("added.js").exists());
+        assert!(!sites_dir.join("p1/previous").exists());
+        assert_eq!(
+            previous.units,
+            ServiceUnits {
+                service: "old service".to_string(),
+                socket: "old socket".to_string(),
+                proxy: "old proxy".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn first_deploy_has_no_previous_release() {
+        let tempdir = tempfile::tempdir().expect("tempdir");
+        fs::create_dir_all(tempdir.path().join("sites/p1/source")).expect("mkdir");
+
+        assert!(PreviousRelease::set_aside(
+            &tempdir.path().join("sites"),
+            tempdir.path(),
+            "p1",
+            &PrivilegeWrapper::new()
+        )
+        .expect("set aside")
+        .is_none());
+        assert!(tempdir.path().join("sites/p1/source").is_dir());
+    }
+}
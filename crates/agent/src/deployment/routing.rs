@@ -0,0 +1,379 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+const MAX_BODY_SIZE_MB: u32 = 10_240;
+const MAX_READ_TIMEOUT_SECONDS: u32 = 86_400;
+const MAX_RESPONSE_HEADERS: usize = 32;
+const MAX_HEADER_VALUE_LENGTH: usize = 4_096;
+const MAX_RATE_LIMIT: u32 = 10_000;
+const DEFAULT_RATE_LIMIT_STATUS: u16 = 429;
+const MAX_WAKING_WAIT_SECONDS: u32 = 60;
+const MAX_WAKING_REFRESH_SECONDS: u32 = 300;
+const MAX_PAGE_TITLE_LENGTH: usize = 200;
+const MAX_PAGE_MESSAGE_LENGTH: usize = 2_000;
+
+/// Per-project nginx options, set in the dashboard or in the manifest's `[routing]` table.
+///
+/// Unset fields keep nginx's defaults, so an empty config renders the same site as before.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RoutingConfig {
+    /// `client_max_body_size` in megabytes; nginx allows 1 MB when unset.
+    pub max_body_size_mb: Option<u32>,
+    /// `proxy_read_timeout` in seconds; nginx waits 60 seconds when unset.
+    pub read_timeout_seconds: Option<u32>,
+    /// Forwards `Upgrade`/`Connection` so WebSocket handshakes reach the app.
+    pub websockets: Option<bool>,
+    /// Compresses text responses from the app.
+    pub gzip: Option<bool>,
+    /// Extra response headers, e.g. `Strict-Transport-Security` or `Content-Security-Policy`.
+    pub headers: BTreeMap<String, String>,
+    /// Path prefixes served with a long-lived `Cache-Control` header.
+    pub cache_paths: Vec<CachePath>,
+    /// Per-client request rate and connection limits.
+    pub rate_limit: Option<RateLimit>,
+    /// Page shown while a scaled-to-zero app boots; requests wait out the cold start when unset.
+    pub waking_page: Option<WakingPage>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CachePath {
+    /// URL prefix such as `/assets/`.
+    pub path: String,
+    pub max_age_seconds: u32,
+}
+
+/// Limits applied per client IP with nginx's `limit_req` and `limit_conn`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    pub requests_per_second: u32,
+    /// Requests above the rate that are still served straight away instead of rejected.
+    #[serde(default)]
+    pub burst: u32,
+    /// Concurrent connections allowed per client IP; unlimited when unset.
+    pub max_connections: Option<u32>,
+    /// Path prefixes the limits apply to; the whole site when empty.
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Status returned to limited requests.
+    #[serde(default = "default_rate_limit_status")]
+    pub status: u16,
+}
+
+const fn default_rate_limit_status() -> u16 {
+    DEFAULT_RATE_LIMIT_STATUS
+}
+
+/// The "waking up" page served with a 503 when the app is still booting after `wait_seconds`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WakingPage {
+    /// Heading of the page; a generic one is used when unset.
+    pub title: Option<String>,
+    /// Text below the heading.
+    pub message: Option<String>,
+    /// How long a request waits for the app before the page is shown instead.
+    pub wait_seconds: u32,
+    /// How often the page reloads itself, also sent as `Retry-After`.
+    pub refresh_seconds: u32,
+}
+
+impl Default for WakingPage {
+    fn default() -> Self {
+        Self {
+            title: None,
+            message: None,
+            wait_seconds: 5,
+            refresh_seconds: 5,
+        }
+    }
+}
+
+impl RateLimit {
+    /// Whether requests under the location prefix `path` (`None` for `/`) are limited.
+    #[must_use]
+    pub fn applies_to(&self, path: Option<&str>) -> bool {
+        self.paths.is_empty()
+            || path.is_some_and(|path| self.paths.iter().any(|prefix| path.starts_with(prefix)))
+    }
+}
+
+impl RoutingConfig {
+    /// Checks every value before it is written into an nginx config.
+    ///
+    /// # Errors
+    /// Returns an error for out-of-range sizes or timeouts, header names or values that could
+    /// break out of their directive, and cache paths that are not plain absolute prefixes.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(max_body_size_mb) = self.max_body_size_mb {
+            if !(1..=MAX_BODY_SIZE_MB).contains(&max_body_size_mb) {
+                bail!("routing.max_body_size_mb must be between 1 and {MAX_BODY_SIZE_MB}");
+            }
+        }
+        if let Some(read_timeout_seconds) = self.read_timeout_seconds {
+            if !(1..=MAX_READ_TIMEOUT_SECONDS).contains(&read_timeout_seconds) {
+                bail!(
+                    "routing.read_timeout_seconds must be between 1 and {MAX_READ_TIMEOUT_SECONDS}"
+                );
+            }
+        }
+
+        if self.headers.len() > MAX_RESPONSE_HEADERS {
+            bail!("routing.headers allows at most {MAX_RESPONSE_HEADERS} headers");
+        }
+        for (name, value) in &self.headers {
+            let name_valid = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|character| character.is_ascii_alphanumeric() || character == '-');
+            if !name_valid {
+                bail!("routing header name must match ^[A-Za-z0-9-]+$: {name:?}");
+            }
+            if name.eq_ignore_ascii_case("cache-control") && !self.cache_paths.is_empty() {
+                bail!("routing header Cache-Control conflicts with routing.cache_paths");
+            }
+            let value_valid = !value.trim().is_empty()
+                && value.len() <= MAX_HEADER_VALUE_LENGTH
+                && value.chars().all(|character| {
+                    (' '..='~').contains(&character) && !"\"\\$".contains(character)
+                });
+            if !value_valid {
+                bail!(
+                    "routing header {name} needs a printable ASCII value without quotes, backslashes or $"
+                );
+            }
+        }
+
+        for cache_path in &self.cache_paths {
+            validate_path_prefix("cache path", &cache_path.path)?;
+            if cache_path.max_age_seconds == 0 {
+                bail!(
+                    "routing cache path {} needs max_age_seconds > 0",
+                    cache_path.path
+                );
+            }
+        }
+
+        if let Some(rate_limit) = &self.rate_limit {
+            let counts = [
+                ("requests_per_second", rate_limit.requests_per_second),
+                ("max_connections", rate_limit.max_connections.unwrap_or(1)),
+            ];
+            for (field, value) in counts {
+                if !(1..=MAX_RATE_LIMIT).contains(&value) {
+                    bail!("routing.rate_limit.{field} must be between 1 and {MAX_RATE_LIMIT}");
+                }
+            }
+            if rate_limit.burst > MAX_RATE_LIMIT {
+                bail!("routing.rate_limit.burst must be at most {MAX_RATE_LIMIT}");
+            }
+            if !(400..=599).contains(&rate_limit.status) {
+                bail!("routing.rate_limit.status must be a 4xx or 5xx status");
+            }
+            for (index, path) in rate_limit.paths.iter().enumerate() {
+                validate_path_prefix("rate limit path", path)?;
+                if rate_limit.paths[..index].contains(path) {
+                    bail!("routing rate limit path {path} is listed twice");
+                }
+            }
+        }
+
+        if let Some(waking_page) = &self.waking_page {
+            if !(1..=MAX_WAKING_WAIT_SECONDS).contains(&waking_page.wait_seconds) {
+                bail!(
+                    "routing.waking_page.wait_seconds must be between 1 and {MAX_WAKING_WAIT_SECONDS}"
+                );
+            }
+            if !(1..=MAX_WAKING_REFRESH_SECONDS).contains(&waking_page.refresh_seconds) {
+                bail!(
+                    "routing.waking_page.refresh_seconds must be between 1 and {MAX_WAKING_REFRESH_SECONDS}"
+                );
+            }
+            validate_page_text(
+                "routing.waking_page.title",
+                waking_page.title.as_deref(),
+                MAX_PAGE_TITLE_LENGTH,
+            )?;
+            validate_page_text(
+                "routing.waking_page.message",
+                waking_page.message.as_deref(),
+                MAX_PAGE_MESSAGE_LENGTH,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies `overrides` (the repository manifest) on top of this config: set fields win,
+    /// headers are merged by name and a non-empty `cache_paths` replaces the list.
+    pub fn merge(&mut self, overrides: Self) {
+        if overrides.max_body_size_mb.is_some() {
+            self.max_body_size_mb = overrides.max_body_size_mb;
+        }
+        if overrides.read_timeout_seconds.is_some() {
+            self.read_timeout_seconds = overrides.read_timeout_seconds;
+        }
+        if overrides.websockets.is_some() {
+            self.websockets = overrides.websockets;
+        }
+        if overrides.gzip.is_some() {
+            self.gzip = overrides.gzip;
+        }
+        self.headers.extend(overrides.headers);
+        if !overrides.cache_paths.is_empty() {
+            self.cache_paths = overrides.cache_paths;
+        }
+        if overrides.rate_limit.is_some() {
+            self.rate_limit = overrides.rate_limit;
+        }
+        if overrides.waking_page.is_some() {
+            self.waking_page = overrides.waking_page;
+        }
+    }
+}
+
+/// A path prefix gets its own `location ^~` block, so it must be a plain absolute path other
+/// than `/` (which would duplicate `location /`).
+fn validate_path_prefix(kind: &str, path: &str) -> Result<()> {
+    let path_valid = path.len() > 1
+        && path.starts_with('/')
+        && !path.contains("..")
+        && path
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "/-_.".contains(character));
+    if !path_valid {
+        bail!("routing {kind} must be an absolute URL prefix of [A-Za-z0-9/-_.] other than /: {path:?}");
+    }
+
+    Ok(())
+}
+
+/// Checks text shown on a status page: it is HTML-escaped when rendered, so only its length
+/// and control characters matter.
+///
+/// # Errors
+/// Returns an error naming `field` when the text is blank, too long, or has control characters.
+pub fn validate_page_text(field: &str, text: Option<&str>, max_length: usize) -> Result<()> {
+    let Some(text) = text else {
+        return Ok(());
+    };
+    let valid = !text.trim().is_empty()
+        && text.chars().count() <= max_length
+        && !text
+            .chars()
+            .any(|character| character.is_control() && character != '\n');
+    if !valid {
+        bail!("{field} must be 1-{max_length} characters without control characters");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_rejects_values_that_break_out_of_directives() {
+        RoutingConfig::default().validate().expect("empty config");
+
+        let with_header = |name: &str, value: &str| RoutingConfig {
+            headers: [(name.to_string(), value.to_string())]
+                .into_iter()
+                .collect(),
+            ..RoutingConfig::default()
+        };
+        with_header(
+            "Content-Security-Policy",
+            "default-src 'self'; img-src 'self' data:",
+        )
+        .validate()
+        .expect("csp");
+        assert!(with_header("X-Evil\"", "1").validate().is_err());
+        assert!(with_header("X-Test", "a\"; return 302 https://evil")
+            .validate()
+            .is_err());
+        assert!(with_header("X-Test", "line\nbreak").validate().is_err());
+        assert!(with_header("X-Test", "$host").validate().is_err());
+
+        let cache = |path: &str, max_age_seconds| RoutingConfig {
+            cache_paths: vec![CachePath {
+                path: path.to_string(),
+                max_age_seconds,
+            }],
+            ..RoutingConfig::default()
+        };
+        cache("/assets/", 3600).validate().expect("cache path");
+        assert!(cache("assets/", 3600).validate().is_err());
+        assert!(cache("/assets/ { }", 3600).validate().is_err());
+        assert!(cache("/assets/", 0).validate().is_err());
+        assert!(cache("/", 3600).validate().is_err());
+
+        let rate = |requests_per_second, paths: &[&str]| RoutingConfig {
+            rate_limit: Some(RateLimit {
+                requests_per_second,
+                burst: 20,
+                max_connections: Some(10),
+                paths: paths.iter().map(ToString::to_string).collect(),
+                status: 429,
+            }),
+            ..RoutingConfig::default()
+        };
+        rate(10, &["/api/"]).validate().expect("rate limit");
+        assert!(rate(0, &[]).validate().is_err());
+        assert!(rate(10, &["/api/;"]).validate().is_err());
+        assert!(rate(10, &["/api/", "/api/"]).validate().is_err());
+
+        let waking = |wait_seconds, title: &str| RoutingConfig {
+            waking_page: Some(WakingPage {
+                title: Some(title.to_string()),
+                wait_seconds,
+                ..WakingPage::default()
+            }),
+            ..RoutingConfig::default()
+        };
+        waking(5, "Acme is <waking> up")
+            .validate()
+            .expect("waking page");
+        assert!(waking(0, "Acme").validate().is_err());
+        assert!(waking(5, " ").validate().is_err());
+        assert!(waking(5, "a\u{7}b").validate().is_err());
+
+        let body = RoutingConfig {
+            max_body_size_mb: Some(0),
+            ..RoutingConfig::default()
+        };
+        assert!(body.validate().is_err());
+    }
+
+    #[test]
+    fn merge_prefers_overrides_and_merges_headers() {
+        let mut dashboard = RoutingConfig {
+            max_body_size_mb: Some(10),
+            websockets: Some(true),
+            headers: [
+                ("X-Frame-Options".to_string(), "DENY".to_string()),
+                ("X-Team".to_string(), "web".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+            ..RoutingConfig::default()
+        };
+        dashboard.merge(RoutingConfig {
+            max_body_size_mb: Some(50),
+            headers: [("X-Frame-Options".to_string(), "SAMEORIGIN".to_string())]
+                .into_iter()
+                .collect(),
+            ..RoutingConfig::default()
+        });
+
+        assert_eq!(dashboard.max_body_size_mb, Some(50));
+        assert_eq!(dashboard.websockets, Some(true));
+        assert_eq!(dashboard.headers["X-Frame-Options"], "SAMEORIGIN");
+        assert_eq!(dashboard.headers["X-Team"], "web");
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashSet;
+use std::fmt::Write as _;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::deployment::firewall::{FirewallRule, Protocol};
+
+/// Lowest public port a project may expose; the ports below belong to the host.
+const MIN_PUBLIC_PORT: u16 = 1024;
+/// The internal API, which no project may take over.
+const RESERVED_PORTS: [u16; 1] = [4000];
+const MAX_STREAM_PORTS: usize = 16;
+
+/// A public TCP or UDP port that nginx forwards, unchanged, to the app.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct StreamPort {
+    /// Port opened on every interface of the server.
+    pub port: u16,
+    pub protocol: Protocol,
+    /// Port the app listens on, on `127.0.0.1`.
+    pub target_port: u16,
+}
+
+impl StreamPort {
+    /// e.g. `1883/tcp -> 127.0.0.1:11883`.
+    #[must_use]
+    pub fn describe(&self) -> String {
+        format!(
+            "{}/{} -> 127.0.0.1:{}",
+            self.port, self.protocol, self.target_port
+        )
+    }
+}
+
+/// Checks a project's stream ports against each other and against its HTTP port.
+///
+/// # Errors
+/// Returns an error if a port is reserved, below 1024, used twice, or the same as `app_port`.
+pub fn validate(streams: &[StreamPort], app_port: u16) -> Result<()> {
+    if streams.len() > MAX_STREAM_PORTS {
+        bail!("at most {MAX_STREAM_PORTS} TCP/UDP ports can be exposed");
+    }
+
+    let mut public = HashSet::new();
+    let mut targets = HashSet::new();
+    for stream in streams {
+        if stream.port < MIN_PUBLIC_PORT || RESERVED_PORTS.contains(&stream.port) {
+            bail!(
+                "port {} cannot be exposed; use a port from {MIN_PUBLIC_PORT} up other than {RESERVED_PORTS:?}",
+                stream.port
+            );
+        }
+        if stream.target_port < MIN_PUBLIC_PORT {
+            bail!(
+                "target port {} must be {MIN_PUBLIC_PORT} or higher",
+                stream.target_port
+            );
+        }
+        if stream.port == app_port || stream.target_port == app_port {
+            bail!("port {app_port} is the project's HTTP port");
+        }
+        if !public.insert((stream.port, stream.protocol)) {
+            bail!("port {}/{} is exposed twice", stream.port, stream.protocol);
+        }
+        if !targets.insert((stream.target_port, stream.protocol)) {
+            bail!(
+                "target port {}/{} is used twice",
+                stream.target_port,
+                stream.protocol
+            );
+        }
+    }
+    // nginx holds each public port on every interface, so the app cannot bind it on loopback.
+    if let Some(stream) = streams
+        .iter()
+        .find(|stream| public.contains(&(stream.target_port, stream.protocol)))
+    {
+        bail!(
+            "target port {}/{} is also exposed publicly",
+            stream.target_port,
+            stream.protocol
+        );
+    }
+
+    Ok(())
+}
+
+/// ufw rules opening every public stream port to anyone.
+#[must_use]
+pub fn firewall_rules(streams: &[StreamPort]) -> Vec<FirewallRule> {
+    streams
+        .iter()
+        .map(|stream| FirewallRule {
+            port: stream.port,
+            protocol: stream.protocol,
+            from: None,
+        })
+        .collect()
+}
+
+/// nginx `stream` servers for the project, or `None` when it exposes no ports.
+#[must_use]
+pub fn render(project_id: &str, streams: &[StreamPort]) -> Option<String> {
+    if streams.is_empty() {
+        return None;
+    }
+
+    let mut conf = format!("# NanoScale TCP/UDP ports for {project_id}\n");
+    for stream in streams {
+        let udp = match stream.protocol {
+            Protocol::Tcp => "",
+            Protocol::Udp => " udp",
+        };
+        let _ = write!(
+            conf,
+            "server {{\n    listen {}{udp};\n    proxy_pass 127.0.0.1:{};\n}}\n",
+            stream.port, stream.target_port
+        );
+    }
+    Some(conf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(port: u16, protocol: Protocol, target_port: u16) -> StreamPort {
+        StreamPort {
+            port,
+            protocol,
+            target_port,
+        }
+    }
+
+    #[test]
+    fn render_forwards_each_port_to_loopback() {
+        let streams = [
+            stream(1883, Protocol::Tcp, 11883),
+            stream(27015, Protocol::Udp, 27016),
+        ];
+        validate(&streams, 3100).expect("valid");
+
+        assert_eq!(
+            render("p1", &streams).expect("conf"),
+            "# NanoScale TCP/UDP ports for p1\nserver {\n    listen 1883;\n    proxy_pass 127.0.0.1:11883;\n}\nserver {\n    listen 27015 udp;\n    proxy_pass 127.0.0.1:27016;\n}\n"
+        );
+        assert!(render("p1", &[]).is_none());
+        assert_eq!(
+            firewall_rules(&streams[1..]),
+            [FirewallRule {
+                port: 27015,
+                protocol: Protocol::Udp,
+                from: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn validate_rejects_reserved_and_clashing_ports() {
+        // The same number over both protocols is fine.
+        validate(
+            &[
+                stream(5000, Protocol::Tcp, 15000),
+                stream(5000, Protocol::Udp, 15000),
+            ],
+            3100,
+        )
+        .expect("tcp and udp");
+
+        for streams in [
+            vec![stream(25, Protocol::Tcp, 2525)],
+            vec![stream(4000, Protocol::Tcp, 14000)],
+            vec![stream(5000, Protocol::Tcp, 80)],
+            vec![stream(3100, Protocol::Tcp, 13100)],
+            vec![stream(5000, Protocol::Tcp, 3100)],
+            vec![
+                stream(5000, Protocol::Tcp, 15000),
+                stream(5000, Protocol::Tcp, 15001),
+            ],
+            vec![
+                stream(5000, Protocol::Tcp, 15000),
+                stream(5001, Protocol::Tcp, 15000),
+            ],
+            vec![
+                stream(5000, Protocol::Tcp, 5001),
+                stream(5001, Protocol::Tcp, 15001),
+            ],
+        ] {
+            assert!(validate(&streams, 3100).is_err(), "{streams:?}");
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+
+use anyhow::{bail, Result};
+
+use crate::system::PrivilegeWrapper;
+
+use super::TMP_BASE_PATH;
+
+const ENV_FILE_TARGET_PATH: &str = "/etc/default";
+
+pub(super) fn target_path(service_name: &str) -> String {
+    format!("{ENV_FILE_TARGET_PATH}/{service_name}")
+}
+
+/// Installs the project's env vars as a root-owned systemd `EnvironmentFile`, replacing any
+/// previous file so removed variables do not linger across redeploys.
+pub(super) fn install(
+    service_name: &str,
+    env_vars: &[(String, String)],
+    privilege_wrapper: &PrivilegeWrapper,
+) -> Result<()> {
+    let contents = contents(env_vars)?;
+    let tmp_env_path = format!("{TMP_BASE_PATH}/{service_name}.env");
+    let env_target = target_path(service_name);
+
+    // Env values are secrets; never let the temp copy be readable by other users.
+    let _ = fs::remove_file(&tmp_env_path);
+    let mut env_file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&tmp_env_path)?;
+    env_file.write_all(contents.as_bytes())?;
+    drop(env_file);
+
+    privilege_wrapper.run("/usr/bin/mv", &[&tmp_env_path, &env_target])?;
+    privilege_wrapper.run("/usr/bin/chown", &["root:root", &env_target])?;
+    Ok(())
+}
+
+pub(crate) fn validate_key(key: &str) -> Result<()> {
+    let mut characters = key.chars();
+    let valid_start = characters
+        .next()
+        .is_some_and(|character| character.is_ascii_alphabetic() || character == '_');
+    if !valid_start
+        || !characters.all(|character| character.is_ascii_alphanumeric() || character == '_')
+    {
+        bail!("invalid environment variable name: {key:?}");
+    }
+
+    Ok(())
+}
+
+fn contents(env_vars: &[(String, String)]) -> Result<String> {
+    let mut contents = String::new();
+    for (key, value) in env_vars {
+        validate_key(key)?;
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('$', "\\$")
+            .replace('`', "\\`");
+        let _ = writeln!(contents, "{key}=\"{escaped}\"");
+    }
+
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contents_quotes_values_and_rejects_bad_keys() {
+        let rendered = contents(&[
+            ("DATABASE_URL".to_string(), "postgres://a:b@c/d".to_string()),
+            ("QUOTED".to_string(), "say \"hi\" $HOME".to_string()),
+        ])
+        .expect("contents");
+        assert!(rendered.contains("DATABASE_URL=\"postgres://a:b@c/d\"\n"));
+        assert!(rendered.contains("QUOTED=\"say \\\"hi\\\" \\$HOME\"\n"));
+
+        assert!(contents(&[("1BAD".to_string(), String::new())]).is_err());
+        assert!(contents(&[("A-B".to_string(), String::new())]).is_err());
+    }
+}
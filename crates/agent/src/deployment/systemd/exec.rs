@@ -0,0 +1,270 @@
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+
+use super::{env_file, ServiceSettings, SystemdGenerator};
+use crate::deployment::build::AppRuntime;
+
+impl SystemdGenerator {
+    /// Runs the image in the service's own cgroup (`--cgroups=split`) so systemd accounting,
+    /// limits and scale-to-zero stops apply to the container exactly as to a native process.
+    /// Only the app's own container publishes ports; a job's would clash with it.
+    pub(super) fn container_exec_start(
+        service_name: &str,
+        podman_binary: &str,
+        image: &str,
+        settings: &ServiceSettings<'_>,
+        port: u16,
+        publish: bool,
+    ) -> Result<String> {
+        let mut arguments = vec![
+            podman_binary.to_string(),
+            "run".to_string(),
+            "--rm".to_string(),
+            "--replace".to_string(),
+            format!("--name={service_name}"),
+            "--cgroups=split".to_string(),
+        ];
+        if publish {
+            arguments.push(format!("--publish=127.0.0.1:{port}:{port}"));
+            arguments.push("--env=PORT".to_string());
+            for stream in settings.stream_ports {
+                arguments.push(format!(
+                    "--publish=127.0.0.1:{0}:{0}/{1}",
+                    stream.target_port, stream.protocol
+                ));
+            }
+        }
+        for service in &settings.private_network.services {
+            arguments.push(format!("--add-host={}:{}", service.hostname(), service.ip));
+        }
+        for volume in settings.volumes {
+            if !volume.mount_path.starts_with('/') {
+                bail!(
+                    "volume mount path {} must be absolute for a container",
+                    volume.mount_path
+                );
+            }
+            arguments.push(format!(
+                "--volume={}:{}",
+                volume.source.display(),
+                volume.target("/")
+            ));
+        }
+
+        for (key, _value) in settings.env_vars {
+            env_file::validate_key(key)?;
+            arguments.push(format!("--env={key}"));
+        }
+
+        if let Some(memory_max_mb) = settings.resource_limits.memory_max_mb {
+            arguments.push(format!("--memory={memory_max_mb}m"));
+        }
+        if let Some(cpu_quota_percent) = settings.resource_limits.cpu_quota_percent {
+            let cpus = f64::from(cpu_quota_percent) / 100.0;
+            arguments.push(format!("--cpus={cpus}"));
+        }
+
+        arguments.push(image.to_string());
+
+        let trimmed_run_command = settings.run_command.trim();
+        if !trimmed_run_command.is_empty() {
+            let (program, command_arguments) = Self::parse_command(trimmed_run_command)?;
+            arguments.push(program);
+            arguments.extend(command_arguments);
+        }
+
+        Ok(arguments.join(" "))
+    }
+
+    pub(super) fn resolve_exec_start(
+        source_dir: &str,
+        runtime: &AppRuntime,
+        run_command: &str,
+        port: u16,
+    ) -> Result<String> {
+        let trimmed_run_command = run_command.trim();
+        if trimmed_run_command.is_empty() {
+            return match runtime {
+                AppRuntime::StandaloneNode => Ok(format!("/usr/bin/node {source_dir}/server.js")),
+                AppRuntime::BunStart { bun_binary } => Ok(format!(
+                    "{bun_binary} run start -- --hostname 127.0.0.1 --port {port}"
+                )),
+                AppRuntime::StaticSite => Ok(format!(
+                    "/usr/bin/python3 -m http.server {port} --bind 127.0.0.1 --directory {source_dir}"
+                )),
+                AppRuntime::Command => {
+                    bail!("run command is required: no default start command for this project")
+                }
+                AppRuntime::Container { .. } => {
+                    bail!("container runtime exec start is rendered by the container template")
+                }
+            };
+        }
+
+        let (program, arguments) = Self::parse_command(trimmed_run_command)?;
+        let executable = if program == "bun" {
+            Self::bun_binary()?
+        } else if program.contains('/') && !program.starts_with('/') {
+            // systemd only accepts absolute paths or bare names searched in its fixed PATH.
+            format!("{source_dir}/{}", program.trim_start_matches("./"))
+        } else {
+            program
+        };
+
+        if arguments.is_empty() {
+            return Ok(executable);
+        }
+
+        Ok(format!("{executable} {}", arguments.join(" ")))
+    }
+
+    fn parse_command(raw_command: &str) -> Result<(String, Vec<String>)> {
+        let trimmed_command = raw_command.trim();
+        if trimmed_command.is_empty() {
+            bail!("run command cannot be empty");
+        }
+
+        let forbidden_characters = [';', '|', '&', '>', '<', '`', '$', '\n', '\r'];
+        if trimmed_command
+            .chars()
+            .any(|character| forbidden_characters.contains(&character))
+        {
+            bail!("run command contains unsupported shell control characters");
+        }
+
+        let mut parts = trimmed_command.split_whitespace();
+        let program = parts
+            .next()
+            .ok_or_else(|| anyhow!("run command program is missing"))?
+            .to_string();
+        let arguments = parts.map(ToString::to_string).collect::<Vec<String>>();
+
+        Ok((program, arguments))
+    }
+
+    fn bun_binary() -> Result<String> {
+        if let Ok(configured_binary) = std::env::var("NANOSCALE_BUN_BIN") {
+            let trimmed_binary = configured_binary.trim();
+            if !trimmed_binary.is_empty() {
+                return Ok(trimmed_binary.to_string());
+            }
+        }
+
+        for candidate in ["/usr/bin/bun", "/bin/bun", "/usr/local/bin/bun"] {
+            if Path::new(candidate).is_file() {
+                return Ok(candidate.to_string());
+            }
+        }
+
+        if let Ok(path_value) = std::env::var("PATH") {
+            for path_entry in path_value.split(':') {
+                if path_entry.is_empty() {
+                    continue;
+                }
+
+                let candidate_path = Path::new(path_entry).join("bun");
+                if candidate_path.is_file() {
+                    return Ok(candidate_path.to_string_lossy().to_string());
+                }
+            }
+        }
+
+        let current_path = std::env::var("PATH").unwrap_or_default();
+        bail!("bun binary not found; install bun or set NANOSCALE_BUN_BIN (PATH={current_path})")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Mutex, OnceLock};
+
+    fn env_lock() -> &'static Mutex<()> {
+        static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+        LOCK.get_or_init(|| Mutex::new(()))
+    }
+
+    #[test]
+    fn parse_command_rejects_shell_control_characters() {
+        assert!(SystemdGenerator::parse_command("echo hi; rm -rf /").is_err());
+        assert!(SystemdGenerator::parse_command(" ").is_err());
+    }
+
+    #[test]
+    fn resolve_exec_start_defaults_based_on_runtime() {
+        let standalone = SystemdGenerator::resolve_exec_start(
+            "/opt/nanoscale/sites/p1/source",
+            &AppRuntime::StandaloneNode,
+            "",
+            3100,
+        )
+        .expect("exec");
+        assert_eq!(
+            standalone,
+            "/usr/bin/node /opt/nanoscale/sites/p1/source/server.js"
+        );
+
+        let bun = SystemdGenerator::resolve_exec_start(
+            "/opt/nanoscale/sites/p1/source",
+            &AppRuntime::BunStart {
+                bun_binary: "/custom/bun".to_string(),
+            },
+            "",
+            3100,
+        )
+        .expect("exec");
+        assert!(bun.contains("/custom/bun"));
+        assert!(bun.contains("--port 3100"));
+
+        let static_site = SystemdGenerator::resolve_exec_start(
+            "/opt/nanoscale/sites/p1/source",
+            &AppRuntime::StaticSite,
+            "",
+            3100,
+        )
+        .expect("exec");
+        assert!(static_site.contains("--directory /opt/nanoscale/sites/p1/source"));
+
+        assert!(SystemdGenerator::resolve_exec_start(
+            "/opt/nanoscale/sites/p1/source",
+            &AppRuntime::Command,
+            "",
+            3100,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn resolve_exec_start_anchors_relative_programs_to_source_dir() {
+        let exec = SystemdGenerator::resolve_exec_start(
+            "/opt/nanoscale/sites/p1/source",
+            &AppRuntime::Command,
+            "./target/release/api --verbose",
+            3100,
+        )
+        .expect("exec");
+        assert_eq!(
+            exec,
+            "/opt/nanoscale/sites/p1/source/target/release/api --verbose"
+        );
+    }
+
+    #[test]
+    fn resolve_exec_start_uses_env_bun_for_explicit_bun_command() {
+        let _guard = env_lock().lock().expect("env lock poisoned");
+        std::env::set_var("NANOSCALE_BUN_BIN", "  /env/bun  ");
+
+        let exec = SystemdGenerator::resolve_exec_start(
+            "/src",
+            &AppRuntime::StandaloneNode,
+            "bun run start",
+            9999,
+        )
+        .expect("exec");
+        assert!(exec.starts_with("/env/bun"));
+        assert!(exec.contains("run start"));
+
+        std::env::remove_var("NANOSCALE_BUN_BIN");
+    }
+}
@@ -0,0 +1,102 @@
+use anyhow::{anyhow, Result};
+
+use super::unit::ProjectUnit;
+use super::{ServiceSettings, SystemdGenerator};
+use crate::deployment::build::AppRuntime;
+use crate::system::PrivilegeWrapper;
+
+impl SystemdGenerator {
+    /// Loads a container release's image into the project's rootless storage, once per deploy,
+    /// so the units only run it. Each build has its own tag, so the live release keeps starting
+    /// its own image until the new one is installed. Does nothing for a native runtime.
+    ///
+    /// # Errors
+    /// Returns an error if the unit cannot be installed or the image fails to load.
+    pub fn load_image(
+        project_id: &str,
+        runtime: &AppRuntime,
+        settings: &ServiceSettings<'_>,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        let unit_name = format!("nanoscale-{project_id}-image-load");
+        let Some(template) = Self::image_load_template(&unit_name, project_id, runtime, settings)?
+        else {
+            return Ok(());
+        };
+
+        Self::run_oneshot(&unit_name, &template, privilege_wrapper).map_err(|error| {
+            anyhow!("container image load failed (see journalctl -u {unit_name}): {error:#}")
+        })
+    }
+
+    /// Removes the project's images other than the live release's, once it serves.
+    ///
+    /// # Errors
+    /// Returns an error if the unit cannot be installed or podman fails to remove an image.
+    pub fn prune_images(
+        project_id: &str,
+        runtime: &AppRuntime,
+        settings: &ServiceSettings<'_>,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        let AppRuntime::Container {
+            podman_binary,
+            image,
+            ..
+        } = runtime
+        else {
+            return Ok(());
+        };
+        let unit_name = format!("nanoscale-{project_id}-image-prune");
+        let mut unit = ProjectUnit::new(project_id, runtime, "", settings)?;
+        unit.description = format!("NanoScale container image cleanup ({unit_name})");
+        unit.exec_start = format!(
+            "/bin/sh -c '{podman_binary} images --noheading --format \"{{{{.Repository}}}}:{{{{.Tag}}}}\" --filter reference=localhost/nanoscale-{project_id} | grep -vxF {image} | xargs -r {podman_binary} rmi --ignore'"
+        );
+
+        Self::run_oneshot(&unit_name, &unit.render(), privilege_wrapper)
+    }
+
+    /// Removes the image load and cleanup units, if installed. The caller reloads systemd.
+    ///
+    /// # Errors
+    /// Returns an error if a unit file cannot be removed.
+    pub fn remove_image_units(
+        project_id: &str,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        Self::remove_oneshot(
+            &format!("nanoscale-{project_id}-image-load"),
+            privilege_wrapper,
+        )?;
+        Self::remove_oneshot(
+            &format!("nanoscale-{project_id}-image-prune"),
+            privilege_wrapper,
+        )
+    }
+
+    /// Oneshot service loading a container release's image archive; `None` for a native runtime.
+    pub(super) fn image_load_template(
+        unit_name: &str,
+        project_id: &str,
+        runtime: &AppRuntime,
+        settings: &ServiceSettings<'_>,
+    ) -> Result<Option<String>> {
+        let AppRuntime::Container {
+            podman_binary,
+            image_archive,
+            ..
+        } = runtime
+        else {
+            return Ok(None);
+        };
+        let image_archive = image_archive
+            .to_str()
+            .ok_or_else(|| anyhow!("invalid container image archive path"))?;
+        let mut unit = ProjectUnit::new(project_id, runtime, "", settings)?;
+        unit.description = format!("NanoScale container image load ({unit_name})");
+        unit.exec_start = format!("{podman_binary} load --quiet --input {image_archive}");
+
+        Ok(Some(unit.render()))
+    }
+}
@@ -0,0 +1,254 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use super::unit::ProjectUnit;
+use super::{ServiceSettings, SystemdGenerator, SYSTEMD_TARGET_PATH, TMP_BASE_PATH};
+use crate::deployment::build::AppRuntime;
+use crate::deployment::jobs::{self, CronJob};
+use crate::system::PrivilegeWrapper;
+
+impl SystemdGenerator {
+    /// Installs a oneshot service and a timer per job, running the job's command as the app's
+    /// service does: same user, environment file, sandboxing and limits. Job units of the
+    /// project that are no longer wanted are removed first.
+    ///
+    /// # Errors
+    /// Returns an error if a schedule or command is invalid, unit files cannot be written, or
+    /// privileged install/enable commands fail.
+    pub fn install_jobs(
+        project_id: &str,
+        source_dir: &Path,
+        runtime: &AppRuntime,
+        settings: &ServiceSettings<'_>,
+        jobs: &[CronJob],
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        let stale = jobs::installed(Path::new(SYSTEMD_TARGET_PATH), project_id)?
+            .into_iter()
+            .filter(|name| !jobs.iter().any(|job| job.name == *name))
+            .collect::<Vec<_>>();
+        for name in &stale {
+            Self::remove_job(project_id, name, privilege_wrapper)?;
+        }
+        if jobs.is_empty() {
+            if !stale.is_empty() {
+                privilege_wrapper.run("/usr/bin/systemctl", &["daemon-reload"])?;
+            }
+            return Ok(());
+        }
+
+        let service_name = format!("nanoscale-{project_id}");
+        let source_dir = source_dir
+            .to_str()
+            .ok_or_else(|| anyhow!("invalid source path"))?;
+        let runs_dir = jobs::runs_dir(project_id);
+        fs::create_dir_all(&runs_dir)?;
+        let runs_dir = runs_dir
+            .to_str()
+            .ok_or_else(|| anyhow!("invalid job runs path"))?;
+        privilege_wrapper.run(
+            "/usr/bin/chown",
+            &["-R", &format!("{service_name}:{service_name}"), runs_dir],
+        )?;
+
+        for job in jobs {
+            let unit_name = jobs::unit_name(project_id, &job.name);
+            let service_template = Self::job_service_template(
+                &unit_name, project_id, source_dir, runtime, settings, job, runs_dir,
+            )?;
+            let timer_template = Self::job_timer_template(&unit_name, job)?;
+            for (suffix, template) in [("service", service_template), ("timer", timer_template)] {
+                let tmp_path = format!("{TMP_BASE_PATH}/{unit_name}.{suffix}");
+                let target = format!("{SYSTEMD_TARGET_PATH}/{unit_name}.{suffix}");
+                fs::write(&tmp_path, template)?;
+                privilege_wrapper.run("/usr/bin/mv", &[&tmp_path, &target])?;
+                privilege_wrapper.run("/usr/bin/chown", &["root:root", &target])?;
+            }
+        }
+
+        privilege_wrapper.run("/usr/bin/systemctl", &["daemon-reload"])?;
+        for job in jobs {
+            privilege_wrapper.run(
+                "/usr/bin/systemctl",
+                &[
+                    "enable",
+                    "--now",
+                    &format!("{}.timer", jobs::unit_name(project_id, &job.name)),
+                ],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Stops the job's timer and removes its units; a run in progress is stopped too. The
+    /// caller reloads systemd.
+    ///
+    /// # Errors
+    /// Returns an error if a unit file cannot be removed.
+    pub fn remove_job(
+        project_id: &str,
+        name: &str,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        let unit_name = jobs::unit_name(project_id, name);
+        let timer = format!("{unit_name}.timer");
+        let service = format!("{unit_name}.service");
+        let _ = privilege_wrapper.run("/usr/bin/systemctl", &["disable", "--now", &timer]);
+        let _ = privilege_wrapper.run("/usr/bin/systemctl", &["stop", &service]);
+
+        for path in [
+            format!("{SYSTEMD_TARGET_PATH}/{timer}"),
+            format!("{SYSTEMD_TARGET_PATH}/{service}"),
+            format!("{SYSTEMD_TARGET_PATH}/timers.target.wants/{timer}"),
+        ] {
+            if Path::new(&path).exists() {
+                privilege_wrapper.run("/usr/bin/rm", &["-f", &path])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Oneshot service for one run of `job`. `ExecStartPre` and `ExecStopPost` record the
+    /// run's start, end, result and exit status in the job's runs file under `runs_dir`.
+    pub(super) fn job_service_template(
+        unit_name: &str,
+        project_id: &str,
+        source_dir: &str,
+        runtime: &AppRuntime,
+        settings: &ServiceSettings<'_>,
+        job: &CronJob,
+        runs_dir: &str,
+    ) -> Result<String> {
+        let started = format!("{runs_dir}/{}.started", job.name);
+        let runs = format!("{runs_dir}/{}.runs", job.name);
+        let mut unit = ProjectUnit::new(project_id, runtime, source_dir, settings)?;
+        unit.timeout_seconds = Some(job.timeout_seconds);
+        unit.writable_paths.push(runs_dir);
+        unit.exec_start_pre
+            .push(format!("/bin/sh -c 'date +%%s > {started}'"));
+        unit.exec_stop_post = Some(format!(
+            "/bin/sh -c 'echo \"$$(cat {started} 2>/dev/null || echo -) $$(date +%%s) $${{SERVICE_RESULT:--}} $${{EXIT_STATUS:--}}\" >> {runs}; rm -f {started}; tail -n {} {runs} > {runs}.tmp && mv {runs}.tmp {runs}'",
+            jobs::MAX_RUNS
+        ));
+        let job_settings = ServiceSettings {
+            run_command: &job.command,
+            ..*settings
+        };
+
+        if let AppRuntime::Container {
+            podman_binary,
+            image,
+            ..
+        } = runtime
+        {
+            unit.description = format!("NanoScale container job {} ({unit_name})", job.name);
+            unit.exec_start = Self::container_exec_start(
+                unit_name,
+                podman_binary,
+                image,
+                &job_settings,
+                settings.backend_port,
+                false,
+            )?;
+        } else {
+            unit.description = format!("NanoScale job {} ({unit_name})", job.name);
+            unit.exec_start =
+                Self::resolve_exec_start(source_dir, runtime, &job.command, settings.backend_port)?;
+        }
+
+        Ok(unit.render())
+    }
+
+    fn job_timer_template(unit_name: &str, job: &CronJob) -> Result<String> {
+        let calendar = jobs::on_calendar(&job.schedule)?;
+        Ok(format!(
+            "[Unit]\nDescription=NanoScale schedule for job {} ({unit_name})\n\n[Timer]\nOnCalendar={calendar}\nAccuracySec=1s\n\n[Install]\nWantedBy=timers.target\n",
+            job.name
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deployment::systemd::tests::{container_runtime, settings};
+    use crate::deployment::systemd::ResourceLimits;
+
+    #[test]
+    fn job_templates_run_the_command_like_the_app_on_a_timer() {
+        let settings = ServiceSettings {
+            resource_limits: ResourceLimits {
+                memory_max_mb: Some(512),
+                cpu_quota_percent: None,
+            },
+            hosts_file: Some(Path::new("/opt/nanoscale/hosts/p1")),
+            ..settings()
+        };
+        let job = CronJob {
+            name: "cleanup".to_string(),
+            schedule: "0 3 * * *".to_string(),
+            command: "node scripts/cleanup.js".to_string(),
+            timeout_seconds: 600,
+        };
+        let service = SystemdGenerator::job_service_template(
+            "nanoscale-p1-job-cleanup",
+            "p1",
+            "/opt/nanoscale/sites/p1/source",
+            &AppRuntime::StandaloneNode,
+            &settings,
+            &job,
+            "/opt/nanoscale/sites/p1/jobs",
+        )
+        .expect("service");
+        assert!(service.contains("Type=oneshot\nUser=nanoscale-p1\nGroup=nanoscale-p1\n"));
+        assert!(service.contains("EnvironmentFile=-/etc/default/nanoscale-p1\n"));
+        assert!(service.contains("ExecStart=node scripts/cleanup.js\n"));
+        assert!(service.contains("TimeoutStartSec=600\n"));
+        assert!(service.contains("MemoryMax=512M"));
+        assert!(service.contains(
+            "ReadWritePaths=/opt/nanoscale/sites/p1/source /opt/nanoscale/sites/p1/jobs\n"
+        ));
+        assert!(service.contains("BindReadOnlyPaths=/opt/nanoscale/hosts/p1:/etc/hosts\n"));
+        assert!(service.contains(
+            "ExecStartPre=/bin/sh -c 'date +%%s > /opt/nanoscale/sites/p1/jobs/cleanup.started'\n"
+        ));
+        assert!(service.contains("$${SERVICE_RESULT:--} $${EXIT_STATUS:--}\" >> /opt/nanoscale/sites/p1/jobs/cleanup.runs;"));
+        assert!(!service.contains("[Install]"));
+
+        let timer =
+            SystemdGenerator::job_timer_template("nanoscale-p1-job-cleanup", &job).expect("timer");
+        assert!(timer.contains("OnCalendar=*-*-* 03:00:00\n"));
+        assert!(timer.contains("WantedBy=timers.target\n"));
+    }
+
+    #[test]
+    fn container_job_template_does_not_publish_ports() {
+        let settings = settings();
+        let runtime = container_runtime("/opt/nanoscale/sites/p1/source/image.tar");
+        let job = CronJob {
+            name: "report".to_string(),
+            schedule: "@daily".to_string(),
+            command: "bin/report --all".to_string(),
+            timeout_seconds: 60,
+        };
+        let service = SystemdGenerator::job_service_template(
+            "nanoscale-p1-job-report",
+            "p1",
+            "/opt/nanoscale/sites/p1/source",
+            &runtime,
+            &settings,
+            &job,
+            "/opt/nanoscale/sites/p1/jobs",
+        )
+        .expect("service");
+
+        assert!(service.contains(
+            "ExecStart=/usr/bin/podman run --rm --replace --name=nanoscale-p1-job-report --cgroups=split localhost/nanoscale-p1:latest bin/report --all\n"
+        ));
+        assert!(!service.contains("--publish"));
+    }
+}
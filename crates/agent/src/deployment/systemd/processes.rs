@@ -0,0 +1,237 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use super::unit::ProjectUnit;
+use super::{ServiceSettings, SystemdGenerator, SYSTEMD_TARGET_PATH, TMP_BASE_PATH};
+use crate::deployment::build::AppRuntime;
+use crate::deployment::processes::{self, ProcessType};
+use crate::system::PrivilegeWrapper;
+
+impl SystemdGenerator {
+    /// Installs a template unit per process type and runs `replicas` instances of it, like the
+    /// app's service but without a socket or port, with the process's own limits. Instances
+    /// already running are restarted on the new build; surplus instances and processes no
+    /// longer wanted are stopped and removed. A `stopped` project only gets its template units
+    /// replaced.
+    ///
+    /// # Errors
+    /// Returns an error if a command is invalid, unit files cannot be written, or privileged
+    /// install/enable commands fail.
+    pub fn install_processes(
+        project_id: &str,
+        source_dir: &Path,
+        runtime: &AppRuntime,
+        settings: &ServiceSettings<'_>,
+        processes: &[ProcessType],
+        stopped: bool,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        let wanted = processes
+            .iter()
+            .filter(|process| process.replicas > 0)
+            .collect::<Vec<_>>();
+        let stale = processes::installed(Path::new(SYSTEMD_TARGET_PATH), project_id)?
+            .into_iter()
+            .filter(|name| !wanted.iter().any(|process| process.name == *name))
+            .collect::<Vec<_>>();
+        for name in &stale {
+            Self::remove_process(project_id, name, privilege_wrapper)?;
+        }
+        if wanted.is_empty() {
+            if !stale.is_empty() {
+                privilege_wrapper.run("/usr/bin/systemctl", &["daemon-reload"])?;
+            }
+            return Ok(());
+        }
+
+        let source_dir = source_dir
+            .to_str()
+            .ok_or_else(|| anyhow!("invalid source path"))?;
+        for process in &wanted {
+            let unit_name = processes::unit_name(project_id, &process.name);
+            let template = Self::process_service_template(
+                &unit_name, project_id, source_dir, runtime, settings, process,
+            )?;
+            let tmp_path = format!("{TMP_BASE_PATH}/{unit_name}@.service");
+            let target = format!("{SYSTEMD_TARGET_PATH}/{unit_name}@.service");
+            fs::write(&tmp_path, template)?;
+            privilege_wrapper.run("/usr/bin/mv", &[&tmp_path, &target])?;
+            privilege_wrapper.run("/usr/bin/chown", &["root:root", &target])?;
+        }
+
+        privilege_wrapper.run("/usr/bin/systemctl", &["daemon-reload"])?;
+        if stopped {
+            return Ok(());
+        }
+        let enabled = processes::enabled_instances(Path::new(SYSTEMD_TARGET_PATH), project_id)?;
+        for process in &wanted {
+            let unit_name = processes::unit_name(project_id, &process.name);
+            for instance in 1..=process.replicas {
+                let unit = format!("{unit_name}@{instance}.service");
+                let running = enabled.contains(&unit);
+                privilege_wrapper.run("/usr/bin/systemctl", &["enable", "--now", &unit])?;
+                if running {
+                    privilege_wrapper.run("/usr/bin/systemctl", &["restart", &unit])?;
+                }
+            }
+            let surplus = enabled.iter().filter(|unit| {
+                unit.strip_prefix(&format!("{unit_name}@"))
+                    .and_then(|rest| rest.strip_suffix(".service"))
+                    .and_then(|instance| instance.parse::<u32>().ok())
+                    .is_none_or(|instance| instance > process.replicas)
+            });
+            for unit in surplus {
+                privilege_wrapper.run("/usr/bin/systemctl", &["disable", "--now", unit])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stops every instance of the process and removes its template unit. The caller reloads
+    /// systemd.
+    ///
+    /// # Errors
+    /// Returns an error if the enabled instances cannot be listed or a unit file cannot be
+    /// removed.
+    pub fn remove_process(
+        project_id: &str,
+        name: &str,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        let instance_prefix = format!("{}@", processes::unit_name(project_id, name));
+        let instances = processes::enabled_instances(Path::new(SYSTEMD_TARGET_PATH), project_id)?
+            .into_iter()
+            .filter(|unit| unit.starts_with(&instance_prefix));
+        for unit in instances {
+            let _ = privilege_wrapper.run("/usr/bin/systemctl", &["disable", "--now", &unit]);
+            let link = format!("{SYSTEMD_TARGET_PATH}/multi-user.target.wants/{unit}");
+            if Path::new(&link).exists() {
+                privilege_wrapper.run("/usr/bin/rm", &["-f", &link])?;
+            }
+        }
+
+        let template = format!("{SYSTEMD_TARGET_PATH}/{instance_prefix}.service");
+        if Path::new(&template).exists() {
+            privilege_wrapper.run("/usr/bin/rm", &["-f", &template])?;
+        }
+
+        Ok(())
+    }
+
+    /// Template unit for one process type; `%i` is the instance number.
+    pub(super) fn process_service_template(
+        unit_name: &str,
+        project_id: &str,
+        source_dir: &str,
+        runtime: &AppRuntime,
+        settings: &ServiceSettings<'_>,
+        process: &ProcessType,
+    ) -> Result<String> {
+        let mut unit = ProjectUnit::new(project_id, runtime, source_dir, settings)?;
+        unit.long_running = true;
+        unit.limits = process.resources;
+        let process_settings = ServiceSettings {
+            run_command: &process.command,
+            resource_limits: process.resources,
+            ..*settings
+        };
+
+        if let AppRuntime::Container {
+            podman_binary,
+            image,
+            ..
+        } = runtime
+        {
+            let container_name = format!("{unit_name}-%i");
+            unit.description = format!(
+                "NanoScale container process {} ({unit_name}@%i)",
+                process.name
+            );
+            unit.exec_start = Self::container_exec_start(
+                &container_name,
+                podman_binary,
+                image,
+                &process_settings,
+                settings.backend_port,
+                false,
+            )?;
+            unit.exec_stop = Some(format!(
+                "{podman_binary} stop --ignore --time 10 {container_name}"
+            ));
+        } else {
+            unit.description = format!("NanoScale process {} ({unit_name}@%i)", process.name);
+            unit.exec_start = Self::resolve_exec_start(
+                source_dir,
+                runtime,
+                &process.command,
+                settings.backend_port,
+            )?;
+        }
+
+        Ok(unit.render())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deployment::systemd::tests::{container_runtime, settings};
+    use crate::deployment::systemd::ResourceLimits;
+
+    #[test]
+    fn process_templates_run_instances_with_their_own_limits() {
+        let settings = ServiceSettings {
+            run_command: "bun run start",
+            resource_limits: ResourceLimits {
+                memory_max_mb: Some(512),
+                cpu_quota_percent: None,
+            },
+            ..settings()
+        };
+        let process = ProcessType {
+            name: "worker".to_string(),
+            command: "node worker.js".to_string(),
+            replicas: 2,
+            resources: ResourceLimits {
+                memory_max_mb: Some(256),
+                cpu_quota_percent: Some(25),
+            },
+        };
+        let service = SystemdGenerator::process_service_template(
+            "nanoscale-p1-worker",
+            "p1",
+            "/opt/nanoscale/sites/p1/source",
+            &AppRuntime::StandaloneNode,
+            &settings,
+            &process,
+        )
+        .expect("service");
+        assert!(service.contains("Description=NanoScale process worker (nanoscale-p1-worker@%i)\n"));
+        assert!(service.contains("EnvironmentFile=-/etc/default/nanoscale-p1\n"));
+        assert!(service.contains("ExecStart=node worker.js\n"));
+        assert!(service.contains("MemoryMax=256M\nCPUQuota=25%\n"));
+        assert!(!service.contains("MemoryMax=512M"));
+        assert!(!service.contains("PORT="));
+        assert!(service.contains("WantedBy=multi-user.target\n"));
+
+        let runtime = container_runtime("/opt/nanoscale/sites/p1/source/image.tar");
+        let service = SystemdGenerator::process_service_template(
+            "nanoscale-p1-worker",
+            "p1",
+            "/opt/nanoscale/sites/p1/source",
+            &runtime,
+            &settings,
+            &process,
+        )
+        .expect("container service");
+        assert!(service.contains(
+            "ExecStart=/usr/bin/podman run --rm --replace --name=nanoscale-p1-worker-%i --cgroups=split --memory=256m --cpus=0.25 localhost/nanoscale-p1:latest node worker.js\n"
+        ));
+        assert!(service
+            .contains("ExecStop=/usr/bin/podman stop --ignore --time 10 nanoscale-p1-worker-%i\n"));
+        assert!(!service.contains("--publish"));
+    }
+}
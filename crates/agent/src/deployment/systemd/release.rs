@@ -0,0 +1,206 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+
+use super::unit::ProjectUnit;
+use super::{env_file, ServiceSettings, SystemdGenerator, SYSTEMD_TARGET_PATH, TMP_BASE_PATH};
+use crate::deployment::build::AppRuntime;
+use crate::system::PrivilegeWrapper;
+
+impl SystemdGenerator {
+    /// Runs the release command once, from the built but not yet installed release, as a
+    /// oneshot service with the app's user, environment file, sandboxing and limits. Waits for
+    /// it to finish and fails if it exits unsuccessfully or outlives `timeout_seconds`.
+    ///
+    /// # Errors
+    /// Returns an error if the command is invalid, the unit cannot be installed, or the command
+    /// fails.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run_release(
+        project_id: &str,
+        release_dir: &Path,
+        runtime: &AppRuntime,
+        settings: &ServiceSettings<'_>,
+        command: &str,
+        timeout_seconds: u64,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        let service_name = format!("nanoscale-{project_id}");
+        let unit_name = release_unit_name(project_id);
+        let release_dir = release_dir
+            .to_str()
+            .ok_or_else(|| anyhow!("invalid release path"))?;
+        env_file::install(&service_name, settings.env_vars, privilege_wrapper)?;
+
+        let template = Self::release_service_template(
+            &unit_name,
+            project_id,
+            release_dir,
+            runtime,
+            settings,
+            command,
+            timeout_seconds,
+        )?;
+        Self::run_oneshot(&unit_name, &template, privilege_wrapper).map_err(|error| {
+            anyhow!("release command failed (see journalctl -u {unit_name}): {error:#}")
+        })
+    }
+
+    /// Removes the release command's unit, if one is installed. The caller reloads systemd.
+    ///
+    /// # Errors
+    /// Returns an error if the unit file cannot be removed.
+    pub fn remove_release(project_id: &str, privilege_wrapper: &PrivilegeWrapper) -> Result<()> {
+        Self::remove_oneshot(&release_unit_name(project_id), privilege_wrapper)
+    }
+
+    /// Oneshot service running the release command from `release_dir`, the built release
+    /// still outside the sites directory.
+    pub(super) fn release_service_template(
+        unit_name: &str,
+        project_id: &str,
+        release_dir: &str,
+        runtime: &AppRuntime,
+        settings: &ServiceSettings<'_>,
+        command: &str,
+        timeout_seconds: u64,
+    ) -> Result<String> {
+        if command.trim().is_empty() {
+            bail!("release command cannot be empty");
+        }
+        let mut unit = ProjectUnit::new(project_id, runtime, release_dir, settings)?;
+        unit.timeout_seconds = Some(timeout_seconds);
+        unit.writable_working_dir = false;
+        let release_settings = ServiceSettings {
+            run_command: command,
+            ..*settings
+        };
+
+        if let AppRuntime::Container {
+            podman_binary,
+            image,
+            ..
+        } = runtime
+        {
+            unit.description = format!("NanoScale container release command ({unit_name})");
+            unit.exec_start = Self::container_exec_start(
+                unit_name,
+                podman_binary,
+                image,
+                &release_settings,
+                settings.backend_port,
+                false,
+            )?;
+        } else {
+            unit.description = format!("NanoScale release command ({unit_name})");
+            unit.exec_start =
+                Self::resolve_exec_start(release_dir, runtime, command, settings.backend_port)?;
+        }
+
+        Ok(unit.render())
+    }
+
+    /// Installs a oneshot service and starts it, waiting for it to exit.
+    pub(super) fn run_oneshot(
+        unit_name: &str,
+        template: &str,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        let tmp_path = format!("{TMP_BASE_PATH}/{unit_name}.service");
+        let target = format!("{SYSTEMD_TARGET_PATH}/{unit_name}.service");
+        fs::write(&tmp_path, template)?;
+        privilege_wrapper.run("/usr/bin/mv", &[&tmp_path, &target])?;
+        privilege_wrapper.run("/usr/bin/chown", &["root:root", &target])?;
+        privilege_wrapper.run("/usr/bin/systemctl", &["daemon-reload"])?;
+        privilege_wrapper.run(
+            "/usr/bin/systemctl",
+            &["start", &format!("{unit_name}.service")],
+        )?;
+
+        Ok(())
+    }
+
+    pub(super) fn remove_oneshot(
+        unit_name: &str,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        let unit = format!("{unit_name}.service");
+        let target = format!("{SYSTEMD_TARGET_PATH}/{unit}");
+        if Path::new(&target).exists() {
+            let _ = privilege_wrapper.run("/usr/bin/systemctl", &["stop", &unit]);
+            privilege_wrapper.run("/usr/bin/rm", &["-f", &target])?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Unit name, without suffix, of the project's release command.
+#[must_use]
+pub fn release_unit_name(project_id: &str) -> String {
+    format!("nanoscale-{project_id}-release")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deployment::systemd::tests::{container_runtime, settings};
+
+    #[test]
+    fn release_template_runs_once_from_the_built_release() {
+        let settings = ServiceSettings {
+            run_command: "bun run start",
+            hosts_file: Some(Path::new("/opt/nanoscale/hosts/p1")),
+            ..settings()
+        };
+        let service = SystemdGenerator::release_service_template(
+            "nanoscale-p1-release",
+            "p1",
+            "/opt/nanoscale/tmp/p1/source",
+            &AppRuntime::BunStart {
+                bun_binary: "/usr/bin/bun".to_string(),
+            },
+            &settings,
+            "./bin/migrate --up",
+            300,
+        )
+        .expect("service");
+        assert!(service.contains("Type=oneshot\n"));
+        assert!(service.contains("User=nanoscale-p1\n"));
+        assert!(service.contains("WorkingDirectory=/opt/nanoscale/tmp/p1/source\n"));
+        assert!(service.contains("EnvironmentFile=-/etc/default/nanoscale-p1\n"));
+        assert!(service.contains("ExecStart=/opt/nanoscale/tmp/p1/source/bin/migrate --up\n"));
+        assert!(service.contains("TimeoutStartSec=300\n"));
+        assert!(service.contains("BindReadOnlyPaths=/opt/nanoscale/hosts/p1:/etc/hosts\n"));
+        assert!(!service.contains("ReadWritePaths"));
+        assert!(!service.contains("[Install]"));
+
+        let runtime = container_runtime("/opt/nanoscale/tmp/p1/image/image.tar");
+        let service = SystemdGenerator::release_service_template(
+            "nanoscale-p1-release",
+            "p1",
+            "/opt/nanoscale/tmp/p1/image",
+            &runtime,
+            &settings,
+            "rails db:migrate",
+            300,
+        )
+        .expect("container service");
+        assert!(!service.contains("podman load"));
+        assert!(service.contains(
+            "ExecStart=/usr/bin/podman run --rm --replace --name=nanoscale-p1-release --cgroups=split localhost/nanoscale-p1:latest rails db:migrate\n"
+        ));
+
+        assert!(SystemdGenerator::release_service_template(
+            "nanoscale-p1-release",
+            "p1",
+            "/opt/nanoscale/tmp/p1/image",
+            &runtime,
+            &settings,
+            " ",
+            300,
+        )
+        .is_err());
+    }
+}
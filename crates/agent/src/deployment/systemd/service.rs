@@ -0,0 +1,171 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+
+use super::unit::ProjectUnit;
+use super::{ServiceSettings, ServiceUnits, SystemdGenerator, SYSTEMD_TARGET_PATH, TMP_BASE_PATH};
+use crate::deployment::build::AppRuntime;
+use crate::system::PrivilegeWrapper;
+
+impl SystemdGenerator {
+    pub(super) fn install_service(
+        service_name: &str,
+        units: &ServiceUnits,
+        stopped: bool,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
+        let tmp_service_path = PathBuf::from(format!("{TMP_BASE_PATH}/{service_name}.service"));
+        let tmp_socket_path = PathBuf::from(format!("{TMP_BASE_PATH}/{service_name}.socket"));
+        let tmp_proxy_path = PathBuf::from(format!("{TMP_BASE_PATH}/{service_name}-proxy.service"));
+
+        let service_target = format!("{SYSTEMD_TARGET_PATH}/{service_name}.service");
+        let socket_target = format!("{SYSTEMD_TARGET_PATH}/{service_name}.socket");
+        let proxy_target = format!("{SYSTEMD_TARGET_PATH}/{service_name}-proxy.service");
+        // A redeploy replaces the running release in place rather than after a teardown.
+        let upgrading = Path::new(&service_target).exists();
+        let listener_changed = fs::read_to_string(&socket_target).ok().as_deref()
+            != Some(units.socket.as_str())
+            || fs::read_to_string(&proxy_target).ok().as_deref() != Some(units.proxy.as_str());
+
+        fs::write(&tmp_service_path, &units.service)?;
+        fs::write(&tmp_socket_path, &units.socket)?;
+        fs::write(&tmp_proxy_path, &units.proxy)?;
+
+        let tmp_service_string = tmp_service_path
+            .to_str()
+            .ok_or_else(|| anyhow!("invalid temp service path"))?;
+        let tmp_socket_string = tmp_socket_path
+            .to_str()
+            .ok_or_else(|| anyhow!("invalid temp socket path"))?;
+        let tmp_proxy_string = tmp_proxy_path
+            .to_str()
+            .ok_or_else(|| anyhow!("invalid temp proxy path"))?;
+
+        privilege_wrapper.run("/usr/bin/mv", &[tmp_service_string, &service_target])?;
+        privilege_wrapper.run("/usr/bin/mv", &[tmp_socket_string, &socket_target])?;
+        privilege_wrapper.run("/usr/bin/mv", &[tmp_proxy_string, &proxy_target])?;
+
+        privilege_wrapper.run("/usr/bin/chown", &["root:root", &service_target])?;
+        privilege_wrapper.run("/usr/bin/chown", &["root:root", &socket_target])?;
+        privilege_wrapper.run("/usr/bin/chown", &["root:root", &proxy_target])?;
+
+        privilege_wrapper.run("/usr/bin/systemctl", &["daemon-reload"])?;
+        if stopped {
+            return Ok(());
+        }
+        privilege_wrapper.run(
+            "/usr/bin/systemctl",
+            &["enable", "--now", &format!("{service_name}.service")],
+        )?;
+        if upgrading {
+            privilege_wrapper.run(
+                "/usr/bin/systemctl",
+                &["restart", &format!("{service_name}.service")],
+            )?;
+        }
+
+        if upgrading && listener_changed {
+            privilege_wrapper.run(
+                "/usr/bin/systemctl",
+                &["stop", &format!("{service_name}-proxy.service")],
+            )?;
+            privilege_wrapper.run(
+                "/usr/bin/systemctl",
+                &["restart", &format!("{service_name}.socket")],
+            )?;
+        }
+        privilege_wrapper.run(
+            "/usr/bin/systemctl",
+            &["enable", "--now", &format!("{service_name}.socket")],
+        )?;
+
+        Ok(())
+    }
+
+    pub(super) fn service_template(
+        service_name: &str,
+        project_id: &str,
+        source_dir: &str,
+        runtime: &AppRuntime,
+        settings: &ServiceSettings<'_>,
+        port: u16,
+    ) -> Result<String> {
+        let mut unit = ProjectUnit::new(project_id, runtime, source_dir, settings)?;
+        unit.long_running = true;
+        unit.port = Some(port);
+
+        if let AppRuntime::Container {
+            podman_binary,
+            image,
+            ..
+        } = runtime
+        {
+            unit.description = format!("NanoScale container service ({service_name})");
+            unit.exec_start = Self::container_exec_start(
+                service_name,
+                podman_binary,
+                image,
+                settings,
+                port,
+                true,
+            )?;
+            unit.exec_stop = Some(format!(
+                "{podman_binary} stop --ignore --time 10 {service_name}"
+            ));
+        } else {
+            unit.description = format!("NanoScale app service ({service_name})");
+            unit.exec_start =
+                Self::resolve_exec_start(source_dir, runtime, settings.run_command, port)?;
+        }
+
+        Ok(unit.render())
+    }
+
+    pub(super) fn socket_template(service_name: &str, port: u16, network_socket: bool) -> String {
+        let listen = if network_socket {
+            port.to_string()
+        } else {
+            format!("127.0.0.1:{port}")
+        };
+        format!(
+            "[Unit]\nDescription=NanoScale app socket ({service_name})\n\n[Socket]\nListenStream={listen}\nNoDelay=true\nService={service_name}-proxy.service\n\n[Install]\nWantedBy=sockets.target\n"
+        )
+    }
+
+    pub(super) fn proxy_service_template(
+        service_name: &str,
+        backend_port: u16,
+        socket_proxyd_bin: &str,
+    ) -> String {
+        format!(
+            "[Unit]\nDescription=NanoScale app socket proxy ({service_name})\nAfter=network.target\n\n[Service]\nType=simple\nRestart=always\nRestartSec=1\nExecStartPre=/usr/bin/systemctl start {service_name}.service\nExecStart={socket_proxyd_bin} 127.0.0.1:{backend_port}\n"
+        )
+    }
+}
+
+pub(super) fn socket_proxyd_binary() -> Result<String> {
+    if let Ok(configured_binary) = std::env::var("NANOSCALE_SOCKET_PROXYD_BIN") {
+        let trimmed = configured_binary.trim();
+        if !trimmed.is_empty() {
+            if Path::new(trimmed).is_file() {
+                return Ok(trimmed.to_string());
+            }
+            bail!("NANOSCALE_SOCKET_PROXYD_BIN is set but does not exist: {trimmed}");
+        }
+    }
+
+    for candidate in [
+        "/lib/systemd/systemd-socket-proxyd",
+        "/usr/lib/systemd/systemd-socket-proxyd",
+        "/usr/libexec/systemd/systemd-socket-proxyd",
+    ] {
+        if Path::new(candidate).is_file() {
+            return Ok(candidate.to_string());
+        }
+    }
+
+    bail!(
+        "systemd-socket-proxyd not found; install systemd package providing it or set NANOSCALE_SOCKET_PROXYD_BIN"
+    )
+}
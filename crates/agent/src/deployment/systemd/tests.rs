@@ -0,0 +1,281 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+use super::*;
+use crate::deployment::build::AppRuntime;
+use crate::deployment::firewall::Protocol;
+use crate::deployment::jobs::CronJob;
+use crate::deployment::private_network::PrivateService;
+use crate::deployment::processes::ProcessType;
+
+static NO_PRIVATE_NETWORK: PrivateNetwork = PrivateNetwork {
+    services: Vec::new(),
+    reachable: false,
+    allowed_sources: Vec::new(),
+};
+
+pub(super) fn settings() -> ServiceSettings<'static> {
+    ServiceSettings {
+        run_command: "",
+        port: 3100,
+        backend_port: 13_100,
+        env_vars: &[],
+        resource_limits: ResourceLimits::default(),
+        network_socket: false,
+        private_network: &NO_PRIVATE_NETWORK,
+        hosts_file: None,
+        stream_ports: &[],
+        volumes: &[],
+    }
+}
+
+pub(super) fn container_runtime(image_archive: &str) -> AppRuntime {
+    AppRuntime::Container {
+        podman_binary: "/usr/bin/podman".to_string(),
+        image: "localhost/nanoscale-p1:latest".to_string(),
+        image_archive: PathBuf::from(image_archive),
+        storage_dir: PathBuf::from("/opt/nanoscale/sites/p1/container"),
+    }
+}
+
+#[test]
+fn service_template_renders_env_file_and_resource_limits() {
+    let settings = ServiceSettings {
+        resource_limits: ResourceLimits {
+            memory_max_mb: Some(512),
+            cpu_quota_percent: Some(50),
+        },
+        hosts_file: Some(Path::new("/opt/nanoscale/hosts/p1")),
+        volumes: &[VolumeMount {
+            source: PathBuf::from("/opt/nanoscale/data/volumes/p1/db"),
+            mount_path: "./data".to_string(),
+        }],
+        ..settings()
+    };
+    let template = SystemdGenerator::service_template(
+        "nanoscale-p1",
+        "p1",
+        "/opt/nanoscale/sites/p1/source",
+        &AppRuntime::StandaloneNode,
+        &settings,
+        13_100,
+    )
+    .expect("template");
+    assert!(template.contains("EnvironmentFile=-/etc/default/nanoscale-p1"));
+    assert!(template.contains("MemoryMax=512M"));
+    assert!(template.contains("CPUQuota=50%"));
+    assert!(template.contains("BindReadOnlyPaths=/opt/nanoscale/hosts/p1:/etc/hosts\n"));
+    assert!(template.contains(
+        "BindPaths=/opt/nanoscale/data/volumes/p1/db:/opt/nanoscale/sites/p1/source/data\n"
+    ));
+}
+
+#[test]
+fn container_service_template_runs_podman_in_service_cgroup() {
+    let env_vars = [("API_KEY".to_string(), "secret".to_string())];
+    let private_network = PrivateNetwork {
+        services: vec![PrivateService {
+            name: "api".to_string(),
+            ip: "10.0.0.2".parse().expect("ip"),
+            port: 3105,
+        }],
+        ..PrivateNetwork::default()
+    };
+    let settings = ServiceSettings {
+        env_vars: &env_vars,
+        resource_limits: ResourceLimits {
+            memory_max_mb: Some(256),
+            cpu_quota_percent: Some(150),
+        },
+        private_network: &private_network,
+        stream_ports: &[StreamPort {
+            port: 27015,
+            protocol: Protocol::Udp,
+            target_port: 27016,
+        }],
+        volumes: &[VolumeMount {
+            source: PathBuf::from("/opt/nanoscale/data/volumes/p1/uploads"),
+            mount_path: "/app/uploads".to_string(),
+        }],
+        ..settings()
+    };
+    let runtime = container_runtime("/opt/nanoscale/sites/p1/source/image.tar");
+    let template = SystemdGenerator::service_template(
+        "nanoscale-p1",
+        "p1",
+        "/opt/nanoscale/sites/p1/source",
+        &runtime,
+        &settings,
+        13_100,
+    )
+    .expect("template");
+
+    assert!(template.contains("User=nanoscale-p1"));
+    assert!(!template.contains("ExecStartPre"));
+    assert!(template.contains("Environment=XDG_RUNTIME_DIR=/run/nanoscale-p1\n"));
+    assert!(template.contains("RuntimeDirectory=nanoscale-p1\nRuntimeDirectoryPreserve=yes\n"));
+    assert!(!template.contains("NoNewPrivileges"));
+    assert!(template.contains("--cgroups=split"));
+    assert!(template.contains("--publish=127.0.0.1:13100:13100"));
+    assert!(template.contains("--env=API_KEY"));
+    assert!(template.contains("--add-host=api.internal:10.0.0.2"));
+    assert!(template.contains("--publish=127.0.0.1:27016:27016/udp"));
+    assert!(!template.contains("secret"));
+    assert!(template.contains("--memory=256m"));
+    assert!(template.contains("--cpus=1.5"));
+    assert!(template.contains("Delegate=yes"));
+    assert!(template.contains(
+        "ReadWritePaths=/opt/nanoscale/sites/p1/container /opt/nanoscale/data/volumes/p1/uploads\n"
+    ));
+    assert!(template.contains("--volume=/opt/nanoscale/data/volumes/p1/uploads:/app/uploads"));
+}
+
+#[test]
+fn socket_template_contains_listen_port() {
+    let template = SystemdGenerator::socket_template("nanoscale-p1", 3100, false);
+    assert!(template.contains("ListenStream=127.0.0.1:3100"));
+    let template = SystemdGenerator::socket_template("nanoscale-p1", 3100, true);
+    assert!(template.contains("ListenStream=3100\n"));
+}
+
+#[test]
+#[allow(clippy::too_many_lines)]
+fn container_units_pass_systemd_verification() {
+    let Ok(analyze) = which_systemd_analyze() else {
+        return;
+    };
+    let dir = tempfile::tempdir().expect("tempdir");
+    let podman = dir.path().join("podman");
+    fs::write(&podman, "#!/bin/sh\n").expect("stub");
+    let mut permissions = fs::metadata(&podman).expect("stub").permissions();
+    std::os::unix::fs::PermissionsExt::set_mode(&mut permissions, 0o755);
+    fs::set_permissions(&podman, permissions).expect("stub");
+
+    let settings = ServiceSettings {
+        run_command: "bin/worker",
+        resource_limits: ResourceLimits {
+            memory_max_mb: Some(256),
+            cpu_quota_percent: Some(50),
+        },
+        ..settings()
+    };
+    let runtime = AppRuntime::Container {
+        podman_binary: podman.display().to_string(),
+        image: "localhost/nanoscale-p1:0123456789ab".to_string(),
+        image_archive: PathBuf::from("/opt/nanoscale/tmp/p1/image/image.tar"),
+        storage_dir: PathBuf::from("/opt/nanoscale/sites/p1/container"),
+    };
+    let job = CronJob {
+        name: "report".to_string(),
+        schedule: "0 3 * * *".to_string(),
+        command: "bin/report".to_string(),
+        timeout_seconds: 600,
+    };
+    let process = ProcessType {
+        name: "worker".to_string(),
+        command: "bin/worker".to_string(),
+        replicas: 1,
+        resources: ResourceLimits::default(),
+    };
+    let source_dir = "/opt/nanoscale/sites/p1/source";
+    let units = [
+        (
+            "nanoscale-p1.service",
+            SystemdGenerator::service_template(
+                "nanoscale-p1",
+                "p1",
+                source_dir,
+                &runtime,
+                &settings,
+                13_100,
+            ),
+        ),
+        (
+            "nanoscale-p1-job-report.service",
+            SystemdGenerator::job_service_template(
+                "nanoscale-p1-job-report",
+                "p1",
+                source_dir,
+                &runtime,
+                &settings,
+                &job,
+                "/opt/nanoscale/sites/p1/jobs",
+            ),
+        ),
+        (
+            "nanoscale-p1-worker@.service",
+            SystemdGenerator::process_service_template(
+                "nanoscale-p1-worker",
+                "p1",
+                source_dir,
+                &runtime,
+                &settings,
+                &process,
+            ),
+        ),
+        (
+            "nanoscale-p1-release.service",
+            SystemdGenerator::release_service_template(
+                "nanoscale-p1-release",
+                "p1",
+                "/opt/nanoscale/tmp/p1/image",
+                &runtime,
+                &settings,
+                "rails db:migrate",
+                300,
+            ),
+        ),
+        (
+            "nanoscale-p1-image-load.service",
+            SystemdGenerator::image_load_template(
+                "nanoscale-p1-image-load",
+                "p1",
+                &runtime,
+                &settings,
+            )
+            .map(|template| template.expect("container runtime")),
+        ),
+    ];
+
+    for (name, template) in units {
+        let template = template.expect("template");
+        // Rootless podman needs a runtime directory and the setuid id mappers.
+        assert!(
+            template.contains("RuntimeDirectory=nanoscale-p1\n"),
+            "{name}"
+        );
+        assert!(
+            template.contains("Environment=XDG_RUNTIME_DIR=/run/nanoscale-p1\n"),
+            "{name}"
+        );
+        assert!(!template.contains("NoNewPrivileges"), "{name}");
+        assert!(
+            !template.contains("podman load") || name.contains("image-load"),
+            "{name}"
+        );
+
+        let path = dir.path().join(name);
+        fs::write(&path, template).expect("unit");
+        let output = std::process::Command::new(&analyze)
+            .arg("verify")
+            .arg(&path)
+            .output()
+            .expect("systemd-analyze");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "{name}: {stderr}");
+        assert!(
+            !stderr.contains(name),
+            "{name} has unit file warnings: {stderr}"
+        );
+    }
+}
+
+fn which_systemd_analyze() -> Result<PathBuf> {
+    ["/usr/bin/systemd-analyze", "/bin/systemd-analyze"]
+        .into_iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_file())
+        .ok_or_else(|| anyhow!("systemd-analyze is not installed"))
+}
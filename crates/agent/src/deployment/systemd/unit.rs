@@ -0,0 +1,203 @@
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use super::{env_file, ResourceLimits, ServiceSettings, SystemdGenerator};
+use crate::deployment::build::AppRuntime;
+use crate::deployment::volumes::VolumeMount;
+
+impl SystemdGenerator {
+    /// `BindPaths=` lines mounting the project's volumes into an app working in `working_dir`.
+    fn volume_directives(volumes: &[VolumeMount], working_dir: &str) -> String {
+        let mut directives = String::new();
+        for volume in volumes {
+            let _ = writeln!(
+                directives,
+                "BindPaths={}:{}",
+                volume.source.display(),
+                volume.target(working_dir)
+            );
+        }
+
+        directives
+    }
+
+    fn resource_limit_directives(limits: ResourceLimits) -> String {
+        let mut directives = String::new();
+        if let Some(memory_max_mb) = limits.memory_max_mb {
+            let _ = writeln!(directives, "MemoryMax={memory_max_mb}M");
+        }
+        if let Some(cpu_quota_percent) = limits.cpu_quota_percent {
+            let _ = writeln!(directives, "CPUQuota={cpu_quota_percent}%");
+        }
+
+        if directives.is_empty() {
+            return directives;
+        }
+
+        format!("\n# RESOURCE LIMITS\n{directives}")
+    }
+}
+
+/// A unit running the project's code: the app, a process, a job or the release command.
+/// They all share the user, environment, accounting, limits and sandbox written by
+/// [`ProjectUnit::render`]; the templates only set what runs and how.
+pub(super) struct ProjectUnit<'a> {
+    pub(super) description: String,
+    pub(super) project_id: &'a str,
+    /// Restarted when it exits and enabled at boot; a oneshot otherwise.
+    pub(super) long_running: bool,
+    /// The container's storage directory, or the directory a native command runs in.
+    pub(super) working_dir: &'a str,
+    pub(super) container: bool,
+    /// Whether a native command may write to its working directory; a container always
+    /// writes to its storage.
+    pub(super) writable_working_dir: bool,
+    /// Further writable directories, such as a job's runs directory.
+    pub(super) writable_paths: Vec<&'a str>,
+    pub(super) port: Option<u16>,
+    pub(super) limits: ResourceLimits,
+    pub(super) volumes: &'a [VolumeMount],
+    pub(super) hosts_file: Option<&'a Path>,
+    pub(super) exec_start_pre: Vec<String>,
+    pub(super) exec_start: String,
+    pub(super) exec_stop: Option<String>,
+    pub(super) exec_stop_post: Option<String>,
+    pub(super) timeout_seconds: Option<u64>,
+}
+
+impl<'a> ProjectUnit<'a> {
+    /// A oneshot unit in the runtime's working directory with the settings' limits, volumes
+    /// and hosts file; the caller sets the description and commands.
+    pub(super) fn new(
+        project_id: &'a str,
+        runtime: &'a AppRuntime,
+        source_dir: &'a str,
+        settings: &ServiceSettings<'a>,
+    ) -> Result<Self> {
+        let (working_dir, container) = match runtime {
+            AppRuntime::Container { storage_dir, .. } => (
+                storage_dir
+                    .to_str()
+                    .ok_or_else(|| anyhow!("invalid container storage path"))?,
+                true,
+            ),
+            _ => (source_dir, false),
+        };
+
+        Ok(Self {
+            description: String::new(),
+            project_id,
+            long_running: false,
+            working_dir,
+            container,
+            writable_working_dir: true,
+            writable_paths: Vec::new(),
+            port: None,
+            limits: settings.resource_limits,
+            volumes: settings.volumes,
+            hosts_file: settings.hosts_file,
+            exec_start_pre: Vec::new(),
+            exec_start: String::new(),
+            exec_stop: None,
+            exec_stop_post: None,
+            timeout_seconds: None,
+        })
+    }
+
+    pub(super) fn render(&self) -> String {
+        let user = format!("nanoscale-{}", self.project_id);
+        let service_type = if self.long_running {
+            "simple"
+        } else {
+            "oneshot"
+        };
+        let mut unit = format!(
+            "[Unit]\nDescription={}\nAfter=network.target\n\n[Service]\nType={service_type}\nUser={user}\nGroup={user}\nWorkingDirectory={}\n",
+            self.description, self.working_dir
+        );
+
+        if self.container {
+            let _ = writeln!(unit, "Environment=HOME={}", self.working_dir);
+            let _ = writeln!(unit, "Environment=XDG_RUNTIME_DIR=/run/{user}");
+        } else {
+            unit.push_str("Environment=NODE_ENV=production\n");
+        }
+        if let Some(port) = self.port {
+            let _ = writeln!(unit, "Environment=PORT={port}");
+        }
+        let _ = writeln!(unit, "EnvironmentFile=-{}", env_file::target_path(&user));
+        for command in &self.exec_start_pre {
+            let _ = writeln!(unit, "ExecStartPre={command}");
+        }
+        let _ = writeln!(unit, "ExecStart={}", self.exec_start);
+        if let Some(command) = &self.exec_stop {
+            let _ = writeln!(unit, "ExecStop={command}");
+        }
+        if let Some(command) = &self.exec_stop_post {
+            let _ = writeln!(unit, "ExecStopPost={command}");
+        }
+        if let Some(timeout_seconds) = self.timeout_seconds {
+            let _ = writeln!(unit, "TimeoutStartSec={timeout_seconds}");
+        }
+        if self.long_running {
+            unit.push_str("Restart=always\nRestartSec=2\n");
+        }
+        if self.container {
+            // Rootless podman keeps its state in the runtime directory, shared by the project's
+            // units and kept when one of them stops.
+            let _ = writeln!(
+                unit,
+                "KillMode=mixed\nDelegate=yes\nRuntimeDirectory={user}\nRuntimeDirectoryPreserve=yes"
+            );
+        }
+
+        let _ = write!(
+            unit,
+            "\n# ACCOUNTING (for stats)\nCPUAccounting=yes\nMemoryAccounting=yes\nIPAccounting=yes\n{}\n# SECURITY HARDENING\nProtectSystem=strict\nProtectHome=yes\nPrivateTmp=yes\n",
+            SystemdGenerator::resource_limit_directives(self.limits)
+        );
+        if !self.container {
+            unit.push_str("NoNewPrivileges=yes\nProtectProc=invisible\n");
+        }
+        // A container gets no NoNewPrivileges: podman maps its users through the setuid
+        // newuidmap and newgidmap.
+
+        let mut writable = Vec::new();
+        if self.container || self.writable_working_dir {
+            writable.push(self.working_dir.to_string());
+        }
+        writable.extend(self.writable_paths.iter().map(ToString::to_string));
+        if self.container {
+            // podman mounts the volumes itself, so the unit must be able to write them.
+            writable.extend(
+                self.volumes
+                    .iter()
+                    .map(|volume| volume.source.display().to_string()),
+            );
+        }
+        if !writable.is_empty() {
+            let _ = writeln!(unit, "ReadWritePaths={}", writable.join(" "));
+        }
+        if !self.container {
+            unit.push_str(&SystemdGenerator::volume_directives(
+                self.volumes,
+                self.working_dir,
+            ));
+            if let Some(hosts_file) = self.hosts_file {
+                let _ = writeln!(
+                    unit,
+                    "BindReadOnlyPaths={}:/etc/hosts",
+                    hosts_file.display()
+                );
+            }
+        }
+
+        if self.long_running {
+            unit.push_str("\n[Install]\nWantedBy=multi-user.target\n");
+        }
+
+        unit
+    }
+}
@@ -3,10 +3,24 @@ use std::process::Command;
 
 use anyhow::Result;
 
+use crate::deployment::access::{self, HTPASSWD_PATH};
+use crate::deployment::build::BuildSystem;
+use crate::deployment::firewall::{self, FIREWALL_STATE_PATH};
+use crate::deployment::jobs;
+use crate::deployment::lifecycle::{self, STOPPED_STATE_PATH};
+use crate::deployment::nginx::NginxGenerator;
+use crate::deployment::pages::{self, PAGES_PATH};
+use crate::deployment::private_network::{self, HOSTS_PATH};
+use crate::deployment::processes;
+use crate::deployment::systemd::SystemdGenerator;
+use crate::deployment::volumes::{self, VOLUMES_PATH};
 use crate::system::PrivilegeWrapper;
 
 const SYSTEMD_PATH: &str = "/etc/systemd/system";
 const NGINX_ENABLED_PATH: &str = "/etc/nginx/sites-enabled";
+const NGINX_CONF_D_PATH: &str = "/etc/nginx/conf.d";
+const NGINX_STREAMS_PATH: &str = "/etc/nginx/streams-enabled";
+const ENV_FILE_PATH: &str = "/etc/default";
 const PROJECT_SITES_PATH: &str = "/opt/nanoscale/sites";
 const PROJECT_TMP_PATH: &str = "/opt/nanoscale/tmp";
 
@@ -14,11 +28,19 @@ const PROJECT_TMP_PATH: &str = "/opt/nanoscale/tmp";
 pub struct Teardown;
 
 impl Teardown {
-    /// Deletes systemd units, nginx config, site directories, and the project user.
+    /// Deletes systemd units including process instances, cron job timers and the release
+    /// command, the env file, nginx site and TCP/UDP config, htpasswd file, status pages, hosts
+    /// file, firewall rules, stopped state, site directories, image builds left in the agent's
+    /// storage, and the project user. The project's volumes are deleted only when
+    /// `delete_volumes` is set; the project user is kept while any remain.
     ///
     /// # Errors
     /// Returns an error if a required privileged deletion or reload command fails.
-    pub fn delete_project(project_id: &str, privilege_wrapper: &PrivilegeWrapper) -> Result<()> {
+    pub fn delete_project(
+        project_id: &str,
+        delete_volumes: bool,
+        privilege_wrapper: &PrivilegeWrapper,
+    ) -> Result<()> {
         let service_name = format!("nanoscale-{project_id}.service");
         let socket_name = format!("nanoscale-{project_id}.socket");
         let proxy_name = format!("nanoscale-{project_id}-proxy.service");
@@ -28,7 +50,10 @@ impl Teardown {
         let proxy_unit_path = format!("{SYSTEMD_PATH}/{proxy_name}");
         let service_wants_path = format!("{SYSTEMD_PATH}/multi-user.target.wants/{service_name}");
         let socket_wants_path = format!("{SYSTEMD_PATH}/sockets.target.wants/{socket_name}");
+        let env_file_path = format!("{ENV_FILE_PATH}/nanoscale-{project_id}");
         let nginx_conf_path = format!("{NGINX_ENABLED_PATH}/nanoscale-{project_id}.conf");
+        let nginx_zones_path = format!("{NGINX_CONF_D_PATH}/nanoscale-{project_id}-zones.conf");
+        let nginx_streams_path = format!("{NGINX_STREAMS_PATH}/nanoscale-{project_id}.conf");
         let project_sites_path = format!("{PROJECT_SITES_PATH}/{project_id}");
         let project_tmp_path = format!("{PROJECT_TMP_PATH}/{project_id}");
 
@@ -42,18 +67,46 @@ impl Teardown {
         Self::remove_file_if_exists(privilege_wrapper, &proxy_unit_path)?;
         Self::remove_file_if_exists(privilege_wrapper, &service_wants_path)?;
         Self::remove_file_if_exists(privilege_wrapper, &socket_wants_path)?;
+        Self::remove_file_if_exists(privilege_wrapper, &env_file_path)?;
+        for process in processes::installed(Path::new(SYSTEMD_PATH), project_id)? {
+            SystemdGenerator::remove_process(project_id, &process, privilege_wrapper)?;
+        }
+        for job in jobs::installed(Path::new(SYSTEMD_PATH), project_id)? {
+            SystemdGenerator::remove_job(project_id, &job, privilege_wrapper)?;
+        }
+        SystemdGenerator::remove_release(project_id, privilege_wrapper)?;
+        SystemdGenerator::remove_image_units(project_id, privilege_wrapper)?;
 
         privilege_wrapper.run("/usr/bin/systemctl", &["daemon-reload"])?;
 
         let nginx_removed = Self::remove_file_if_exists(privilege_wrapper, &nginx_conf_path)?;
-        if nginx_removed {
-            privilege_wrapper.run("/usr/sbin/service", &["nginx", "reload"])?;
+        let zones_removed = Self::remove_file_if_exists(privilege_wrapper, &nginx_zones_path)?;
+        let streams_removed = Self::remove_file_if_exists(privilege_wrapper, &nginx_streams_path)?;
+        if nginx_removed || zones_removed || streams_removed {
+            NginxGenerator::reload(privilege_wrapper)?;
         }
+        access::remove_htpasswd(Path::new(HTPASSWD_PATH), project_id)?;
+        pages::remove_pages(Path::new(PAGES_PATH), project_id)?;
+        private_network::remove_hosts(Path::new(HOSTS_PATH), project_id)?;
+        firewall::remove(
+            Path::new(FIREWALL_STATE_PATH),
+            project_id,
+            privilege_wrapper,
+        )?;
+        lifecycle::remove(Path::new(STOPPED_STATE_PATH), project_id)?;
 
         Self::remove_directory_if_exists(privilege_wrapper, &project_sites_path)?;
         Self::remove_directory_if_exists(privilege_wrapper, &project_tmp_path)?;
+        BuildSystem::remove_images(project_id)?;
+        if delete_volumes {
+            volumes::remove_all(Path::new(VOLUMES_PATH), project_id, privilege_wrapper)?;
+        }
 
-        Self::remove_project_user(project_id, privilege_wrapper)?;
+        // Kept volumes stay owned by the project user; deleting it would hand their files to
+        // whichever user gets its uid next.
+        if !volumes::any_kept(Path::new(VOLUMES_PATH), project_id)? {
+            Self::remove_project_user(project_id, privilege_wrapper)?;
+        }
 
         Ok(())
     }
@@ -1,76 +1,213 @@
 use std::fs;
+use std::future::Future;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::{anyhow, Context, Result};
 
-use crate::system::PrivilegeWrapper;
+use crate::deployment::acme::{self, AcmeSettings, IssuedCertificate};
+use crate::deployment::cert_renewal::{needs_renewal, parse_certificate};
 
 pub const ACME_WEBROOT_PATH: &str = "/opt/nanoscale/acme";
+/// NanoScale-owned certificate store: `<name>/fullchain.pem` and `<name>/privkey.pem`.
+pub const CERTIFICATES_PATH: &str = "/opt/nanoscale/certs";
+
+/// Certificates closer to expiry than this are reissued on deploy.
+const KEEP_UNTIL_EXPIRING_DAYS: u64 = 30;
 
 pub struct TlsProvisioner;
 
 impl TlsProvisioner {
-    /// .
+    /// Requests (or keeps) one certificate covering every domain in `domains`.
+    ///
+    /// The first domain names the certificate under [`CERTIFICATES_PATH`]. An existing
+    /// certificate is kept while it covers every domain and is not close to expiry.
     ///
     /// # Errors
     ///
     /// This function will return an error if:
-    /// 1) domain is 0-length string
+    /// 1) no domain is given or any domain is a 0-length string
     /// 2) tls email is 0-length string
-    /// 3) certbot fails to generated a certificate
+    /// 3) the ACME order fails or the certificate cannot be written
     pub fn ensure_certificate(
-        domain: &str,
+        domains: &[String],
         email: &str,
-        privilege_wrapper: &PrivilegeWrapper,
+        acme_settings: &AcmeSettings,
     ) -> Result<()> {
-        let domain = domain.trim();
-        if domain.is_empty() {
+        let domains = domains
+            .iter()
+            .map(|domain| domain.trim().to_string())
+            .collect::<Vec<_>>();
+        if domains.is_empty() || domains.iter().any(String::is_empty) {
             return Err(anyhow!("domain cannot be empty"));
         }
 
+        Self::ensure_named_certificate(&domains[0], &domains, email, acme_settings)
+    }
+
+    /// Requests (or keeps) the `*.<base_domain>` certificate every project under the base
+    /// domain shares, validated with DNS-01. Returns the certificate name.
+    ///
+    /// # Errors
+    /// Returns an error if the base domain or email is empty, no DNS provider is configured,
+    /// or the ACME order fails or the certificate cannot be written.
+    pub fn ensure_wildcard_certificate(
+        base_domain: &str,
+        email: &str,
+        acme_settings: &AcmeSettings,
+    ) -> Result<String> {
+        let base_domain = base_domain.trim();
+        if base_domain.is_empty() {
+            return Err(anyhow!("base domain cannot be empty"));
+        }
+
+        let name = wildcard_certificate_name(base_domain);
+        Self::ensure_named_certificate(&name, &[format!("*.{base_domain}")], email, acme_settings)?;
+        Ok(name)
+    }
+
+    fn ensure_named_certificate(
+        name: &str,
+        domains: &[String],
+        email: &str,
+        acme_settings: &AcmeSettings,
+    ) -> Result<()> {
         let email = email.trim();
         if email.is_empty() {
             return Err(anyhow!("tls email cannot be empty"));
         }
 
-        Self::ensure_acme_webroot()?;
-
-        let args = [
-            "certonly",
-            "--webroot",
-            "-w",
-            ACME_WEBROOT_PATH,
-            "-d",
-            domain,
-            "--non-interactive",
-            "--agree-tos",
-            "--keep-until-expiring",
-            "--email",
-            email,
-        ];
-
-        let _ = privilege_wrapper
-            .run("/usr/bin/certbot", &args)
-            .with_context(|| format!("certbot failed for domain {domain}"))?;
+        let certificate_dir = Path::new(CERTIFICATES_PATH).join(name);
+        if Self::is_current(&certificate_dir, domains) {
+            return Ok(());
+        }
 
-        Ok(())
+        Self::ensure_acme_webroot(acme_settings)?;
+        let issued = block_on(acme::issue_certificate(acme_settings, domains, Some(email)))?
+            .with_context(|| format!("ACME order failed for domains {}", domains.join(", ")))?;
+        install_certificate(&certificate_dir, &issued)
+    }
+
+    /// Reissues the certificate `name` for the domains it already covers.
+    ///
+    /// # Errors
+    /// Returns an error if the ACME order fails or the certificate cannot be written.
+    pub fn renew(name: &str, domains: &[String], acme_settings: &AcmeSettings) -> Result<()> {
+        Self::ensure_acme_webroot(acme_settings)?;
+        let issued = block_on(acme::issue_certificate(acme_settings, domains, None))?
+            .with_context(|| format!("ACME renewal failed for certificate {name}"))?;
+        install_certificate(&Path::new(CERTIFICATES_PATH).join(name), &issued)
     }
 
-    fn ensure_acme_webroot() -> Result<()> {
-        fs::create_dir_all(ACME_WEBROOT_PATH)
-            .with_context(|| format!("failed to create ACME webroot: {ACME_WEBROOT_PATH}"))?;
+    fn is_current(certificate_dir: &Path, domains: &[String]) -> bool {
+        let Ok(pem) = fs::read(certificate_dir.join("fullchain.pem")) else {
+            return false;
+        };
+        let Ok(certificate) = parse_certificate("", &pem) else {
+            return false;
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| i64::try_from(elapsed.as_secs()).unwrap_or(i64::MAX))
+            .unwrap_or_default();
+
+        domains
+            .iter()
+            .all(|domain| certificate.domains.contains(domain))
+            && !needs_renewal(certificate.not_after, now, KEEP_UNTIL_EXPIRING_DAYS)
+    }
+
+    fn ensure_acme_webroot(acme_settings: &AcmeSettings) -> Result<()> {
+        fs::create_dir_all(&acme_settings.webroot).with_context(|| {
+            format!(
+                "failed to create ACME webroot: {}",
+                acme_settings.webroot.display()
+            )
+        })?;
         Ok(())
     }
 }
 
+/// Directory name of the base domain's wildcard certificate. Underscores never appear in
+/// routed hostnames, so it cannot collide with a project's own certificate.
+#[must_use]
+pub fn wildcard_certificate_name(base_domain: &str) -> String {
+    format!("_wildcard.{base_domain}")
+}
+
+/// Writes the key before the chain so nginx never sees a chain without its key.
+fn install_certificate(certificate_dir: &Path, issued: &IssuedCertificate) -> Result<()> {
+    acme::write_private_file(&certificate_dir.join("privkey.pem"), &issued.key_pem)?;
+    acme::write_private_file(&certificate_dir.join("fullchain.pem"), &issued.chain_pem)?;
+    Ok(())
+}
+
+/// Drives an ACME future from the blocking deployment and renewal tasks.
+fn block_on<F: Future>(future: F) -> Result<F::Output> {
+    let handle = tokio::runtime::Handle::try_current()
+        .context("ACME requests need to run inside the agent's Tokio runtime")?;
+    Ok(handle.block_on(future))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn ensure_certificate_rejects_empty_inputs_before_side_effects() {
-        let wrapper = PrivilegeWrapper::new();
-        assert!(TlsProvisioner::ensure_certificate("", "a@b.com", &wrapper).is_err());
-        assert!(TlsProvisioner::ensure_certificate("example.com", "", &wrapper).is_err());
-        assert!(TlsProvisioner::ensure_certificate("   ", "   ", &wrapper).is_err());
+        let settings = AcmeSettings::default();
+        let domains = |values: &[&str]| values.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert!(TlsProvisioner::ensure_certificate(&domains(&[""]), "a@b.com", &settings).is_err());
+        assert!(TlsProvisioner::ensure_certificate(&[], "a@b.com", &settings).is_err());
+        assert!(
+            TlsProvisioner::ensure_certificate(&domains(&["example.com"]), "", &settings).is_err()
+        );
+        assert!(TlsProvisioner::ensure_certificate(
+            &domains(&["example.com", " "]),
+            "a@b.com",
+            &settings
+        )
+        .is_err());
+        assert!(TlsProvisioner::ensure_certificate(&domains(&["   "]), "   ", &settings).is_err());
+    }
+
+    #[test]
+    fn ensure_wildcard_certificate_requires_base_domain_and_email() {
+        let settings = AcmeSettings::default();
+        assert!(TlsProvisioner::ensure_wildcard_certificate(" ", "a@b.com", &settings).is_err());
+        assert!(
+            TlsProvisioner::ensure_wildcard_certificate("apps.example.com", " ", &settings)
+                .is_err()
+        );
+        assert_eq!(
+            wildcard_certificate_name("apps.example.com"),
+            "_wildcard.apps.example.com"
+        );
+    }
+
+    #[test]
+    fn install_certificate_writes_private_files() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let certificate_dir = dir.path().join("app.example.com");
+        install_certificate(
+            &certificate_dir,
+            &IssuedCertificate {
+                chain_pem: "chain".to_string(),
+                key_pem: "key".to_string(),
+            },
+        )
+        .expect("install");
+
+        assert_eq!(
+            fs::read_to_string(certificate_dir.join("fullchain.pem")).expect("chain"),
+            "chain"
+        );
+        let key_mode = fs::metadata(certificate_dir.join("privkey.pem"))
+            .expect("key metadata")
+            .permissions()
+            .mode();
+        assert_eq!(key_mode & 0o777, 0o600);
     }
 }
@@ -0,0 +1,258 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::system::PrivilegeWrapper;
+
+/// Directory holding one directory per project with its volumes, outside the sites directory
+/// replaced on every deploy.
+pub const VOLUMES_PATH: &str = "/opt/nanoscale/data/volumes";
+const MAX_VOLUMES: usize = 8;
+const MAX_VOLUME_NAME_LENGTH: usize = 32;
+const MAX_MOUNT_PATH_LENGTH: usize = 255;
+
+/// A named directory that keeps its data across deploys, mounted into the app at
+/// `mount_path`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Volume {
+    pub name: String,
+    /// Absolute, or relative to the app's working directory for native apps.
+    pub mount_path: String,
+}
+
+/// A volume's data directory on this server and where the app sees it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VolumeMount {
+    pub source: PathBuf,
+    pub mount_path: String,
+}
+
+impl VolumeMount {
+    /// Where the volume is mounted for an app working in `working_dir`.
+    #[must_use]
+    pub fn target(&self, working_dir: &str) -> String {
+        let mount_path = self.mount_path.trim_end_matches('/');
+        if mount_path.starts_with('/') {
+            return mount_path.to_string();
+        }
+        format!(
+            "{}/{}",
+            working_dir.trim_end_matches('/'),
+            mount_path.trim_start_matches("./")
+        )
+    }
+}
+
+/// Checks a project's volumes before they become directories and mounts.
+///
+/// # Errors
+/// Returns an error for too many volumes, a duplicate or malformed name, or a mount path that
+/// is not a plain path, is the root directory, or is used twice.
+pub fn validate(volumes: &[Volume]) -> Result<()> {
+    if volumes.len() > MAX_VOLUMES {
+        bail!("at most {MAX_VOLUMES} volumes can be defined");
+    }
+
+    let mut names = HashSet::new();
+    let mut mount_paths = HashSet::new();
+    for volume in volumes {
+        validate_name(&volume.name)?;
+        if !names.insert(volume.name.as_str()) {
+            bail!("volume {} is declared twice", volume.name);
+        }
+        validate_mount_path(&volume.mount_path)
+            .map_err(|error| anyhow!("volume {}: {error}", volume.name))?;
+        let mount_path = volume.mount_path.trim_start_matches("./");
+        if !mount_paths.insert(mount_path.trim_end_matches('/')) {
+            bail!("mount path {} is used twice", volume.mount_path);
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_VOLUME_NAME_LENGTH
+        && name.chars().all(|character| {
+            character.is_ascii_alphanumeric() || character == '-' || character == '_'
+        });
+    if !valid {
+        bail!("volume name must match ^[A-Za-z0-9_-]{{1,{MAX_VOLUME_NAME_LENGTH}}}$: {name:?}");
+    }
+
+    Ok(())
+}
+
+fn validate_mount_path(mount_path: &str) -> Result<()> {
+    let relative = mount_path
+        .strip_prefix('/')
+        .unwrap_or_else(|| mount_path.trim_start_matches("./"))
+        .trim_end_matches('/');
+    let valid_characters = mount_path.chars().all(|character| {
+        character.is_ascii_alphanumeric() || matches!(character, '/' | '-' | '_' | '.')
+    });
+    let valid_segments = !relative.is_empty()
+        && relative
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..");
+    if mount_path.len() > MAX_MOUNT_PATH_LENGTH || !valid_characters || !valid_segments {
+        bail!("mount path must be a directory path below / or the app directory: {mount_path:?}");
+    }
+
+    Ok(())
+}
+
+/// Data directory of the project's volume `name` under `dir`.
+#[must_use]
+pub fn volume_dir(dir: &Path, project_id: &str, name: &str) -> PathBuf {
+    dir.join(project_id).join(name)
+}
+
+/// Creates the project's volume directories under `dir` that do not exist yet and hands them
+/// to the project user. Existing data is left alone, including volumes no longer configured.
+///
+/// # Errors
+/// Returns an error if a directory cannot be created or its ownership cannot be set.
+pub fn install(
+    dir: &Path,
+    project_id: &str,
+    volumes: &[Volume],
+    privilege_wrapper: &PrivilegeWrapper,
+) -> Result<Vec<VolumeMount>> {
+    let owner = format!("nanoscale-{project_id}:nanoscale-{project_id}");
+    let mut mounts = Vec::with_capacity(volumes.len());
+    for volume in volumes {
+        let source = volume_dir(dir, project_id, &volume.name);
+        if !source.is_dir() {
+            fs::create_dir_all(&source)?;
+            let path = source
+                .to_str()
+                .ok_or_else(|| anyhow!("invalid volume path"))?;
+            privilege_wrapper.run("/usr/bin/chown", &["-R", &owner, path])?;
+        }
+        mounts.push(VolumeMount {
+            source,
+            mount_path: volume.mount_path.clone(),
+        });
+    }
+
+    Ok(mounts)
+}
+
+/// Whether the project still has volume directories under `dir`.
+///
+/// # Errors
+/// Returns an error if the project's directory exists but cannot be read.
+pub fn any_kept(dir: &Path, project_id: &str) -> Result<bool> {
+    match fs::read_dir(dir.join(project_id)) {
+        Ok(mut entries) => Ok(entries.next().is_some()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Deletes every volume of the project under `dir`, with the data in it.
+///
+/// # Errors
+/// Returns an error if the directory cannot be removed.
+pub fn remove_all(
+    dir: &Path,
+    project_id: &str,
+    privilege_wrapper: &PrivilegeWrapper,
+) -> Result<()> {
+    let project_dir = dir.join(project_id);
+    if !project_dir.exists() {
+        return Ok(());
+    }
+
+    let path = project_dir
+        .to_str()
+        .ok_or_else(|| anyhow!("invalid volume path"))?;
+    privilege_wrapper.run("/usr/bin/rm", &["-rf", path])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volume(name: &str, mount_path: &str) -> Volume {
+        Volume {
+            name: name.to_string(),
+            mount_path: mount_path.to_string(),
+        }
+    }
+
+    #[test]
+    fn validate_accepts_absolute_and_app_relative_paths() {
+        validate(&[
+            volume("db", "data"),
+            volume("uploads", "./public/uploads/"),
+            volume("cache", "/var/cache/app"),
+        ])
+        .expect("valid");
+
+        assert!(validate(&[volume("db", "data"), volume("db", "other")]).is_err());
+        assert!(validate(&[volume("db", "data"), volume("files", "./data")]).is_err());
+        assert!(validate(&[volume("bad name", "data")]).is_err());
+        for mount_path in [
+            "/",
+            ".",
+            "",
+            "../data",
+            "data/../..",
+            "/data dir",
+            "data;rm",
+            "a//b",
+        ] {
+            assert!(
+                validate(&[volume("db", mount_path)]).is_err(),
+                "{mount_path:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn mount_targets_resolve_relative_paths_against_the_working_directory() {
+        let mount = VolumeMount {
+            source: volume_dir(Path::new(VOLUMES_PATH), "p1", "db"),
+            mount_path: "./data".to_string(),
+        };
+        assert_eq!(
+            mount.source,
+            PathBuf::from("/opt/nanoscale/data/volumes/p1/db")
+        );
+        assert_eq!(
+            mount.target("/opt/nanoscale/sites/p1/source/"),
+            "/opt/nanoscale/sites/p1/source/data"
+        );
+
+        let mount = VolumeMount {
+            mount_path: "/var/lib/app".to_string(),
+            ..mount
+        };
+        assert_eq!(
+            mount.target("/opt/nanoscale/sites/p1/source"),
+            "/var/lib/app"
+        );
+    }
+
+    #[test]
+    fn any_kept_sees_only_existing_volume_directories() {
+        let tempdir = tempfile::tempdir().expect("tempdir");
+        assert!(!any_kept(tempdir.path(), "p1").expect("missing"));
+
+        fs::create_dir_all(tempdir.path().join("p1")).expect("mkdir");
+        assert!(!any_kept(tempdir.path(), "p1").expect("empty"));
+
+        fs::create_dir_all(volume_dir(tempdir.path(), "p1", "db")).expect("mkdir");
+        assert!(any_kept(tempdir.path(), "p1").expect("kept"));
+        assert!(!any_kept(tempdir.path(), "p2").expect("other project"));
+    }
+}
@@ -3,7 +3,7 @@ use std::{collections::HashMap, time::Duration};
 
 use anyhow::Result;
 use axum::middleware;
-use axum::routing::{delete, get, post};
+use axum::routing::{delete, get, post, put};
 use axum::Router;
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
@@ -15,21 +15,37 @@ use crate::cluster::signature::verify_cluster_signature;
 use crate::cluster::token_store::TokenStore;
 use crate::config::NanoScaleConfig;
 use crate::db::{DbClient, NewServer};
+use crate::deployment::cert_renewal::CertificateRenewer;
 use crate::deployment::inactivity_monitor::{InactivityMonitor, MonitoredProject};
 use crate::request_logging;
 
 use self::stats_cache::StatsCache;
 
+mod access;
 mod api_types;
 mod auth;
 mod cluster;
+mod deployments;
+mod dns;
+mod domains;
 mod github;
 mod internal;
+mod jobs;
+mod lifecycle;
+mod maintenance;
+mod network;
+mod ports;
+mod processes;
+mod project_detect;
 mod project_domain;
 mod project_mapping;
 mod projects;
+mod replicas;
 mod servers;
 mod stats_cache;
+mod streams;
+mod volumes;
+mod watch_paths;
 mod worker_client;
 
 #[cfg(test)]
@@ -42,10 +58,14 @@ pub struct OrchestratorState {
     pub monitored_projects: Arc<RwLock<Vec<MonitoredProject>>>,
     pub local_server_id: String,
     pub base_domain: Option<String>,
+    /// Dedicated server serving every project's domains, from `orchestrator.ingress_server_id`.
+    pub ingress_server_id: Option<String>,
     pub tls_email: Option<String>,
     pub stats_cache: Arc<RwLock<StatsCache>>,
     pub(super) github: Arc<github::GitHubService>,
     pub(super) redeploy_debounce: Arc<Mutex<HashMap<String, u64>>>,
+    pub(super) dns_resolver: Arc<dyn dns::DnsResolver>,
+    pub(super) certificates: CertificateRenewer,
 }
 
 /// .
@@ -87,14 +107,21 @@ pub async fn run() -> Result<()> {
         monitored_projects: Arc::new(RwLock::new(Vec::new())),
         local_server_id,
         base_domain,
+        ingress_server_id: config.orchestrator_ingress_server_id(),
         tls_email,
         stats_cache: Arc::new(RwLock::new(StatsCache::default())),
         github: Arc::new(github::GitHubService::from_config(&config)?),
         redeploy_debounce: Arc::new(Mutex::new(HashMap::new())),
+        dns_resolver: Arc::new(dns::SystemDnsResolver::from_system_conf()?),
+        certificates: CertificateRenewer::new(
+            config.tls_renewal_window_days(),
+            config.acme_settings()?,
+        ),
     };
 
     let monitor = InactivityMonitor::new(state.monitored_projects.clone());
     monitor.spawn();
+    state.certificates.clone().spawn();
 
     // keep explicit reference to satisfy clippy for imported Duration and document default debounce
     let _default_webhook_redeploy_debounce = Duration::from_secs(15);
@@ -107,6 +134,15 @@ pub async fn run() -> Result<()> {
     let internal_router = Router::new()
         .route("/projects", post(internal::internal_projects))
         .route("/projects/:id", delete(internal::internal_delete_project))
+        .route(
+            "/projects/:id/start",
+            post(internal::internal_start_project),
+        )
+        .route("/projects/:id/stop", post(internal::internal_stop_project))
+        .route(
+            "/projects/:id/restart",
+            post(internal::internal_restart_project),
+        )
         .route("/ports/check", post(internal::internal_port_check))
         .route("/verify-signature", post(cluster::verify_signature_guarded))
         .route_layer(middleware::from_fn_with_state(
@@ -151,6 +187,7 @@ pub async fn run() -> Result<()> {
             "/api/projects",
             get(projects::list_projects).post(projects::create_project),
         )
+        .route("/api/projects/detect", post(project_detect::detect_project))
         .route(
             "/api/projects/:id",
             get(projects::get_project).delete(projects::delete_project),
@@ -159,6 +196,68 @@ pub async fn run() -> Result<()> {
             "/api/projects/:id/redeploy",
             post(projects::redeploy_project),
         )
+        .route("/api/projects/:id/start", post(lifecycle::start_project))
+        .route("/api/projects/:id/stop", post(lifecycle::stop_project))
+        .route(
+            "/api/projects/:id/restart",
+            post(lifecycle::restart_project),
+        )
+        .route(
+            "/api/projects/:id/deployments",
+            get(deployments::list_project_deployments),
+        )
+        .route(
+            "/api/projects/:id/domains",
+            get(domains::list_project_domains).post(domains::add_project_domain),
+        )
+        .route(
+            "/api/projects/:id/domains/:domain_id",
+            put(domains::update_project_domain).delete(domains::delete_project_domain),
+        )
+        .route(
+            "/api/projects/:id/domains/:domain_id/verify",
+            post(domains::verify_project_domain),
+        )
+        .route(
+            "/api/projects/:id/access",
+            get(access::get_project_access).put(access::update_project_access),
+        )
+        .route(
+            "/api/projects/:id/access/users/:username",
+            put(access::set_project_access_user).delete(access::delete_project_access_user),
+        )
+        .route(
+            "/api/projects/:id/maintenance",
+            get(maintenance::get_project_maintenance).put(maintenance::update_project_maintenance),
+        )
+        .route(
+            "/api/projects/:id/network",
+            get(network::get_project_network).put(network::update_project_network),
+        )
+        .route(
+            "/api/projects/:id/stream-ports",
+            get(streams::get_project_stream_ports).put(streams::update_project_stream_ports),
+        )
+        .route(
+            "/api/projects/:id/jobs",
+            get(jobs::get_project_jobs).put(jobs::update_project_jobs),
+        )
+        .route(
+            "/api/projects/:id/jobs/runs",
+            get(jobs::list_project_job_runs),
+        )
+        .route(
+            "/api/projects/:id/jobs/:name/run",
+            post(jobs::run_project_job),
+        )
+        .route(
+            "/api/projects/:id/processes",
+            get(processes::get_project_processes).put(processes::update_project_processes),
+        )
+        .route(
+            "/api/projects/:id/volumes",
+            get(volumes::get_project_volumes).put(volumes::update_project_volumes),
+        )
         .route(
             "/api/cluster/generate-token",
             post(cluster::generate_cluster_token),
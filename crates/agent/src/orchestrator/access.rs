@@ -0,0 +1,149 @@
+use axum::extract::Path as AxumPath;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use tower_sessions::Session;
+
+use crate::deployment::access::{hash_password, validate_username, AccessRules};
+
+use super::api_types::{ProjectAccessResponse, ProjectAccessUserItem, SetProjectAccessUserRequest};
+use super::auth::require_authenticated;
+use super::domains::load_project;
+use super::projects::redeploy_project_by_id;
+use super::OrchestratorState;
+
+pub(super) async fn get_project_access(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<Json<ProjectAccessResponse>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    Ok(Json(project_access(&state, &project_id).await?))
+}
+
+/// Replaces the project's IP allow/deny rules and reroutes it straight away.
+pub(super) async fn update_project_access(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+    Json(payload): Json<AccessRules>,
+) -> Result<Json<ProjectAccessResponse>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    load_project(&state, &project_id).await?;
+    payload
+        .validate()
+        .map_err(|error| (StatusCode::BAD_REQUEST, format!("{error:#}")))?;
+    let rules = serde_json::to_string(&payload).map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to serialize access rules: {error}"),
+        )
+    })?;
+    state
+        .db
+        .set_project_access_rules(&project_id, &rules)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to update access rules: {error}"),
+            )
+        })?;
+
+    redeploy_project_by_id(&state, &project_id).await?;
+    Ok(Json(project_access(&state, &project_id).await?))
+}
+
+/// Adds a basic auth user, or changes its password. Only the SHA-512 crypt hash is stored.
+pub(super) async fn set_project_access_user(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath((project_id, username)): AxumPath<(String, String)>,
+    Json(payload): Json<SetProjectAccessUserRequest>,
+) -> Result<Json<ProjectAccessResponse>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    load_project(&state, &project_id).await?;
+    validate_username(&username).map_err(|error| (StatusCode::BAD_REQUEST, format!("{error}")))?;
+    let password_hash = hash_password(&payload.password)
+        .map_err(|error| (StatusCode::BAD_REQUEST, format!("{error}")))?;
+    state
+        .db
+        .upsert_project_access_user(&project_id, &username, &password_hash)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to store basic auth user: {error}"),
+            )
+        })?;
+
+    redeploy_project_by_id(&state, &project_id).await?;
+    Ok(Json(project_access(&state, &project_id).await?))
+}
+
+pub(super) async fn delete_project_access_user(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath((project_id, username)): AxumPath<(String, String)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let removed = state
+        .db
+        .delete_project_access_user(&project_id, &username)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to delete basic auth user: {error}"),
+            )
+        })?;
+    if !removed {
+        return Err((StatusCode::NOT_FOUND, "User not found".to_string()));
+    }
+
+    redeploy_project_by_id(&state, &project_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn project_access(
+    state: &OrchestratorState,
+    project_id: &str,
+) -> Result<ProjectAccessResponse, (StatusCode, String)> {
+    let project = load_project(state, project_id).await?;
+    let rules = serde_json::from_str::<AccessRules>(&project.access_rules).map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to deserialize access rules: {error}"),
+        )
+    })?;
+    let users = state
+        .db
+        .list_project_access_users(project_id)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to load basic auth users: {error}"),
+            )
+        })?
+        .into_iter()
+        .map(|user| ProjectAccessUserItem {
+            username: user.username,
+            created_at: user.created_at,
+        })
+        .collect();
+
+    Ok(ProjectAccessResponse { rules, users })
+}
@@ -1,5 +1,20 @@
 use serde::{Deserialize, Serialize};
 
+use crate::deployment::access::{AccessControl, AccessRules};
+use crate::deployment::build::ProjectRuntime;
+use crate::deployment::cert_renewal::DomainTlsStatus;
+use crate::deployment::firewall::Protocol;
+use crate::deployment::jobs::CronJob;
+use crate::deployment::nginx::{DomainRedirect, RoutedDomain};
+use crate::deployment::pages::MaintenanceMode;
+use crate::deployment::private_network::PrivateNetwork;
+use crate::deployment::processes::ProcessType;
+use crate::deployment::replicas::ReplicaRole;
+use crate::deployment::routing::RoutingConfig;
+use crate::deployment::streams::StreamPort;
+use crate::deployment::systemd::ResourceLimits;
+use crate::deployment::volumes::Volume;
+
 #[derive(Debug, Deserialize)]
 pub(super) struct SetupRequest {
     pub(super) username: String,
@@ -81,6 +96,55 @@ pub(super) struct CreateProjectRequest {
     pub(super) port: Option<u16>,
     pub(super) env_vars: Vec<ProjectEnvVar>,
     pub(super) github_source: Option<GitHubProjectSourceRequest>,
+    #[serde(default)]
+    pub(super) runtime: ProjectRuntime,
+    #[serde(default)]
+    pub(super) resource_limits: ResourceLimits,
+    #[serde(default)]
+    pub(super) root_directory: String,
+    #[serde(default)]
+    pub(super) watch_paths: Vec<String>,
+    #[serde(default)]
+    pub(super) routing: RoutingConfig,
+    #[serde(default)]
+    pub(super) access: AccessRules,
+    #[serde(default)]
+    pub(super) maintenance: MaintenanceMode,
+    /// Servers to run the project on; the selected server is the ingress.
+    #[serde(default = "default_replicas")]
+    pub(super) replicas: u32,
+    #[serde(default)]
+    pub(super) stream_ports: Vec<StreamPort>,
+    #[serde(default)]
+    pub(super) jobs: Vec<CronJob>,
+    #[serde(default)]
+    pub(super) processes: Vec<ProcessType>,
+    #[serde(default)]
+    pub(super) volumes: Vec<Volume>,
+    #[serde(default)]
+    pub(super) release_command: String,
+    /// Taken from the stored project on redeploys; new projects always start.
+    #[serde(skip_deserializing)]
+    pub(super) stopped: bool,
+}
+
+const fn default_replicas() -> u32 {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct DetectProjectRequest {
+    #[serde(default)]
+    pub(super) repo_url: String,
+    #[serde(default = "default_branch")]
+    pub(super) branch: String,
+    pub(super) github_source: Option<GitHubProjectSourceRequest>,
+    #[serde(default)]
+    pub(super) root_directory: String,
+}
+
+fn default_branch() -> String {
+    "main".to_string()
 }
 
 #[derive(Debug, Deserialize)]
@@ -127,6 +191,93 @@ pub(super) struct ProjectDetailsResponse {
     pub(super) domain: Option<String>,
     pub(super) source_provider: String,
     pub(super) source_repo_id: Option<i64>,
+    pub(super) runtime: String,
+    pub(super) memory_limit_mb: Option<i64>,
+    pub(super) cpu_quota_percent: Option<i64>,
+    pub(super) root_directory: String,
+    pub(super) watch_paths: Vec<String>,
+    pub(super) routing: RoutingConfig,
+    pub(super) maintenance: MaintenanceMode,
+    pub(super) replicas: i64,
+    pub(super) stream_ports: Vec<StreamPort>,
+    pub(super) jobs: Vec<CronJob>,
+    pub(super) processes: Vec<ProcessType>,
+    pub(super) volumes: Vec<Volume>,
+    pub(super) release_command: String,
+    pub(super) created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct AddProjectDomainRequest {
+    pub(super) domain: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct UpdateProjectDomainRequest {
+    #[serde(default)]
+    pub(super) redirect: Option<DomainRedirect>,
+    #[serde(default)]
+    pub(super) primary: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct ProjectDomainItem {
+    pub(super) id: String,
+    pub(super) domain: String,
+    pub(super) status: String,
+    pub(super) verification_error: Option<String>,
+    pub(super) verified_at: Option<String>,
+    pub(super) redirect: Option<DomainRedirect>,
+    pub(super) primary: bool,
+    /// Certificate state on the project's server; `None` when it could not be read.
+    pub(super) tls: Option<DomainTlsStatus>,
+    pub(super) created_at: String,
+}
+
+/// A project's IP rules and basic auth users; password hashes are never returned.
+#[derive(Debug, Serialize)]
+pub(super) struct ProjectAccessResponse {
+    #[serde(flatten)]
+    pub(super) rules: AccessRules,
+    pub(super) users: Vec<ProjectAccessUserItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct ProjectAccessUserItem {
+    pub(super) username: String,
+    pub(super) created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct SetProjectAccessUserRequest {
+    pub(super) password: String,
+}
+
+/// Who may call a project's service over the private network.
+#[derive(Debug, Deserialize)]
+pub(super) struct UpdateProjectNetworkRequest {
+    #[serde(default)]
+    pub(super) internal_name: Option<String>,
+    #[serde(default)]
+    pub(super) allowed_callers: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct ProjectNetworkResponse {
+    pub(super) internal_name: Option<String>,
+    /// `<internal_name>.internal`, what allowed callers resolve.
+    pub(super) hostname: Option<String>,
+    pub(super) port: i64,
+    pub(super) allowed_callers: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct DeploymentItem {
+    pub(super) id: String,
+    pub(super) git_ref: String,
+    pub(super) commit_sha: Option<String>,
+    pub(super) status: String,
+    pub(super) log: Vec<String>,
     pub(super) created_at: String,
 }
 
@@ -174,9 +325,64 @@ pub(super) struct WorkerCreateProjectRequest {
     pub(super) run_command: String,
     pub(super) output_directory: String,
     pub(super) port: u16,
+    pub(super) backend_port: u16,
     pub(super) domain: Option<String>,
+    #[serde(default)]
+    pub(super) custom_domains: Vec<RoutedDomain>,
     pub(super) tls_email: Option<String>,
+    #[serde(default)]
+    pub(super) base_domain: Option<String>,
     pub(super) env_vars: Vec<ProjectEnvVar>,
+    #[serde(default)]
+    pub(super) runtime: ProjectRuntime,
+    #[serde(default)]
+    pub(super) resource_limits: ResourceLimits,
+    #[serde(default)]
+    pub(super) root_directory: String,
+    #[serde(default)]
+    pub(super) routing: RoutingConfig,
+    #[serde(default)]
+    pub(super) access: AccessControl,
+    #[serde(default)]
+    pub(super) maintenance: MaintenanceMode,
+    #[serde(default)]
+    pub(super) replica: ReplicaRole,
+    #[serde(default)]
+    pub(super) network: PrivateNetwork,
+    #[serde(default)]
+    pub(super) stream_ports: Vec<StreamPort>,
+    #[serde(default)]
+    pub(super) jobs: Vec<CronJob>,
+    #[serde(default)]
+    pub(super) processes: Vec<ProcessType>,
+    /// Set for the project's own server only, which runs its scheduled jobs and worker
+    /// processes.
+    #[serde(default)]
+    pub(super) primary: bool,
+    #[serde(default)]
+    pub(super) volumes: Vec<Volume>,
+    #[serde(default)]
+    pub(super) release_command: String,
+    /// Set for the one server that runs the release command before going live.
+    #[serde(default)]
+    pub(super) runs_release: bool,
+    /// Keeps the new release down, as the project was stopped.
+    #[serde(default)]
+    pub(super) stopped: bool,
+}
+
+/// Query of `DELETE /api/projects/:id`.
+#[derive(Debug, Deserialize)]
+pub(super) struct DeleteProjectQuery {
+    /// Confirms that the project's volumes are deleted with it; they are kept otherwise.
+    #[serde(default)]
+    pub(super) delete_volumes: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(super) struct InternalDeleteProjectRequest {
+    #[serde(default)]
+    pub(super) delete_volumes: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -185,9 +391,19 @@ pub(super) struct InternalProjectResponse {
     pub(super) message: String,
 }
 
+#[derive(Debug, Serialize)]
+pub(super) struct InternalDeploymentResponse {
+    pub(super) status: &'static str,
+    pub(super) message: String,
+    pub(super) log: Vec<String>,
+    pub(super) commit_sha: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub(super) struct PortAvailabilityRequest {
     pub(super) port: u16,
+    #[serde(default)]
+    pub(super) protocol: Protocol,
 }
 
 #[derive(Debug, Serialize)]
@@ -0,0 +1,70 @@
+use axum::extract::Path as AxumPath;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use tower_sessions::Session;
+use uuid::Uuid;
+
+use crate::db::NewDeployment;
+
+use super::api_types::DeploymentItem;
+use super::auth::require_authenticated;
+use super::project_mapping::map_deployment_record;
+use super::worker_client::WorkerDeploymentResponse;
+use super::OrchestratorState;
+
+pub(super) async fn list_project_deployments(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<Json<Vec<DeploymentItem>>, StatusCode> {
+    require_authenticated(&session).await?;
+
+    state
+        .db
+        .get_project_by_id(&project_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let deployments = state
+        .db
+        .list_deployments_for_project(&project_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(
+        deployments.into_iter().map(map_deployment_record).collect(),
+    ))
+}
+
+/// Stores the outcome of a worker deployment call. Failures to record are only logged so they
+/// never mask the deployment result itself.
+pub(super) async fn record_deployment(
+    state: &OrchestratorState,
+    project_id: &str,
+    git_ref: &str,
+    result: &anyhow::Result<WorkerDeploymentResponse>,
+) {
+    let (status, commit_sha, log) = match result {
+        Ok(response) => (
+            "succeeded",
+            response.commit_sha.clone(),
+            response.log.clone(),
+        ),
+        Err(error) => ("failed", None, vec![format!("{error:#}")]),
+    };
+
+    let deployment = NewDeployment {
+        id: Uuid::new_v4().to_string(),
+        project_id: project_id.to_string(),
+        git_ref: git_ref.to_string(),
+        commit_sha,
+        status: status.to_string(),
+        log: serde_json::to_string(&log).unwrap_or_else(|_| "[]".to_string()),
+    };
+
+    if let Err(error) = state.db.insert_deployment(&deployment).await {
+        eprintln!("failed to record deployment for project {project_id}: {error:#}");
+    }
+}
@@ -0,0 +1,187 @@
+use std::fmt;
+use std::future::Future;
+use std::net::IpAddr;
+use std::pin::Pin;
+
+use anyhow::Result;
+use hickory_resolver::error::{ResolveError, ResolveErrorKind};
+use hickory_resolver::proto::rr::{RData, RecordType};
+use hickory_resolver::TokioAsyncResolver;
+
+pub(crate) type LookupFuture<'a> = Pin<Box<dyn Future<Output = Result<DnsRecords>> + Send + 'a>>;
+
+/// What public DNS says about a hostname.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct DnsRecords {
+    /// A and AAAA answers, after following any CNAME chain.
+    pub(crate) addresses: Vec<IpAddr>,
+    /// CNAME targets, lowercase and without the trailing dot.
+    pub(crate) cnames: Vec<String>,
+}
+
+/// Looks up the records used for domain ownership checks; tests substitute a stub.
+pub(crate) trait DnsResolver: fmt::Debug + Send + Sync {
+    fn lookup<'a>(&'a self, host: &'a str) -> LookupFuture<'a>;
+}
+
+/// Resolver backed by the host's `/etc/resolv.conf`.
+pub(super) struct SystemDnsResolver {
+    resolver: TokioAsyncResolver,
+}
+
+impl fmt::Debug for SystemDnsResolver {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("SystemDnsResolver")
+            .finish_non_exhaustive()
+    }
+}
+
+impl SystemDnsResolver {
+    pub(super) fn from_system_conf() -> Result<Self> {
+        Ok(Self {
+            resolver: TokioAsyncResolver::tokio_from_system_conf()?,
+        })
+    }
+}
+
+impl DnsResolver for SystemDnsResolver {
+    fn lookup<'a>(&'a self, host: &'a str) -> LookupFuture<'a> {
+        Box::pin(async move {
+            let addresses = match self.resolver.lookup_ip(host).await {
+                Ok(lookup) => lookup.iter().collect(),
+                Err(error) if is_no_records(&error) => Vec::new(),
+                Err(error) => return Err(error.into()),
+            };
+
+            let cnames = match self.resolver.lookup(host, RecordType::CNAME).await {
+                Ok(lookup) => lookup
+                    .iter()
+                    .filter_map(|record| match record {
+                        RData::CNAME(target) => Some(normalize_host(&target.0.to_utf8())),
+                        _ => None,
+                    })
+                    .collect(),
+                Err(error) if is_no_records(&error) => Vec::new(),
+                Err(error) => return Err(error.into()),
+            };
+
+            Ok(DnsRecords { addresses, cnames })
+        })
+    }
+}
+
+/// Resolver answering every lookup with fixed records.
+#[cfg(test)]
+#[derive(Debug)]
+pub(super) struct StaticDnsResolver(pub(super) DnsRecords);
+
+#[cfg(test)]
+impl DnsResolver for StaticDnsResolver {
+    fn lookup<'a>(&'a self, _host: &'a str) -> LookupFuture<'a> {
+        let records = self.0.clone();
+        Box::pin(async move { Ok(records) })
+    }
+}
+
+fn is_no_records(error: &ResolveError) -> bool {
+    matches!(error.kind(), ResolveErrorKind::NoRecordsFound { .. })
+}
+
+/// Lowercases a hostname and strips the trailing root dot.
+pub(super) fn normalize_host(host: &str) -> String {
+    host.trim().trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Decides whether `records` prove the domain points at this project.
+///
+/// A domain passes when it resolves to `server_ip` (directly or through a CNAME chain) or when
+/// it is a CNAME to one of `cname_targets` (the project's assigned domain).
+pub(super) fn check_records(
+    domain: &str,
+    records: &DnsRecords,
+    server_ip: IpAddr,
+    cname_targets: &[&str],
+) -> Result<(), String> {
+    if records.addresses.contains(&server_ip) {
+        return Ok(());
+    }
+
+    if records.cnames.iter().any(|cname| {
+        cname_targets
+            .iter()
+            .any(|target| normalize_host(target) == *cname)
+    }) {
+        return Ok(());
+    }
+
+    let expected = match cname_targets.first() {
+        Some(target) => {
+            format!("an A/AAAA record for {domain} pointing to {server_ip} or a CNAME to {target}")
+        }
+        None => format!("an A/AAAA record for {domain} pointing to {server_ip}"),
+    };
+
+    if records.addresses.is_empty() && records.cnames.is_empty() {
+        return Err(format!("No DNS records found; expected {expected}"));
+    }
+
+    let found = records
+        .addresses
+        .iter()
+        .map(ToString::to_string)
+        .chain(records.cnames.iter().cloned())
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(format!("DNS points to {found}; expected {expected}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_ip() -> IpAddr {
+        "203.0.113.10".parse().expect("ip")
+    }
+
+    #[tokio::test]
+    async fn check_records_accepts_matching_address_through_resolver() {
+        let resolver = StaticDnsResolver(DnsRecords {
+            addresses: vec![server_ip()],
+            cnames: Vec::new(),
+        });
+        let records = resolver.lookup("app.customer.com").await.expect("lookup");
+        check_records("app.customer.com", &records, server_ip(), &[]).expect("verified");
+    }
+
+    #[test]
+    fn check_records_accepts_cname_to_assigned_domain() {
+        let records = DnsRecords {
+            addresses: Vec::new(),
+            cnames: vec!["my-app.apps.example.com".to_string()],
+        };
+        check_records(
+            "www.customer.com",
+            &records,
+            server_ip(),
+            &["My-App.apps.example.com."],
+        )
+        .expect("verified");
+    }
+
+    #[test]
+    fn check_records_explains_mismatches() {
+        let empty = check_records("x.customer.com", &DnsRecords::default(), server_ip(), &[])
+            .expect_err("no records");
+        assert!(empty.contains("No DNS records found"));
+
+        let wrong = DnsRecords {
+            addresses: vec!["198.51.100.1".parse().expect("ip")],
+            cnames: Vec::new(),
+        };
+        let error = check_records("x.customer.com", &wrong, server_ip(), &["app.example.com"])
+            .expect_err("wrong address");
+        assert!(error.contains("198.51.100.1"));
+        assert!(error.contains("CNAME to app.example.com"));
+    }
+}
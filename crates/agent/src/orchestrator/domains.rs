@@ -0,0 +1,427 @@
+use std::net::IpAddr;
+
+use axum::extract::Path as AxumPath;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use tower_sessions::Session;
+use uuid::Uuid;
+
+use crate::db::{NewProjectDomain, ProjectDetailsRecord, ProjectDomainRecord};
+use crate::deployment::cert_renewal::DomainTlsStatus;
+
+use super::api_types::{AddProjectDomainRequest, ProjectDomainItem, UpdateProjectDomainRequest};
+use super::auth::require_authenticated;
+use super::dns::{check_records, normalize_host};
+use super::project_mapping::map_project_domain_record;
+use super::projects::redeploy_project_by_id;
+use super::replicas::routing_server_id;
+use super::worker_client::call_worker_tls_status;
+use super::OrchestratorState;
+
+const MAX_DOMAIN_LENGTH: usize = 253;
+
+pub(super) async fn list_project_domains(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<Json<Vec<ProjectDomainItem>>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let project = load_project(&state, &project_id).await?;
+    let domains = state
+        .db
+        .list_project_domains(&project_id)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to load project domains: {error}"),
+            )
+        })?;
+
+    let names = domains
+        .iter()
+        .map(|domain| domain.domain.clone())
+        .collect::<Vec<_>>();
+    let mut tls_statuses = match domain_tls_status(&state, &project, names).await {
+        Ok(statuses) => statuses,
+        Err(error) => {
+            eprintln!("TLS status lookup failed for project {project_id}: {error:#}");
+            Vec::new()
+        }
+    };
+
+    Ok(Json(
+        domains
+            .into_iter()
+            .map(|domain| {
+                let tls = tls_statuses
+                    .iter()
+                    .position(|status| status.domain == domain.domain)
+                    .map(|index| tls_statuses.swap_remove(index));
+                ProjectDomainItem {
+                    tls,
+                    ..map_project_domain_record(domain)
+                }
+            })
+            .collect(),
+    ))
+}
+
+/// Reads certificate state for `domains` from the host serving the project.
+async fn domain_tls_status(
+    state: &OrchestratorState,
+    project: &ProjectDetailsRecord,
+    domains: Vec<String>,
+) -> anyhow::Result<Vec<DomainTlsStatus>> {
+    if domains.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let server_id = routing_server_id(state, &project.server_id);
+    if server_id == state.local_server_id {
+        let certificates = state.certificates.clone();
+        return tokio::task::spawn_blocking(move || certificates.domain_status(&domains)).await?;
+    }
+
+    let connection = state
+        .db
+        .get_server_connection_info(server_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("project host server was not found"))?;
+    call_worker_tls_status(
+        &connection.id,
+        &connection.ip_address,
+        &connection.secret_key,
+        &domains,
+    )
+    .await
+}
+
+pub(super) async fn add_project_domain(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+    Json(payload): Json<AddProjectDomainRequest>,
+) -> Result<(StatusCode, Json<ProjectDomainItem>), (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    load_project(&state, &project_id).await?;
+    let domain = validate_custom_domain(&payload.domain, state.base_domain.as_deref())
+        .map_err(|message| (StatusCode::BAD_REQUEST, message))?;
+
+    let in_use = state
+        .db
+        .is_project_domain_in_use(&domain)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to validate domain uniqueness: {error}"),
+            )
+        })?;
+    if in_use {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Domain {domain} is already attached to a project"),
+        ));
+    }
+
+    let domain_id = Uuid::new_v4().to_string();
+    state
+        .db
+        .insert_project_domain(&NewProjectDomain {
+            id: domain_id.clone(),
+            project_id: project_id.clone(),
+            domain,
+        })
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to persist project domain: {error}"),
+            )
+        })?;
+
+    let record = load_domain(&state, &project_id, &domain_id).await?;
+    Ok((StatusCode::CREATED, Json(map_project_domain_record(record))))
+}
+
+/// Re-checks DNS for a domain. A domain that becomes verified is routed straight away by
+/// redeploying the project.
+pub(super) async fn verify_project_domain(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath((project_id, domain_id)): AxumPath<(String, String)>,
+) -> Result<Json<ProjectDomainItem>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let project = load_project(&state, &project_id).await?;
+    let domain = load_domain(&state, &project_id, &domain_id).await?;
+    let was_verified = domain.status == "verified";
+
+    let verification_error = domain_verification_error(&state, &project, &domain.domain).await?;
+    state
+        .db
+        .set_project_domain_verification(&domain_id, verification_error.as_deref())
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to store verification result: {error}"),
+            )
+        })?;
+
+    if was_verified != verification_error.is_none() {
+        redeploy_project_by_id(&state, &project_id).await?;
+    }
+
+    let record = load_domain(&state, &project_id, &domain_id).await?;
+    Ok(Json(map_project_domain_record(record)))
+}
+
+/// Changes whether a domain serves the project or redirects to the primary domain, and which
+/// domain is primary. Verified domains are rerouted straight away.
+pub(super) async fn update_project_domain(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath((project_id, domain_id)): AxumPath<(String, String)>,
+    Json(payload): Json<UpdateProjectDomainRequest>,
+) -> Result<Json<ProjectDomainItem>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let project = load_project(&state, &project_id).await?;
+    let domain = load_domain(&state, &project_id, &domain_id).await?;
+
+    if let Some(redirect) = payload.redirect {
+        if !matches!(redirect.status, 301 | 308) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Redirect status must be 301 or 308".to_string(),
+            ));
+        }
+        if payload.primary {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "The primary domain must serve the project, not redirect".to_string(),
+            ));
+        }
+
+        let domains = state
+            .db
+            .list_project_domains(&project_id)
+            .await
+            .map_err(|error| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Unable to load project domains: {error}"),
+                )
+            })?;
+        let has_target = project.domain.is_some()
+            || domains.iter().any(|other| {
+                other.id != domain_id
+                    && other.status == "verified"
+                    && other.redirect_status.is_none()
+            });
+        if !has_target {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "A redirect needs another verified domain that serves the project".to_string(),
+            ));
+        }
+    }
+
+    state
+        .db
+        .set_project_domain_routing(
+            &project_id,
+            &domain_id,
+            payload.redirect.map(|redirect| i64::from(redirect.status)),
+            payload
+                .redirect
+                .is_none_or(|redirect| redirect.preserve_path),
+            payload.primary,
+        )
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to update project domain: {error}"),
+            )
+        })?;
+
+    if domain.status == "verified" {
+        redeploy_project_by_id(&state, &project_id).await?;
+    }
+
+    let record = load_domain(&state, &project_id, &domain_id).await?;
+    Ok(Json(map_project_domain_record(record)))
+}
+
+pub(super) async fn delete_project_domain(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath((project_id, domain_id)): AxumPath<(String, String)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let domain = load_domain(&state, &project_id, &domain_id).await?;
+    state
+        .db
+        .delete_project_domain(&project_id, &domain_id)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to delete project domain: {error}"),
+            )
+        })?;
+
+    if domain.status == "verified" {
+        redeploy_project_by_id(&state, &project_id).await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Looks the domain up and returns why it does not point at the server serving the project's
+/// domains, or `None` when it does.
+pub(super) async fn domain_verification_error(
+    state: &OrchestratorState,
+    project: &ProjectDetailsRecord,
+    domain: &str,
+) -> Result<Option<String>, (StatusCode, String)> {
+    let connection = state
+        .db
+        .get_server_connection_info(routing_server_id(state, &project.server_id))
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to load server connection info: {error}"),
+            )
+        })?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "Project host server was not found".to_string(),
+        ))?;
+    let server_ip = connection.ip_address.parse::<IpAddr>().map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "Server address {} is not an IP address",
+                connection.ip_address
+            ),
+        )
+    })?;
+
+    let records = match state.dns_resolver.lookup(domain).await {
+        Ok(records) => records,
+        Err(error) => return Ok(Some(format!("DNS lookup failed: {error:#}"))),
+    };
+    let cname_targets = project.domain.as_deref().into_iter().collect::<Vec<_>>();
+
+    Ok(check_records(domain, &records, server_ip, &cname_targets).err())
+}
+
+/// Normalizes a user-supplied hostname and checks it is a plain DNS name outside the
+/// platform's own base domain.
+fn validate_custom_domain(domain: &str, base_domain: Option<&str>) -> Result<String, String> {
+    let domain = normalize_host(domain);
+    let labels = domain.split('.').collect::<Vec<_>>();
+
+    let valid_labels = labels.iter().all(|label| {
+        (1..=63).contains(&label.len())
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric() || character == '-')
+    });
+    if domain.len() > MAX_DOMAIN_LENGTH || labels.len() < 2 || !valid_labels {
+        return Err(format!("{domain:?} is not a valid domain name"));
+    }
+
+    if let Some(base_domain) = base_domain.map(normalize_host) {
+        if domain == base_domain || domain.ends_with(&format!(".{base_domain}")) {
+            return Err(format!(
+                "Subdomains of {base_domain} are assigned automatically and cannot be added"
+            ));
+        }
+    }
+
+    Ok(domain)
+}
+
+pub(super) async fn load_project(
+    state: &OrchestratorState,
+    project_id: &str,
+) -> Result<ProjectDetailsRecord, (StatusCode, String)> {
+    state
+        .db
+        .get_project_by_id(project_id)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to load project: {error}"),
+            )
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Project not found".to_string()))
+}
+
+async fn load_domain(
+    state: &OrchestratorState,
+    project_id: &str,
+    domain_id: &str,
+) -> Result<ProjectDomainRecord, (StatusCode, String)> {
+    state
+        .db
+        .get_project_domain(project_id, domain_id)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to load project domain: {error}"),
+            )
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Domain not found".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_custom_domain_normalizes_and_rejects_invalid_names() {
+        assert_eq!(
+            validate_custom_domain(" App.Customer.com. ", None).expect("valid"),
+            "app.customer.com"
+        );
+        assert!(validate_custom_domain("localhost", None).is_err());
+        assert!(validate_custom_domain("-bad.example.com", None).is_err());
+        assert!(validate_custom_domain("*.example.com", None).is_err());
+        assert!(validate_custom_domain("a..example.com", None).is_err());
+        assert!(validate_custom_domain(&format!("{}.com", "a".repeat(64)), None).is_err());
+    }
+
+    #[test]
+    fn validate_custom_domain_rejects_platform_subdomains() {
+        assert!(
+            validate_custom_domain("other.apps.example.com", Some("apps.example.com")).is_err()
+        );
+        assert!(validate_custom_domain("apps.example.com", Some("apps.example.com")).is_err());
+        validate_custom_domain("example.com", Some("apps.example.com")).expect("apex allowed");
+    }
+}
@@ -28,6 +28,7 @@ use super::api_types::{
 };
 use super::auth::current_user_id;
 use super::projects::redeploy_project_by_id;
+use super::watch_paths::{effective_watch_paths, should_redeploy};
 use super::OrchestratorState;
 
 const OAUTH_STATE_TTL_SECONDS: u64 = 15 * 60;
@@ -595,6 +596,8 @@ struct GitHubWebhookPayload {
     repository: WebhookRepository,
     r#ref: Option<String>,
     after: Option<String>,
+    #[serde(default)]
+    commits: Vec<WebhookCommit>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -602,6 +605,39 @@ struct WebhookRepository {
     id: i64,
 }
 
+#[derive(Debug, Deserialize)]
+struct WebhookCommit {
+    #[serde(default)]
+    added: Vec<String>,
+    #[serde(default)]
+    removed: Vec<String>,
+    #[serde(default)]
+    modified: Vec<String>,
+}
+
+impl GitHubWebhookPayload {
+    /// Files touched by the pushed commits, or `None` when the payload lists no commits.
+    fn changed_files(&self) -> Option<Vec<String>> {
+        if self.commits.is_empty() {
+            return None;
+        }
+
+        let files = self
+            .commits
+            .iter()
+            .flat_map(|commit| {
+                commit
+                    .added
+                    .iter()
+                    .chain(&commit.removed)
+                    .chain(&commit.modified)
+            })
+            .cloned()
+            .collect::<HashSet<_>>();
+        Some(files.into_iter().collect())
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub(super) struct SyncReposRequest {
     installation_id: i64,
@@ -964,6 +1000,7 @@ pub(super) async fn github_webhook(
         }
     };
 
+    let changed_files = payload.changed_files();
     let ref_name = payload.r#ref.unwrap_or_default();
     let branch = ref_name.strip_prefix("refs/heads/").unwrap_or(&ref_name);
 
@@ -1013,6 +1050,15 @@ pub(super) async fn github_webhook(
             continue;
         }
 
+        if let Ok(Some(project)) = state.db.get_project_by_id(&link.project_id).await {
+            let watch_paths =
+                serde_json::from_str::<Vec<String>>(&project.watch_paths).unwrap_or_default();
+            let patterns = effective_watch_paths(&project.root_directory, &watch_paths);
+            if !should_redeploy(&patterns, changed_files.as_deref()) {
+                continue;
+            }
+        }
+
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map(|duration| duration.as_secs())
@@ -1135,7 +1181,9 @@ async fn sync_repositories_for_installation(
     Ok(())
 }
 
-async fn fetch_user_installations(token: &str) -> Result<Vec<InstallationItem>, (StatusCode, String)> {
+async fn fetch_user_installations(
+    token: &str,
+) -> Result<Vec<InstallationItem>, (StatusCode, String)> {
     let client = reqwest::Client::new();
     let mut page = 1_usize;
     let mut installations = Vec::new();
@@ -1,159 +1,101 @@
-use std::path::PathBuf;
+use std::path::Path;
 
-use anyhow::{Context, Result};
+use axum::body::Bytes;
 use axum::extract::Path as AxumPath;
 use axum::extract::State;
 use axum::http::StatusCode;
 use axum::Json;
 
-use crate::deployment::build::{BuildSettings, BuildSystem};
-use crate::deployment::git::Git;
-use crate::deployment::nginx::{NginxGenerator, NginxTlsMode};
-use crate::deployment::systemd::SystemdGenerator;
+use crate::deployment::firewall::Protocol;
+use crate::deployment::lifecycle::{self, ProjectAction, STOPPED_STATE_PATH};
+use crate::deployment::pipeline::{self, DeploymentLog, DeploymentSpec};
 use crate::deployment::teardown::Teardown;
-use crate::deployment::tls::TlsProvisioner;
 use crate::system::PrivilegeWrapper;
 
 use super::api_types::{
-    InternalProjectResponse, PortAvailabilityRequest, PortAvailabilityResponse,
-    WorkerCreateProjectRequest,
+    InternalDeleteProjectRequest, InternalDeploymentResponse, InternalProjectResponse,
+    PortAvailabilityRequest, PortAvailabilityResponse, WorkerCreateProjectRequest,
 };
 use super::OrchestratorState;
 
-fn repo_paths(project_id: &str) -> (PathBuf, PathBuf) {
-    let repo_dir = PathBuf::from(format!("/opt/nanoscale/tmp/{project_id}/source"));
-    let parent_dir = repo_dir
-        .parent()
-        .map_or_else(|| PathBuf::from("/opt/nanoscale/tmp"), PathBuf::from);
-    (repo_dir, parent_dir)
-}
-
 #[allow(clippy::too_many_lines)]
 pub(super) async fn internal_projects(
     State(state): State<OrchestratorState>,
     Json(payload): Json<WorkerCreateProjectRequest>,
-) -> (StatusCode, Json<InternalProjectResponse>) {
-    let project_id = payload.project_id;
-    let repo_url = payload.repo_url;
-    let branch = payload.branch;
-    let build_command = payload.build_command;
-    let install_command = payload.install_command;
-    let run_command = payload.run_command;
-    let output_directory = payload.output_directory;
+) -> (StatusCode, Json<InternalDeploymentResponse>) {
+    let project_id = payload.project_id.clone();
     let port = payload.port;
-    let domain = payload.domain;
-    let tls_email = payload.tls_email;
-    let _env_var_pairs = payload
-        .env_vars
-        .into_iter()
-        .map(|env_var| (env_var.key, env_var.value))
-        .collect::<Vec<(String, String)>>();
-
-    let (repo_dir, parent_dir) = repo_paths(&project_id);
-
-    let repo_url_for_clone = repo_url.clone();
-    let branch_for_checkout = branch.clone();
-    let repo_dir_for_clone = repo_dir.clone();
-    let project_id_for_build = project_id.clone();
-    let build_command_for_run = build_command.clone();
-    let install_command_for_run = install_command.clone();
-    let run_command_for_systemd = run_command.clone();
-    let output_directory_for_run = output_directory.clone();
-
-    let clone_result = tokio::task::spawn_blocking(move || -> Result<String> {
-        Git::validate_repo_url(&repo_url_for_clone).context("repo URL validation failed")?;
-        Git::validate_branch(&branch_for_checkout).context("branch validation failed")?;
-
-        std::fs::create_dir_all(&parent_dir).context("failed to create repo parent directory")?;
-
-        if repo_dir_for_clone.exists() {
-            std::fs::remove_dir_all(&repo_dir_for_clone)
-                .context("failed to clean existing repo directory")?;
-        }
-
-        Git::clone(&repo_url_for_clone, &repo_dir_for_clone).context("git clone step failed")?;
-        Git::checkout(&repo_dir_for_clone, &branch_for_checkout)
-            .context("git checkout step failed")?;
-
-        let privilege_wrapper = PrivilegeWrapper::new();
-        let build_settings = BuildSettings {
-            build_command: build_command_for_run,
-            output_directory: output_directory_for_run,
-            install_command: install_command_for_run,
-        };
-
-        let build_output = BuildSystem::execute(
-            &project_id_for_build,
-            &repo_dir_for_clone,
-            &build_settings,
-            &privilege_wrapper,
-        )
-        .context("build pipeline failed")?;
-
-        SystemdGenerator::generate_and_install(
-            &project_id_for_build,
-            &build_output.source_dir,
-            &build_output.runtime,
-            &run_command_for_systemd,
-            port,
-            &privilege_wrapper,
-        )
-        .context("systemd generation failed")?;
-
-        NginxGenerator::generate_and_install(
-            &project_id_for_build,
-            port,
-            domain.as_deref(),
-            NginxTlsMode::Disabled,
-            &privilege_wrapper,
-        )
-        .context("nginx generation failed")?;
-
-        let tls_summary = match (domain.as_deref(), tls_email.as_deref()) {
-            (Some(domain), Some(email)) => {
-                match TlsProvisioner::ensure_certificate(domain, email, &privilege_wrapper) {
-                    Ok(()) => {
-                        NginxGenerator::generate_and_install(
-                            &project_id_for_build,
-                            port,
-                            Some(domain),
-                            NginxTlsMode::Enabled { domain },
-                            &privilege_wrapper,
-                        )
-                        .context("nginx TLS generation failed")?;
-                        "TLS enabled".to_string()
-                    }
-                    Err(error) => {
-                        eprintln!("TLS provisioning failed for {domain}: {error:#}");
-                        format!("TLS provisioning failed: {error}")
-                    }
-                }
-            }
-            (Some(_), None) => "TLS skipped: NANOSCALE_TLS_EMAIL not configured".to_string(),
-            _ => "TLS skipped: no domain assigned".to_string(),
-        };
+    // nginx forwards raw TCP/UDP straight to the app, which cannot wake it.
+    let scale_to_zero = payload.replica.scales_to_zero() && payload.stream_ports.is_empty();
+    let runs_app = payload.replica.runs_app();
+    let stopped = payload.stopped;
+    let spec = DeploymentSpec {
+        project_id: payload.project_id,
+        repo_url: payload.repo_url,
+        branch: payload.branch,
+        build_command: payload.build_command,
+        install_command: payload.install_command,
+        run_command: payload.run_command,
+        output_directory: payload.output_directory,
+        port,
+        backend_port: payload.backend_port,
+        domain: payload.domain,
+        custom_domains: payload.custom_domains,
+        tls_email: payload.tls_email,
+        base_domain: payload.base_domain,
+        acme: state.certificates.acme_settings().clone(),
+        env_vars: payload
+            .env_vars
+            .into_iter()
+            .map(|env_var| (env_var.key, env_var.value))
+            .collect(),
+        runtime: payload.runtime,
+        resource_limits: payload.resource_limits,
+        routing: payload.routing,
+        access: payload.access,
+        maintenance: payload.maintenance,
+        replica: payload.replica,
+        network: payload.network,
+        stream_ports: payload.stream_ports,
+        jobs: payload.jobs,
+        processes: payload.processes,
+        primary: payload.primary,
+        volumes: payload.volumes,
+        release_command: payload.release_command,
+        release_timeout_seconds: None,
+        runs_release: payload.runs_release,
+        stopped,
+        root_directory: payload.root_directory,
+    };
 
-        Ok(tls_summary)
+    let clone_result = tokio::task::spawn_blocking(move || {
+        let mut log = DeploymentLog::default();
+        let result = pipeline::run(spec, &mut log);
+        (result, log.into_lines())
     })
     .await;
 
-    let (git_message, tls_message) = match clone_result {
-        Ok(Ok(tls_message)) => ("Source cloned and branch checked out.", tls_message),
-        Ok(Err(error)) => {
+    let (outcome, log) = match clone_result {
+        Ok((Ok(outcome), log)) => (outcome, log),
+        Ok((Err(error), log)) => {
             return (
                 StatusCode::BAD_REQUEST,
-                Json(InternalProjectResponse {
+                Json(InternalDeploymentResponse {
                     status: "error",
                     message: format!("Deployment pipeline failed: {error:#}"),
+                    log,
+                    commit_sha: None,
                 }),
             );
         }
         Err(error) => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(InternalProjectResponse {
+                Json(InternalDeploymentResponse {
                     status: "error",
                     message: format!("Git task failed: {error:#}"),
+                    log: Vec::new(),
+                    commit_sha: None,
                 }),
             );
         }
@@ -163,20 +105,29 @@ pub(super) async fn internal_projects(
         let mut monitored_projects = state.monitored_projects.write().await;
         monitored_projects
             .retain(|project| project.service_name != format!("nanoscale-{project_id}.service"));
-        monitored_projects.push(crate::deployment::inactivity_monitor::MonitoredProject {
-            service_name: format!("nanoscale-{project_id}.service"),
-            port,
-            scale_to_zero: true,
-        });
+        if runs_app {
+            monitored_projects.push(crate::deployment::inactivity_monitor::MonitoredProject {
+                service_name: format!("nanoscale-{project_id}.service"),
+                port,
+                scale_to_zero,
+                stopped,
+            });
+        }
+    }
+
+    for certificate in &outcome.certificates {
+        state
+            .certificates
+            .record_attempt(&certificate.domains, certificate.error.as_deref());
     }
 
     (
         StatusCode::ACCEPTED,
-        Json(InternalProjectResponse {
+        Json(InternalDeploymentResponse {
             status: "accepted",
-            message: format!(
-                "{git_message} Build pipeline, systemd generation, and nginx configuration completed. {tls_message}.",
-            ),
+            message: outcome.summary(),
+            log,
+            commit_sha: outcome.commit_sha,
         }),
     )
 }
@@ -184,8 +135,11 @@ pub(super) async fn internal_projects(
 pub(super) async fn internal_port_check(
     Json(payload): Json<PortAvailabilityRequest>,
 ) -> (StatusCode, Json<PortAvailabilityResponse>) {
-    let bind_result = tokio::net::TcpListener::bind(("127.0.0.1", payload.port)).await;
-    let available = bind_result.is_ok();
+    let address = ("127.0.0.1", payload.port);
+    let available = match payload.protocol {
+        Protocol::Tcp => tokio::net::TcpListener::bind(address).await.is_ok(),
+        Protocol::Udp => tokio::net::UdpSocket::bind(address).await.is_ok(),
+    };
 
     (StatusCode::OK, Json(PortAvailabilityResponse { available }))
 }
@@ -193,11 +147,33 @@ pub(super) async fn internal_port_check(
 pub(super) async fn internal_delete_project(
     State(state): State<OrchestratorState>,
     AxumPath(project_id): AxumPath<String>,
+    body: Bytes,
 ) -> (StatusCode, Json<InternalProjectResponse>) {
+    // Older orchestrators send no body; their deletes keep the volumes.
+    let request = if body.is_empty() {
+        InternalDeleteProjectRequest::default()
+    } else {
+        match serde_json::from_slice::<InternalDeleteProjectRequest>(&body) {
+            Ok(request) => request,
+            Err(error) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(InternalProjectResponse {
+                        status: "error",
+                        message: format!("Invalid delete request: {error}"),
+                    }),
+                )
+            }
+        }
+    };
     let project_id_for_cleanup = project_id.clone();
     let delete_result = tokio::task::spawn_blocking(move || {
         let privilege_wrapper = PrivilegeWrapper::new();
-        Teardown::delete_project(&project_id_for_cleanup, &privilege_wrapper)
+        Teardown::delete_project(
+            &project_id_for_cleanup,
+            request.delete_volumes,
+            &privilege_wrapper,
+        )
     })
     .await;
 
@@ -233,18 +209,74 @@ pub(super) async fn internal_delete_project(
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn repo_paths_build_expected_paths() {
-        let (repo_dir, parent_dir) = repo_paths("p1");
-        assert!(repo_dir
-            .to_string_lossy()
-            .ends_with("/opt/nanoscale/tmp/p1/source"));
-        assert!(parent_dir
-            .to_string_lossy()
-            .ends_with("/opt/nanoscale/tmp/p1"));
+pub(super) async fn internal_start_project(
+    State(state): State<OrchestratorState>,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    control_project(&state, project_id, ProjectAction::Start).await
+}
+
+pub(super) async fn internal_stop_project(
+    State(state): State<OrchestratorState>,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    control_project(&state, project_id, ProjectAction::Stop).await
+}
+
+pub(super) async fn internal_restart_project(
+    State(state): State<OrchestratorState>,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    control_project(&state, project_id, ProjectAction::Restart).await
+}
+
+/// Applies `action` to the project's units here and tells the inactivity monitor whether the
+/// project is stopped.
+async fn control_project(
+    state: &OrchestratorState,
+    project_id: String,
+    action: ProjectAction,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let service_name = format!("nanoscale-{project_id}.service");
+    let scale_to_zero = state
+        .monitored_projects
+        .read()
+        .await
+        .iter()
+        .any(|project| project.service_name == service_name && project.scale_to_zero);
+    let project_id_for_units = project_id.clone();
+    tokio::task::spawn_blocking(move || {
+        lifecycle::apply(
+            Path::new(STOPPED_STATE_PATH),
+            &project_id_for_units,
+            action,
+            scale_to_zero,
+            &PrivilegeWrapper::new(),
+        )
+    })
+    .await
+    .map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Project {} task failed: {error}", action.as_str()),
+        )
+    })?
+    .map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Unable to {} the project: {error:#}", action.as_str()),
+        )
+    })?;
+
+    if action != ProjectAction::Restart {
+        let mut monitored_projects = state.monitored_projects.write().await;
+        if let Some(project) = monitored_projects
+            .iter_mut()
+            .find(|project| project.service_name == service_name)
+        {
+            project.stopped = action == ProjectAction::Stop;
+        }
     }
+
+    Ok(StatusCode::NO_CONTENT)
 }
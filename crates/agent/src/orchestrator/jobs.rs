@@ -0,0 +1,195 @@
+use axum::extract::Path as AxumPath;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use tower_sessions::Session;
+
+use crate::db::ProjectDetailsRecord;
+use crate::deployment::jobs::{self, CronJob, JobStatus};
+use crate::system::PrivilegeWrapper;
+
+use super::auth::require_authenticated;
+use super::domains::load_project;
+use super::projects::redeploy_project_by_id;
+use super::worker_client::{call_worker_job_status, call_worker_run_job};
+use super::OrchestratorState;
+
+pub(super) async fn get_project_jobs(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<Json<Vec<CronJob>>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let project = load_project(&state, &project_id).await?;
+    Ok(Json(stored_jobs(&project)?))
+}
+
+/// Replaces the project's scheduled jobs and redeploys it so the timers follow. Jobs from the
+/// repository manifest are kept unless a job here has the same name.
+pub(super) async fn update_project_jobs(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+    Json(payload): Json<Vec<CronJob>>,
+) -> Result<Json<Vec<CronJob>>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    load_project(&state, &project_id).await?;
+    validate_jobs(&payload)?;
+    let jobs = serde_json::to_string(&payload).map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to serialize cron jobs: {error}"),
+        )
+    })?;
+    state
+        .db
+        .set_project_jobs(&project_id, &jobs)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to update cron jobs: {error}"),
+            )
+        })?;
+
+    redeploy_project_by_id(&state, &project_id).await?;
+    Ok(Json(payload))
+}
+
+/// Lists the jobs installed for the project, manifest jobs included, with their recent runs.
+pub(super) async fn list_project_job_runs(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<Json<Vec<JobStatus>>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let project = load_project(&state, &project_id).await?;
+    job_status(&state, &project)
+        .await
+        .map(Json)
+        .map_err(|error| {
+            (
+                StatusCode::BAD_GATEWAY,
+                format!("Unable to read cron job runs: {error:#}"),
+            )
+        })
+}
+
+/// Starts a run of one of the project's jobs now; the run shows up in the job's runs once it
+/// finishes.
+pub(super) async fn run_project_job(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath((project_id, name)): AxumPath<(String, String)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let project = load_project(&state, &project_id).await?;
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || character == '-')
+    {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Cron job {name} was not found"),
+        ));
+    }
+
+    let started = start_job(&state, &project, &name).await.map_err(|error| {
+        (
+            StatusCode::BAD_GATEWAY,
+            format!("Unable to start cron job {name}: {error:#}"),
+        )
+    })?;
+    if !started {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Cron job {name} was not found"),
+        ));
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Jobs run on the project's own server only, whatever its replicas.
+async fn job_status(
+    state: &OrchestratorState,
+    project: &ProjectDetailsRecord,
+) -> anyhow::Result<Vec<JobStatus>> {
+    if project.server_id == state.local_server_id {
+        let project_id = project.id.clone();
+        return tokio::task::spawn_blocking(move || {
+            jobs::status(&project_id, &PrivilegeWrapper::new())
+        })
+        .await?;
+    }
+
+    let connection = state
+        .db
+        .get_server_connection_info(&project.server_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("project server was not found"))?;
+    call_worker_job_status(
+        &connection.id,
+        &connection.ip_address,
+        &connection.secret_key,
+        &project.id,
+    )
+    .await
+}
+
+async fn start_job(
+    state: &OrchestratorState,
+    project: &ProjectDetailsRecord,
+    name: &str,
+) -> anyhow::Result<bool> {
+    if project.server_id == state.local_server_id {
+        let project_id = project.id.clone();
+        let name = name.to_string();
+        return tokio::task::spawn_blocking(move || {
+            jobs::trigger(&project_id, &name, &PrivilegeWrapper::new())
+        })
+        .await?;
+    }
+
+    let connection = state
+        .db
+        .get_server_connection_info(&project.server_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("project server was not found"))?;
+    call_worker_run_job(
+        &connection.id,
+        &connection.ip_address,
+        &connection.secret_key,
+        &project.id,
+        name,
+    )
+    .await
+}
+
+pub(super) fn validate_jobs(jobs: &[CronJob]) -> Result<(), (StatusCode, String)> {
+    jobs::validate(jobs).map_err(|error| (StatusCode::BAD_REQUEST, format!("{error}")))
+}
+
+pub(super) fn stored_jobs(
+    project: &ProjectDetailsRecord,
+) -> Result<Vec<CronJob>, (StatusCode, String)> {
+    serde_json::from_str(&project.jobs).map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to deserialize cron jobs: {error}"),
+        )
+    })
+}
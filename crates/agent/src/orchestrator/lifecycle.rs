@@ -0,0 +1,108 @@
+use axum::extract::Path as AxumPath;
+use axum::extract::State;
+use axum::http::StatusCode;
+use tower_sessions::Session;
+
+use crate::deployment::lifecycle::ProjectAction;
+
+use super::auth::require_authenticated;
+use super::domains::load_project;
+use super::replicas::{control_deployment, load_replica_servers, placement};
+use super::OrchestratorState;
+
+pub(super) async fn start_project(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    control_project(&state, &session, &project_id, ProjectAction::Start).await
+}
+
+pub(super) async fn stop_project(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    control_project(&state, &session, &project_id, ProjectAction::Stop).await
+}
+
+pub(super) async fn restart_project(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    control_project(&state, &session, &project_id, ProjectAction::Restart).await
+}
+
+/// Applies `action` on every server running the project, without a rebuild. A stop is
+/// recorded before the servers are reached, so a redeploy keeps the project down even when
+/// one of them could not be; a start is recorded once every server has started it.
+async fn control_project(
+    state: &OrchestratorState,
+    session: &Session,
+    project_id: &str,
+    action: ProjectAction,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_authenticated(session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let project = load_project(state, project_id).await?;
+    if action == ProjectAction::Restart && project.stopped {
+        return Err((
+            StatusCode::CONFLICT,
+            "Project is stopped; start it instead".to_string(),
+        ));
+    }
+
+    let connection = state
+        .db
+        .get_server_connection_info(&project.server_id)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to load server connection info: {error}"),
+            )
+        })?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "Project host server was not found".to_string(),
+        ))?;
+    let members = load_replica_servers(state, project_id).await?;
+    let placement = placement(state, connection, members).await?;
+
+    if action == ProjectAction::Stop {
+        set_stopped(state, project_id, true).await?;
+    }
+    control_deployment(state, &placement, project_id, action)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::BAD_GATEWAY,
+                format!("Unable to {} the project: {error:#}", action.as_str()),
+            )
+        })?;
+    if action == ProjectAction::Start {
+        set_stopped(state, project_id, false).await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn set_stopped(
+    state: &OrchestratorState,
+    project_id: &str,
+    stopped: bool,
+) -> Result<(), (StatusCode, String)> {
+    state
+        .db
+        .set_project_stopped(project_id, stopped)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to update the project state: {error}"),
+            )
+        })
+}
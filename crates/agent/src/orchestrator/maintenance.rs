@@ -0,0 +1,116 @@
+use std::path::Path;
+
+use axum::extract::Path as AxumPath;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use tower_sessions::Session;
+
+use crate::db::ProjectDetailsRecord;
+use crate::deployment::pages::{self, MaintenanceMode, PAGES_PATH};
+
+use super::auth::require_authenticated;
+use super::domains::load_project;
+use super::replicas::routing_server_id;
+use super::worker_client::call_worker_set_maintenance;
+use super::OrchestratorState;
+
+pub(super) async fn get_project_maintenance(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<Json<MaintenanceMode>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let project = load_project(&state, &project_id).await?;
+    Ok(Json(stored_maintenance(&project)?))
+}
+
+/// Turns maintenance on or off. Only the status page on the host changes, so the app keeps
+/// running untouched and nginx is not reloaded.
+pub(super) async fn update_project_maintenance(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+    Json(payload): Json<MaintenanceMode>,
+) -> Result<Json<MaintenanceMode>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let project = load_project(&state, &project_id).await?;
+    payload
+        .validate()
+        .map_err(|error| (StatusCode::BAD_REQUEST, format!("{error}")))?;
+    let maintenance = serde_json::to_string(&payload).map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to serialize maintenance mode: {error}"),
+        )
+    })?;
+
+    apply_maintenance(&state, &project, &payload)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::BAD_GATEWAY,
+                format!("Unable to update the maintenance page: {error:#}"),
+            )
+        })?;
+    state
+        .db
+        .set_project_maintenance(&project_id, &maintenance)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to update maintenance mode: {error}"),
+            )
+        })?;
+
+    Ok(Json(payload))
+}
+
+/// Writes or removes the maintenance page on the host serving the project's domains.
+async fn apply_maintenance(
+    state: &OrchestratorState,
+    project: &ProjectDetailsRecord,
+    maintenance: &MaintenanceMode,
+) -> anyhow::Result<()> {
+    let server_id = routing_server_id(state, &project.server_id);
+    if server_id == state.local_server_id {
+        let project_id = project.id.clone();
+        let maintenance = maintenance.clone();
+        return tokio::task::spawn_blocking(move || {
+            pages::set_maintenance(Path::new(PAGES_PATH), &project_id, &maintenance)
+        })
+        .await?;
+    }
+
+    let connection = state
+        .db
+        .get_server_connection_info(server_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("project host server was not found"))?;
+    call_worker_set_maintenance(
+        &connection.id,
+        &connection.ip_address,
+        &connection.secret_key,
+        &project.id,
+        maintenance,
+    )
+    .await
+}
+
+pub(super) fn stored_maintenance(
+    project: &ProjectDetailsRecord,
+) -> Result<MaintenanceMode, (StatusCode, String)> {
+    serde_json::from_str(&project.maintenance).map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to deserialize maintenance mode: {error}"),
+        )
+    })
+}
@@ -0,0 +1,235 @@
+use std::net::IpAddr;
+
+use axum::extract::Path as AxumPath;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use tower_sessions::Session;
+
+use crate::db::ProjectDetailsRecord;
+use crate::deployment::private_network::{
+    validate_service_name, PrivateNetwork, PrivateService, INTERNAL_DOMAIN,
+};
+
+use super::api_types::{ProjectNetworkResponse, UpdateProjectNetworkRequest};
+use super::auth::require_authenticated;
+use super::domains::load_project;
+use super::projects::redeploy_project_by_id;
+use super::OrchestratorState;
+
+pub(super) async fn get_project_network(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<Json<ProjectNetworkResponse>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    Ok(Json(project_network_response(&state, &project_id).await?))
+}
+
+/// Names the project's service and replaces the projects allowed to call it, then redeploys
+/// the project and every caller gaining or losing access so hosts entries, env vars and
+/// firewall rules match.
+pub(super) async fn update_project_network(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+    Json(payload): Json<UpdateProjectNetworkRequest>,
+) -> Result<Json<ProjectNetworkResponse>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    load_project(&state, &project_id).await?;
+    let internal_name = payload
+        .internal_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+    if let Some(name) = internal_name {
+        validate_service_name(name)
+            .map_err(|error| (StatusCode::BAD_REQUEST, format!("{error}")))?;
+        let in_use = state
+            .db
+            .is_internal_name_in_use(name, &project_id)
+            .await
+            .map_err(|error| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Unable to check service name: {error}"),
+                )
+            })?;
+        if in_use {
+            return Err((
+                StatusCode::CONFLICT,
+                format!("Service name {name} is already used by another project"),
+            ));
+        }
+    }
+    if internal_name.is_none() && !payload.allowed_callers.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "A service name is required before allowing callers".to_string(),
+        ));
+    }
+
+    let mut callers = payload.allowed_callers;
+    callers.sort();
+    callers.dedup();
+    for caller in &callers {
+        if *caller == project_id {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "A project cannot be its own caller".to_string(),
+            ));
+        }
+        load_project(&state, caller)
+            .await
+            .map_err(|(status, message)| {
+                if status == StatusCode::NOT_FOUND {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!("Caller project {caller} was not found"),
+                    )
+                } else {
+                    (status, message)
+                }
+            })?;
+    }
+
+    let previous_callers = state
+        .db
+        .list_project_service_callers(&project_id)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to load allowed callers: {error}"),
+            )
+        })?;
+    state
+        .db
+        .set_project_internal_name(&project_id, internal_name)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to update service name: {error}"),
+            )
+        })?;
+    state
+        .db
+        .set_project_service_callers(&project_id, &callers)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to update allowed callers: {error}"),
+            )
+        })?;
+
+    redeploy_project_by_id(&state, &project_id).await?;
+    let mut affected = previous_callers;
+    affected.extend(callers);
+    affected.sort();
+    affected.dedup();
+    for caller in &affected {
+        redeploy_project_by_id(&state, caller).await?;
+    }
+
+    Ok(Json(project_network_response(&state, &project_id).await?))
+}
+
+/// The private network a project is deployed with: the services it may call, and the servers
+/// allowed to call it.
+pub(super) async fn project_network(
+    state: &OrchestratorState,
+    project: &ProjectDetailsRecord,
+) -> Result<PrivateNetwork, (StatusCode, String)> {
+    let services = state
+        .db
+        .list_project_service_targets(&project.id)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to load private services: {error}"),
+            )
+        })?
+        .into_iter()
+        .map(|target| {
+            Ok(PrivateService {
+                ip: parse_server_ip(&target.ip_address)?,
+                port: u16::try_from(target.port).map_err(|_| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Stored port is out of range: {}", target.port),
+                    )
+                })?,
+                name: target.internal_name,
+            })
+        })
+        .collect::<Result<Vec<_>, (StatusCode, String)>>()?;
+
+    let allowed_sources = if project.internal_name.is_some() {
+        state
+            .db
+            .list_project_caller_server_ips(&project.id)
+            .await
+            .map_err(|error| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Unable to load caller servers: {error}"),
+                )
+            })?
+            .iter()
+            .map(|ip_address| parse_server_ip(ip_address))
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        Vec::new()
+    };
+
+    Ok(PrivateNetwork {
+        services,
+        reachable: !allowed_sources.is_empty(),
+        allowed_sources,
+    })
+}
+
+async fn project_network_response(
+    state: &OrchestratorState,
+    project_id: &str,
+) -> Result<ProjectNetworkResponse, (StatusCode, String)> {
+    let project = load_project(state, project_id).await?;
+    let allowed_callers = state
+        .db
+        .list_project_service_callers(project_id)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to load allowed callers: {error}"),
+            )
+        })?;
+
+    Ok(ProjectNetworkResponse {
+        hostname: project
+            .internal_name
+            .as_ref()
+            .map(|name| format!("{name}.{INTERNAL_DOMAIN}")),
+        internal_name: project.internal_name,
+        port: project.port,
+        allowed_callers,
+    })
+}
+
+fn parse_server_ip(ip_address: &str) -> Result<IpAddr, (StatusCode, String)> {
+    ip_address.parse().map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Server address is not an IP: {ip_address:?}"),
+        )
+    })
+}
@@ -0,0 +1,227 @@
+use axum::http::StatusCode;
+
+use crate::db::{DbClient, PortKind, ProjectDetailsRecord, ServerConnectionInfo};
+use crate::deployment::firewall::Protocol;
+
+use super::replicas::worker_host;
+use super::worker_client::call_worker_port_available;
+use super::OrchestratorState;
+
+/// Pool ports tried before giving up when each one turns out to be bound on a server.
+const MAX_PORT_PROBES: usize = 100;
+
+/// The ports a project's app is reached on, the same on every server running it.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct ProjectPorts {
+    /// The socket systemd starts the app from; the ingress and other projects connect here.
+    pub(super) port: u16,
+    /// The app's own listener behind the socket proxy.
+    pub(super) backend_port: u16,
+}
+
+/// Reserves a new project's ports on every server that will run its app: `requested`, or the
+/// lowest free port of the front pool, and a backend port. Pool ports some other process has
+/// already bound on one of the servers are skipped. Nothing stays reserved when allocation
+/// fails.
+pub(super) async fn allocate_project_ports(
+    state: &OrchestratorState,
+    project_id: &str,
+    servers: &[ServerConnectionInfo],
+    requested: Option<u16>,
+) -> Result<ProjectPorts, (StatusCode, String)> {
+    let port = match requested {
+        Some(port) => reserve_requested_port(state, project_id, servers, port).await?,
+        None => reserve_pool_port(state, project_id, servers, PortKind::Front).await?,
+    };
+    match reserve_pool_port(state, project_id, servers, PortKind::Backend).await {
+        Ok(backend_port) => Ok(ProjectPorts { port, backend_port }),
+        Err(error) => {
+            let _ = state.db.release_project_ports(project_id).await;
+            Err(error)
+        }
+    }
+}
+
+/// The stored ports of a project running its app on `servers`. Projects migrated without a
+/// backend port get one reserved and stored.
+pub(super) async fn project_ports(
+    state: &OrchestratorState,
+    project: &ProjectDetailsRecord,
+    servers: &[ServerConnectionInfo],
+) -> Result<ProjectPorts, (StatusCode, String)> {
+    let stored = |port: i64| {
+        u16::try_from(port).map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Stored port is out of range: {port}"),
+            )
+        })
+    };
+    let port = stored(project.port)?;
+    let backend_port = if let Some(backend_port) = project.backend_port {
+        stored(backend_port)?
+    } else {
+        let backend_port =
+            reserve_pool_port(state, &project.id, servers, PortKind::Backend).await?;
+        state
+            .db
+            .set_project_backend_port(&project.id, i64::from(backend_port))
+            .await
+            .map_err(|error| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Failed to store backend port: {error}"),
+                )
+            })?;
+        backend_port
+    };
+
+    Ok(ProjectPorts { port, backend_port })
+}
+
+async fn reserve_requested_port(
+    state: &OrchestratorState,
+    project_id: &str,
+    servers: &[ServerConnectionInfo],
+    port: u16,
+) -> Result<u16, (StatusCode, String)> {
+    if i64::from(port) < DbClient::min_project_port() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Requested port must be {} or higher",
+                DbClient::min_project_port()
+            ),
+        ));
+    }
+
+    let reserved = state
+        .db
+        .reserve_port(
+            project_id,
+            &server_ids(servers),
+            PortKind::Front,
+            i64::from(port),
+        )
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to reserve requested port: {error}"),
+            )
+        })?;
+    if !reserved {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Requested port {port} is already in use"),
+        ));
+    }
+
+    match bound_on(state, servers, port).await {
+        Ok(None) => Ok(port),
+        Ok(Some(server_id)) => {
+            let _ = state.db.release_port(project_id, i64::from(port)).await;
+            Err((
+                StatusCode::CONFLICT,
+                format!("Requested port {port} is already bound on server {server_id}"),
+            ))
+        }
+        Err(error) => {
+            let _ = state.db.release_port(project_id, i64::from(port)).await;
+            Err(error)
+        }
+    }
+}
+
+/// Reserves pool ports until one is free on every server. The ports found bound are held
+/// while searching so they are not handed out again, then released.
+async fn reserve_pool_port(
+    state: &OrchestratorState,
+    project_id: &str,
+    servers: &[ServerConnectionInfo],
+    kind: PortKind,
+) -> Result<u16, (StatusCode, String)> {
+    let server_ids = server_ids(servers);
+    let mut bound = Vec::new();
+    let mut result = Err((
+        StatusCode::CONFLICT,
+        format!(
+            "No free {} port found on the project's servers",
+            kind.as_str()
+        ),
+    ));
+    for _ in 0..MAX_PORT_PROBES {
+        let reserved = match state
+            .db
+            .reserve_pooled_port(project_id, &server_ids, kind)
+            .await
+        {
+            Ok(Some(port)) => port,
+            Ok(None) => break,
+            Err(error) => {
+                result = Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Unable to allocate {} port: {error}", kind.as_str()),
+                ));
+                break;
+            }
+        };
+        let Ok(port) = u16::try_from(reserved) else {
+            bound.push(reserved);
+            continue;
+        };
+        match bound_on(state, servers, port).await {
+            Ok(None) => {
+                result = Ok(port);
+                break;
+            }
+            Ok(Some(_)) => bound.push(reserved),
+            Err(error) => {
+                bound.push(reserved);
+                result = Err(error);
+                break;
+            }
+        }
+    }
+    for port in bound {
+        let _ = state.db.release_port(project_id, port).await;
+    }
+
+    result
+}
+
+/// The first of `servers` where `port` is already bound, if any.
+async fn bound_on<'a>(
+    state: &OrchestratorState,
+    servers: &'a [ServerConnectionInfo],
+    port: u16,
+) -> Result<Option<&'a str>, (StatusCode, String)> {
+    for server in servers {
+        let available = call_worker_port_available(
+            &server.id,
+            worker_host(state, server),
+            &server.secret_key,
+            port,
+            Protocol::Tcp,
+        )
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Unable to validate port {port} on server {}: {error}",
+                    server.id
+                ),
+            )
+        })?;
+        if !available {
+            return Ok(Some(&server.id));
+        }
+    }
+
+    Ok(None)
+}
+
+fn server_ids(servers: &[ServerConnectionInfo]) -> Vec<String> {
+    servers.iter().map(|server| server.id.clone()).collect()
+}
@@ -0,0 +1,76 @@
+use axum::extract::Path as AxumPath;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use tower_sessions::Session;
+
+use crate::db::ProjectDetailsRecord;
+use crate::deployment::processes::{self, ProcessType};
+
+use super::auth::require_authenticated;
+use super::domains::load_project;
+use super::projects::redeploy_project_by_id;
+use super::OrchestratorState;
+
+pub(super) async fn get_project_processes(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<Json<Vec<ProcessType>>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let project = load_project(&state, &project_id).await?;
+    Ok(Json(stored_processes(&project)?))
+}
+
+/// Replaces the project's process types and redeploys it so the units and instance counts
+/// follow. Procfile processes are kept unless one here has the same name.
+pub(super) async fn update_project_processes(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+    Json(payload): Json<Vec<ProcessType>>,
+) -> Result<Json<Vec<ProcessType>>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    load_project(&state, &project_id).await?;
+    validate_processes(&payload)?;
+    let processes = serde_json::to_string(&payload).map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to serialize process types: {error}"),
+        )
+    })?;
+    state
+        .db
+        .set_project_processes(&project_id, &processes)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to update process types: {error}"),
+            )
+        })?;
+
+    redeploy_project_by_id(&state, &project_id).await?;
+    Ok(Json(payload))
+}
+
+pub(super) fn validate_processes(processes: &[ProcessType]) -> Result<(), (StatusCode, String)> {
+    processes::validate(processes).map_err(|error| (StatusCode::BAD_REQUEST, format!("{error}")))
+}
+
+pub(super) fn stored_processes(
+    project: &ProjectDetailsRecord,
+) -> Result<Vec<ProcessType>, (StatusCode, String)> {
+    serde_json::from_str(&project.processes).map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to deserialize process types: {error}"),
+        )
+    })
+}
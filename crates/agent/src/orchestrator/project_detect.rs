@@ -0,0 +1,115 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use tower_sessions::Session;
+use uuid::Uuid;
+
+use crate::deployment::detect::{self, DetectedSettings};
+use crate::deployment::git::Git;
+use crate::deployment::pipeline::validate_root_directory;
+
+use super::api_types::DetectProjectRequest;
+use super::auth::current_user_id;
+use super::github::{authenticated_clone_url, resolve_github_source};
+use super::OrchestratorState;
+
+/// Proposes runtime and command defaults for a repository before the project is created,
+/// looking in its `root_directory` as a deploy would.
+pub(super) async fn detect_project(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    Json(payload): Json<DetectProjectRequest>,
+) -> Result<Json<DetectedSettings>, (StatusCode, String)> {
+    let user_id = current_user_id(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let (repo_url, branch) = if let Some(source) = payload.github_source.as_ref() {
+        let resolved = resolve_github_source(&state, &user_id, source).await?;
+        let clone_url = authenticated_clone_url(&state, &resolved).await?;
+        (clone_url, resolved.selected_branch)
+    } else {
+        (payload.repo_url, payload.branch)
+    };
+
+    if repo_url.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Repository URL is required".to_string(),
+        ));
+    }
+
+    validate_root_directory(&payload.root_directory)
+        .map_err(|error| (StatusCode::BAD_REQUEST, format!("{error}")))?;
+    let root_directory = payload.root_directory;
+
+    let detected = tokio::task::spawn_blocking(move || {
+        detect_remote_repo(&repo_url, &branch, &root_directory)
+    })
+    .await
+    .map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Detection task failed: {error}"),
+        )
+    })?
+    .map_err(|error| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Repository detection failed: {error:#}"),
+        )
+    })?;
+
+    detected.map(Json).ok_or((
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "No supported project type detected; enter the commands manually".to_string(),
+    ))
+}
+
+fn detect_remote_repo(
+    repo_url: &str,
+    branch: &str,
+    root_directory: &str,
+) -> Result<Option<DetectedSettings>> {
+    Git::validate_ref(branch).context("git ref validation failed")?;
+
+    let checkout_dir = PathBuf::from(format!("/opt/nanoscale/tmp/detect-{}", Uuid::new_v4()));
+    let detected = Git::fetch_ref(repo_url, branch, &checkout_dir)
+        .context("git fetch step failed")
+        .and_then(|()| detect_in(&checkout_dir, root_directory));
+
+    let _ = std::fs::remove_dir_all(&checkout_dir);
+    detected
+}
+
+fn detect_in(checkout_dir: &Path, root_directory: &str) -> Result<Option<DetectedSettings>> {
+    let root_directory = root_directory.trim().trim_end_matches('/');
+    let project_dir = checkout_dir.join(root_directory);
+    if !project_dir.is_dir() {
+        bail!("root directory not found in repository: {root_directory}");
+    }
+
+    Ok(detect::detect(&project_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_in_looks_in_the_root_directory() {
+        let tempdir = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir_all(tempdir.path().join("apps/web")).expect("mkdir");
+        std::fs::write(tempdir.path().join("apps/web/index.html"), "<html></html>").expect("write");
+
+        assert!(detect_in(tempdir.path(), "").expect("root").is_none());
+        let detected = detect_in(tempdir.path(), "apps/web/")
+            .expect("subdirectory")
+            .expect("detected");
+        assert_eq!(detected.output_directory, ".");
+        assert!(detect_in(tempdir.path(), "apps/api").is_err());
+    }
+}
@@ -1,6 +1,9 @@
-use crate::db::{ProjectDetailsRecord, ProjectListRecord};
+use crate::db::{DeploymentRecord, ProjectDetailsRecord, ProjectDomainRecord, ProjectListRecord};
+use crate::deployment::nginx::{DomainRedirect, RoutedDomain};
 
-use super::api_types::{ProjectDetailsResponse, ProjectListItem};
+use super::api_types::{
+    DeploymentItem, ProjectDetailsResponse, ProjectDomainItem, ProjectListItem,
+};
 
 pub(super) fn map_project_list_record(project: ProjectListRecord) -> ProjectListItem {
     ProjectListItem {
@@ -13,7 +16,7 @@ pub(super) fn map_project_list_record(project: ProjectListRecord) -> ProjectList
         domain: project.domain,
         source_provider: project.source_provider,
         source_repo_id: project.source_repo_id,
-        status: "deployed".to_string(),
+        status: project_status(project.stopped),
         created_at: project.created_at,
     }
 }
@@ -29,11 +32,68 @@ pub(super) fn map_project_details_record(project: ProjectDetailsRecord) -> Proje
         install_command: project.install_command,
         build_command: project.build_command,
         run_command: project.start_command,
-        status: "deployed".to_string(),
+        status: project_status(project.stopped),
         port: project.port,
         domain: project.domain,
         source_provider: project.source_provider,
         source_repo_id: project.source_repo_id,
+        runtime: project.runtime,
+        memory_limit_mb: project.memory_limit_mb,
+        cpu_quota_percent: project.cpu_quota_percent,
+        root_directory: project.root_directory,
+        watch_paths: serde_json::from_str(&project.watch_paths).unwrap_or_default(),
+        routing: serde_json::from_str(&project.routing).unwrap_or_default(),
+        maintenance: serde_json::from_str(&project.maintenance).unwrap_or_default(),
+        replicas: project.replicas,
+        stream_ports: serde_json::from_str(&project.stream_ports).unwrap_or_default(),
+        jobs: serde_json::from_str(&project.jobs).unwrap_or_default(),
+        processes: serde_json::from_str(&project.processes).unwrap_or_default(),
+        volumes: serde_json::from_str(&project.volumes).unwrap_or_default(),
+        release_command: project.release_command,
         created_at: project.created_at,
     }
 }
+
+fn project_status(stopped: bool) -> String {
+    if stopped { "stopped" } else { "deployed" }.to_string()
+}
+
+pub(super) fn map_project_domain_record(domain: ProjectDomainRecord) -> ProjectDomainItem {
+    ProjectDomainItem {
+        redirect: domain_redirect(&domain),
+        primary: domain.is_primary,
+        tls: None,
+        id: domain.id,
+        domain: domain.domain,
+        status: domain.status,
+        verification_error: domain.verification_error,
+        verified_at: domain.verified_at,
+        created_at: domain.created_at,
+    }
+}
+
+pub(super) fn map_routed_domain(domain: ProjectDomainRecord) -> RoutedDomain {
+    RoutedDomain {
+        redirect: domain_redirect(&domain),
+        primary: domain.is_primary,
+        domain: domain.domain,
+    }
+}
+
+fn domain_redirect(domain: &ProjectDomainRecord) -> Option<DomainRedirect> {
+    domain.redirect_status.map(|status| DomainRedirect {
+        status: u16::try_from(status).unwrap_or(301),
+        preserve_path: domain.redirect_preserve_path,
+    })
+}
+
+pub(super) fn map_deployment_record(deployment: DeploymentRecord) -> DeploymentItem {
+    DeploymentItem {
+        id: deployment.id,
+        git_ref: deployment.git_ref,
+        commit_sha: deployment.commit_sha,
+        status: deployment.status,
+        log: serde_json::from_str(&deployment.log).unwrap_or_default(),
+        created_at: deployment.created_at,
+    }
+}
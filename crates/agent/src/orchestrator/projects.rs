@@ -1,26 +1,47 @@
 use axum::extract::Path as AxumPath;
-use axum::extract::State;
+use axum::extract::{Query, State};
 use axum::http::StatusCode;
 use axum::Json;
 use tower_sessions::Session;
 use uuid::Uuid;
 
-use crate::db::{DbClient, NewProject};
+use crate::db::{NewProject, ProjectDetailsRecord};
+use crate::deployment::access::{AccessRules, BasicAuthUser};
+use crate::deployment::build::ProjectRuntime;
+use crate::deployment::git::Git;
+use crate::deployment::pipeline::validate_root_directory;
+use crate::deployment::private_network::PrivateNetwork;
+use crate::deployment::replicas::MAX_REPLICAS;
+use crate::deployment::routing::RoutingConfig;
+use crate::deployment::systemd::ResourceLimits;
 
 use super::api_types::{
-    CreateProjectRequest, CreateProjectResponse, ProjectDetailsResponse, ProjectEnvVar,
-    ProjectListItem,
+    CreateProjectRequest, CreateProjectResponse, DeleteProjectQuery, ProjectDetailsResponse,
+    ProjectEnvVar, ProjectListItem,
 };
 use super::auth::{current_user_id, require_authenticated};
+use super::deployments::record_deployment;
 use super::github::{
     authenticated_clone_url, deactivate_project_webhook, ensure_project_webhook,
     resolve_github_source,
 };
+use super::jobs::{stored_jobs, validate_jobs};
+use super::maintenance::stored_maintenance;
+use super::network::project_network;
+use super::ports::{allocate_project_ports, project_ports};
+use super::processes::{stored_processes, validate_processes};
 use super::project_domain::assigned_project_domain;
-use super::project_mapping::{map_project_details_record, map_project_list_record};
-use super::worker_client::{
-    call_worker_create_project, call_worker_delete_project, call_worker_port_available,
+use super::project_mapping::{
+    map_project_details_record, map_project_list_record, map_routed_domain,
 };
+use super::replicas::{
+    load_replica_servers, place_new_replicas, placement, remove_deployment, roll_out, SiteRoutes,
+};
+use super::streams::{
+    ensure_stream_ports_free, ensure_streams_allowed, stored_stream_ports, validate_stream_ports,
+};
+use super::volumes::{ensure_volumes_allowed, stored_volumes, validate_volumes};
+use super::watch_paths::validate_watch_paths;
 use super::OrchestratorState;
 
 pub(super) async fn redeploy_project(
@@ -36,6 +57,7 @@ pub(super) async fn redeploy_project(
     Ok(StatusCode::ACCEPTED)
 }
 
+#[allow(clippy::too_many_lines)]
 pub(super) async fn redeploy_project_by_id(
     state: &OrchestratorState,
     project_id: &str,
@@ -67,12 +89,6 @@ pub(super) async fn redeploy_project_by_id(
             "Project host server was not found".to_string(),
         ))?;
 
-    let worker_host = if connection.id == state.local_server_id {
-        "127.0.0.1"
-    } else {
-        &connection.ip_address
-    };
-
     let env_vars =
         serde_json::from_str::<Vec<ProjectEnvVar>>(&project.env_vars).map_err(|error| {
             (
@@ -81,12 +97,63 @@ pub(super) async fn redeploy_project_by_id(
             )
         })?;
 
-    let project_port: u16 = project.port.try_into().map_err(|_| {
+    let members = load_replica_servers(state, project_id).await?;
+    let app_servers = std::iter::once(connection.clone())
+        .chain(members.iter().cloned())
+        .collect::<Vec<_>>();
+    let ports = project_ports(state, &project, &app_servers).await?;
+
+    let runtime = ProjectRuntime::parse(&project.runtime)
+        .map_err(|error| (StatusCode::INTERNAL_SERVER_ERROR, format!("{error}")))?;
+    let resource_limits = stored_resource_limits(&project)?;
+    let custom_domains = state
+        .db
+        .list_verified_project_domains(project_id)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to load project domains: {error}"),
+            )
+        })?
+        .into_iter()
+        .map(map_routed_domain)
+        .collect::<Vec<_>>();
+    let watch_paths =
+        serde_json::from_str::<Vec<String>>(&project.watch_paths).map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to deserialize watch paths: {error}"),
+            )
+        })?;
+    let routing = serde_json::from_str::<RoutingConfig>(&project.routing).map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to deserialize routing config: {error}"),
+        )
+    })?;
+    let access = serde_json::from_str::<AccessRules>(&project.access_rules).map_err(|error| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Project port out of range: {}", project.port),
+            format!("Failed to deserialize access rules: {error}"),
         )
     })?;
+    let access_users = state
+        .db
+        .list_project_access_users(project_id)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to load basic auth users: {error}"),
+            )
+        })?
+        .into_iter()
+        .map(|user| BasicAuthUser {
+            username: user.username,
+            password_hash: user.password_hash,
+        })
+        .collect::<Vec<_>>();
 
     let payload = CreateProjectRequest {
         server_id: project.server_id.clone(),
@@ -97,39 +164,51 @@ pub(super) async fn redeploy_project_by_id(
         install_command: project.install_command.clone(),
         run_command: project.start_command.clone(),
         output_directory: project.output_directory.clone(),
-        port: Some(project_port),
+        port: Some(ports.port),
         env_vars,
         github_source: None,
+        runtime,
+        resource_limits,
+        root_directory: project.root_directory.clone(),
+        watch_paths,
+        routing,
+        access,
+        maintenance: stored_maintenance(&project)?,
+        replicas: u32::try_from(project.replicas).map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Stored replica count is out of range: {}", project.replicas),
+            )
+        })?,
+        stream_ports: stored_stream_ports(&project)?,
+        jobs: stored_jobs(&project)?,
+        processes: stored_processes(&project)?,
+        volumes: stored_volumes(&project)?,
+        release_command: project.release_command.clone(),
+        stopped: project.stopped,
     };
 
-    if let Err(error) = call_worker_delete_project(
-        &connection.id,
-        worker_host,
-        &connection.secret_key,
-        project_id,
-    )
-    .await
-    {
-        return Err((
-            StatusCode::BAD_GATEWAY,
-            format!("Worker cleanup call failed: {error}"),
-        ));
-    }
-
+    let network = project_network(state, &project).await?;
+    let placement = placement(state, connection, members).await?;
     let _ = deactivate_project_webhook(state, project_id).await;
 
-    if let Err(error) = call_worker_create_project(
-        &connection.id,
-        worker_host,
-        &connection.secret_key,
+    let deployment = roll_out(
+        state,
+        &placement,
+        &SiteRoutes {
+            domain: project.domain.as_deref(),
+            custom_domains: &custom_domains,
+            access_users: &access_users,
+        },
         &payload,
+        &network,
         project_id,
-        project.domain.as_deref(),
-        project_port,
-        state.tls_email.as_deref(),
+        ports,
     )
-    .await
-    {
+    .await;
+    record_deployment(state, project_id, &project.branch, &deployment).await;
+
+    if let Err(error) = deployment {
         return Err((
             StatusCode::BAD_GATEWAY,
             format!("Worker deployment call failed: {error}"),
@@ -177,6 +256,7 @@ pub(super) async fn delete_project(
     State(state): State<OrchestratorState>,
     session: Session,
     AxumPath(project_id): AxumPath<String>,
+    Query(query): Query<DeleteProjectQuery>,
 ) -> Result<StatusCode, (StatusCode, String)> {
     require_authenticated(&session)
         .await
@@ -209,19 +289,10 @@ pub(super) async fn delete_project(
             "Project host server was not found".to_string(),
         ))?;
 
-    let worker_host = if connection.id == state.local_server_id {
-        "127.0.0.1"
-    } else {
-        &connection.ip_address
-    };
-
-    if let Err(error) = call_worker_delete_project(
-        &connection.id,
-        worker_host,
-        &connection.secret_key,
-        &project_id,
-    )
-    .await
+    let members = load_replica_servers(&state, &project_id).await?;
+    let placement = placement(&state, connection, members).await?;
+    if let Err(error) =
+        remove_deployment(&state, &placement, &project_id, query.delete_volumes).await
     {
         return Err((
             StatusCode::BAD_GATEWAY,
@@ -294,131 +365,28 @@ pub(super) async fn create_project(
     let project_id = Uuid::new_v4().to_string();
     let project_domain = assigned_project_domain(&state, &project_id, &payload.name).await?;
 
-    let worker_host = if connection.id == state.local_server_id {
-        "127.0.0.1"
-    } else {
-        &connection.ip_address
-    };
-    let project_port = if let Some(requested_port) = payload.port {
-        let requested_port = i64::from(requested_port);
-        if requested_port < DbClient::min_project_port() {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                format!(
-                    "Requested port must be {} or higher",
-                    DbClient::min_project_port()
-                ),
-            ));
-        }
-
-        let in_use = state
-            .db
-            .is_project_port_in_use(requested_port)
-            .await
-            .map_err(|error| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Unable to validate requested port: {error}"),
-                )
-            })?;
-
-        if in_use {
-            return Err((
-                StatusCode::CONFLICT,
-                format!("Requested port {requested_port} is already in use"),
-            ));
-        }
-
-        let requested_port_u16: u16 = requested_port.try_into().map_err(|_| {
-            (
-                StatusCode::BAD_REQUEST,
-                "Requested port is out of range".to_string(),
-            )
-        })?;
-
-        let is_available = call_worker_port_available(
-            &connection.id,
-            worker_host,
-            &connection.secret_key,
-            requested_port_u16,
-        )
-        .await
-        .map_err(|error| {
-            (
-                StatusCode::BAD_REQUEST,
-                format!("Unable to validate requested port on worker: {error}"),
-            )
-        })?;
-
-        if !is_available {
-            return Err((
-                StatusCode::CONFLICT,
-                format!("Requested port {requested_port} is already bound on the target server"),
-            ));
-        }
-
-        requested_port
-    } else {
-        let mut candidate = state
-            .db
-            .next_available_project_port()
-            .await
-            .map_err(|error| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Unable to allocate project port: {error}"),
-                )
-            })?;
-
-        // The DB can be out of sync with already-deployed systemd sockets/services (e.g.
-        // after a redeploy/reset). Probe the worker to find a bindable port.
-        for _ in 0..100 {
-            let candidate_u16: u16 = candidate.try_into().map_err(|_| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Allocated port is out of range".to_string(),
-                )
-            })?;
-
-            let in_use = state
-                .db
-                .is_project_port_in_use(candidate)
+    let members = place_new_replicas(&state, &connection.id, payload.replicas).await?;
+    let app_servers = std::iter::once(connection.clone())
+        .chain(members.iter().cloned())
+        .collect::<Vec<_>>();
+    let member_ids = members
+        .iter()
+        .map(|member| member.id.clone())
+        .collect::<Vec<_>>();
+    let placement = placement(&state, connection.clone(), members).await?;
+
+    let ports = allocate_project_ports(&state, &project_id, &app_servers, payload.port).await?;
+    let streams_checked = match validate_stream_ports(&payload.stream_ports, ports.port) {
+        Ok(()) => {
+            ensure_stream_ports_free(&state, &connection, &project_id, &[], &payload.stream_ports)
                 .await
-                .map_err(|error| {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        format!("Unable to validate allocated port: {error}"),
-                    )
-                })?;
-
-            if in_use {
-                candidate += 1;
-                continue;
-            }
-
-            let is_available = call_worker_port_available(
-                &connection.id,
-                worker_host,
-                &connection.secret_key,
-                candidate_u16,
-            )
-            .await
-            .map_err(|error| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    format!("Unable to validate allocated port on worker: {error}"),
-                )
-            })?;
-
-            if is_available {
-                break;
-            }
-
-            candidate += 1;
         }
-
-        candidate
+        Err(error) => Err(error),
     };
+    if let Err(error) = streams_checked {
+        let _ = state.db.release_project_ports(&project_id).await;
+        return Err(error);
+    }
 
     let project = NewProject {
         id: project_id.clone(),
@@ -436,7 +404,8 @@ pub(super) async fn create_project(
                 format!("Failed to serialize env vars: {error}"),
             )
         })?,
-        port: project_port,
+        port: i64::from(ports.port),
+        backend_port: i64::from(ports.backend_port),
         domain: project_domain.clone(),
         source_provider: if resolved_github_source.is_some() {
             "github".to_string()
@@ -444,14 +413,73 @@ pub(super) async fn create_project(
             "manual".to_string()
         },
         source_repo_id: resolved_github_source.as_ref().map(|source| source.repo_id),
+        runtime: payload.runtime.as_str().to_string(),
+        memory_limit_mb: payload.resource_limits.memory_max_mb.map(i64::from),
+        cpu_quota_percent: payload.resource_limits.cpu_quota_percent.map(i64::from),
+        root_directory: payload
+            .root_directory
+            .trim()
+            .trim_end_matches('/')
+            .to_string(),
+        watch_paths: serde_json::to_string(&payload.watch_paths).map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to serialize watch paths: {error}"),
+            )
+        })?,
+        routing: serde_json::to_string(&payload.routing).map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to serialize routing config: {error}"),
+            )
+        })?,
+        access_rules: serde_json::to_string(&payload.access).map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to serialize access rules: {error}"),
+            )
+        })?,
+        maintenance: serde_json::to_string(&payload.maintenance).map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to serialize maintenance mode: {error}"),
+            )
+        })?,
+        replicas: i64::from(payload.replicas),
+        stream_ports: serde_json::to_string(&payload.stream_ports).map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to serialize stream ports: {error}"),
+            )
+        })?,
+        jobs: serde_json::to_string(&payload.jobs).map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to serialize cron jobs: {error}"),
+            )
+        })?,
+        processes: serde_json::to_string(&payload.processes).map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to serialize process types: {error}"),
+            )
+        })?,
+        volumes: serde_json::to_string(&payload.volumes).map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to serialize volumes: {error}"),
+            )
+        })?,
+        release_command: payload.release_command.trim().to_string(),
     };
 
-    state.db.insert_project(&project).await.map_err(|error| {
-        (
+    if let Err(error) = state.db.insert_project(&project).await {
+        let _ = state.db.release_project_ports(&project_id).await;
+        return Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             format!("Failed to persist project record: {error}"),
-        )
-    })?;
+        ));
+    }
 
     let mut worker_payload = payload;
     worker_payload.repo_url = repo_url;
@@ -461,30 +489,45 @@ pub(super) async fn create_project(
         worker_payload.repo_url = authenticated_clone_url(&state, source).await?;
     }
 
-    if let Err(error) = call_worker_create_project(
-        &connection.id,
-        worker_host,
-        &connection.secret_key,
-        &worker_payload,
-        &project_id,
-        project_domain.as_deref(),
-        u16::try_from(project_port).map_err(|_| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Allocated port out of range: {project_port}"),
-            )
-        })?,
-        state.tls_email.as_deref(),
-    )
-    .await
+    if let Err(error) = state
+        .db
+        .set_project_replicas(&project_id, &member_ids)
+        .await
     {
         let _ = state.db.delete_project_by_id(&project_id).await;
         return Err((
-            StatusCode::BAD_GATEWAY,
-            format!("Worker deployment call failed: {error}"),
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to persist replica placement: {error}"),
         ));
     }
 
+    let deployment = match roll_out(
+        &state,
+        &placement,
+        &SiteRoutes {
+            domain: project_domain.as_deref(),
+            custom_domains: &[],
+            access_users: &[],
+        },
+        &worker_payload,
+        &PrivateNetwork::default(),
+        &project_id,
+        ports,
+    )
+    .await
+    {
+        Ok(deployment) => deployment,
+        Err(error) => {
+            let _ = remove_deployment(&state, &placement, &project_id, true).await;
+            let _ = state.db.delete_project_by_id(&project_id).await;
+            return Err((
+                StatusCode::BAD_GATEWAY,
+                format!("Worker deployment call failed: {error:#}"),
+            ));
+        }
+    };
+    record_deployment(&state, &project_id, &worker_payload.branch, &Ok(deployment)).await;
+
     if let Some(source) = resolved_github_source.as_ref() {
         ensure_project_webhook(&state, &project_id, source).await?;
     }
@@ -500,30 +543,88 @@ fn validate_create_project_required_fields(
 ) -> Result<(), (StatusCode, String)> {
     let repo_missing = payload.repo_url.trim().is_empty() && payload.github_source.is_none();
 
-    if payload.name.trim().is_empty()
-        || repo_missing
-        || payload.install_command.trim().is_empty()
-        || payload.build_command.trim().is_empty()
-        || payload.run_command.trim().is_empty()
+    // Blank commands are filled in by runtime detection on the worker after checkout.
+    if payload.name.trim().is_empty() || repo_missing {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Project name and repository URL are required".to_string(),
+        ));
+    }
+
+    if payload.resource_limits.memory_max_mb == Some(0)
+        || payload.resource_limits.cpu_quota_percent == Some(0)
     {
         return Err((
             StatusCode::BAD_REQUEST,
-            "Project name, repository URL, install/build/run commands are required".to_string(),
+            "Resource limits must be greater than zero".to_string(),
         ));
     }
 
+    let git_ref = payload
+        .github_source
+        .as_ref()
+        .map_or(&payload.branch, |source| &source.selected_branch);
+    Git::validate_ref(git_ref).map_err(|error| (StatusCode::BAD_REQUEST, format!("{error}")))?;
+
+    validate_root_directory(&payload.root_directory)
+        .map_err(|error| (StatusCode::BAD_REQUEST, format!("{error}")))?;
+    validate_watch_paths(&payload.watch_paths).map_err(|error| (StatusCode::BAD_REQUEST, error))?;
+    payload
+        .routing
+        .validate()
+        .map_err(|error| (StatusCode::BAD_REQUEST, format!("{error}")))?;
+    payload
+        .access
+        .validate()
+        .map_err(|error| (StatusCode::BAD_REQUEST, format!("{error:#}")))?;
+    payload
+        .maintenance
+        .validate()
+        .map_err(|error| (StatusCode::BAD_REQUEST, format!("{error}")))?;
+    if !(1..=MAX_REPLICAS).contains(&payload.replicas) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Replicas must be between 1 and {MAX_REPLICAS}"),
+        ));
+    }
+    ensure_streams_allowed(payload.replicas, &payload.stream_ports)?;
+    validate_jobs(&payload.jobs)?;
+    validate_processes(&payload.processes)?;
+    ensure_volumes_allowed(payload.replicas, &payload.volumes)?;
+    validate_volumes(&payload.volumes)?;
+
     Ok(())
 }
 
+fn stored_resource_limits(
+    project: &ProjectDetailsRecord,
+) -> Result<ResourceLimits, (StatusCode, String)> {
+    let to_limit = |value: Option<i64>, label: &str| {
+        value.map(u32::try_from).transpose().map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Stored {label} limit is out of range"),
+            )
+        })
+    };
+
+    Ok(ResourceLimits {
+        memory_max_mb: to_limit(project.memory_limit_mb, "memory")?,
+        cpu_quota_percent: to_limit(project.cpu_quota_percent, "CPU")?,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::deployment::firewall::Protocol;
+    use crate::deployment::pages::MaintenanceMode;
+    use crate::deployment::streams::StreamPort;
 
-    #[test]
-    fn validate_create_project_required_fields_rejects_blanks() {
-        let payload = CreateProjectRequest {
+    fn payload() -> CreateProjectRequest {
+        CreateProjectRequest {
             server_id: "srv".to_string(),
-            name: String::new(),
+            name: "My Project".to_string(),
             repo_url: "https://example.com/repo.git".to_string(),
             branch: "main".to_string(),
             build_command: "bun run build".to_string(),
@@ -533,6 +634,28 @@ mod tests {
             port: None,
             env_vars: vec![],
             github_source: None,
+            runtime: ProjectRuntime::Auto,
+            resource_limits: ResourceLimits::default(),
+            root_directory: String::new(),
+            watch_paths: vec![],
+            routing: RoutingConfig::default(),
+            access: AccessRules::default(),
+            maintenance: MaintenanceMode::default(),
+            replicas: 1,
+            stream_ports: vec![],
+            jobs: vec![],
+            processes: vec![],
+            volumes: vec![],
+            release_command: String::new(),
+            stopped: false,
+        }
+    }
+
+    #[test]
+    fn validate_create_project_required_fields_rejects_blanks() {
+        let payload = CreateProjectRequest {
+            name: String::new(),
+            ..payload()
         };
 
         assert_eq!(
@@ -545,20 +668,56 @@ mod tests {
 
     #[test]
     fn validate_create_project_required_fields_accepts_minimal_payload() {
-        let payload = CreateProjectRequest {
-            server_id: "srv".to_string(),
-            name: "My Project".to_string(),
-            repo_url: "https://example.com/repo.git".to_string(),
-            branch: "main".to_string(),
-            build_command: "bun run build".to_string(),
-            install_command: "bun install".to_string(),
-            run_command: "bun run start".to_string(),
-            output_directory: String::new(),
-            port: None,
-            env_vars: vec![],
-            github_source: None,
-        };
+        let payload = payload();
 
         validate_create_project_required_fields(&payload).expect("should be valid");
     }
+
+    #[test]
+    fn validate_create_project_required_fields_allows_blank_commands() {
+        let mut payload = CreateProjectRequest {
+            name: "Container App".to_string(),
+            build_command: String::new(),
+            install_command: String::new(),
+            run_command: String::new(),
+            runtime: ProjectRuntime::Container,
+            resource_limits: ResourceLimits {
+                memory_max_mb: Some(256),
+                cpu_quota_percent: Some(50),
+            },
+            root_directory: "services/api".to_string(),
+            watch_paths: vec!["services/api/**".to_string()],
+            ..payload()
+        };
+
+        validate_create_project_required_fields(&payload).expect("container should be valid");
+
+        payload.resource_limits.memory_max_mb = Some(0);
+        assert!(validate_create_project_required_fields(&payload).is_err());
+
+        payload.resource_limits.memory_max_mb = None;
+        payload.root_directory = "../outside".to_string();
+        assert!(validate_create_project_required_fields(&payload).is_err());
+
+        payload.root_directory = String::new();
+        payload.routing.max_body_size_mb = Some(0);
+        assert!(validate_create_project_required_fields(&payload).is_err());
+
+        payload.routing.max_body_size_mb = None;
+        payload.replicas = 0;
+        assert!(validate_create_project_required_fields(&payload).is_err());
+
+        payload.replicas = MAX_REPLICAS;
+        payload.stream_ports = vec![StreamPort {
+            port: 1883,
+            protocol: Protocol::Tcp,
+            target_port: 11883,
+        }];
+        assert!(validate_create_project_required_fields(&payload).is_err());
+
+        payload.stream_ports = vec![];
+        payload.runtime = ProjectRuntime::Auto;
+        validate_create_project_required_fields(&payload)
+            .expect("blank commands fall back to detection");
+    }
 }
@@ -0,0 +1,396 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::http::StatusCode;
+
+use crate::db::{ServerConnectionInfo, ServerLoadRecord};
+use crate::deployment::access::BasicAuthUser;
+use crate::deployment::lifecycle::ProjectAction;
+use crate::deployment::nginx::RoutedDomain;
+use crate::deployment::private_network::PrivateNetwork;
+use crate::deployment::replicas::ReplicaRole;
+
+use super::api_types::CreateProjectRequest;
+use super::ports::ProjectPorts;
+use super::worker_client::{
+    call_worker_create_project, call_worker_delete_project, call_worker_project_action,
+    WorkerDeploymentResponse,
+};
+use super::OrchestratorState;
+
+/// The servers a project is deployed to.
+pub(super) struct Placement {
+    /// Serves the project's domains: the configured ingress server, or else the project's
+    /// own server.
+    pub(super) ingress: ServerConnectionInfo,
+    /// Whether the ingress runs the app too, rather than only routing to the replicas.
+    pub(super) ingress_runs_app: bool,
+    /// Servers running the app besides the ingress.
+    pub(super) replicas: Vec<ServerConnectionInfo>,
+}
+
+/// What the ingress answers for.
+pub(super) struct SiteRoutes<'a> {
+    pub(super) domain: Option<&'a str>,
+    pub(super) custom_domains: &'a [RoutedDomain],
+    pub(super) access_users: &'a [BasicAuthUser],
+}
+
+/// The server holding a project's nginx site, certificates and status pages.
+pub(super) fn routing_server_id<'a>(
+    state: &'a OrchestratorState,
+    project_server_id: &'a str,
+) -> &'a str {
+    state
+        .ingress_server_id
+        .as_deref()
+        .unwrap_or(project_server_id)
+}
+
+/// Places a project whose app runs on `app_server` and `replicas`. With a dedicated ingress
+/// server configured, the app servers all become replicas behind it.
+pub(super) async fn placement(
+    state: &OrchestratorState,
+    app_server: ServerConnectionInfo,
+    replicas: Vec<ServerConnectionInfo>,
+) -> Result<Placement, (StatusCode, String)> {
+    let ingress_id = routing_server_id(state, &app_server.id);
+    if ingress_id == app_server.id {
+        return Ok(Placement {
+            ingress: app_server,
+            ingress_runs_app: true,
+            replicas,
+        });
+    }
+
+    let ingress = state
+        .db
+        .get_server_connection_info(ingress_id)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to load server connection info: {error}"),
+            )
+        })?
+        .ok_or((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Ingress server {ingress_id} was not found"),
+        ))?;
+    let replicas = std::iter::once(app_server)
+        .chain(replicas)
+        .filter(|server| server.id != ingress.id)
+        .collect();
+
+    Ok(Placement {
+        ingress,
+        ingress_runs_app: false,
+        replicas,
+    })
+}
+
+/// Picks the servers for a project's replicas beyond the ingress: the least loaded online
+/// servers, each used at most once.
+pub(super) fn place_replicas(
+    excluded_server_ids: &[&str],
+    replicas: u32,
+    servers: &[ServerLoadRecord],
+) -> Result<Vec<String>, (StatusCode, String)> {
+    let wanted = usize::try_from(replicas.saturating_sub(1)).unwrap_or(usize::MAX);
+    let mut candidates = servers
+        .iter()
+        .filter(|server| {
+            !excluded_server_ids.contains(&server.id.as_str()) && server.status == "online"
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by(|left, right| {
+        left.workloads
+            .cmp(&right.workloads)
+            .then_with(|| left.name.cmp(&right.name))
+    });
+
+    if candidates.len() < wanted {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "{replicas} replicas need {wanted} online servers besides the selected one, but only {} are available",
+                candidates.len()
+            ),
+        ));
+    }
+
+    Ok(candidates
+        .into_iter()
+        .take(wanted)
+        .map(|server| server.id.clone())
+        .collect())
+}
+
+/// Chooses and loads the servers for a new project's extra replicas, keeping them off the
+/// dedicated ingress server.
+pub(super) async fn place_new_replicas(
+    state: &OrchestratorState,
+    app_server_id: &str,
+    replicas: u32,
+) -> Result<Vec<ServerConnectionInfo>, (StatusCode, String)> {
+    if replicas <= 1 {
+        return Ok(Vec::new());
+    }
+
+    let servers = state.db.list_server_loads().await.map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Unable to load servers: {error}"),
+        )
+    })?;
+    let mut members = Vec::new();
+    let excluded = [app_server_id, routing_server_id(state, app_server_id)];
+    for server_id in place_replicas(&excluded, replicas, &servers)? {
+        let connection = state
+            .db
+            .get_server_connection_info(&server_id)
+            .await
+            .map_err(|error| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Unable to load server connection info: {error}"),
+                )
+            })?
+            .ok_or((
+                StatusCode::NOT_FOUND,
+                "Replica server was not found".to_string(),
+            ))?;
+        members.push(connection);
+    }
+
+    Ok(members)
+}
+
+pub(super) async fn load_replica_servers(
+    state: &OrchestratorState,
+    project_id: &str,
+) -> Result<Vec<ServerConnectionInfo>, (StatusCode, String)> {
+    state
+        .db
+        .list_project_replica_servers(project_id)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to load replica servers: {error}"),
+            )
+        })
+}
+
+/// Deploys the project on each replica server in turn, then on the ingress with an upstream
+/// over all of them. Replicas roll one at a time, so while one restarts the ingress keeps
+/// sending traffic to the others. Stops at the first failure, leaving the servers not reached
+/// yet on their previous release.
+///
+/// The first server deployed runs the project's release command, once for the whole rollout.
+///
+/// The returned response carries every server's log and the commit the replicas built.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn roll_out(
+    state: &OrchestratorState,
+    placement: &Placement,
+    routes: &SiteRoutes<'_>,
+    payload: &CreateProjectRequest,
+    network: &PrivateNetwork,
+    project_id: &str,
+    ports: ProjectPorts,
+) -> anyhow::Result<WorkerDeploymentResponse> {
+    let member_role = ReplicaRole::Member {
+        ingress_ip: placement.ingress.ip_address.clone(),
+    };
+    let mut peers = Vec::with_capacity(placement.replicas.len());
+    let mut log = Vec::new();
+    let mut commit_sha = None;
+    for (index, member) in placement.replicas.iter().enumerate() {
+        let host = worker_host(state, member);
+        let response = call_worker_create_project(
+            &member.id,
+            host,
+            &member.secret_key,
+            payload,
+            project_id,
+            None,
+            &[],
+            ports,
+            None,
+            None,
+            &[],
+            &member_role,
+            network,
+            index == 0,
+        )
+        .await
+        .map_err(|error| error.context(format!("replica on server {} failed", member.id)))?;
+        log.extend(response.log);
+        commit_sha = commit_sha.or(response.commit_sha);
+        peers.push(peer_address(&member.ip_address, ports.port)?);
+    }
+
+    let connection = &placement.ingress;
+    let host = worker_host(state, connection);
+    let role = match (placement.ingress_runs_app, peers.is_empty()) {
+        (true, true) => ReplicaRole::Single,
+        (true, false) => ReplicaRole::Ingress { peers },
+        (false, _) => ReplicaRole::Edge { backends: peers },
+    };
+    let edge_network = PrivateNetwork::default();
+    let mut response = call_worker_create_project(
+        &connection.id,
+        host,
+        &connection.secret_key,
+        payload,
+        project_id,
+        routes.domain,
+        routes.custom_domains,
+        ports,
+        state.tls_email.as_deref(),
+        state.base_domain.as_deref(),
+        routes.access_users,
+        &role,
+        if placement.ingress_runs_app {
+            network
+        } else {
+            &edge_network
+        },
+        placement.ingress_runs_app && placement.replicas.is_empty(),
+    )
+    .await?;
+    log.append(&mut response.log);
+    response.log = log;
+    response.commit_sha = response.commit_sha.or(commit_sha);
+
+    Ok(response)
+}
+
+/// Tears the project down on every replica server, then on the ingress, deleting its volumes
+/// too when `delete_volumes` is set. Keeps going past failures so one unreachable server does
+/// not leave the rest running, and reports the first error.
+pub(super) async fn remove_deployment(
+    state: &OrchestratorState,
+    placement: &Placement,
+    project_id: &str,
+    delete_volumes: bool,
+) -> anyhow::Result<()> {
+    let mut first_error = None;
+    for server in placement
+        .replicas
+        .iter()
+        .chain(std::iter::once(&placement.ingress))
+    {
+        if let Err(error) = call_worker_delete_project(
+            &server.id,
+            worker_host(state, server),
+            &server.secret_key,
+            project_id,
+            delete_volumes,
+        )
+        .await
+        {
+            first_error.get_or_insert(error);
+        }
+    }
+
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Starts, stops or restarts the project on every server running its app. Keeps going past
+/// failures, like a teardown, and reports the first error.
+pub(super) async fn control_deployment(
+    state: &OrchestratorState,
+    placement: &Placement,
+    project_id: &str,
+    action: ProjectAction,
+) -> anyhow::Result<()> {
+    let ingress = placement.ingress_runs_app.then_some(&placement.ingress);
+    let mut first_error = None;
+    for server in placement.replicas.iter().chain(ingress) {
+        if let Err(error) = call_worker_project_action(
+            &server.id,
+            worker_host(state, server),
+            &server.secret_key,
+            project_id,
+            action,
+        )
+        .await
+        {
+            first_error.get_or_insert(error);
+        }
+    }
+
+    first_error.map_or(Ok(()), Err)
+}
+
+pub(super) fn worker_host<'a>(
+    state: &OrchestratorState,
+    connection: &'a ServerConnectionInfo,
+) -> &'a str {
+    if connection.id == state.local_server_id {
+        "127.0.0.1"
+    } else {
+        &connection.ip_address
+    }
+}
+
+fn peer_address(ip_address: &str, port: u16) -> anyhow::Result<String> {
+    let ip = ip_address
+        .parse::<IpAddr>()
+        .map_err(|_| anyhow::anyhow!("replica server address is not an IP: {ip_address:?}"))?;
+    Ok(SocketAddr::new(ip, port).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(id: &str, status: &str, workloads: i64) -> ServerLoadRecord {
+        ServerLoadRecord {
+            id: id.to_string(),
+            name: format!("server-{id}"),
+            status: status.to_string(),
+            workloads,
+        }
+    }
+
+    #[test]
+    fn place_replicas_prefers_least_loaded_online_servers() {
+        let servers = vec![
+            server("a", "online", 0),
+            server("b", "online", 3),
+            server("c", "offline", 0),
+            server("d", "online", 1),
+            server("e", "online", 1),
+        ];
+
+        assert_eq!(
+            place_replicas(&["a"], 3, &servers).expect("placement"),
+            vec!["d".to_string(), "e".to_string()]
+        );
+        assert!(place_replicas(&["a"], 1, &servers)
+            .expect("single replica")
+            .is_empty());
+        assert_eq!(
+            place_replicas(&["a", "d"], 3, &servers).expect("placement without ingress"),
+            vec!["e".to_string(), "b".to_string()]
+        );
+        assert_eq!(
+            place_replicas(&["a"], 5, &servers)
+                .expect_err("too few servers")
+                .0,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn peer_address_brackets_ipv6() {
+        assert_eq!(peer_address("10.0.0.2", 3100).expect("v4"), "10.0.0.2:3100");
+        assert_eq!(
+            peer_address("2001:db8::2", 3100).expect("v6"),
+            "[2001:db8::2]:3100"
+        );
+        assert!(peer_address("worker.local", 3100).is_err());
+    }
+}
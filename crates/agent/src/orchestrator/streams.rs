@@ -0,0 +1,214 @@
+use axum::extract::Path as AxumPath;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use tower_sessions::Session;
+
+use crate::db::{PortKind, ProjectDetailsRecord, ServerConnectionInfo};
+use crate::deployment::firewall::Protocol;
+use crate::deployment::streams::{self, StreamPort};
+
+use super::auth::require_authenticated;
+use super::domains::load_project;
+use super::projects::redeploy_project_by_id;
+use super::replicas::worker_host;
+use super::worker_client::call_worker_port_available;
+use super::OrchestratorState;
+
+pub(super) async fn get_project_stream_ports(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<Json<Vec<StreamPort>>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let project = load_project(&state, &project_id).await?;
+    Ok(Json(stored_stream_ports(&project)?))
+}
+
+/// Replaces the project's public TCP/UDP ports and redeploys it so nginx and the firewall
+/// follow.
+pub(super) async fn update_project_stream_ports(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+    Json(payload): Json<Vec<StreamPort>>,
+) -> Result<Json<Vec<StreamPort>>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let project = load_project(&state, &project_id).await?;
+    ensure_streams_allowed(
+        u32::try_from(project.replicas).unwrap_or(u32::MAX),
+        &payload,
+    )?;
+    let port = u16::try_from(project.port).map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Stored port is out of range: {}", project.port),
+        )
+    })?;
+    validate_stream_ports(&payload, port)?;
+    let connection = state
+        .db
+        .get_server_connection_info(&project.server_id)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to load server connection info: {error}"),
+            )
+        })?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "Project server was not found".to_string(),
+        ))?;
+    ensure_stream_ports_free(
+        &state,
+        &connection,
+        &project_id,
+        &stored_stream_ports(&project)?,
+        &payload,
+    )
+    .await?;
+
+    let stream_ports = serde_json::to_string(&payload).map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to serialize stream ports: {error}"),
+        )
+    })?;
+    state
+        .db
+        .set_project_stream_ports(&project_id, &stream_ports)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to update stream ports: {error}"),
+            )
+        })?;
+
+    redeploy_project_by_id(&state, &project_id).await?;
+    Ok(Json(payload))
+}
+
+/// Stream ports are forwarded on the one server running the app, so replicated projects
+/// cannot have them.
+pub(super) fn ensure_streams_allowed(
+    replicas: u32,
+    stream_ports: &[StreamPort],
+) -> Result<(), (StatusCode, String)> {
+    if replicas > 1 && !stream_ports.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "TCP/UDP ports need a project running on a single server".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub(super) fn validate_stream_ports(
+    stream_ports: &[StreamPort],
+    app_port: u16,
+) -> Result<(), (StatusCode, String)> {
+    streams::validate(stream_ports, app_port)
+        .map_err(|error| (StatusCode::BAD_REQUEST, format!("{error}")))
+}
+
+/// Fails when a requested public or target port is already held on the server, by another
+/// project or for this project's HTTP ports, or bound there. Ports the project already uses
+/// are not probed again.
+pub(super) async fn ensure_stream_ports_free(
+    state: &OrchestratorState,
+    connection: &ServerConnectionInfo,
+    project_id: &str,
+    current: &[StreamPort],
+    requested: &[StreamPort],
+) -> Result<(), (StatusCode, String)> {
+    for stream in requested {
+        let probes = [
+            (
+                stream.port,
+                current
+                    .iter()
+                    .any(|used| used.port == stream.port && used.protocol == stream.protocol),
+            ),
+            (
+                stream.target_port,
+                current.iter().any(|used| {
+                    used.target_port == stream.target_port && used.protocol == stream.protocol
+                }),
+            ),
+        ];
+        for (port, already_used) in probes {
+            if already_used {
+                continue;
+            }
+            ensure_port_free(state, connection, project_id, port, stream.protocol).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn ensure_port_free(
+    state: &OrchestratorState,
+    connection: &ServerConnectionInfo,
+    project_id: &str,
+    port: u16,
+    protocol: Protocol,
+) -> Result<(), (StatusCode, String)> {
+    // The project's own stream reservation covers the other protocol on the same number.
+    let in_use = state
+        .db
+        .find_port_allocation(&connection.id, i64::from(port))
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unable to validate port {port}: {error}"),
+            )
+        })?
+        .is_some_and(|allocation| {
+            allocation.project_id != project_id || allocation.kind != PortKind::Stream.as_str()
+        });
+    let available = !in_use
+        && call_worker_port_available(
+            &connection.id,
+            worker_host(state, connection),
+            &connection.secret_key,
+            port,
+            protocol,
+        )
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Unable to validate port {port} on worker: {error}"),
+            )
+        })?;
+    if !available {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Port {port}/{protocol} is already in use on the project's server"),
+        ));
+    }
+
+    Ok(())
+}
+
+pub(super) fn stored_stream_ports(
+    project: &ProjectDetailsRecord,
+) -> Result<Vec<StreamPort>, (StatusCode, String)> {
+    serde_json::from_str(&project.stream_ports).map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to deserialize stream ports: {error}"),
+        )
+    })
+}
@@ -31,6 +31,7 @@ fn new_state(db: DbClient) -> OrchestratorState {
         monitored_projects: Arc::new(RwLock::new(Vec::new())),
         local_server_id: "orchestrator-test".to_string(),
         base_domain: None,
+        ingress_server_id: None,
         tls_email: None,
         stats_cache: Arc::new(RwLock::new(stats_cache::StatsCache::default())),
         github: Arc::new(
@@ -38,6 +39,11 @@ fn new_state(db: DbClient) -> OrchestratorState {
                 .expect("github service"),
         ),
         redeploy_debounce: Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::new())),
+        dns_resolver: Arc::new(dns::StaticDnsResolver(dns::DnsRecords::default())),
+        certificates: crate::deployment::cert_renewal::CertificateRenewer::new(
+            30,
+            crate::deployment::acme::AcmeSettings::default(),
+        ),
     }
 }
 
@@ -338,46 +344,116 @@ async fn internal_verify_signature_middleware_rejects_invalid_signature() {
     assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 }
 
-#[test]
-fn project_mapping_preserves_fields_and_sets_deployed_status() {
-    let list_record = crate::db::ProjectListRecord {
+fn project_details_record() -> crate::db::ProjectDetailsRecord {
+    crate::db::ProjectDetailsRecord {
         id: "p1".to_string(),
+        server_id: "srv".to_string(),
         name: "Project".to_string(),
         repo_url: "https://example.com/repo.git".to_string(),
         branch: "main".to_string(),
+        install_command: "bun install".to_string(),
+        build_command: "bun run build".to_string(),
         start_command: "bun run start".to_string(),
+        output_directory: ".next/standalone".to_string(),
+        env_vars: "[]".to_string(),
         port: 3100,
-        domain: Some("p1.example.com".to_string()),
+        backend_port: Some(13_100),
+        domain: None,
         source_provider: "manual".to_string(),
         source_repo_id: None,
+        runtime: "container".to_string(),
+        memory_limit_mb: Some(512),
+        cpu_quota_percent: None,
+        root_directory: "apps/web".to_string(),
+        watch_paths: r#"["apps/web/**"]"#.to_string(),
+        routing: r#"{"max_body_size_mb":50,"websockets":true}"#.to_string(),
+        access_rules: "{}".to_string(),
+        maintenance: "{}".to_string(),
+        replicas: 1,
+        internal_name: None,
+        stream_ports: "[]".to_string(),
+        jobs: "[]".to_string(),
+        processes: "[]".to_string(),
+        volumes: "[]".to_string(),
+        release_command: String::new(),
+        stopped: false,
         created_at: "now".to_string(),
-    };
-
-    let item = project_mapping::map_project_list_record(list_record);
-    assert_eq!(item.id, "p1");
-    assert_eq!(item.status, "deployed");
+        server_name: Some("server".to_string()),
+    }
+}
 
-    let details_record = crate::db::ProjectDetailsRecord {
+#[test]
+fn project_mapping_preserves_fields_and_sets_deployed_status() {
+    let list_record = crate::db::ProjectListRecord {
         id: "p1".to_string(),
-        server_id: "srv".to_string(),
         name: "Project".to_string(),
         repo_url: "https://example.com/repo.git".to_string(),
         branch: "main".to_string(),
-        install_command: "bun install".to_string(),
-        build_command: "bun run build".to_string(),
         start_command: "bun run start".to_string(),
-        output_directory: ".next/standalone".to_string(),
-        env_vars: "[]".to_string(),
         port: 3100,
-        domain: None,
+        domain: Some("p1.example.com".to_string()),
         source_provider: "manual".to_string(),
         source_repo_id: None,
+        stopped: false,
         created_at: "now".to_string(),
-        server_name: Some("server".to_string()),
     };
 
+    let item = project_mapping::map_project_list_record(list_record.clone());
+    assert_eq!(item.id, "p1");
+    assert_eq!(item.status, "deployed");
+    let stopped = project_mapping::map_project_list_record(crate::db::ProjectListRecord {
+        stopped: true,
+        ..list_record
+    });
+    assert_eq!(stopped.status, "stopped");
+
+    let details_record = project_details_record();
+
     let details = project_mapping::map_project_details_record(details_record);
     assert_eq!(details.id, "p1");
     assert_eq!(details.status, "deployed");
     assert_eq!(details.server_name.as_deref(), Some("server"));
+    assert_eq!(details.runtime, "container");
+    assert_eq!(details.memory_limit_mb, Some(512));
+    assert_eq!(details.watch_paths, vec!["apps/web/**".to_string()]);
+    assert_eq!(details.routing.max_body_size_mb, Some(50));
+    assert_eq!(details.routing.websockets, Some(true));
+}
+
+#[tokio::test]
+async fn domain_verification_checks_dns_against_project_server() {
+    let db = temp_db().await;
+    db.insert_server(&crate::db::NewServer {
+        id: "srv".to_string(),
+        name: "server".to_string(),
+        ip_address: "203.0.113.10".to_string(),
+        status: "online".to_string(),
+        secret_key: "secret".to_string(),
+    })
+    .await
+    .expect("insert server");
+
+    let mut state = new_state(db);
+    let mut project = project_details_record();
+    project.domain = Some("p1.apps.example.com".to_string());
+
+    state.dns_resolver = Arc::new(dns::StaticDnsResolver(dns::DnsRecords {
+        addresses: vec!["203.0.113.10".parse().expect("ip")],
+        cnames: Vec::new(),
+    }));
+    let error = domains::domain_verification_error(&state, &project, "app.customer.com")
+        .await
+        .expect("verification");
+    assert!(error.is_none());
+
+    state.dns_resolver = Arc::new(dns::StaticDnsResolver(dns::DnsRecords {
+        addresses: vec!["198.51.100.7".parse().expect("ip")],
+        cnames: vec!["elsewhere.example.net".to_string()],
+    }));
+    let error = domains::domain_verification_error(&state, &project, "app.customer.com")
+        .await
+        .expect("verification")
+        .expect("mismatch reported");
+    assert!(error.contains("203.0.113.10"));
+    assert!(error.contains("p1.apps.example.com"));
 }
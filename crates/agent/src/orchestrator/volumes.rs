@@ -0,0 +1,95 @@
+use axum::extract::Path as AxumPath;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use tower_sessions::Session;
+
+use crate::db::ProjectDetailsRecord;
+use crate::deployment::volumes::{self, Volume};
+
+use super::auth::require_authenticated;
+use super::domains::load_project;
+use super::projects::redeploy_project_by_id;
+use super::OrchestratorState;
+
+pub(super) async fn get_project_volumes(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+) -> Result<Json<Vec<Volume>>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let project = load_project(&state, &project_id).await?;
+    Ok(Json(stored_volumes(&project)?))
+}
+
+/// Replaces the project's volumes and redeploys it so the mounts follow. The data of a volume
+/// taken off the list stays on the server until the project is deleted with its volumes.
+pub(super) async fn update_project_volumes(
+    State(state): State<OrchestratorState>,
+    session: Session,
+    AxumPath(project_id): AxumPath<String>,
+    Json(payload): Json<Vec<Volume>>,
+) -> Result<Json<Vec<Volume>>, (StatusCode, String)> {
+    require_authenticated(&session)
+        .await
+        .map_err(|status| (status, "Authentication required".to_string()))?;
+
+    let project = load_project(&state, &project_id).await?;
+    ensure_volumes_allowed(
+        u32::try_from(project.replicas).unwrap_or(u32::MAX),
+        &payload,
+    )?;
+    validate_volumes(&payload)?;
+    let volumes = serde_json::to_string(&payload).map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to serialize volumes: {error}"),
+        )
+    })?;
+    state
+        .db
+        .set_project_volumes(&project_id, &volumes)
+        .await
+        .map_err(|error| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to update volumes: {error}"),
+            )
+        })?;
+
+    redeploy_project_by_id(&state, &project_id).await?;
+    Ok(Json(payload))
+}
+
+/// Volumes live on the one server running the app, so replicated projects cannot have them.
+pub(super) fn ensure_volumes_allowed(
+    replicas: u32,
+    volumes: &[Volume],
+) -> Result<(), (StatusCode, String)> {
+    if replicas > 1 && !volumes.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Volumes need a project running on a single server".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub(super) fn validate_volumes(volumes: &[Volume]) -> Result<(), (StatusCode, String)> {
+    volumes::validate(volumes).map_err(|error| (StatusCode::BAD_REQUEST, format!("{error}")))
+}
+
+pub(super) fn stored_volumes(
+    project: &ProjectDetailsRecord,
+) -> Result<Vec<Volume>, (StatusCode, String)> {
+    serde_json::from_str(&project.volumes).map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to deserialize volumes: {error}"),
+        )
+    })
+}
@@ -0,0 +1,132 @@
+/// Globs a push must touch for a project to redeploy.
+///
+/// Explicit watch paths win; otherwise a project with a root directory watches everything
+/// under it, and a project at the repository root watches the whole repository.
+pub(super) fn effective_watch_paths(root_directory: &str, watch_paths: &[String]) -> Vec<String> {
+    if !watch_paths.is_empty() {
+        return watch_paths.to_vec();
+    }
+
+    let root_directory = root_directory.trim().trim_matches('/');
+    if root_directory.is_empty() {
+        return Vec::new();
+    }
+
+    vec![format!("{root_directory}/**")]
+}
+
+/// Decides whether a push affects a project.
+///
+/// `changed_files` is `None` when the payload carries no file list (e.g. branch creation or a
+/// force push); the safe answer then is to redeploy.
+pub(super) fn should_redeploy(patterns: &[String], changed_files: Option<&[String]>) -> bool {
+    if patterns.is_empty() {
+        return true;
+    }
+
+    let Some(changed_files) = changed_files else {
+        return true;
+    };
+
+    changed_files
+        .iter()
+        .any(|file| patterns.iter().any(|pattern| glob_matches(pattern, file)))
+}
+
+/// Validates user-supplied watch globs.
+pub(super) fn validate_watch_paths(watch_paths: &[String]) -> Result<(), String> {
+    for pattern in watch_paths {
+        if pattern.trim().is_empty() || pattern.starts_with('/') || pattern.contains("..") {
+            return Err(format!(
+                "Watch paths must be relative globs inside the repository: {pattern:?}"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Matches repository paths against a glob: `**` spans directories, `*` and `?` stay within
+/// one path segment. A pattern without wildcards also matches everything beneath it.
+fn glob_matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim().trim_matches('/');
+    let pattern_segments = pattern.split('/').collect::<Vec<_>>();
+    let path_segments = path.split('/').collect::<Vec<_>>();
+
+    if !pattern.contains(['*', '?']) {
+        return path_segments.starts_with(&pattern_segments);
+    }
+
+    segments_match(&pattern_segments, &path_segments)
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| segments_match(rest, &path[skip..])),
+        Some((segment, rest)) => path.split_first().is_some_and(|(path_segment, path_rest)| {
+            segment_matches(segment.as_bytes(), path_segment.as_bytes())
+                && segments_match(rest, path_rest)
+        }),
+    }
+}
+
+fn segment_matches(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|skip| segment_matches(rest, &text[skip..])),
+        Some((b'?', rest)) => !text.is_empty() && segment_matches(rest, &text[1..]),
+        Some((character, rest)) => text
+            .split_first()
+            .is_some_and(|(text_character, text_rest)| {
+                character == text_character && segment_matches(rest, text_rest)
+            }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matches_supports_double_star_and_prefixes() {
+        assert!(glob_matches("apps/web/**", "apps/web/src/page.tsx"));
+        assert!(glob_matches("apps/web/**", "apps/web"));
+        assert!(glob_matches(
+            "packages/*/src/*.ts",
+            "packages/ui/src/button.ts"
+        ));
+        assert!(!glob_matches(
+            "packages/*/src/*.ts",
+            "packages/ui/src/deep/button.ts"
+        ));
+        assert!(glob_matches("**/*.go", "cmd/api/main.go"));
+        assert!(glob_matches("apps/api", "apps/api/main.go"));
+        assert!(!glob_matches("apps/api", "apps/api-gateway/main.go"));
+        assert!(glob_matches("go.?od", "go.mod"));
+    }
+
+    #[test]
+    fn should_redeploy_filters_by_changed_files() {
+        let patterns = effective_watch_paths("apps/web", &[]);
+        assert_eq!(patterns, vec!["apps/web/**".to_string()]);
+
+        let docs_only = vec!["docs/readme.md".to_string()];
+        let web_change = vec!["apps/web/app/page.tsx".to_string()];
+        assert!(!should_redeploy(&patterns, Some(&docs_only)));
+        assert!(should_redeploy(&patterns, Some(&web_change)));
+        assert!(should_redeploy(&patterns, None));
+        assert!(should_redeploy(
+            &effective_watch_paths("", &[]),
+            Some(&docs_only)
+        ));
+    }
+
+    #[test]
+    fn validate_watch_paths_rejects_absolute_and_parent_paths() {
+        validate_watch_paths(&["apps/**".to_string()]).expect("valid");
+        assert!(validate_watch_paths(&["/etc/**".to_string()]).is_err());
+        assert!(validate_watch_paths(&["../other/**".to_string()]).is_err());
+        assert!(validate_watch_paths(&[" ".to_string()]).is_err());
+    }
+}
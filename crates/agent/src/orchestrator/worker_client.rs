@@ -4,11 +4,23 @@ use anyhow::Result;
 use hmac::Mac;
 use serde::{Deserialize, Serialize};
 
+use crate::deployment::access::{AccessControl, BasicAuthUser};
+use crate::deployment::cert_renewal::DomainTlsStatus;
+use crate::deployment::firewall::Protocol;
+use crate::deployment::jobs::JobStatus;
+use crate::deployment::lifecycle::ProjectAction;
+use crate::deployment::nginx::RoutedDomain;
+use crate::deployment::pages::MaintenanceMode;
+use crate::deployment::private_network::PrivateNetwork;
+use crate::deployment::replicas::ReplicaRole;
+
 use super::api_types::{CreateProjectRequest, WorkerCreateProjectRequest};
+use super::ports::ProjectPorts;
 
 #[derive(Debug, Serialize)]
 struct WorkerPortAvailabilityRequest {
     port: u16,
+    protocol: Protocol,
 }
 
 #[derive(Debug, Deserialize)]
@@ -16,11 +28,21 @@ struct WorkerPortAvailabilityResponse {
     available: bool,
 }
 
+#[derive(Debug, Serialize)]
+struct WorkerDeleteProjectRequest {
+    delete_volumes: bool,
+}
+
 #[derive(Debug, Serialize)]
 struct WorkerStatsRequest {
     project_ids: Vec<String>,
 }
 
+#[derive(Debug, Serialize)]
+struct WorkerTlsStatusRequest<'a> {
+    domains: &'a [String],
+}
+
 #[derive(Debug, Deserialize)]
 pub(super) struct WorkerStatsResponse {
     pub(super) totals: WorkerStatsTotals,
@@ -49,6 +71,14 @@ pub(super) struct WorkerProjectStats {
     pub(super) network_egress_bytes_total: u64,
 }
 
+/// What a host reports back after a successful deployment.
+#[derive(Debug, Deserialize)]
+pub(super) struct WorkerDeploymentResponse {
+    #[serde(default)]
+    pub(super) log: Vec<String>,
+    pub(super) commit_sha: Option<String>,
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(super) async fn call_worker_create_project(
     server_id: &str,
@@ -57,9 +87,15 @@ pub(super) async fn call_worker_create_project(
     payload: &CreateProjectRequest,
     project_id: &str,
     domain: Option<&str>,
-    project_port: u16,
+    custom_domains: &[RoutedDomain],
+    ports: ProjectPorts,
     tls_email: Option<&str>,
-) -> Result<()> {
+    base_domain: Option<&str>,
+    access_users: &[BasicAuthUser],
+    replica: &ReplicaRole,
+    network: &PrivateNetwork,
+    runs_release: bool,
+) -> Result<WorkerDeploymentResponse> {
     let worker_payload = WorkerCreateProjectRequest {
         project_id: project_id.to_string(),
         name: payload.name.clone(),
@@ -69,10 +105,32 @@ pub(super) async fn call_worker_create_project(
         install_command: payload.install_command.clone(),
         run_command: payload.run_command.clone(),
         output_directory: payload.output_directory.clone(),
-        port: project_port,
+        port: ports.port,
+        backend_port: ports.backend_port,
         domain: domain.map(ToOwned::to_owned),
+        custom_domains: custom_domains.to_vec(),
         tls_email: tls_email.map(ToOwned::to_owned),
+        base_domain: base_domain.map(ToOwned::to_owned),
         env_vars: payload.env_vars.clone(),
+        runtime: payload.runtime,
+        resource_limits: payload.resource_limits,
+        root_directory: payload.root_directory.clone(),
+        routing: payload.routing.clone(),
+        access: AccessControl {
+            rules: payload.access.clone(),
+            users: access_users.to_vec(),
+        },
+        maintenance: payload.maintenance.clone(),
+        replica: replica.clone(),
+        network: network.clone(),
+        stream_ports: payload.stream_ports.clone(),
+        jobs: payload.jobs.clone(),
+        processes: payload.processes.clone(),
+        primary: server_id == payload.server_id,
+        volumes: payload.volumes.clone(),
+        release_command: payload.release_command.clone(),
+        runs_release,
+        stopped: payload.stopped,
     };
 
     let body = serde_json::to_vec(&worker_payload)?;
@@ -99,7 +157,7 @@ pub(super) async fn call_worker_create_project(
         anyhow::bail!("internal projects endpoint returned {status}: {body}");
     }
 
-    Ok(())
+    Ok(response.json::<WorkerDeploymentResponse>().await?)
 }
 
 pub(super) async fn call_worker_delete_project(
@@ -107,8 +165,9 @@ pub(super) async fn call_worker_delete_project(
     worker_host: &str,
     secret_key: &str,
     project_id: &str,
+    delete_volumes: bool,
 ) -> Result<()> {
-    let body: [u8; 0] = [];
+    let body = serde_json::to_vec(&WorkerDeleteProjectRequest { delete_volumes })?;
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)?
         .as_secs()
@@ -121,6 +180,8 @@ pub(super) async fn call_worker_delete_project(
         .header("X-Cluster-Timestamp", timestamp)
         .header("X-Cluster-Signature", signature)
         .header("X-Server-Id", server_id)
+        .header("content-type", "application/json")
+        .body(body)
         .send()
         .await?;
 
@@ -133,6 +194,45 @@ pub(super) async fn call_worker_delete_project(
     Ok(())
 }
 
+/// Starts, stops or restarts the project's units on the server.
+pub(super) async fn call_worker_project_action(
+    server_id: &str,
+    worker_host: &str,
+    secret_key: &str,
+    project_id: &str,
+    action: ProjectAction,
+) -> Result<()> {
+    let body: [u8; 0] = [];
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)?
+        .as_secs()
+        .to_string();
+    let signature = sign_internal_payload(&body, &timestamp, secret_key)?;
+    let url = format!(
+        "http://{worker_host}:4000/internal/projects/{project_id}/{}",
+        action.as_str()
+    );
+
+    let response = reqwest::Client::new()
+        .post(url)
+        .header("X-Cluster-Timestamp", timestamp)
+        .header("X-Cluster-Signature", signature)
+        .header("X-Server-Id", server_id)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!(
+            "internal project {} endpoint returned {status}: {body}",
+            action.as_str()
+        );
+    }
+
+    Ok(())
+}
+
 pub(super) async fn call_worker_stats(
     server_id: &str,
     worker_host: &str,
@@ -167,13 +267,149 @@ pub(super) async fn call_worker_stats(
     Ok(response.json::<WorkerStatsResponse>().await?)
 }
 
+pub(super) async fn call_worker_tls_status(
+    server_id: &str,
+    worker_host: &str,
+    secret_key: &str,
+    domains: &[String],
+) -> Result<Vec<DomainTlsStatus>> {
+    let payload = WorkerTlsStatusRequest { domains };
+    let body = serde_json::to_vec(&payload)?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)?
+        .as_secs()
+        .to_string();
+    let signature = sign_internal_payload(&body, &timestamp, secret_key)?;
+    let url = format!("http://{worker_host}:4000/internal/tls/status");
+
+    let response = reqwest::Client::new()
+        .post(url)
+        .header("X-Cluster-Timestamp", timestamp)
+        .header("X-Cluster-Signature", signature)
+        .header("X-Server-Id", server_id)
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("internal TLS status endpoint returned {status}: {body}");
+    }
+
+    Ok(response.json::<Vec<DomainTlsStatus>>().await?)
+}
+
+pub(super) async fn call_worker_set_maintenance(
+    server_id: &str,
+    worker_host: &str,
+    secret_key: &str,
+    project_id: &str,
+    maintenance: &MaintenanceMode,
+) -> Result<()> {
+    let body = serde_json::to_vec(maintenance)?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)?
+        .as_secs()
+        .to_string();
+    let signature = sign_internal_payload(&body, &timestamp, secret_key)?;
+    let url = format!("http://{worker_host}:4000/internal/projects/{project_id}/maintenance");
+
+    let response = reqwest::Client::new()
+        .post(url)
+        .header("X-Cluster-Timestamp", timestamp)
+        .header("X-Cluster-Signature", signature)
+        .header("X-Server-Id", server_id)
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("internal maintenance endpoint returned {status}: {body}");
+    }
+
+    Ok(())
+}
+
+pub(super) async fn call_worker_job_status(
+    server_id: &str,
+    worker_host: &str,
+    secret_key: &str,
+    project_id: &str,
+) -> Result<Vec<JobStatus>> {
+    let body: [u8; 0] = [];
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)?
+        .as_secs()
+        .to_string();
+    let signature = sign_internal_payload(&body, &timestamp, secret_key)?;
+    let url = format!("http://{worker_host}:4000/internal/projects/{project_id}/jobs");
+
+    let response = reqwest::Client::new()
+        .post(url)
+        .header("X-Cluster-Timestamp", timestamp)
+        .header("X-Cluster-Signature", signature)
+        .header("X-Server-Id", server_id)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("internal jobs endpoint returned {status}: {body}");
+    }
+
+    Ok(response.json::<Vec<JobStatus>>().await?)
+}
+
+/// Starts a run of the project's job `name`; `false` when the worker has no such job.
+pub(super) async fn call_worker_run_job(
+    server_id: &str,
+    worker_host: &str,
+    secret_key: &str,
+    project_id: &str,
+    name: &str,
+) -> Result<bool> {
+    let body: [u8; 0] = [];
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)?
+        .as_secs()
+        .to_string();
+    let signature = sign_internal_payload(&body, &timestamp, secret_key)?;
+    let url = format!("http://{worker_host}:4000/internal/projects/{project_id}/jobs/{name}/run");
+
+    let response = reqwest::Client::new()
+        .post(url)
+        .header("X-Cluster-Timestamp", timestamp)
+        .header("X-Cluster-Signature", signature)
+        .header("X-Server-Id", server_id)
+        .send()
+        .await?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(false);
+    }
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("internal job run endpoint returned {status}: {body}");
+    }
+
+    Ok(true)
+}
+
 pub(super) async fn call_worker_port_available(
     server_id: &str,
     worker_host: &str,
     secret_key: &str,
     port: u16,
+    protocol: Protocol,
 ) -> Result<bool> {
-    let payload = WorkerPortAvailabilityRequest { port };
+    let payload = WorkerPortAvailabilityRequest { port, protocol };
     let body = serde_json::to_vec(&payload)?;
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)?
@@ -83,13 +83,40 @@ fn orchestrator_method_name(method: &str, route: &str) -> &'static str {
         ("GET", "/api/servers/:id/stats") => "servers.get_server_stats",
         ("GET", "/api/projects") => "projects.list_projects",
         ("POST", "/api/projects") => "projects.create_project",
+        ("POST", "/api/projects/detect") => "project_detect.detect_project",
         ("GET", "/api/projects/:id") => "projects.get_project",
         ("DELETE", "/api/projects/:id") => "projects.delete_project",
         ("POST", "/api/projects/:id/redeploy") => "projects.redeploy_project",
+        ("POST", "/api/projects/:id/start") => "lifecycle.start_project",
+        ("POST", "/api/projects/:id/stop") => "lifecycle.stop_project",
+        ("POST", "/api/projects/:id/restart") => "lifecycle.restart_project",
+        ("GET", "/api/projects/:id/deployments") => "deployments.list_project_deployments",
+        ("GET", "/api/projects/:id/domains") => "domains.list_project_domains",
+        ("POST", "/api/projects/:id/domains") => "domains.add_project_domain",
+        ("GET", "/api/projects/:id/maintenance") => "maintenance.get_project_maintenance",
+        ("PUT", "/api/projects/:id/maintenance") => "maintenance.update_project_maintenance",
+        ("GET", "/api/projects/:id/network") => "network.get_project_network",
+        ("PUT", "/api/projects/:id/network") => "network.update_project_network",
+        ("GET", "/api/projects/:id/stream-ports") => "streams.get_project_stream_ports",
+        ("PUT", "/api/projects/:id/stream-ports") => "streams.update_project_stream_ports",
+        ("GET", "/api/projects/:id/jobs") => "jobs.get_project_jobs",
+        ("PUT", "/api/projects/:id/jobs") => "jobs.update_project_jobs",
+        ("GET", "/api/projects/:id/jobs/runs") => "jobs.list_project_job_runs",
+        ("POST", "/api/projects/:id/jobs/:name/run") => "jobs.run_project_job",
+        ("GET", "/api/projects/:id/processes") => "processes.get_project_processes",
+        ("PUT", "/api/projects/:id/processes") => "processes.update_project_processes",
+        ("GET", "/api/projects/:id/volumes") => "volumes.get_project_volumes",
+        ("PUT", "/api/projects/:id/volumes") => "volumes.update_project_volumes",
+        ("PUT", "/api/projects/:id/domains/:domain_id") => "domains.update_project_domain",
+        ("DELETE", "/api/projects/:id/domains/:domain_id") => "domains.delete_project_domain",
+        ("POST", "/api/projects/:id/domains/:domain_id/verify") => "domains.verify_project_domain",
         ("POST", "/api/cluster/generate-token") => "cluster.generate_cluster_token",
         ("POST", "/api/cluster/join") => "cluster.join_cluster",
         ("POST", "/internal/projects") => "internal.internal_projects",
         ("DELETE", "/internal/projects/:id") => "internal.internal_delete_project",
+        ("POST", "/internal/projects/:id/start") => "internal.internal_start_project",
+        ("POST", "/internal/projects/:id/stop") => "internal.internal_stop_project",
+        ("POST", "/internal/projects/:id/restart") => "internal.internal_restart_project",
         ("POST", "/internal/ports/check") => "internal.internal_port_check",
         ("POST", "/internal/verify-signature") => "cluster.verify_signature_guarded",
         _ => "unknown.unknown_handler",
@@ -102,8 +129,15 @@ fn worker_method_name(method: &str, route: &str) -> &'static str {
         ("POST", "/internal/stats") => "handlers.internal_stats",
         ("POST", "/internal/deploy") => "handlers.internal_deploy",
         ("POST", "/internal/ports/check") => "handlers.internal_port_check",
+        ("POST", "/internal/tls/status") => "handlers.internal_tls_status",
         ("POST", "/internal/projects") => "handlers.internal_projects",
         ("DELETE", "/internal/projects/:id") => "handlers.internal_delete_project",
+        ("POST", "/internal/projects/:id/start") => "handlers.internal_start_project",
+        ("POST", "/internal/projects/:id/stop") => "handlers.internal_stop_project",
+        ("POST", "/internal/projects/:id/restart") => "handlers.internal_restart_project",
+        ("POST", "/internal/projects/:id/maintenance") => "handlers.internal_set_maintenance",
+        ("POST", "/internal/projects/:id/jobs") => "handlers.internal_job_status",
+        ("POST", "/internal/projects/:id/jobs/:name/run") => "handlers.internal_run_job",
         _ => "unknown.unknown_handler",
     }
 }
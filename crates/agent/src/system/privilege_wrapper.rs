@@ -8,18 +8,19 @@ const SYSTEMCTL_BIN: &str = "/usr/bin/systemctl";
 const SERVICE_BIN: &str = "/usr/sbin/service";
 const USERADD_BIN: &str = "/usr/sbin/useradd";
 const USERDEL_BIN: &str = "/usr/sbin/userdel";
-const CERTBOT_BIN: &str = "/usr/bin/certbot";
 const MV_BIN: &str = "/usr/bin/mv";
 const RM_BIN: &str = "/usr/bin/rm";
 const CHOWN_BIN: &str = "/usr/bin/chown";
 const FALLOCATE_BIN: &str = "/usr/bin/fallocate";
+const SUBIDS_BIN: &str = "/usr/local/sbin/nanoscale-subids";
+const NGINX_CHECK_BIN: &str = "/usr/local/sbin/nanoscale-nginx-check";
+const UFW_BIN: &str = "/usr/local/sbin/nanoscale-ufw";
 
 #[derive(Debug)]
 pub struct PrivilegeWrapper {
     allowed_binaries: HashSet<&'static str>,
 }
 
-mod certbot;
 mod validators;
 
 impl Default for PrivilegeWrapper {
@@ -36,11 +37,13 @@ impl PrivilegeWrapper {
             SERVICE_BIN,
             USERADD_BIN,
             USERDEL_BIN,
-            CERTBOT_BIN,
             MV_BIN,
             RM_BIN,
             CHOWN_BIN,
             FALLOCATE_BIN,
+            SUBIDS_BIN,
+            NGINX_CHECK_BIN,
+            UFW_BIN,
         ]);
 
         Self { allowed_binaries }
@@ -1,23 +1,29 @@
+use std::net::IpAddr;
 use std::path::Path;
 
 use anyhow::{anyhow, Result};
 
 use super::{
-    certbot, CHOWN_BIN, FALLOCATE_BIN, MV_BIN, RM_BIN, SERVICE_BIN, SYSTEMCTL_BIN, USERADD_BIN,
-    USERDEL_BIN,
+    CHOWN_BIN, FALLOCATE_BIN, MV_BIN, NGINX_CHECK_BIN, RM_BIN, SERVICE_BIN, SUBIDS_BIN,
+    SYSTEMCTL_BIN, UFW_BIN, USERADD_BIN, USERDEL_BIN,
 };
 
+/// Ports ufw may never be asked to change: SSH and the internal API.
+const RESERVED_PORTS: [u16; 2] = [22, 4000];
+
 pub(super) fn validate_command_args(binary_path: &str, args: &[&str]) -> Result<()> {
     match binary_path {
         SYSTEMCTL_BIN => validate_systemctl_args(args),
         SERVICE_BIN => validate_service_args(args),
         USERADD_BIN => validate_useradd_args(args),
         USERDEL_BIN => validate_userdel_args(args),
-        super::CERTBOT_BIN => certbot::validate_certbot_args(args),
         MV_BIN => validate_mv_args(args),
         RM_BIN => validate_rm_args(args),
         CHOWN_BIN => validate_chown_args(args),
         FALLOCATE_BIN => validate_fallocate_args(args),
+        SUBIDS_BIN => validate_subids_args(args),
+        NGINX_CHECK_BIN => validate_nginx_check_args(args),
+        UFW_BIN => validate_ufw_args(args),
         _ => Err(anyhow!("unsupported binary path: {binary_path}")),
     }
 }
@@ -31,7 +37,7 @@ fn validate_systemctl_args(args: &[&str]) -> Result<()> {
         && matches!(args[0], "enable" | "disable")
         && args[1] == "--now"
         && args[2].starts_with("nanoscale-")
-        && (args[2].ends_with(".service") || args[2].ends_with(".socket"))
+        && has_unit_suffix(args[2])
     {
         return Ok(());
     }
@@ -45,7 +51,7 @@ fn validate_systemctl_args(args: &[&str]) -> Result<()> {
         && args[1].starts_with("--property=")
         && args[2] == "--value"
         && args[3].starts_with("nanoscale-")
-        && (args[3].ends_with(".service") || args[3].ends_with(".socket"))
+        && has_unit_suffix(args[3])
     {
         return Ok(());
     }
@@ -54,7 +60,7 @@ fn validate_systemctl_args(args: &[&str]) -> Result<()> {
         && args[0] == "show"
         && args[1].starts_with("--property=")
         && args[2].starts_with("nanoscale-")
-        && (args[2].ends_with(".service") || args[2].ends_with(".socket"))
+        && has_unit_suffix(args[2])
     {
         return Ok(());
     }
@@ -66,6 +72,16 @@ fn validate_systemctl_args(args: &[&str]) -> Result<()> {
         return Ok(());
     }
 
+    // Manual cron job runs, which would otherwise wait for the oneshot service to finish.
+    if args.len() == 3
+        && args[0] == "start"
+        && args[1] == "--no-block"
+        && args[2].starts_with("nanoscale-")
+        && args[2].ends_with(".service")
+    {
+        return Ok(());
+    }
+
     Err(anyhow!("systemctl arguments are not allowed: {args:?}"))
 }
 
@@ -106,17 +122,27 @@ fn validate_mv_args(args: &[&str]) -> Result<()> {
     let source = args[0];
     let destination = args[1];
 
+    if source.starts_with("/opt/nanoscale/tmp/nanoscale-")
+        && Path::new(source)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("env"))
+        && !source.contains("..")
+        && env_file_target_allowed(destination)
+    {
+        return Ok(());
+    }
+
     let source_allowed = source.starts_with("/opt/nanoscale/tmp/nanoscale-")
-        && (source.ends_with(".service")
-            || source.ends_with(".socket")
-            || has_conf_extension(source));
+        && (has_unit_suffix(source) || has_conf_extension(source));
 
     let destination_allowed = (destination.starts_with("/etc/systemd/system/nanoscale-")
-        && (destination.ends_with(".service") || destination.ends_with(".socket")))
+        && has_unit_suffix(destination))
         || (destination.starts_with("/etc/nginx/sites-available/nanoscale-")
             && has_conf_extension(destination))
         || (destination.starts_with("/etc/nginx/sites-enabled/nanoscale-")
-            && has_conf_extension(destination));
+            && has_conf_extension(destination))
+        || nginx_zones_target_allowed(destination)
+        || nginx_streams_target_allowed(destination);
 
     if source_allowed && destination_allowed {
         return Ok(());
@@ -137,7 +163,8 @@ fn validate_chown_args(args: &[&str]) -> Result<()> {
         };
 
         let destination_allowed = destination.starts_with("/opt/nanoscale/sites/nanoscale-")
-            || destination.starts_with("/opt/nanoscale/sites/");
+            || destination.starts_with("/opt/nanoscale/sites/")
+            || volume_target_allowed(destination);
 
         if owner_allowed && destination_allowed {
             return Ok(());
@@ -146,7 +173,7 @@ fn validate_chown_args(args: &[&str]) -> Result<()> {
 
     if args.len() == 2 && args[0] == "root:root" {
         let target = args[1];
-        if systemd_unit_target_allowed(target) {
+        if systemd_unit_target_allowed(target) || env_file_target_allowed(target) {
             return Ok(());
         }
     }
@@ -163,7 +190,14 @@ fn systemd_unit_target_allowed(target: &str) -> bool {
         return false;
     }
 
-    target.ends_with(".service") || target.ends_with(".socket")
+    has_unit_suffix(target)
+}
+
+/// Project env files hold secrets, so only the flat `/etc/default/nanoscale-<id>` files qualify.
+fn env_file_target_allowed(target: &str) -> bool {
+    target
+        .strip_prefix("/etc/default/nanoscale-")
+        .is_some_and(|suffix| !suffix.is_empty() && !suffix.contains('/') && !suffix.contains(".."))
 }
 
 fn validate_rm_args(args: &[&str]) -> Result<()> {
@@ -186,18 +220,46 @@ fn validate_rm_args(args: &[&str]) -> Result<()> {
 }
 
 fn rm_file_target_allowed(target: &str) -> bool {
-    (target.starts_with("/etc/systemd/system/nanoscale-")
-        && (target.ends_with(".service") || target.ends_with(".socket")))
+    (target.starts_with("/etc/systemd/system/nanoscale-") && has_unit_suffix(target))
         || (target.starts_with("/etc/systemd/system/multi-user.target.wants/nanoscale-")
             && target.ends_with(".service"))
         || (target.starts_with("/etc/systemd/system/sockets.target.wants/nanoscale-")
             && target.ends_with(".socket"))
+        || (target.starts_with("/etc/systemd/system/timers.target.wants/nanoscale-")
+            && has_timer_extension(target))
         || (target.starts_with("/etc/nginx/sites-enabled/nanoscale-") && has_conf_extension(target))
+        || nginx_zones_target_allowed(target)
+        || nginx_streams_target_allowed(target)
+        || env_file_target_allowed(target)
+}
+
+/// Per-project http-level includes (`limit_req_zone` and friends) in `conf.d`.
+fn nginx_zones_target_allowed(target: &str) -> bool {
+    target
+        .strip_prefix("/etc/nginx/conf.d/nanoscale-")
+        .and_then(|suffix| suffix.strip_suffix("-zones.conf"))
+        .is_some_and(|id| !id.is_empty() && !id.contains('/') && !id.contains(".."))
+}
+
+/// Per-project TCP/UDP servers included in the top-level `stream` block.
+fn nginx_streams_target_allowed(target: &str) -> bool {
+    target
+        .strip_prefix("/etc/nginx/streams-enabled/nanoscale-")
+        .and_then(|suffix| suffix.strip_suffix(".conf"))
+        .is_some_and(|id| !id.is_empty() && !id.contains('/') && !id.contains(".."))
 }
 
 fn rm_directory_target_allowed(target: &str) -> bool {
-    (target.starts_with("/opt/nanoscale/sites/") || target.starts_with("/opt/nanoscale/tmp/"))
-        && !target.contains("..")
+    ((target.starts_with("/opt/nanoscale/sites/") || target.starts_with("/opt/nanoscale/tmp/"))
+        && !target.contains(".."))
+        || volume_target_allowed(target)
+}
+
+/// A project's volumes directory or one volume in it, never the volumes root itself.
+fn volume_target_allowed(target: &str) -> bool {
+    target
+        .strip_prefix("/opt/nanoscale/data/volumes/")
+        .is_some_and(|rest| !rest.is_empty() && !rest.starts_with('/') && !rest.contains(".."))
 }
 
 fn validate_fallocate_args(args: &[&str]) -> Result<()> {
@@ -208,6 +270,69 @@ fn validate_fallocate_args(args: &[&str]) -> Result<()> {
     Err(anyhow!("fallocate arguments are not allowed: {args:?}"))
 }
 
+/// The project user whose subordinate id range the helper provisions, e.g. `nanoscale-p1`.
+fn validate_subids_args(args: &[&str]) -> Result<()> {
+    let allowed = match args {
+        [user] => user.strip_prefix("nanoscale-").is_some_and(|id| {
+            !id.is_empty()
+                && id.chars().all(|character| {
+                    character.is_ascii_alphanumeric() || character == '-' || character == '_'
+                })
+        }),
+        _ => false,
+    };
+    if allowed {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "nanoscale-subids arguments are not allowed: {args:?}"
+    ))
+}
+
+fn validate_nginx_check_args(args: &[&str]) -> Result<()> {
+    if args.is_empty() {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "nanoscale-nginx-check arguments are not allowed: {args:?}"
+    ))
+}
+
+/// `[delete] allow [from <ip>] to any port <port> proto <tcp|udp>` for an unprivileged port.
+fn validate_ufw_args(args: &[&str]) -> Result<()> {
+    let rule = args.strip_prefix(&["delete"]).unwrap_or(args);
+    let rule = match rule {
+        ["allow", "from", address, rest @ ..] if address.parse::<IpAddr>().is_ok() => rest,
+        ["allow", rest @ ..] => rest,
+        _ => &[],
+    };
+    let allowed = match rule {
+        ["to", "any", "port", port, "proto", "tcp" | "udp"] => port
+            .parse::<u16>()
+            .is_ok_and(|port| port >= 1024 && !RESERVED_PORTS.contains(&port)),
+        _ => false,
+    };
+    if allowed {
+        return Ok(());
+    }
+
+    Err(anyhow!("ufw arguments are not allowed: {args:?}"))
+}
+
+/// Project units: the app's service and socket, and cron job services and timers.
+fn has_unit_suffix(name: &str) -> bool {
+    name.ends_with(".service") || name.ends_with(".socket") || has_timer_extension(name)
+}
+
+/// Unit names are case sensitive, so unlike config files `.TIMER` is not accepted.
+fn has_timer_extension(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .is_some_and(|ext| ext == "timer")
+}
+
 fn has_conf_extension(path: &str) -> bool {
     Path::new(path)
         .extension()
@@ -253,6 +378,104 @@ mod tests {
     fn validate_systemctl_rejects_other_args() {
         assert!(validate_command_args(SYSTEMCTL_BIN, &["isolate", "rescue"]).is_err());
         assert!(validate_command_args(SYSTEMCTL_BIN, &["restart", "ssh"]).is_err());
+        assert!(
+            validate_command_args(SYSTEMCTL_BIN, &["start", "--no-block", "ssh.service"]).is_err()
+        );
+        assert!(
+            validate_command_args(SYSTEMCTL_BIN, &["enable", "--now", "apt-daily.timer"]).is_err()
+        );
+    }
+
+    #[test]
+    fn validate_cron_job_units_are_allowed() {
+        validate_command_args(
+            SYSTEMCTL_BIN,
+            &["enable", "--now", "nanoscale-p1-job-cleanup.timer"],
+        )
+        .expect("enable timer");
+        validate_command_args(
+            SYSTEMCTL_BIN,
+            &["start", "--no-block", "nanoscale-p1-job-cleanup.service"],
+        )
+        .expect("start job");
+        validate_command_args(
+            SYSTEMCTL_BIN,
+            &[
+                "show",
+                "--property=NextElapseUSecRealtime",
+                "--value",
+                "nanoscale-p1-job-cleanup.timer",
+            ],
+        )
+        .expect("show timer");
+        validate_command_args(
+            MV_BIN,
+            &[
+                "/opt/nanoscale/tmp/nanoscale-p1-job-cleanup.timer",
+                "/etc/systemd/system/nanoscale-p1-job-cleanup.timer",
+            ],
+        )
+        .expect("mv timer");
+        validate_command_args(
+            CHOWN_BIN,
+            &[
+                "root:root",
+                "/etc/systemd/system/nanoscale-p1-job-cleanup.timer",
+            ],
+        )
+        .expect("chown timer");
+        validate_command_args(
+            RM_BIN,
+            &[
+                "-f",
+                "/etc/systemd/system/timers.target.wants/nanoscale-p1-job-cleanup.timer",
+            ],
+        )
+        .expect("rm timer link");
+        validate_command_args(
+            CHOWN_BIN,
+            &[
+                "-R",
+                "nanoscale-p1:nanoscale-p1",
+                "/opt/nanoscale/sites/p1/jobs",
+            ],
+        )
+        .expect("chown runs dir");
+        validate_command_args(
+            CHOWN_BIN,
+            &[
+                "-R",
+                "nanoscale-p1:nanoscale-p1",
+                "/opt/nanoscale/data/volumes/p1/db",
+            ],
+        )
+        .expect("chown volume");
+        validate_command_args(RM_BIN, &["-rf", "/opt/nanoscale/data/volumes/p1"])
+            .expect("rm volumes");
+        for target in [
+            "/opt/nanoscale/data/volumes/",
+            "/opt/nanoscale/data/volumes//",
+            "/opt/nanoscale/data/volumes/../firewall",
+            "/opt/nanoscale/data/firewall",
+        ] {
+            assert!(validate_command_args(RM_BIN, &["-rf", target]).is_err());
+        }
+
+        assert!(validate_command_args(
+            RM_BIN,
+            &[
+                "-f",
+                "/etc/systemd/system/timers.target.wants/apt-daily.timer"
+            ]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn validate_nginx_check_takes_no_arguments() {
+        validate_command_args(NGINX_CHECK_BIN, &[]).expect("nginx check");
+        assert!(validate_command_args(NGINX_CHECK_BIN, &["-c", "/tmp/evil.conf"]).is_err());
+        assert!(validate_command_args("/usr/sbin/nginx", &["-t"]).is_err());
     }
 
     #[test]
@@ -275,6 +498,49 @@ mod tests {
         )
         .expect("mv nginx conf");
 
+        validate_command_args(
+            MV_BIN,
+            &[
+                "/opt/nanoscale/tmp/nanoscale-p1-zones.enabled.conf",
+                "/etc/nginx/conf.d/nanoscale-p1-zones.conf",
+            ],
+        )
+        .expect("mv nginx zones");
+        validate_command_args(RM_BIN, &["-f", "/etc/nginx/conf.d/nanoscale-p1-zones.conf"])
+            .expect("rm nginx zones");
+        assert!(validate_command_args(
+            MV_BIN,
+            &[
+                "/opt/nanoscale/tmp/nanoscale-p1-zones.enabled.conf",
+                "/etc/nginx/conf.d/default.conf",
+            ],
+        )
+        .is_err());
+        assert!(validate_command_args(
+            RM_BIN,
+            &["-f", "/etc/nginx/conf.d/nanoscale-../x-zones.conf"]
+        )
+        .is_err());
+
+        validate_command_args(
+            MV_BIN,
+            &[
+                "/opt/nanoscale/tmp/nanoscale-p1.enabled.conf",
+                "/etc/nginx/streams-enabled/nanoscale-p1.conf",
+            ],
+        )
+        .expect("mv nginx streams");
+        validate_command_args(
+            RM_BIN,
+            &["-f", "/etc/nginx/streams-enabled/nanoscale-p1.conf"],
+        )
+        .expect("rm nginx streams");
+        assert!(validate_command_args(
+            RM_BIN,
+            &["-f", "/etc/nginx/streams-enabled/nanoscale-../x.conf"]
+        )
+        .is_err());
+
         validate_command_args(RM_BIN, &["-f", "/etc/systemd/system/nanoscale-p1.service"])
             .expect("rm service");
 
@@ -313,6 +579,85 @@ mod tests {
         .is_err());
     }
 
+    #[test]
+    fn validate_env_file_commands_are_scoped_to_project_env_files() {
+        validate_command_args(
+            MV_BIN,
+            &[
+                "/opt/nanoscale/tmp/nanoscale-p1.env",
+                "/etc/default/nanoscale-p1",
+            ],
+        )
+        .expect("mv env file");
+        validate_command_args(CHOWN_BIN, &["root:root", "/etc/default/nanoscale-p1"])
+            .expect("chown env file");
+        validate_command_args(RM_BIN, &["-f", "/etc/default/nanoscale-p1"]).expect("rm env file");
+
+        assert!(validate_command_args(
+            MV_BIN,
+            &["/opt/nanoscale/tmp/nanoscale-p1.env", "/etc/default/grub"]
+        )
+        .is_err());
+        assert!(validate_command_args(
+            MV_BIN,
+            &[
+                "/opt/nanoscale/tmp/nanoscale-p1.env",
+                "/etc/default/nanoscale-../grub"
+            ]
+        )
+        .is_err());
+        assert!(validate_command_args(RM_BIN, &["-f", "/etc/default/nanoscale-"]).is_err());
+    }
+
+    #[test]
+    fn validate_subids_allows_only_project_users() {
+        validate_command_args(SUBIDS_BIN, &["nanoscale-p1"]).expect("project user");
+
+        for args in [
+            &["nanoscale"][..],
+            &["nanoscale-"],
+            &["root"],
+            &["nanoscale-p1", "root"],
+            &["nanoscale-../p1"],
+            &["nanoscale-p1 root"],
+            &[],
+        ] {
+            assert!(validate_command_args(SUBIDS_BIN, args).is_err(), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn validate_ufw_allows_only_project_port_rules() {
+        let rule = [
+            "allow", "from", "10.0.0.1", "to", "any", "port", "3100", "proto", "tcp",
+        ];
+        validate_command_args(UFW_BIN, &rule).expect("scoped rule");
+        validate_command_args(UFW_BIN, &[&["delete"], &rule[..]].concat()).expect("delete");
+        validate_command_args(
+            UFW_BIN,
+            &["allow", "to", "any", "port", "5353", "proto", "udp"],
+        )
+        .expect("open rule");
+
+        for args in [
+            &["allow", "to", "any", "port", "22", "proto", "tcp"][..],
+            &["allow", "to", "any", "port", "4000", "proto", "tcp"],
+            &[
+                "allow", "from", "any", "to", "any", "port", "3100", "proto", "tcp",
+            ],
+            &["deny", "to", "any", "port", "3100", "proto", "tcp"],
+            &["disable"],
+            &["allow", "to", "any", "port", "3100:3200", "proto", "tcp"],
+            &[
+                "allow", "to", "any", "port", "3100", "proto", "tcp", "comment", "x",
+            ],
+        ] {
+            assert!(validate_command_args(UFW_BIN, args).is_err(), "{args:?}");
+        }
+        // ufw itself is only reachable through the wrapper.
+        assert!(validate_command_args("/usr/sbin/ufw", &rule).is_err());
+    }
+
     #[test]
     fn has_conf_extension_checks_case_insensitively() {
         assert!(has_conf_extension(
@@ -5,6 +5,10 @@ use std::process::Command;
 use anyhow::Result;
 use sysinfo::{Disks, Networks, ProcessesToUpdate, System};
 
+use crate::deployment::processes;
+
+const SYSTEMD_PATH: &str = "/etc/systemd/system";
+
 #[derive(Clone, Debug)]
 pub struct SystemTotalsSnapshot {
     pub cpu_usage_percent: f32,
@@ -96,10 +100,39 @@ fn collect_totals_snapshot(
     }
 }
 
+/// Sums the web service and every process instance of the project, so a project's usage
+/// includes its workers.
 fn collect_project_counters(project_id: &str, system: &System) -> Result<ProjectCountersSnapshot> {
-    let service_name = format!("nanoscale-{project_id}.service");
+    let mut counters = collect_unit_counters(&format!("nanoscale-{project_id}.service"), system)?;
+    let instances =
+        processes::enabled_instances(Path::new(SYSTEMD_PATH), project_id).unwrap_or_default();
+    for unit in instances {
+        let Ok(instance) = collect_unit_counters(&unit, system) else {
+            continue;
+        };
+        counters.cpu_usage_nsec_total = counters
+            .cpu_usage_nsec_total
+            .saturating_add(instance.cpu_usage_nsec_total);
+        counters.memory_current_bytes = counters
+            .memory_current_bytes
+            .saturating_add(instance.memory_current_bytes);
+        counters.network_ingress_bytes_total = counters
+            .network_ingress_bytes_total
+            .saturating_add(instance.network_ingress_bytes_total);
+        counters.network_egress_bytes_total = counters
+            .network_egress_bytes_total
+            .saturating_add(instance.network_egress_bytes_total);
+    }
+
+    counters.disk_usage_bytes =
+        directory_size_bytes(Path::new(&format!("/opt/nanoscale/sites/{project_id}")));
+
+    Ok(counters)
+}
+
+fn collect_unit_counters(service_name: &str, system: &System) -> Result<ProjectCountersSnapshot> {
     let systemd_props = systemctl_show(
-        &service_name,
+        service_name,
         &[
             "MainPID",
             "CPUUsageNSec",
@@ -141,15 +174,12 @@ fn collect_project_counters(project_id: &str, system: &System) -> Result<Project
         .and_then(|value| value.parse::<u64>().ok())
         .unwrap_or(0);
 
-    let disk_usage_bytes =
-        directory_size_bytes(Path::new(&format!("/opt/nanoscale/sites/{project_id}")));
-
     Ok(ProjectCountersSnapshot {
         cpu_usage_nsec_total,
         memory_current_bytes,
         network_ingress_bytes_total,
         network_egress_bytes_total,
-        disk_usage_bytes,
+        disk_usage_bytes: 0,
     })
 }
 
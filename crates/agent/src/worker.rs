@@ -8,6 +8,7 @@ use tokio::sync::RwLock;
 
 use crate::cluster::protocol::{JoinClusterRequest, JoinClusterResponse};
 use crate::config::NanoScaleConfig;
+use crate::deployment::cert_renewal::CertificateRenewer;
 use crate::deployment::inactivity_monitor::InactivityMonitor;
 use crate::request_logging;
 use crate::system::PrivilegeWrapper;
@@ -64,20 +65,50 @@ pub async fn run(join_token: &str) -> Result<()> {
 
     let worker_state = WorkerState {
         monitored_projects: Arc::new(RwLock::new(Vec::new())),
+        certificates: CertificateRenewer::new(
+            config.tls_renewal_window_days(),
+            config.acme_settings()?,
+        ),
     };
     let monitor = InactivityMonitor::new(worker_state.monitored_projects.clone());
     monitor.spawn();
+    worker_state.certificates.clone().spawn();
 
     let app = Router::new()
         .route("/internal/health", post(handlers::internal_health))
         .route("/internal/stats", post(handlers::internal_stats))
         .route("/internal/deploy", post(handlers::internal_deploy))
         .route("/internal/ports/check", post(handlers::internal_port_check))
+        .route("/internal/tls/status", post(handlers::internal_tls_status))
         .route("/internal/projects", post(handlers::internal_projects))
         .route(
             "/internal/projects/:id",
             delete(handlers::internal_delete_project),
         )
+        .route(
+            "/internal/projects/:id/start",
+            post(handlers::internal_start_project),
+        )
+        .route(
+            "/internal/projects/:id/stop",
+            post(handlers::internal_stop_project),
+        )
+        .route(
+            "/internal/projects/:id/restart",
+            post(handlers::internal_restart_project),
+        )
+        .route(
+            "/internal/projects/:id/maintenance",
+            post(handlers::internal_set_maintenance),
+        )
+        .route(
+            "/internal/projects/:id/jobs",
+            post(handlers::internal_job_status),
+        )
+        .route(
+            "/internal/projects/:id/jobs/:name/run",
+            post(handlers::internal_run_job),
+        )
         .route_layer(axum::middleware::from_fn(
             request_logging::log_worker_request,
         ))
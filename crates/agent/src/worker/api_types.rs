@@ -1,6 +1,21 @@
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
+
+use crate::deployment::access::AccessControl;
+use crate::deployment::build::ProjectRuntime;
+use crate::deployment::cert_renewal::CertificateRenewer;
+use crate::deployment::firewall::Protocol;
+use crate::deployment::jobs::CronJob;
+use crate::deployment::nginx::RoutedDomain;
+use crate::deployment::pages::MaintenanceMode;
+use crate::deployment::private_network::PrivateNetwork;
+use crate::deployment::processes::ProcessType;
+use crate::deployment::replicas::ReplicaRole;
+use crate::deployment::routing::RoutingConfig;
+use crate::deployment::streams::StreamPort;
+use crate::deployment::systemd::ResourceLimits;
+use crate::deployment::volumes::Volume;
 use tokio::sync::RwLock;
 
 use crate::deployment::inactivity_monitor::MonitoredProject;
@@ -12,6 +27,12 @@ pub(super) struct HealthResponse {
     pub(super) total_memory_bytes: u64,
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub(super) struct DeleteProjectRequest {
+    #[serde(default)]
+    pub(super) delete_volumes: bool,
+}
+
 #[derive(Debug, Deserialize)]
 pub(super) struct StatsRequest {
     pub(super) project_ids: Vec<String>,
@@ -61,9 +82,50 @@ pub(super) struct WorkerCreateProjectRequest {
     pub(super) run_command: String,
     pub(super) output_directory: String,
     pub(super) port: u16,
+    pub(super) backend_port: u16,
     pub(super) domain: Option<String>,
+    #[serde(default)]
+    pub(super) custom_domains: Vec<RoutedDomain>,
     pub(super) tls_email: Option<String>,
+    #[serde(default)]
+    pub(super) base_domain: Option<String>,
     pub(super) env_vars: Vec<WorkerProjectEnvVar>,
+    #[serde(default)]
+    pub(super) runtime: ProjectRuntime,
+    #[serde(default)]
+    pub(super) resource_limits: ResourceLimits,
+    #[serde(default)]
+    pub(super) root_directory: String,
+    #[serde(default)]
+    pub(super) routing: RoutingConfig,
+    #[serde(default)]
+    pub(super) access: AccessControl,
+    #[serde(default)]
+    pub(super) maintenance: MaintenanceMode,
+    #[serde(default)]
+    pub(super) replica: ReplicaRole,
+    #[serde(default)]
+    pub(super) network: PrivateNetwork,
+    #[serde(default)]
+    pub(super) stream_ports: Vec<StreamPort>,
+    #[serde(default)]
+    pub(super) jobs: Vec<CronJob>,
+    #[serde(default)]
+    pub(super) processes: Vec<ProcessType>,
+    /// Set for the project's own server only, which runs its scheduled jobs and worker
+    /// processes.
+    #[serde(default)]
+    pub(super) primary: bool,
+    #[serde(default)]
+    pub(super) volumes: Vec<Volume>,
+    #[serde(default)]
+    pub(super) release_command: String,
+    /// Set for the one server that runs the release command before going live.
+    #[serde(default)]
+    pub(super) runs_release: bool,
+    /// Keeps the new release down, as the project was stopped.
+    #[serde(default)]
+    pub(super) stopped: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -78,9 +140,19 @@ pub(super) struct CreateProjectPlaceholderResponse {
     pub(super) message: String,
 }
 
+#[derive(Debug, Serialize)]
+pub(super) struct ProjectDeploymentResponse {
+    pub(super) status: &'static str,
+    pub(super) message: String,
+    pub(super) log: Vec<String>,
+    pub(super) commit_sha: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub(super) struct PortAvailabilityRequest {
     pub(super) port: u16,
+    #[serde(default)]
+    pub(super) protocol: Protocol,
 }
 
 #[derive(Debug, Serialize)]
@@ -91,4 +163,10 @@ pub(super) struct PortAvailabilityResponse {
 #[derive(Clone, Debug)]
 pub(super) struct WorkerState {
     pub(super) monitored_projects: Arc<RwLock<Vec<MonitoredProject>>>,
+    pub(super) certificates: CertificateRenewer,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct TlsStatusRequest {
+    pub(super) domains: Vec<String>,
 }
@@ -1,5 +1,6 @@
 use super::*;
 
+use crate::deployment::acme::AcmeSettings;
 use axum::http::StatusCode;
 use axum::{body::to_bytes, http::header, http::Request, routing::delete, routing::post, Router};
 use std::sync::Arc;
@@ -39,6 +40,7 @@ fn worker_create_project_request_deserializes() {
   "run_command": "bun run start",
   "output_directory": "",
   "port": 3100,
+  "backend_port": 20000,
   "domain": null,
   "tls_email": null,
   "env_vars": [{"key": "A", "value": "B"}]
@@ -47,6 +49,7 @@ fn worker_create_project_request_deserializes() {
     let decoded =
         serde_json::from_str::<api_types::WorkerCreateProjectRequest>(json).expect("deserialize");
     assert_eq!(decoded.project_id, "p1");
+    assert_eq!(decoded.backend_port, 20_000);
     assert_eq!(decoded.env_vars.len(), 1);
     assert_eq!(decoded.env_vars[0].key, "A");
 }
@@ -55,6 +58,7 @@ fn worker_create_project_request_deserializes() {
 async fn worker_router_health_endpoint_returns_json() {
     let state = api_types::WorkerState {
         monitored_projects: Arc::new(RwLock::new(Vec::new())),
+        certificates: CertificateRenewer::new(30, AcmeSettings::default()),
     };
 
     let app = Router::new()
@@ -91,6 +95,7 @@ async fn worker_router_health_endpoint_returns_json() {
 async fn worker_router_deploy_endpoint_returns_placeholder() {
     let state = api_types::WorkerState {
         monitored_projects: Arc::new(RwLock::new(Vec::new())),
+        certificates: CertificateRenewer::new(30, AcmeSettings::default()),
     };
 
     let app = Router::new()
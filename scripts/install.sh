@@ -6,6 +6,7 @@ readonly CONFIG_FILE_PATH="${NANOSCALE_ROOT}/config.json"
 readonly SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
 readonly SUDOERS_TEMPLATE="${SCRIPT_DIR}/security/sudoers.d/nanoscale"
 readonly SUDOERS_TARGET="/etc/sudoers.d/nanoscale"
+readonly HELPERS_TARGET_DIR="/usr/local/sbin"
 
 ROLE=""
 JOIN_TOKEN=""
@@ -154,7 +155,6 @@ ensure_dependencies() {
   ensure_dependency "nginx" "nginx"
   ensure_dependency "sqlite3" "sqlite3"
   ensure_dependency "ufw" "ufw"
-  ensure_dependency "certbot" "certbot"
 }
 
 build_and_install_agent() {
@@ -248,6 +248,64 @@ configure_sudoers() {
   visudo -c
 }
 
+install_privileged_helpers() {
+  # Root-owned helpers the agent runs through sudo; each checks its own arguments.
+  install -o root -g root -m 0755 "${SCRIPT_DIR}/security/nanoscale-subids" "${HELPERS_TARGET_DIR}/nanoscale-subids"
+  install -o root -g root -m 0755 "${SCRIPT_DIR}/security/nanoscale-nginx-check" "${HELPERS_TARGET_DIR}/nanoscale-nginx-check"
+  install -o root -g root -m 0755 "${SCRIPT_DIR}/security/nanoscale-ufw" "${HELPERS_TARGET_DIR}/nanoscale-ufw"
+}
+
+configure_rootless_podman() {
+  # Container images are built rootless by the agent user and run by each project user, which
+  # gets its own subordinate id range on its first container deploy.
+  "${HELPERS_TARGET_DIR}/nanoscale-subids" nanoscale
+}
+
+configure_certificate_access() {
+  # Certificates and ACME state belong to the agent; nginx's master process reads keys as root.
+  mkdir -p "${NANOSCALE_ROOT}/certs" "${NANOSCALE_ROOT}/acme" "${NANOSCALE_ROOT}/data/acme"
+  chown nanoscale:nanoscale "${NANOSCALE_ROOT}/certs" "${NANOSCALE_ROOT}/acme" "${NANOSCALE_ROOT}/data/acme"
+  chmod 0700 "${NANOSCALE_ROOT}/certs" "${NANOSCALE_ROOT}/data/acme"
+  chmod 0755 "${NANOSCALE_ROOT}/acme"
+}
+
+configure_htpasswd_access() {
+  # nginx workers read basic auth files per request; the setgid bit hands new files their group.
+  local nginx_group
+  nginx_group="$(id -gn www-data 2>/dev/null || id -gn nginx 2>/dev/null || echo root)"
+  mkdir -p "${NANOSCALE_ROOT}/htpasswd"
+  chown "nanoscale:${nginx_group}" "${NANOSCALE_ROOT}/htpasswd"
+  chmod 2750 "${NANOSCALE_ROOT}/htpasswd"
+}
+
+configure_status_pages() {
+  # Waking and maintenance pages are public; nginx serves them straight from disk.
+  mkdir -p "${NANOSCALE_ROOT}/pages"
+  chown nanoscale:nanoscale "${NANOSCALE_ROOT}/pages"
+  chmod 0755 "${NANOSCALE_ROOT}/pages"
+}
+
+configure_nginx_streams() {
+  # Raw TCP/UDP ports are nginx stream servers, which live outside the http block.
+  if command -v apt-get >/dev/null 2>&1; then
+    install_package "libnginx-mod-stream"
+  elif command -v dnf >/dev/null 2>&1 || command -v yum >/dev/null 2>&1; then
+    install_package "nginx-mod-stream"
+  fi
+
+  mkdir -p /etc/nginx/streams-enabled
+  if ! grep -q "streams-enabled" /etc/nginx/nginx.conf; then
+    printf '\nstream {\n    include /etc/nginx/streams-enabled/*.conf;\n}\n' >> /etc/nginx/nginx.conf
+  fi
+}
+
+configure_private_hosts() {
+  # Per-project hosts files for private services; app sandboxes mount them read-only.
+  mkdir -p "${NANOSCALE_ROOT}/hosts"
+  chown nanoscale:nanoscale "${NANOSCALE_ROOT}/hosts"
+  chmod 0755 "${NANOSCALE_ROOT}/hosts"
+}
+
 configure_firewall() {
   ufw --force enable
   ufw allow 22/tcp
@@ -281,7 +339,14 @@ main() {
   build_and_install_agent "${repo_root}"
   build_dashboard "${repo_root}"
 
+  install_privileged_helpers
   configure_sudoers
+  configure_rootless_podman
+  configure_certificate_access
+  configure_htpasswd_access
+  configure_status_pages
+  configure_nginx_streams
+  configure_private_hosts
   configure_firewall
   print_mode_summary
   echo "NanoScale installation baseline complete."